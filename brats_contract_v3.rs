@@ -3,8 +3,16 @@
 
 use anchor_lang::prelude::*;
 use anchor_lang::solana_program;
+use anchor_lang::solana_program::keccak;
 use anchor_lang::solana_program::system_instruction;
-use anchor_spl::token::{self, Burn, Mint, MintTo, Token, TokenAccount, Transfer};
+use anchor_spl::token_2022::spl_token_2022::extension::transfer_fee;
+use anchor_spl::token_2022::Token2022;
+use anchor_spl::token_interface::{
+    self, Burn, CloseAccount, Mint, TokenAccount, TokenInterface, TransferChecked,
+};
+use pyth_sdk_solana::state::SolanaPriceAccount;
+use spl_account_compression::program::SplAccountCompression;
+use spl_account_compression::Noop;
 use std::str::FromStr;
 
 declare_id!("BxaA8XGHQG2z5X1J4JLcPVVdKBpzK3qSt1Bhk3YktW3s"); // Replace with your program ID
@@ -12,21 +20,61 @@ declare_id!("BxaA8XGHQG2z5X1J4JLcPVVdKBpzK3qSt1Bhk3YktW3s"); // Replace with you
 //
 // CONSTANTS
 //
-const STAKING_DURATION: i64 = 180 * 24 * 3600; // 6 months in seconds
+const STAKING_DURATION: i64 = 180 * 24 * 3600; // 6 months in seconds; APY is paid per this period
+// Defaults for the lock periods in `ProgramConfig`
 const EARLY_UNSTAKE_PERIOD: i64 = 7 * 24 * 3600; // 7-day lock after launch before early unstake is allowed
 const LIQUIDITY_LOCK_PERIOD: i64 = 365 * 24 * 3600; // 1 year in seconds
 const EARLY_UNSTAKE_PENALTY_PERCENT: u64 = 20; // 20% penalty for early unstake
+const TOKEN_UNIT: u64 = 1_000_000_000; // 1 $BRATS in base units (9 decimals, same as SOL)
+const PRICE_SCALE: u64 = 100_000_000; // Stage prices are stored with 8 decimals
+const MAX_URI_LEN: usize = 200; // Max length of a URI stored in ProjectInfo
+const WHITELIST_LAST_STAGE: u8 = 2; // Whitelist mode gates purchases starting in stages 1..=2
+// Payment method bits for `PresaleStage::payment_methods`
+const PAYMENT_METHOD_SOL: u8 = 1 << 0;
+const PAYMENT_METHOD_STABLECOIN: u8 = 1 << 1;
+const PAYMENT_METHODS_ALL: u8 = PAYMENT_METHOD_SOL | PAYMENT_METHOD_STABLECOIN;
+// Operation bits for `ProgramConfig::paused_operations`
+const PAUSE_PURCHASES: u8 = 1 << 0;
+const PAUSE_STAKING: u8 = 1 << 1;
+const PAUSE_UNSTAKING: u8 = 1 << 2;
+const PAUSE_CLAIMS: u8 = 1 << 3;
+const PAUSE_LIQUIDITY: u8 = 1 << 4;
+const PAUSE_ALL: u8 = PAUSE_PURCHASES | PAUSE_STAKING | PAUSE_UNSTAKING | PAUSE_CLAIMS | PAUSE_LIQUIDITY;
+// Notification bits for `UserFlags::flags`
+const FLAG_REWARD_POOL_LOW: u8 = 1 << 0;
+const FLAG_POSITION_UNLOCKABLE: u8 = 1 << 1;
+const FLAG_CLAIM_PORTAL_OPEN: u8 = 1 << 2;
+const FLAG_PROPOSAL_ACTIVE: u8 = 1 << 3;
 
-// Our custom SPL token mint address (Devnet)
-const CUSTOM_TOKEN_MINT: &str = "57EMXJXJkGYNCGjr9ngZPKnJr9jdJPZ1SRrWQqcxg9tr";
+const DRIFT_FEE_WALLET: u32 = 1 << 0;
+const DRIFT_FEE_TOKEN_ACCOUNT: u32 = 1 << 1;
+const DRIFT_TREASURY: u32 = 1 << 2;
+const DRIFT_STAKE_VAULT: u32 = 1 << 3;
+const DRIFT_REWARD_VAULT: u32 = 1 << 4;
+const DRIFT_ORACLE: u32 = 1 << 5;
+const RECEIPT_DISPUTE_WINDOW: i64 = 72 * 3600; // Receipts can be corrected up to 72h after purchase
+const RECEIPT_MAX_ADJUSTMENT_BPS: u64 = 1_000; // Corrections are capped at 10% of the allocation
+const UPGRADE_NOTICE_PERIOD: i64 = 7 * 24 * 3600; // Minimum notice between announcing and applying an upgrade
+const MIN_PARAMS_DELAY: i64 = 24 * 3600; // Shortest allowed timelock on parameter changes
+const REWARD_PER_TOKEN_SCALE: u128 = 1_000_000_000_000; // Fixed-point scale of the partner reward accumulator
+const UNSTAKE_THROTTLE_WINDOW: i64 = 24 * 3600; // Window the unstake throughput limit applies to
+const POSITION_MANAGER_SLOTS: usize = 16; // Positions and receipts listed per PositionManager
+const MAX_PARTICIPATION_WINDOW: u64 = 10; // Max recent proposals the participation boost can span
+const MAX_GUARDIANS: usize = 5; // Guardian keys that can take part in an emergency admin rotation
+const ADMIN_ROTATION_DELAY: i64 = 48 * 3600; // Wait between guardian quorum and admin rotation
+const ADMIN_ROTATION_TTL: i64 = 7 * 24 * 3600; // Open rotations lapse without quorum after this
+const STAKING_LEADERBOARD_SIZE: usize = 100; // Wallets ranked on the staking leaderboard
+const MIN_EMISSION_STEP_LENGTH: i64 = 24 * 3600; // Shortest allowed emission decay step
+const EMISSION_SCALE: u128 = 1_000_000_000_000_000_000; // Fixed-point scale of emission multipliers
+const TRANSFER_FEE_BPS: u16 = 300; // Token-2022 transfer fee for the fee wallet and reward pool
+const TRANSFER_BURN_BPS: u16 = 100; // Further transfer fee share that is burned on harvest
 
 // Token metadata (for off‑chain display; integration with Metaplex is recommended)
 const TOKEN_NAME: &str = "Brotherhood of Rats";
 const TOKEN_SYMBOL: &str = "$BRATS";
 
-// The fee wallet to receive fee portions (for both SOL and SPL tokens).
-// All fees (a flat fee of 3) will be sent to this devnet wallet.
-const FEE_WALLET: &str = "57EMXJXJkGYNCGjr9ngZPKnJr9jdJPZ1SRr9jdJPZ1SRr9tr";
+// Wrapped SOL mint; team buy-ins spend the member's wrapped SOL through the swap program.
+const NATIVE_MINT: &str = "So11111111111111111111111111111111111111112";
 
 //
 // ACCOUNTS
@@ -40,700 +88,12628 @@ pub struct PresaleState {
     pub admin: Pubkey,
     pub liquidity_locked: bool,
     pub liquidity_lock_end_time: Option<i64>,
+    pub soft_cap: u64,                // Lamports that must be raised for the presale to succeed (0 = none)
+    pub hard_cap: u64,                // Max lamports the presale accepts (0 = no cap)
+    pub total_raised: u64,            // Lamports raised, stablecoins counted at stage SOL prices
+    pub whitelist_root: [u8; 32],     // Merkle root of whitelisted wallets for early stages (all zeros = off)
+    pub min_purchase: u64,            // Minimum lamports per purchase (0 = none)
+    pub max_purchase_per_wallet: u64, // Max lamports a wallet may contribute (0 = no cap)
+    pub pending_admin: Pubkey,        // Admin nominated by `propose_admin` (default = none)
+    pub buyer_count: u64,             // Wallets with at least one presale purchase
+    pub purchase_count: u64,          // Presale purchases across all payment methods
+    pub launch_tax_owed: u64,         // Launch-tax tokens sold but not yet paid to the fee wallet
 }
 
+/// Break-glass guardians, set by the admin: a `threshold` of them can rotate a compromised
+/// admin key, `ADMIN_ROTATION_DELAY` after reaching quorum on the new key. The admin or any
+/// guardian can cancel an open rotation.
 #[account]
-pub struct GlobalState {
-    pub total_staked: u64,            // Total staked $BRATS tokens across all users
-    pub reward_pool: u64,             // Reward pool (in tokens) for stakers
-    pub apy: u64,                     // Annual percentage yield (mutable via governance)
-    pub transaction_fee_percent: u64, // Transaction fee percent (mutable via governance)
+pub struct GuardianSet {
+    pub guardians: [Pubkey; MAX_GUARDIANS],
+    pub guardian_count: u8,
+    pub threshold: u8,          // Guardian approvals needed to rotate the admin
+    pub proposed_admin: Pubkey, // Admin key of the open rotation (default = none)
+    pub proposed_at: i64,       // When the open rotation was proposed
+    pub approvals: u8,          // Bitmask of guardians (by index) approving the open rotation
+    pub executable_at: i64,     // When the rotation can be executed (0 = quorum not reached yet)
 }
 
-#[account]
-pub struct StakeInfo {
-    pub amount: u64,          // Amount of tokens staked
-    pub start_time: i64,      // Timestamp when staking started
-    pub last_claim_time: i64, // Timestamp of last reward claim
+impl GuardianSet {
+    /// Replace the guardians and threshold, dropping any lapsed rotation's approvals.
+    pub fn configure(&mut self, guardians: &[Pubkey], threshold: u8) -> Result<()> {
+        require!(
+            !guardians.is_empty()
+                && guardians.len() <= MAX_GUARDIANS
+                && threshold > 0
+                && threshold as usize <= guardians.len(),
+            ErrorCode::InvalidGuardianConfig
+        );
+        for (index, guardian) in guardians.iter().enumerate() {
+            require!(
+                *guardian != Pubkey::default() && !guardians[..index].contains(guardian),
+                ErrorCode::InvalidGuardianConfig
+            );
+        }
+        self.guardians = [Pubkey::default(); MAX_GUARDIANS];
+        self.guardians[..guardians.len()].copy_from_slice(guardians);
+        self.guardian_count = guardians.len() as u8;
+        self.threshold = threshold;
+        self.clear_rotation();
+        Ok(())
+    }
+
+    /// Forget the open rotation, if any.
+    pub fn clear_rotation(&mut self) {
+        self.proposed_admin = Pubkey::default();
+        self.approvals = 0;
+        self.executable_at = 0;
+    }
+
+    /// Index of `key` among the guardians, if it is one.
+    pub fn guardian_index(&self, key: Pubkey) -> Option<usize> {
+        self.guardians[..self.guardian_count as usize]
+            .iter()
+            .position(|guardian| *guardian == key)
+    }
+
+    /// Record the approval of the guardian at `index` for the open rotation, starting the
+    /// rotation delay when it completes the quorum.
+    pub fn approve(&mut self, guardian: Pubkey, index: usize, now: i64) {
+        self.approvals |= 1 << index;
+        let approvals = self.approvals.count_ones() as u8;
+        if self.executable_at == 0 && approvals >= self.threshold {
+            self.executable_at = now + ADMIN_ROTATION_DELAY;
+        }
+        emit!(AdminRotationApproved {
+            guardian,
+            new_admin: self.proposed_admin,
+            approvals,
+            executable_at: self.executable_at,
+            timestamp: now,
+        });
+    }
+
+    /// Whether a rotation is open: proposed and, unless quorum was reached, not lapsed.
+    pub fn rotation_open(&self, now: i64) -> bool {
+        self.proposed_admin != Pubkey::default()
+            && (self.executable_at != 0 || now < self.proposed_at + ADMIN_ROTATION_TTL)
+    }
 }
 
-/// This account holds the presale stage data. There are 8 stages.
-/// The `price` is stored as a fixed-point value with 8 decimals (e.g. 0.00021 is stored as 21000).
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
-pub struct PresaleStage {
-    pub stage: u8,
-    pub price: u64,
-    pub tokens_sold: u64,
-    pub total_raised: u64,
+/// Keys for the non-admin roles, so presale operations and treasury withdrawals are held
+/// by separate keys. The admin (in `PresaleState`) manages the roles.
+#[account]
+pub struct Roles {
+    pub operator: Pubkey,  // Updates presale stages and refills the reward pool
+    pub treasurer: Pubkey, // Withdraws treasury SOL
 }
 
+/// Program-wide configuration.
+/// Tracks admin liveness for the dead-man switch: if the admin executes no admin
+/// instruction for `admin_inactivity_period`, the `recovery_key` may claim admin rights.
 #[account]
-pub struct PresaleStageInfo {
-    pub stages: [PresaleStage; 8],
+pub struct ProgramConfig {
+    pub recovery_key: Pubkey,              // Pre-designated key that can recover admin rights
+    pub admin_inactivity_period: i64,      // Seconds of admin inactivity before recovery is allowed
+    pub last_admin_activity: i64,          // Timestamp of the last admin instruction
+    pub burn_cap_per_epoch: u64,           // Max tokens the admin may burn via `burn_tokens` per epoch
+    pub burn_epoch: u64,                   // Epoch that `burned_this_epoch` refers to
+    pub burned_this_epoch: u64,            // Tokens burned via `burn_tokens` in `burn_epoch`
+    pub reward_pool_low_water_mark: u64,   // Crank tops up only while `reward_pool` is below this
+    pub reward_pool_top_up_amount: u64,    // Max tokens moved from the treasury per crank call
+    pub sybil_check_enabled: bool,         // Require an attestation for every purchase
+    pub attestor: Pubkey,                  // Key allowed to write wallet attestations
+    pub early_bird_bonus_bps: u16,         // Early-bird bonus for stage-1 buyers, in bps of their allocation
+    pub withdrawal_cosigner: Pubkey,       // Second signer required on treasury withdrawals above the threshold
+    pub cosign_threshold: u64,             // Withdrawals strictly above this amount need the co-signer
+    pub allocation_transfer_fee: u64,      // Lamports charged to move a presale allocation to a new wallet
+    pub presale_treasury: Pubkey,          // SOL account that escrowed presale funds are released to
+    pub announced_program_hash: [u8; 32],  // Hash of the announced upgrade's program binary (all zeros = none)
+    pub earliest_upgrade_at: i64,          // The announced upgrade must not be deployed before this time
+    pub token_mint: Pubkey,                // $BRATS mint; protected from foreign-token recovery
+    pub fee_discount_stake_threshold: u64, // Tokens a payer must have staked to get the fee discount
+    pub fee_discount_bps: u16,             // Payment fee discount for qualifying stakers, in bps (0 = off)
+    pub referral_bonus_bps: u16,           // Referrer bonus, in bps of the tokens bought by referred buyers
+    pub fee_wallet: Pubkey,                // Wallet receiving payment and allocation-transfer fees
+    pub staking_lock_period: i64,          // Seconds a position must be staked to unstake without penalty
+    pub early_unstake_period: i64,         // Seconds after launch before early unstaking is allowed
+    pub liquidity_lock_period: i64,        // Seconds liquidity stays locked after launch
+    pub paused_operations: u8,             // `PAUSE_*` bits of the subsystems currently paused
+    pub emergency_unstake_enabled: bool,   // Let stakers withdraw principal via `emergency_unstake`
+    pub early_bird_bonus_lock_period: i64, // Seconds early-bird bonuses stay locked as stake (0 = paid liquid)
+    pub referral_bonus_lock_period: i64,   // Seconds referral bonuses stay locked as stake (0 = paid liquid)
+    pub position_transfers_enabled: bool,  // Let owners hand stake positions to another wallet
+    pub sol_usd_price_feed: Pubkey,        // SOL/USD feed for stage USD targets (default = off)
+    pub max_price_age: i64,                // Max age of the SOL/USD price, in seconds
+    pub buyback_swap_program: Pubkey,      // Swap program `buyback_and_burn` may route through
+    pub max_tx_amount: u64,                // Max tokens per payout in the launch window (0 = off)
+    pub max_wallet_bps: u16,               // Max wallet balance in the window, bps of supply
+    pub launch_protection_period: i64,     // Seconds after launch the anti-whale limits apply
+    pub presale_opens_at: i64,             // Presale purchases are rejected before this time (0 = open)
+    pub trading_opens_at: i64,             // Token claims are rejected before this time (0 = open)
+    pub launch_tax_bps: u16,               // Purchase tax when the presale opens, in bps (0 = off)
+    pub launch_tax_duration: i64,          // Seconds over which the launch tax decays to 0
+    pub transfer_fee_reward_bps: u16,      // Reward pool's share of harvested transfer fees, in bps
 }
 
-//
-// PROGRAM
-//
-#[program]
-pub mod brats_contract {
-    use super::*;
+impl ProgramConfig {
+    pub fn record_admin_activity(&mut self, now: i64) {
+        self.last_admin_activity = now;
+    }
 
-    /// Initialize the presale state. Sets the admin to the specified devnet wallet.
-    pub fn initialize_token(ctx: Context<InitializeToken>) -> ProgramResult {
-        let presale_state = &mut ctx.accounts.presale_state;
-        presale_state.is_presale_active = true;
-        presale_state.presale_end_time = None;
-        presale_state.launch_time = None;
-        // Set the admin/owner to the specified devnet wallet
-        presale_state.admin = Pubkey::from_str("57EMXJXJkGYNCGjr9ngZPKnJr9jdJPZ1SRr9jdJPZ1SRr9tr").unwrap();
-        presale_state.liquidity_locked = false;
-        presale_state.liquidity_lock_end_time = None;
+    /// Fail if the subsystem `operation` (a `PAUSE_*` bit) is paused.
+    pub fn check_not_paused(&self, operation: u8) -> Result<()> {
+        require!(self.paused_operations & operation == 0, ErrorCode::OperationPaused);
         Ok(())
     }
 
-    /// Initialize the global state with initial parameters.
-    pub fn initialize_global_state(
-        ctx: Context<InitializeGlobalState>,
-        apy: u64,
-        transaction_fee_percent: u64,
-    ) -> ProgramResult {
-        let global_state = &mut ctx.accounts.global_state;
-        global_state.total_staked = 0;
-        global_state.reward_pool = 0;
-        global_state.apy = apy;
-        global_state.transaction_fee_percent = transaction_fee_percent;
+    /// Launch guard for a payout of `amount` leaving the recipient with `balance_after`
+    /// tokens: no payout before `trading_opens_at`, then the anti-whale limits within
+    /// `launch_protection_period` of launch. Presale purchases
+    /// are not checked: they only book allocations, and `end_presale` sets the launch time
+    /// in the same instruction that closes them, so none can fall inside the window. The
+    /// tokens reach wallets through the claim instructions, which do check.
+    pub fn check_launch_limits(
+        &self,
+        launch_time: i64,
+        now: i64,
+        amount: u64,
+        balance_after: u64,
+        supply: u64,
+    ) -> Result<()> {
+        require!(now >= self.trading_opens_at, ErrorCode::TradingNotOpen);
+        if now >= launch_time.saturating_add(self.launch_protection_period) {
+            return Ok(());
+        }
+        require!(
+            self.max_tx_amount == 0 || amount <= self.max_tx_amount,
+            ErrorCode::MaxTransactionExceeded
+        );
+        require!(
+            self.max_wallet_bps == 0
+                || balance_after as u128 * 10_000 <= supply as u128 * self.max_wallet_bps as u128,
+            ErrorCode::MaxWalletExceeded
+        );
         Ok(())
     }
 
-    /// End the presale and mark the launch time.
-    /// After this, staking is disabled.
-    pub fn end_presale(ctx: Context<EndPresale>) -> ProgramResult {
-        let presale_state = &mut ctx.accounts.presale_state;
-        require!(presale_state.is_presale_active, ErrorCode::PresaleAlreadyEnded);
+    /// Launch tax in effect at `now`, in bps: `launch_tax_bps` when the presale opens, decaying
+    /// linearly to 0 over `launch_tax_duration`.
+    pub fn current_launch_tax_bps(&self, now: i64) -> u64 {
+        let elapsed = now.saturating_sub(self.presale_opens_at).max(0);
+        if self.launch_tax_bps == 0 || elapsed >= self.launch_tax_duration {
+            return 0;
+        }
+        let remaining = (self.launch_tax_duration - elapsed) as u128;
+        (self.launch_tax_bps as u128 * remaining / self.launch_tax_duration as u128) as u64
+    }
+
+    /// SOL/USD price from the configured price feed, with 8 decimals; 0 while no feed is set.
+    pub fn sol_usd_price(&self, price_feed_info: &AccountInfo, now: i64) -> Result<u64> {
+        if self.sol_usd_price_feed == Pubkey::default() {
+            return Ok(0);
+        }
         require!(
-            ctx.accounts.admin.key() == presale_state.admin,
-            ErrorCode::Unauthorized
+            price_feed_info.key() == self.sol_usd_price_feed,
+            ErrorCode::InvalidPriceFeed
         );
-        let clock = Clock::get()?;
-        presale_state.is_presale_active = false;
-        presale_state.presale_end_time = Some(clock.unix_timestamp);
-        presale_state.launch_time = Some(clock.unix_timestamp);
-        presale_state.liquidity_lock_end_time =
-            Some(clock.unix_timestamp + LIQUIDITY_LOCK_PERIOD);
-        Ok(())
+        let price_feed = SolanaPriceAccount::account_info_to_feed(price_feed_info)
+            .map_err(|_| ErrorCode::InvalidPriceFeed)?;
+        let price = price_feed
+            .get_price_no_older_than(now, self.max_price_age as u64)
+            .ok_or(ErrorCode::StalePrice)?;
+        require!(price.price > 0, ErrorCode::InvalidPriceFeed);
+        // Rescale from 10^expo to 8 decimals.
+        let exponent = price.expo + 8;
+        let scaled = if exponent >= 0 {
+            (price.price as u64).checked_mul(10u64.pow(exponent as u32)).unwrap()
+        } else {
+            price.price as u64 / 10u64.pow(exponent.unsigned_abs())
+        };
+        Ok(scaled)
     }
 
-    /// Accept payment in either SOL or our custom SPL token.
-    /// A flat fee of 3 (units) is deducted and sent to the fee wallet.
-    /// The remaining amount is transferred to the treasury.
-    pub fn accept_payment(
-        ctx: Context<AcceptPayment>,
-        amount: u64,
-        token_mint: Pubkey,
-    ) -> ProgramResult {
-        // Check that the fee wallet accounts are set to the correct devnet fee wallet.
-        let fee_wallet_pubkey = Pubkey::from_str(FEE_WALLET).unwrap();
+    /// Anti-sybil hook for purchases. When enabled, the buyer must have an attestation from the
+    /// configured attestor showing transaction history and a first-seen slot before the current one.
+    pub fn check_purchase_attestation(
+        &self,
+        buyer: Pubkey,
+        attestation_info: &AccountInfo,
+        slot: u64,
+    ) -> Result<()> {
+        if !self.sybil_check_enabled {
+            return Ok(());
+        }
+        let attestation: Attestation = load_account(attestation_info)
+            .map_err(|_| ErrorCode::MissingAttestation)?;
         require!(
-            ctx.accounts.fee_wallet_sol_account.key == fee_wallet_pubkey,
-            ErrorCode::InvalidFeeWallet
+            attestation.wallet == buyer && attestation.attestor == self.attestor,
+            ErrorCode::MissingAttestation
         );
         require!(
-            ctx.accounts.fee_wallet_token_account.owner == fee_wallet_pubkey,
-            ErrorCode::InvalidFeeWallet
+            attestation.has_history && attestation.first_seen_slot < slot,
+            ErrorCode::SybilCheckFailed
         );
+        Ok(())
+    }
 
-        if token_mint == Pubkey::default() {
-            // SOL branch.
-            // Ensure the amount is greater than the flat fee of 3.
-            require!(amount > 3, ErrorCode::InvalidAmount);
-            let fee = 3;
-            let net_amount = amount.checked_sub(fee).unwrap();
-
-            // Transfer net_amount from payer to treasury (SOL)
-            let ix1 = system_instruction::transfer(
-                &ctx.accounts.payer.key,
-                ctx.accounts.treasury_sol_account.key,
-                net_amount,
-            );
-            solana_program::program::invoke(
-                &ix1,
-                &[
-                    ctx.accounts.payer.to_account_info(),
-                    ctx.accounts.treasury_sol_account.clone(),
-                    ctx.accounts.system_program.to_account_info(),
-                ],
-            )?;
-
-            // Transfer fee from payer to fee wallet (SOL)
-            let ix2 = system_instruction::transfer(
-                &ctx.accounts.payer.key,
-                ctx.accounts.fee_wallet_sol_account.key,
-                fee,
-            );
-            solana_program::program::invoke(
-                &ix2,
-                &[
-                    ctx.accounts.payer.to_account_info(),
-                    ctx.accounts.fee_wallet_sol_account.clone(),
-                    ctx.accounts.system_program.to_account_info(),
-                ],
-            )?;
-        } else if token_mint == Pubkey::from_str(CUSTOM_TOKEN_MINT).unwrap() {
-            // SPL branch for our custom token.
-            require!(
-                ctx.accounts.payer_token_account.amount >= amount,
-                ErrorCode::InsufficientFunds
-            );
-            let fee = 3;
-            let net_amount = amount.checked_sub(fee).unwrap();
+    /// Apply the staker fee discount to `fee` if the payer's `StakerRecord` shows at least
+    /// `fee_discount_stake_threshold` tokens staked. A missing record means no discount.
+    pub fn discounted_fee(&self, fee: u64, payer: Pubkey, staker_record_info: &AccountInfo) -> u64 {
+        if self.fee_discount_bps == 0 {
+            return fee;
+        }
+        let staker_record: StakerRecord = match load_account(staker_record_info) {
+            Ok(staker_record) => staker_record,
+            Err(_) => return fee,
+        };
+        if staker_record.owner != payer
+            || staker_record.total_staked < self.fee_discount_stake_threshold
+        {
+            return fee;
+        }
+        let discount = (fee as u128) * (self.fee_discount_bps as u128) / 10_000;
+        fee - discount as u64
+    }
 
-            // Transfer net_amount from payer to treasury (SPL)
-            token::transfer(
-                ctx.accounts.stake_transfer_context_generic(
-                    ctx.accounts.payer_token_account.to_account_info(),
-                    ctx.accounts.treasury_token_account.to_account_info(),
-                ),
-                net_amount,
-            )?;
-            // Transfer fee from payer to fee wallet (SPL)
-            token::transfer(
-                ctx.accounts.stake_transfer_context_generic(
-                    ctx.accounts.payer_token_account.to_account_info(),
-                    ctx.accounts.fee_wallet_token_account.to_account_info(),
-                ),
-                fee,
-            )?;
-        } else {
-            return Err(ErrorCode::InvalidTokenMint.into());
+    /// Treasury withdrawals above `cosign_threshold` must also be signed by the configured co-signer.
+    pub fn check_withdrawal_cosigner(&self, amount: u64, cosigner: &AccountInfo) -> Result<()> {
+        if amount <= self.cosign_threshold {
+            return Ok(());
         }
+        require!(
+            cosigner.is_signer && cosigner.key() == self.withdrawal_cosigner,
+            ErrorCode::CosignerRequired
+        );
         Ok(())
     }
 
-    /// Deposit SOL into the treasury.
-    /// This is a dedicated deposit instruction for SOL.
-    pub fn deposit_sol(ctx: Context<DepositSol>, amount: u64) -> ProgramResult {
-        let ix = system_instruction::transfer(
-            &ctx.accounts.payer.key,
-            ctx.accounts.treasury_sol_account.key,
-            amount,
-        );
-        solana_program::program::invoke(
-            &ix,
-            &[
-                ctx.accounts.payer.to_account_info(),
-                ctx.accounts.treasury_sol_account.to_account_info(),
-                ctx.accounts.system_program.to_account_info(),
-            ],
-        )?;
+    /// Count `amount` against the per-epoch burn cap, resetting the counter on a new epoch.
+    pub fn record_burn(&mut self, amount: u64, epoch: u64) -> Result<()> {
+        if epoch != self.burn_epoch {
+            self.burn_epoch = epoch;
+            self.burned_this_epoch = 0;
+        }
+        let burned = self.burned_this_epoch.checked_add(amount).unwrap();
+        require!(burned <= self.burn_cap_per_epoch, ErrorCode::BurnCapExceeded);
+        self.burned_this_epoch = burned;
         Ok(())
     }
+}
 
-    /// Stake tokens during the presale.
-    /// Staking is allowed only while the presale is active and if rewards are available.
-    pub fn stake_tokens(ctx: Context<StakeTokens>, amount: u64) -> ProgramResult {
-        // Allow staking only if presale is active.
-        require!(
-            ctx.accounts.presale_state.is_presale_active,
-            ErrorCode::StakingClosed
-        );
-        // Also, ensure the reward pool is not empty.
-        require!(
-            ctx.accounts.global_state.reward_pool > 0,
-            ErrorCode::StakingRewardsExhausted
-        );
-        require!(amount > 0, ErrorCode::InvalidAmount);
+#[account]
+pub struct GlobalState {
+    pub total_staked: u64,                  // Total staked $BRATS tokens across all users
+    pub reward_pool: u64,                   // Reward pool (in tokens) for stakers
+    pub apy: u64,                           // Annual percentage yield (mutable via governance)
+    pub transaction_fee_percent: u64,       // Transaction fee percent (mutable via governance)
+    pub unique_stakers: u64,                // Wallets with at least one open stake position
+    pub total_positions: u64,               // Open stake positions across all wallets
+    pub total_rewards_expired: u64,         // Unclaimed rewards forfeited back to the pool by the expiry crank
+    pub reward_inflow_admin: u64,           // Tokens added to the reward pool by admin refills
+    pub reward_inflow_treasury: u64,        // Tokens added to the reward pool by the treasury refill crank
+    pub reward_inflow_fees: u64,            // Tokens added to the reward pool from fee shares
+    pub reward_inflow_penalties: u64,       // Tokens added to the reward pool from early-unstake penalties
+    pub early_unstake_penalty_percent: u64, // Penalty on early unstakes (mutable via governance)
+    pub penalty_waiver_until: i64,          // Early unstakes before this time pay no penalty (set via governance)
+    pub acc_reward_per_share: u128,         // Rewards accrued per staked token, scaled by REWARD_PER_TOKEN_SCALE
+    pub last_reward_update: i64,            // Timestamp `acc_reward_per_share` was last advanced to
+    pub apy_curve_enabled: bool,            // Derive the APY from `total_staked` instead of `apy`
+    pub apy_curve_max: u64,                 // Curve APY with nothing staked
+    pub apy_curve_min: u64,                 // Curve APY at or above the target TVL
+    pub apy_curve_target_tvl: u64,          // Staked tokens at which the curve reaches its minimum
+    pub penalty_redistribute_percent: u64,  // Penalty share paid into the reward pool, rest burned (mutable via governance)
+    pub min_launch_liquidity_percent: u64,  // Launch liquidity required, in % of SOL raised (set via governance)
+}
 
-        let stake_info = &mut ctx.accounts.stake_info;
-        let global_state = &mut ctx.accounts.global_state;
-        stake_info.amount = stake_info.amount.checked_add(amount).unwrap();
-        global_state.total_staked = global_state.total_staked.checked_add(amount).unwrap();
-        let clock = Clock::get()?;
-        stake_info.start_time = clock.unix_timestamp;
-        stake_info.last_claim_time = clock.unix_timestamp;
+/// Where a reward pool inflow came from, for `GlobalState` accounting.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum RewardSource {
+    AdminRefill,
+    TreasuryRefill,
+    FeeShare,
+    Penalty,
+}
 
-        // Transfer tokens from the user's account to the staking pool.
-        token::transfer(
-            ctx.accounts.stake_transfer_context(),
-            amount,
-        )?;
-        Ok(())
+impl GlobalState {
+    /// Record a newly opened stake position for the given wallet.
+    pub fn open_position(&mut self, staker_record: &mut StakerRecord) {
+        if staker_record.open_positions == 0 {
+            self.unique_stakers = self.unique_stakers.checked_add(1).unwrap();
+        }
+        staker_record.open_positions = staker_record.open_positions.checked_add(1).unwrap();
+        self.total_positions = self.total_positions.checked_add(1).unwrap();
     }
 
-    /// Unstake tokens.
-    /// If the full staking duration has been met, the full stake is returned.
-    /// Otherwise, if early unstaking is used (allowed only after 7 days from launch),
-    /// a 20% penalty is applied: the user receives (100 - penalty)% of their staked tokens
-    /// and the penalty portion is burned.
-    pub fn unstake_tokens(ctx: Context<UnstakeTokens>) -> ProgramResult {
-        let stake_info = &mut ctx.accounts.stake_info;
-        let global_state = &mut ctx.accounts.global_state;
-        let clock = Clock::get()?;
-        let staking_duration = clock.unix_timestamp - stake_info.start_time;
+    /// Record a closed (fully unstaked) stake position for the given wallet.
+    pub fn close_position(&mut self, staker_record: &mut StakerRecord) {
+        staker_record.open_positions = staker_record.open_positions.checked_sub(1).unwrap();
+        if staker_record.open_positions == 0 {
+            self.unique_stakers = self.unique_stakers.checked_sub(1).unwrap();
+        }
+        self.total_positions = self.total_positions.checked_sub(1).unwrap();
+    }
 
-        // Check that early unstaking is allowed (7 days after launch)
-        if let Some(launch_time) = ctx.accounts.presale_state.launch_time {
-            if clock.unix_timestamp < launch_time + EARLY_UNSTAKE_PERIOD {
-                return Err(ErrorCode::UnstakingNotAllowedBefore7Days.into());
-            }
+    /// Add `amount` to the reward pool and to the counter for its source.
+    pub fn credit_reward_pool(&mut self, amount: u64, source: RewardSource) {
+        self.reward_pool = self.reward_pool.checked_add(amount).unwrap();
+        let counter = match source {
+            RewardSource::AdminRefill => &mut self.reward_inflow_admin,
+            RewardSource::TreasuryRefill => &mut self.reward_inflow_treasury,
+            RewardSource::FeeShare => &mut self.reward_inflow_fees,
+            RewardSource::Penalty => &mut self.reward_inflow_penalties,
+        };
+        *counter = counter.checked_add(amount).unwrap();
+    }
+
+    /// APY on the utilization curve at the current `total_staked`: falls linearly from
+    /// `apy_curve_max` with nothing staked to `apy_curve_min` at the target TVL.
+    pub fn curve_apy(&self) -> u64 {
+        if self.total_staked >= self.apy_curve_target_tvl {
+            return self.apy_curve_min;
         }
+        let decay = (self.apy_curve_max - self.apy_curve_min) as u128 * self.total_staked as u128
+            / self.apy_curve_target_tvl as u128;
+        self.apy_curve_max - decay as u64
+    }
 
-        require!(stake_info.amount > 0, ErrorCode::InvalidAmount);
-        if staking_duration >= STAKING_DURATION {
-            // Full staking period complete: return full staked amount.
-            let unstake_amount = stake_info.amount;
-            global_state.total_staked = global_state.total_staked.checked_sub(unstake_amount).unwrap();
-            stake_info.amount = 0;
-            token::transfer(ctx.accounts.unstake_transfer_context(), unstake_amount)?;
+    /// APY in effect at `now`: the curve APY in curve mode, otherwise `apy` following any ramp,
+    /// scaled down by the emission schedule.
+    pub fn current_apy(
+        &self,
+        param_ramp: &ParamRamp,
+        emission_schedule: &EmissionSchedule,
+        now: i64,
+    ) -> u64 {
+        let apy = if self.apy_curve_enabled {
+            self.curve_apy()
         } else {
-            // Early unstake: apply penalty.
-            let penalty_amount = stake_info
-                .amount
-                .checked_mul(EARLY_UNSTAKE_PENALTY_PERCENT)
-                .unwrap()
-                .checked_div(100)
-                .unwrap();
-            let unstake_amount = stake_info.amount.checked_sub(penalty_amount).unwrap();
-            global_state.total_staked = global_state.total_staked.checked_sub(stake_info.amount).unwrap();
-            stake_info.amount = 0;
-            // Return the remaining tokens to the user.
-            token::transfer(ctx.accounts.unstake_transfer_context(), unstake_amount)?;
-            // Burn the penalty tokens.
-            token::burn(ctx.accounts.early_unstake_burn_context(), penalty_amount)?;
-        }
-        Ok(())
+            param_ramp.current_apy(self.apy, now)
+        };
+        (apy as u128 * emission_schedule.multiplier_bps(now) / 10_000) as u64
     }
 
-    /// Lock liquidity by transferring liquidity tokens to a vault.
-    /// This function should be called (by admin or automatically) while liquidity is still locked.
-    pub fn lock_liquidity(ctx: Context<LockLiquidity>) -> ProgramResult {
-        let clock = Clock::get()?;
-        let presale_state = &mut ctx.accounts.presale_state;
-        if let Some(lock_end) = presale_state.liquidity_lock_end_time {
-            if clock.unix_timestamp < lock_end {
-                let amount = ctx.accounts.liquidity_token_account.amount;
-                require!(amount > 0, ErrorCode::InvalidAmount);
-                token::transfer(
-                    ctx.accounts.liquidity_lock_transfer_context(),
-                    amount,
-                )?;
-                presale_state.liquidity_locked = true;
-                return Ok(());
+    /// Integral of the undecayed APY between `from` and `to` (APY × seconds). In curve mode
+    /// `total_staked` is constant over the period, since rewards are updated before every
+    /// amount change.
+    fn apy_seconds(&self, param_ramp: &ParamRamp, from: i64, to: i64) -> u128 {
+        if self.apy_curve_enabled {
+            if to <= from {
+                return 0;
             }
+            return self.curve_apy() as u128 * (to - from) as u128;
         }
-        Err(ErrorCode::LiquidityLockError.into())
+        param_ramp.apy_seconds(self.apy, from, to)
     }
 
-    /// Claim staking rewards.
-    /// Rewards are calculated based on the staked amount, the time since the last claim,
-    /// and the current APY stored in GlobalState.
-    pub fn claim_rewards(ctx: Context<ClaimRewards>) -> ProgramResult {
-        let stake_info = &mut ctx.accounts.stake_info;
-        let global_state = &mut ctx.accounts.global_state;
-        let clock = Clock::get()?;
-        let staking_time = clock.unix_timestamp - stake_info.last_claim_time;
-        require!(staking_time > 0, ErrorCode::NoRewardsAvailable);
+    /// Rewards accrued per staked token between `from` and `to` at the APY in effect (following
+    /// any active ramp and the emission decay), scaled by `REWARD_PER_TOKEN_SCALE`.
+    pub fn reward_per_share_between(
+        &self,
+        param_ramp: &ParamRamp,
+        emission_schedule: &EmissionSchedule,
+        from: i64,
+        to: i64,
+    ) -> u128 {
+        if to <= from {
+            return 0;
+        }
+        // The APY is affine between the ramp's ends, so each piece is integrated in closed form.
+        let mut breaks = [to; 2];
+        if !self.apy_curve_enabled && param_ramp.is_active {
+            breaks = [param_ramp.start_time, param_ramp.end_time];
+        }
+        let mut weighted_apy_seconds: u128 = 0;
+        let mut start = from;
+        for end in breaks.into_iter().chain([to]) {
+            let end = end.clamp(start, to);
+            let piece = emission_schedule
+                .decayed_apy_seconds(start, end, |a, b| self.apy_seconds(param_ramp, a, b));
+            weighted_apy_seconds = weighted_apy_seconds.checked_add(piece).unwrap();
+            start = end;
+        }
+        weighted_apy_seconds
+            / (100 * STAKING_DURATION as u128 * (EMISSION_SCALE / REWARD_PER_TOKEN_SCALE))
+    }
 
-        let reward_amount = (stake_info.amount)
-            .checked_mul(global_state.apy)
-            .unwrap()
-            .checked_mul(staking_time as u64)
+    /// `acc_reward_per_share` as of `now`, without writing it back.
+    pub fn current_reward_per_share(
+        &self,
+        param_ramp: &ParamRamp,
+        emission_schedule: &EmissionSchedule,
+        now: i64,
+    ) -> u128 {
+        if now <= self.last_reward_update {
+            return self.acc_reward_per_share;
+        }
+        self.acc_reward_per_share
+            .checked_add(self.reward_per_share_between(
+                param_ramp,
+                emission_schedule,
+                self.last_reward_update,
+                now,
+            ))
             .unwrap()
-            .checked_div(100 * STAKING_DURATION as u64)
-            .unwrap();
-
-        require!(
-            ctx.accounts.reward_pool_token_account.amount >= reward_amount,
-            ErrorCode::InsufficientRewards
-        );
+    }
 
-        global_state.reward_pool = global_state.reward_pool.checked_sub(reward_amount).unwrap();
-        token::transfer(ctx.accounts.reward_transfer_context(), reward_amount)?;
-        stake_info.last_claim_time = clock.unix_timestamp;
-        Ok(())
+    /// Advance `acc_reward_per_share` to `now`. Must run before any position's amount changes
+    /// and before the APY, the ramp or the emission schedule changes, so earlier periods keep
+    /// the rate they accrued at.
+    pub fn update_rewards(
+        &mut self,
+        param_ramp: &ParamRamp,
+        emission_schedule: &EmissionSchedule,
+        now: i64,
+    ) -> u128 {
+        self.acc_reward_per_share =
+            self.current_reward_per_share(param_ramp, emission_schedule, now);
+        self.last_reward_update = self.last_reward_update.max(now);
+        self.acc_reward_per_share
     }
 
-    /// Calculate rewards for display (off‑chain) without transferring tokens.
-    pub fn calculate_rewards(ctx: Context<CalculateRewards>) -> Result<u64> {
-        let stake_info = &ctx.accounts.stake_info;
-        let clock = Clock::get()?;
-        let staking_time = clock.unix_timestamp - stake_info.last_claim_time;
-        require!(staking_time > 0, ErrorCode::NoRewardsAvailable);
-        let reward_amount = (stake_info.amount)
-            .checked_mul(ctx.accounts.global_state.apy)
-            .unwrap()
-            .checked_mul(staking_time as u64)
-            .unwrap()
-            .checked_div(100 * STAKING_DURATION as u64)
-            .unwrap();
-        Ok(reward_amount)
+    /// Average staked amount per open position (0 if there are none).
+    pub fn average_position_size(&self) -> u64 {
+        if self.total_positions == 0 {
+            return 0;
+        }
+        self.total_staked / self.total_positions
     }
+}
 
-    /// Burn tokens from a source account. (Admin only)
-    pub fn burn_tokens(ctx: Context<BurnTokens>, amount: u64) -> ProgramResult {
-        require!(
-            ctx.accounts.admin.key() == ctx.accounts.presale_state.admin,
-            ErrorCode::Unauthorized
-        );
-        token::burn(ctx.accounts.burn_context(), amount)?;
-        Ok(())
+/// Per-wallet staking record, used to know when a wallet opens its first
+/// position or closes its last one.
+#[account]
+pub struct StakerRecord {
+    pub owner: Pubkey,       // Wallet this record belongs to
+    pub open_positions: u64, // Number of open stake positions owned by the wallet
+    pub total_staked: u64,   // Tokens staked across all of the wallet's positions
+    pub votes_cast: u64,     // Votes the wallet's positions have cast on proposals
+}
+
+/// Per-wallet index of the wallet's program accounts, so clients can find them with one fetch.
+/// Created by `initialize_position_manager`, then kept up to date by the instructions that
+/// open or close those accounts.
+#[account]
+pub struct PositionManager {
+    pub owner: Pubkey,
+    pub stake_positions: [Pubkey; POSITION_MANAGER_SLOTS], // Open stake positions (default = free)
+    pub stake_position_count: u64,                         // Open stake positions, listed or not
+    pub receipts: [Pubkey; POSITION_MANAGER_SLOTS],        // First purchase receipts, oldest first
+    pub receipt_count: u64,                                // Purchase receipts, listed or not
+    pub vesting_schedule: Pubkey,                          // Vesting schedule (default = none)
+    pub referral_account: Pubkey,                          // Referral account (default = none)
+}
+
+impl PositionManager {
+    /// Apply `update` to the manager in `manager_info`, if the wallet has created one.
+    pub fn update(
+        manager_info: &AccountInfo,
+        update: impl FnOnce(&mut PositionManager),
+    ) -> Result<()> {
+        if manager_info.data_is_empty() {
+            return Ok(());
+        }
+        let mut manager: PositionManager = load_account(manager_info)?;
+        update(&mut manager);
+        store_account(manager_info, &manager)
     }
 
-    /// Refill the reward pool by transferring tokens into the reward pool account. (Admin only)
-    pub fn refill_reward_pool(ctx: Context<RefillRewardPool>, amount: u64) -> ProgramResult {
-        require!(
-            ctx.accounts.admin.key() == ctx.accounts.presale_state.admin,
-            ErrorCode::Unauthorized
-        );
-        token::transfer(ctx.accounts.refill_transfer_context(), amount)?;
-        ctx.accounts.global_state.reward_pool = ctx
-            .accounts
-            .global_state
-            .reward_pool
-            .checked_add(amount)
-            .unwrap();
-        Ok(())
+    pub fn add_stake_position(&mut self, position: Pubkey) {
+        self.stake_position_count = self.stake_position_count.checked_add(1).unwrap();
+        let free_slot = self.stake_positions.iter_mut().find(|slot| **slot == Pubkey::default());
+        if let Some(slot) = free_slot {
+            *slot = position;
+        }
     }
 
-    /// Update APY and transaction fee percent. (Admin only)
-    pub fn update_parameters(
+    pub fn remove_stake_position(&mut self, position: Pubkey) {
+        self.stake_position_count = self.stake_position_count.saturating_sub(1);
+        if let Some(slot) = self.stake_positions.iter_mut().find(|slot| **slot == position) {
+            *slot = Pubkey::default();
+        }
+    }
+
+    pub fn add_receipt(&mut self, receipt: Pubkey) {
+        if let Some(slot) = self.receipts.get_mut(self.receipt_count as usize) {
+            *slot = receipt;
+        }
+        self.receipt_count = self.receipt_count.checked_add(1).unwrap();
+    }
+}
+
+/// Staking limits.
+#[account]
+pub struct StakingConfig {
+    pub max_stake_per_wallet: u64,       // Max tokens a wallet may have staked across all positions (0 = no cap)
+    pub reward_expiry_enabled: bool,     // Whether unclaimed rewards can expire
+    pub reward_expiry_grace_period: i64, // Seconds of inactivity after which accrued rewards expire
+    pub apy_controller_enabled: bool,    // Let the per-epoch crank set the APY from the pool runway
+    pub apy_controller_min: u64,         // Lowest APY the controller may set
+    pub apy_controller_max: u64,         // Highest APY the controller may set
+    pub target_runway: i64,              // Seconds the reward pool should last at the current APY
+    pub apy_adjusted_epoch: u64,         // Epoch of the last controller adjustment
+    pub unstake_throttle_bps: u16,       // Max bps of `total_staked` unstaked per window (0 = off; set via governance)
+    pub unstake_queue_window: i64,       // Last window (index since the epoch) with reserved unstake capacity
+    pub unstake_queue_reserved: u64,     // Tokens reserved in `unstake_queue_window`
+    pub unstake_queue_length: u64,       // Queue positions handed out so far
+    pub unstake_cooldown: i64,           // Seconds before requested unstakes can be withdrawn
+    pub restake_bonus_bps: u16,          // Bonus on rewards locked by `restake_rewards`, in bps
+    pub restake_lock_period: i64,        // Seconds a `restake_rewards` position stays locked
+}
+
+impl StakingConfig {
+    /// Reserve `amount` of unstake throughput, in the earliest window at or after `now` that
+    /// has capacity left; amounts above one window's capacity span several windows.
+    /// Returns the window from which the reservation may be withdrawn.
+    pub fn reserve_unstake(&mut self, amount: u64, total_staked: u64, now: i64) -> i64 {
+        let capacity = ((total_staked as u128) * (self.unstake_throttle_bps as u128) / 10_000)
+            .max(1) as u64;
+        let current_window = now / UNSTAKE_THROTTLE_WINDOW;
+        if self.unstake_queue_window < current_window {
+            self.unstake_queue_window = current_window;
+            self.unstake_queue_reserved = 0;
+        }
+        if self.unstake_queue_reserved > 0
+            && self.unstake_queue_reserved.checked_add(amount).unwrap() > capacity
+        {
+            self.unstake_queue_window += 1;
+            self.unstake_queue_reserved = 0;
+        }
+        let reserved = self.unstake_queue_reserved.checked_add(amount).unwrap();
+        let extra_windows = (reserved - 1) / capacity;
+        self.unstake_queue_window += extra_windows as i64;
+        self.unstake_queue_reserved = reserved - extra_windows * capacity;
+        self.unstake_queue_window
+    }
+
+    /// Give back a queued reservation of `amount` that ends in `window`, for a position that
+    /// leaves the queue without unstaking through it. Only the last window's share can be
+    /// returned; capacity in earlier windows a large reservation spanned stays used.
+    pub fn release_unstake(&mut self, amount: u64, window: i64) {
+        if window == self.unstake_queue_window {
+            self.unstake_queue_reserved = self.unstake_queue_reserved.saturating_sub(amount);
+        }
+    }
+
+    /// Highest APY at which `reward_pool` lasts `target_runway` with `total_staked` tokens
+    /// staked, clamped to the controller bounds.
+    pub fn runway_apy(&self, total_staked: u64, reward_pool: u64) -> u64 {
+        if total_staked == 0 {
+            return self.apy_controller_max;
+        }
+        // Rewards accrue at `apy / (100 * STAKING_DURATION)` per token per second.
+        let apy = (reward_pool as u128) * 100 * (STAKING_DURATION as u128)
+            / ((total_staked as u128) * (self.target_runway as u128));
+        (apy.min(u64::MAX as u128) as u64)
+            .max(self.apy_controller_min)
+            .min(self.apy_controller_max)
+    }
+}
+
+/// Standardized description of a stake position for lending protocols pricing it as
+/// collateral (amount, unlock timestamp, APY), returned by `get_stake_collateral_metadata`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct StakeCollateralMetadata {
+    pub position: Pubkey,      // StakeInfo account address
+    pub owner: Pubkey,         // Position owner
+    pub amount: u64,           // Staked amount
+    pub unlock_timestamp: i64, // When the position can be unstaked without penalty
+    pub apy: u64,              // APY currently applied to the position
+}
+
+/// Reward estimate published by `calculate_rewards` via `set_return_data`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct RewardsEstimate {
+    pub rewards: u64,      // Rewards claimable now
+    pub apy: u64,          // APY currently applied to the position
+    pub elapsed: i64,      // Seconds since the last claim
+    pub pool_covers: bool, // Whether the reward pool can pay `rewards` in full
+}
+
+/// Price of one whole token in the current presale stage, in every payment currency,
+/// returned by `get_stage_prices`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct StagePrices {
+    pub stage: u8,     // 1-based stage currently selling
+    pub lamports: u64, // SOL price, in lamports
+    pub usdc: u64,     // USDC price, in USDC base units
+    pub usdt: u64,     // USDT price, in USDT base units
+}
+
+/// Protocol health metrics returned by `get_global_stats`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct GlobalStats {
+    pub total_staked: u64,
+    pub reward_pool: u64,
+    pub unique_stakers: u64,
+    pub total_positions: u64,
+    pub average_position_size: u64,
+    pub reward_inflow_admin: u64,
+    pub reward_inflow_treasury: u64,
+    pub reward_inflow_fees: u64,
+    pub reward_inflow_penalties: u64,
+}
+
+/// Cumulative totals for dashboards. Current values (TVL, unique stakers, open positions) are
+/// in `GlobalState`; these only grow, and are updated by the instructions that move them once
+/// the account has been created.
+#[account]
+pub struct Stats {
+    pub total_stakers: u64,             // Wallets that have ever staked
+    pub total_rewards_distributed: u64, // Rewards of every kind paid out, compounded or restaked
+    pub total_penalties_burned: u64,    // Early-unstake penalty tokens burned
+    pub total_tokens_burned: u64,       // Tokens burned with `burn_tokens`
+    pub total_sol_raised: u64,          // Lamports paid for presale tokens
+    pub total_usd_raised: u64,          // USD paid for presale tokens in stablecoins, 9 decimals
+    pub total_buyback_sol: u64,         // Treasury lamports spent by `buyback_and_burn`
+    pub total_buyback_burned: u64,      // Tokens bought back and burned by `buyback_and_burn`
+    pub last_updated: i64,
+}
+
+impl Stats {
+    /// Apply `update` to the stats in `stats_info`, if they have been created.
+    pub fn update(
+        stats_info: &AccountInfo,
+        now: i64,
+        update: impl FnOnce(&mut Stats),
+    ) -> Result<()> {
+        if stats_info.data_is_empty() {
+            return Ok(());
+        }
+        let mut stats: Stats = load_account(stats_info)?;
+        update(&mut stats);
+        stats.last_updated = now;
+        store_account(stats_info, &stats)
+    }
+}
+
+/// Pre-committed burns from the burn reserve vault, run by a permissionless crank. Tokens in
+/// the reserve can only leave it by being burned.
+#[account]
+pub struct BurnSchedule {
+    pub interval: i64,       // Seconds between scheduled burns
+    pub amount: u64,         // Tokens burned per run when `supply_bps` is 0
+    pub supply_bps: u16,     // Share of the current mint supply burned per run (0 = use `amount`)
+    pub next_burn_ts: i64,   // Earliest time the next run may execute
+    pub total_burned: u64,   // Tokens burned by the schedule so far
+    pub burns_executed: u64, // Runs executed so far
+}
+
+impl BurnSchedule {
+    /// Tokens the next run burns for a mint supply of `supply`, before capping at the reserve.
+    pub fn burn_amount(&self, supply: u64) -> u64 {
+        if self.supply_bps > 0 {
+            (supply as u128 * self.supply_bps as u128 / 10_000) as u64
+        } else {
+            self.amount
+        }
+    }
+
+    /// Move `next_burn_ts` to the first slot of the schedule after `now`; runs missed while
+    /// nobody cranked are skipped rather than burned in a burst.
+    pub fn advance(&mut self, now: i64) {
+        let missed = (now - self.next_burn_ts) / self.interval;
+        self.next_burn_ts = self
+            .next_burn_ts
+            .checked_add(self.interval.checked_mul(missed + 1).unwrap())
+            .unwrap();
+    }
+}
+
+/// A scheduled gradual APY transition (e.g. 43% -> 20% over 90 days).
+/// While active it overrides `GlobalState::apy` in reward math: `start_apy` applies before
+/// `start_time`, the APY moves linearly until `end_time`, and `end_apy` applies afterwards.
+#[account]
+pub struct ParamRamp {
+    pub is_active: bool,
+    pub start_apy: u64,
+    pub end_apy: u64,
+    pub start_time: i64,
+    pub end_time: i64,
+}
+
+impl ParamRamp {
+    /// APY in effect at `now`.
+    pub fn current_apy(&self, base_apy: u64, now: i64) -> u64 {
+        if !self.is_active {
+            return base_apy;
+        }
+        if now <= self.start_time {
+            return self.start_apy;
+        }
+        if now >= self.end_time {
+            return self.end_apy;
+        }
+        let elapsed = (now - self.start_time) as i128;
+        let duration = (self.end_time - self.start_time) as i128;
+        let delta = self.end_apy as i128 - self.start_apy as i128;
+        (self.start_apy as i128 + delta * elapsed / duration) as u64
+    }
+
+    /// Integral of the APY curve between `from` and `to` (APY × seconds).
+    /// Reward math divides this by the staking period instead of multiplying a single APY by time,
+    /// so a claim spanning the ramp is paid exactly along the curve.
+    pub fn apy_seconds(&self, base_apy: u64, from: i64, to: i64) -> u128 {
+        if to <= from {
+            return 0;
+        }
+        if !self.is_active {
+            return base_apy as u128 * (to - from) as u128;
+        }
+        let mut total: i128 = 0;
+        // Before the ramp starts: flat start_apy.
+        let before_end = to.min(self.start_time);
+        if before_end > from {
+            total += self.start_apy as i128 * (before_end - from) as i128;
+        }
+        // During the ramp: area of the trapezoid under the linear segment.
+        let seg_start = from.max(self.start_time);
+        let seg_end = to.min(self.end_time);
+        if seg_end > seg_start {
+            let duration = (self.end_time - self.start_time) as i128;
+            let start_apy = self.start_apy as i128;
+            let delta = self.end_apy as i128 - start_apy;
+            let offsets = (seg_start - self.start_time) as i128 + (seg_end - self.start_time) as i128;
+            total += (seg_end - seg_start) as i128 * (2 * start_apy * duration + delta * offsets)
+                / (2 * duration);
+        }
+        // After the ramp: flat end_apy.
+        let after_start = from.max(self.end_time);
+        if to > after_start {
+            total += self.end_apy as i128 * (to - after_start) as i128;
+        }
+        total as u128
+    }
+}
+
+/// Long-term emission decay: from `start_time`, the staking reward rate is multiplied by
+/// `decay_bps` every `epochs_per_step` epochs of `epoch_length` seconds (5_000 halves it).
+#[account]
+pub struct EmissionSchedule {
+    pub enabled: bool,
+    pub start_time: i64,      // Start of the first emission epoch
+    pub epoch_length: i64,    // Seconds per emission epoch
+    pub epochs_per_step: u64, // Epochs between two decay steps
+    pub decay_bps: u16,       // Rate kept at each step, in basis points
+}
+
+impl EmissionSchedule {
+    fn step_length(&self) -> i64 {
+        self.epoch_length.checked_mul(self.epochs_per_step as i64).unwrap()
+    }
+
+    /// `decay_bps` raised to `steps`, scaled by `EMISSION_SCALE`.
+    fn decay_factor(&self, mut steps: u64) -> u128 {
+        // Fixed-point exponentiation by squaring.
+        let mut multiplier = EMISSION_SCALE;
+        let mut factor = self.decay_bps as u128 * (EMISSION_SCALE / 10_000);
+        while steps > 0 && multiplier > 0 {
+            if steps & 1 == 1 {
+                multiplier = multiplier * factor / EMISSION_SCALE;
+            }
+            factor = factor * factor / EMISSION_SCALE;
+            steps >>= 1;
+        }
+        multiplier
+    }
+
+    /// Reward rate multiplier in effect at `t`, scaled by `EMISSION_SCALE`.
+    fn multiplier(&self, t: i64) -> u128 {
+        if !self.enabled || t < self.start_time {
+            return EMISSION_SCALE;
+        }
+        self.decay_factor(((t - self.start_time) / self.step_length()) as u64)
+    }
+
+    /// Reward rate multiplier in effect at `t`, in basis points.
+    pub fn multiplier_bps(&self, t: i64) -> u128 {
+        self.multiplier(t) / (EMISSION_SCALE / 10_000)
+    }
+
+    /// Sums of `d^k` and of `k * d^k` over the first `steps` steps, where `d` is the decay,
+    /// scaled by `EMISSION_SCALE`.
+    fn decay_series(&self, steps: u64) -> (u128, u128) {
+        let n = steps as u128;
+        if n == 0 {
+            return (0, 0);
+        }
+        let decay = self.decay_bps as u128 * (EMISSION_SCALE / 10_000);
+        let keep = EMISSION_SCALE - decay;
+        if keep == 0 {
+            return (n * EMISSION_SCALE, n * (n - 1) / 2 * EMISSION_SCALE);
+        }
+        // Geometric series (1 - d^n) / (1 - d), and its derivative form
+        // d * (1 - n * d^(n-1) + (n-1) * d^n) / (1 - d)^2.
+        let last = self.decay_factor(steps);
+        let before_last = self.decay_factor(steps - 1);
+        let sum = EMISSION_SCALE * (EMISSION_SCALE - last) / keep;
+        let numerator = (EMISSION_SCALE + (n - 1) * last).saturating_sub(n * before_last);
+        let weighted_sum =
+            mul_div(decay.checked_mul(numerator).unwrap() / keep, EMISSION_SCALE, keep);
+        (sum, weighted_sum)
+    }
+
+    /// Integral of the APY times the multiplier between `from` and `to`, scaled by
+    /// `EMISSION_SCALE`. `apy_seconds` integrates the APY and must be affine over the period;
+    /// whole steps are summed in closed form, so the cost does not grow with their number.
+    pub fn decayed_apy_seconds(
+        &self,
+        from: i64,
+        to: i64,
+        apy_seconds: impl Fn(i64, i64) -> u128,
+    ) -> u128 {
+        if to <= from {
+            return 0;
+        }
+        if !self.enabled || to <= self.start_time {
+            return apy_seconds(from, to).checked_mul(EMISSION_SCALE).unwrap();
+        }
+        let mut total = 0;
+        let mut from = from;
+        if from < self.start_time {
+            total = apy_seconds(from, self.start_time).checked_mul(EMISSION_SCALE).unwrap();
+            from = self.start_time;
+        }
+        // Partial first step.
+        let head_end = self.step_end(from).min(to);
+        total += apy_seconds(from, head_end).checked_mul(self.multiplier(from)).unwrap();
+        if head_end == to {
+            return total;
+        }
+        // Whole steps: step k pays `first + k * slope` APY-seconds at `d^k` of the first one.
+        let step_length = self.step_length();
+        let tail_start = to - (to - self.start_time) % step_length;
+        let steps = ((tail_start - head_end) / step_length) as u64;
+        if steps > 0 {
+            let first = apy_seconds(head_end, head_end + step_length);
+            let last = apy_seconds(tail_start - step_length, tail_start);
+            let (sum, weighted_sum) = self.decay_series(steps);
+            let mut whole = first.checked_mul(sum).unwrap();
+            if steps > 1 {
+                let divisor = steps as u128 - 1;
+                whole = if last >= first {
+                    whole.checked_add(mul_div(last - first, weighted_sum, divisor)).unwrap()
+                } else {
+                    whole.saturating_sub(mul_div(first - last, weighted_sum, divisor))
+                };
+            }
+            total += mul_div(whole, self.multiplier(head_end), EMISSION_SCALE);
+        }
+        // Partial last step.
+        total + apy_seconds(tail_start, to).checked_mul(self.multiplier(tail_start)).unwrap()
+    }
+
+    /// End of the decay step containing `t` (the next time the multiplier may change).
+    pub fn step_end(&self, t: i64) -> i64 {
+        if !self.enabled {
+            return i64::MAX;
+        }
+        if t < self.start_time {
+            return self.start_time;
+        }
+        let step_length = self.step_length();
+        let steps = (t - self.start_time) / step_length;
+        self.start_time.saturating_add((steps + 1).saturating_mul(step_length))
+    }
+}
+
+/// NFT staking boost: a position holding an escrowed NFT from `collection` earns `boost_bps`
+/// more rewards. The boost is fixed when the NFT is registered.
+#[account]
+pub struct NftBoostConfig {
+    pub collection: Pubkey, // Verified Metaplex collection eligible for the boost
+    pub boost_bps: u16,     // Reward boost for newly registered NFTs (0 = registration closed)
+}
+
+impl NftBoostConfig {
+    /// Fail unless `metadata_info` is the Metaplex metadata account of `mint` and places it in
+    /// the verified boost collection.
+    pub fn check_collection(&self, mint: Pubkey, metadata_info: &AccountInfo) -> Result<()> {
+        let (metadata_address, _) = mpl_token_metadata::accounts::Metadata::find_pda(&mint);
+        require!(
+            metadata_info.key() == metadata_address
+                && *metadata_info.owner == mpl_token_metadata::ID,
+            ErrorCode::InvalidNftMetadata
+        );
+        let metadata = mpl_token_metadata::accounts::Metadata::try_from(metadata_info)
+            .map_err(|_| ErrorCode::InvalidNftMetadata)?;
+        let collection = metadata.collection.ok_or(ErrorCode::NftNotInCollection)?;
+        require!(
+            collection.verified && collection.key == self.collection,
+            ErrorCode::NftNotInCollection
+        );
+        Ok(())
+    }
+}
+
+/// Timelocked APY / fee change: `queue_parameters` records it, and it can only be applied by
+/// `execute_parameters` once `eta` has passed, giving stakers `delay` seconds of notice.
+#[account]
+pub struct PendingParams {
+    pub delay: i64,      // Seconds between queueing and earliest execution
+    pub is_queued: bool, // A change is waiting to be executed
+    pub new_apy: u64,
+    pub new_fee_percent: u64,
+    pub eta: i64,        // Earliest execution time of the queued change
+}
+
+/// Staker governance settings and proposal counter.
+#[account]
+pub struct Governance {
+    pub voting_period: i64,           // Seconds a proposal is open for voting
+    pub quorum: u64,                  // Minimum staked tokens voting (for + against) for a proposal to pass
+    pub proposal_threshold: u64,      // Tokens a wallet must have staked to create a proposal
+    pub proposal_count: u64,          // Proposals created so far; the next proposal's id
+    pub participation_window: u64,    // Recent proposals a wallet must have voted on for the boost
+    pub participation_boost_bps: u16, // Bonus on claimed rewards for wallets that did (0 = off)
+}
+
+impl Governance {
+    /// Participation bonus on `rewards` claimed by `wallet`: `participation_boost_bps` of them
+    /// if `vote_records` show the wallet voted, with any of its positions, on each of the last
+    /// `participation_window` proposals. 0 otherwise, or if governance isn't set up.
+    pub fn participation_bonus(
+        governance_info: &AccountInfo,
+        wallet: Pubkey,
+        vote_records: &[AccountInfo],
+        rewards: u64,
+    ) -> Result<u64> {
+        if governance_info.data_is_empty() {
+            return Ok(0);
+        }
+        let governance: Governance = load_account(governance_info)?;
+        let window = governance.participation_window;
+        if window == 0
+            || governance.participation_boost_bps == 0
+            || governance.proposal_count < window
+        {
+            return Ok(0);
+        }
+        let first = governance.proposal_count - window;
+        let mut voted = vec![false; window as usize];
+        for info in vote_records {
+            require!(info.owner == &crate::ID, ErrorCode::InvalidVoteRecord);
+            let vote_record = VoteRecord::try_deserialize(&mut &info.data.borrow()[..])?;
+            if vote_record.voter == wallet
+                && vote_record.proposal_id >= first
+                && vote_record.proposal_id < governance.proposal_count
+            {
+                voted[(vote_record.proposal_id - first) as usize] = true;
+            }
+        }
+        if !voted.iter().all(|v| *v) {
+            return Ok(0);
+        }
+        let bonus = (rewards as u128)
+            .checked_mul(governance.participation_boost_bps as u128)
+            .unwrap()
+            / 10_000;
+        Ok(bonus as u64)
+    }
+}
+
+/// Parameter change a proposal applies when executed.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum ProposalAction {
+    SetApy(u64),
+    SetTransactionFeePercent(u64),
+    SetEarlyUnstakePenaltyPercent(u64),
+    SetPenaltyWaiverUntil(i64),
+    RevokeMarketMaker,
+    SetUnstakeThrottleBps(u16),
+    ApproveRoundReport,
+    SetPenaltyRedistributePercent(u64),
+    SetMinLaunchLiquidityPercent(u64),
+}
+
+#[account]
+pub struct Proposal {
+    pub id: u64,
+    pub proposer: Pubkey,
+    pub action: ProposalAction,
+    pub votes_for: u64,     // Staked tokens voting for
+    pub votes_against: u64, // Staked tokens voting against
+    pub created_at: i64,
+    pub voting_ends_at: i64,
+    pub executed: bool,
+}
+
+impl Proposal {
+    /// Whether the proposal passed: majority for and quorum reached.
+    pub fn passed(&self, quorum: u64) -> bool {
+        let turnout = self.votes_for.checked_add(self.votes_against).unwrap();
+        self.votes_for > self.votes_against && turnout >= quorum
+    }
+}
+
+/// Marks a stake position as having voted on a proposal.
+#[account]
+pub struct VoteRecord {
+    pub proposal: Pubkey,
+    pub stake_info: Pubkey,
+    pub weight: u64,
+    pub support: bool,
+    pub voter: Pubkey,    // Wallet that owned the position when it voted
+    pub proposal_id: u64, // Id of `proposal`
+}
+
+/// Compact per-wallet notification bits (`FLAG_*`) that wallets can poll cheaply.
+/// Recomputed by the permissionless `refresh_user_flags`.
+#[account]
+pub struct UserFlags {
+    pub wallet: Pubkey,
+    pub flags: u8,
+    pub updated_at: i64,
+}
+
+/// Governs when presale allocations can be claimed after the presale, so TGE timing can be
+/// coordinated precisely and claims can be paused if an issue is found.
+#[account]
+pub struct ClaimPortal {
+    pub is_open: bool,           // Claims are only possible while open
+    pub is_paused: bool,         // Emergency pause, independent of the open/close window
+    pub opens_at: i64,           // Claims open at this timestamp once `is_open` is set
+    pub closes_at: Option<i64>,  // Optional end of the claim window
+    pub total_claimed: u64,      // Tokens claimed through the portal so far
+    pub claimants: u64,          // Wallets that have claimed at least once
+    pub max_claim_per_slot: u64, // Global cap on tokens claimed per slot (0 = unlimited)
+    pub throttle_slot: u64,      // Slot that `claimed_this_slot` refers to
+    pub claimed_this_slot: u64,  // Tokens claimed in `throttle_slot`
+}
+
+impl ClaimPortal {
+    /// Fails unless claims are currently allowed.
+    pub fn assert_claimable(&self, now: i64) -> Result<()> {
+        require!(self.is_open && now >= self.opens_at, ErrorCode::ClaimPortalClosed);
+        if let Some(closes_at) = self.closes_at {
+            require!(now < closes_at, ErrorCode::ClaimPortalClosed);
+        }
+        require!(!self.is_paused, ErrorCode::ClaimPortalPaused);
+        Ok(())
+    }
+
+    /// Book a claim of up to `amount` tokens by the wallet owning `claim_record` and return
+    /// the amount granted: all of it, or what is left of the per-slot throughput limit, so
+    /// allocations larger than the limit are paid out over several slots. The caller keeps
+    /// the rest on the receipt, schedule or leaf being claimed, to be claimed in a later slot.
+    /// Fails only if the current slot's limit is used up.
+    pub fn record_claim(
+        &mut self,
+        claim_record: &mut ClaimRecord,
+        amount: u64,
+        now: i64,
+        slot: u64,
+    ) -> Result<u64> {
+        let mut amount = amount;
+        if self.max_claim_per_slot > 0 {
+            if slot != self.throttle_slot {
+                self.throttle_slot = slot;
+                self.claimed_this_slot = 0;
+            }
+            let capacity = self.max_claim_per_slot.saturating_sub(self.claimed_this_slot);
+            require!(capacity > 0, ErrorCode::ClaimThrottled);
+            amount = amount.min(capacity);
+            self.claimed_this_slot = self.claimed_this_slot.checked_add(amount).unwrap();
+        }
+        if claim_record.amount_claimed == 0 {
+            self.claimants = self.claimants.checked_add(1).unwrap();
+        }
+        claim_record.amount_claimed = claim_record.amount_claimed.checked_add(amount).unwrap();
+        claim_record.last_claim_time = now;
+        self.total_claimed = self.total_claimed.checked_add(amount).unwrap();
+        Ok(amount)
+    }
+}
+
+/// Off-chain heuristics about a wallet, written by the configured attestor and checked
+/// on purchases when the anti-sybil hook is enabled.
+#[account]
+pub struct Attestation {
+    pub wallet: Pubkey,       // Wallet being attested
+    pub attestor: Pubkey,     // Attestor that wrote this attestation
+    pub first_seen_slot: u64, // Slot the wallet was first seen on-chain
+    pub has_history: bool,    // Whether the wallet has any prior transaction history
+    pub updated_at: i64,      // Timestamp of the last update
+}
+
+/// Blacklist flag for a wallet, one PDA per wallet. Blacklisted wallets cannot buy, stake
+/// or claim; entries are kept and cleared rather than closed.
+#[account]
+pub struct BlacklistEntry {
+    pub wallet: Pubkey,  // Wallet the flag applies to
+    pub blocked: bool,   // Whether the wallet is currently blacklisted
+    pub updated_at: i64, // Timestamp of the last change
+}
+
+impl BlacklistEntry {
+    /// Whether the wallet owning `entry_info` is blacklisted; no entry means not blacklisted.
+    pub fn is_blocked(entry_info: &AccountInfo) -> Result<bool> {
+        if entry_info.data_is_empty() {
+            return Ok(false);
+        }
+        let entry: BlacklistEntry = load_account(entry_info)?;
+        Ok(entry.blocked)
+    }
+
+    /// Fail if the wallet owning `entry_info` is blacklisted.
+    pub fn check(entry_info: &AccountInfo) -> Result<()> {
+        require!(!Self::is_blocked(entry_info)?, ErrorCode::WalletBlacklisted);
+        Ok(())
+    }
+}
+
+/// Accepted-mint registry entry, one PDA per SPL payment mint.
+/// SPL payment fees are a percentage of the amount with a floor expressed in the mint's own
+/// decimals, instead of a flat number of base units.
+#[account]
+pub struct AcceptedMint {
+    pub mint: Pubkey,        // Payment mint this config applies to
+    pub price_feed: Pubkey,  // Price feed account for this mint
+    pub decimals: u8,        // Mint decimals, copied from the mint at registration
+    pub fee_bps: u16,        // Fee in basis points of the payment amount
+    pub min_fee: u64,        // Minimum fee, in the mint's base units
+    pub enabled: bool,       // Payments in this mint are accepted only while enabled
+    pub is_stablecoin: bool, // USD stablecoin; usable for `buy_presale_with_stablecoin` at $1
+}
+
+impl AcceptedMint {
+    pub fn fee_for(&self, amount: u64) -> u64 {
+        let percent_fee = ((amount as u128) * (self.fee_bps as u128) / 10_000) as u64;
+        percent_fee.max(self.min_fee)
+    }
+
+    /// Convert base units of this stablecoin to a 9-decimal USD amount at $1, rounded down.
+    pub fn base_units_to_usd(&self, amount: u64) -> u64 {
+        let decimals = self.decimals as u32;
+        if decimals <= 9 {
+            amount.checked_mul(10u64.pow(9 - decimals)).unwrap()
+        } else {
+            amount / 10u64.pow(decimals - 9)
+        }
+    }
+
+    /// Convert a 9-decimal USD amount to base units of this stablecoin at $1, rounded up.
+    pub fn usd_to_base_units(&self, usd: u64) -> u64 {
+        (usd as u128 * 10u128.pow(self.decimals as u32)).div_ceil(1_000_000_000) as u64
+    }
+}
+
+/// A merkle airdrop. Tokens sit in a vault owned by this PDA; eligible wallets claim with a
+/// proof until `claim_deadline`, after which the admin can sweep what's left to the community pool.
+#[account]
+pub struct AirdropDistributor {
+    pub distributor_id: u64,       // Admin-chosen id used in the PDA seeds
+    pub bump: u8,                  // PDA bump, used to sign vault transfers
+    pub merkle_root: [u8; 32],     // Root of keccak(wallet || amount) leaves
+    pub vault: Pubkey,             // Token account holding the airdrop
+    pub sweep_destination: Pubkey, // Community pool token account that receives unclaimed tokens
+    pub total_amount: u64,         // Tokens deposited for the airdrop
+    pub claim_deadline: i64,       // No claims after this timestamp
+    pub total_claimed: u64,        // Tokens claimed so far
+    pub num_claimants: u64,        // Wallets that claimed
+    pub swept_amount: u64,         // Unclaimed tokens returned by `sweep_unclaimed`
+    pub swept_at: Option<i64>,     // When the unclaimed tokens were swept
+}
+
+/// Marks a wallet as having claimed from a given airdrop.
+#[account]
+pub struct AirdropClaim {
+    pub distributor: Pubkey,
+    pub claimant: Pubkey,
+    pub amount: u64,
+    pub claimed_at: i64,
+}
+
+/// Holder reflections: deposits accumulate in the reflection vault, and each posted snapshot
+/// sets aside everything not already owed to an open snapshot, to be claimed pro rata to the
+/// snapshot balances.
+#[account]
+pub struct ReflectionPool {
+    pub snapshot_count: u64,  // Snapshots posted; the next snapshot's id
+    pub reserved: u64,        // Tokens owed to open snapshots and not yet claimed
+    pub total_deposited: u64, // Tokens deposited for reflections
+    pub total_claimed: u64,   // Tokens claimed by holders
+}
+
+/// A posted holder-balance snapshot and the tokens it distributes.
+#[account]
+pub struct ReflectionSnapshot {
+    pub snapshot_id: u64,      // Position in the pool's snapshot sequence, used in the PDA seeds
+    pub merkle_root: [u8; 32], // Root of keccak(wallet || balance) leaves
+    pub total_balance: u64,    // Sum of all balances in the snapshot
+    pub amount: u64,           // Tokens distributed by this snapshot
+    pub claimed: u64,          // Tokens claimed so far
+    pub claim_deadline: i64,   // No claims after this timestamp
+    pub expired: bool,         // Unclaimed tokens were released back to the pool
+}
+
+impl ReflectionSnapshot {
+    /// Share of `amount` owed to a holder with `balance` in the snapshot.
+    pub fn share(&self, balance: u64) -> u64 {
+        (self.amount as u128 * balance as u128 / self.total_balance as u128) as u64
+    }
+}
+
+/// Marks a wallet as having claimed from a given reflection snapshot.
+#[account]
+pub struct ReflectionClaim {
+    pub snapshot: Pubkey,
+    pub holder: Pubkey,
+    pub amount: u64,
+    pub claimed_at: i64,
+}
+
+/// Verified project links (website, audits, tokenomics) with content hashes, so wallets
+/// and explorers can surface them directly from program state.
+#[account]
+pub struct ProjectInfo {
+    pub website: ProjectLink,
+    pub audits: ProjectLink,
+    pub tokenomics: ProjectLink,
+    pub updated_at: i64,
+}
+
+impl ProjectInfo {
+    pub const LEN: usize = 3 * ProjectLink::LEN + 8;
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default)]
+pub struct ProjectLink {
+    pub uri: String,            // At most MAX_URI_LEN bytes
+    pub content_hash: [u8; 32], // Hash of the linked document
+}
+
+impl ProjectLink {
+    pub const LEN: usize = 4 + MAX_URI_LEN + 32;
+}
+
+/// Per-wallet claim tracking for the claim portal.
+#[account]
+pub struct ClaimRecord {
+    pub wallet: Pubkey,       // Wallet that claimed
+    pub amount_claimed: u64,  // Tokens claimed so far
+    pub last_claim_time: i64, // Timestamp of the last claim
+}
+
+/// Receipt for a purchase made on behalf of another wallet.
+/// The beneficiary owns the allocation and its claim rights; the payer is kept for the record.
+#[account]
+pub struct PurchaseReceipt {
+    pub payer: Pubkey,                 // Wallet that paid
+    pub beneficiary: Pubkey,           // Wallet that owns the allocation and claim rights
+    pub receipt_id: u64,               // Payer-chosen id used in the receipt PDA seeds
+    pub token_mint: Pubkey,            // Payment mint (Pubkey::default() for SOL)
+    pub amount: u64,                   // Amount paid (including the fee)
+    pub timestamp: i64,                // Time of purchase
+    pub tokens_allocated: u64,         // Tokens allocated by stage-priced purchases
+    pub weighted_entry_stage: u64,     // Token-weighted average entry stage, ×100 (e.g. 150 = stage 1.5)
+    pub early_bird_bonus_claimed: bool,
+    pub vesting_preset: VestingPreset, // Vesting terms of the stage the purchase started in
+    pub vested: bool,                  // Allocation is vesting (schedule or aggregate)
+    pub tokens_claimed: bool,          // Allocation has been paid out by `claim_presale_tokens`
+    pub tokens_paid: u64,              // Tokens paid out so far; throttled claims pay in parts
+    pub refunded: bool,                // Stablecoin payment refunded after a failed presale
+}
+
+impl PurchaseReceipt {
+    /// Add a stage-priced purchase to the receipt, updating the weighted average entry stage.
+    pub fn record_allocation(&mut self, quote: &PurchaseQuote) {
+        let new_total = self.tokens_allocated.checked_add(quote.tokens_allocated).unwrap();
+        if new_total == 0 {
+            return;
+        }
+        let previous = (self.weighted_entry_stage as u128) * (self.tokens_allocated as u128);
+        let added = (quote.weighted_entry_stage() as u128) * (quote.tokens_allocated as u128);
+        self.weighted_entry_stage = ((previous + added) / new_total as u128) as u64;
+        self.tokens_allocated = new_total;
+    }
+
+    /// 0-based index of the stage the allocation was (mostly) bought in, from the weighted
+    /// entry stage.
+    pub fn entry_stage_index(&self, stage_count: usize) -> usize {
+        (((self.weighted_entry_stage + 50) / 100) as usize).clamp(1, stage_count) - 1
+    }
+
+    /// Early-bird bonus owed for this receipt: `bonus_bps` of the allocation for stage-1 buyers,
+    /// scaled down in inverse proportion to the weighted entry stage.
+    pub fn early_bird_bonus(&self, bonus_bps: u16) -> u64 {
+        if self.weighted_entry_stage == 0 {
+            return 0;
+        }
+        ((self.tokens_allocated as u128) * (bonus_bps as u128) * 100
+            / 10_000
+            / (self.weighted_entry_stage as u128)) as u64
+    }
+}
+
+/// The fields of a receipt closed by `compress_receipt`, hashed into its receipt tree leaf.
+/// Passed back in full to `claim_compressed_receipt`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct CompressedReceipt {
+    pub payer: Pubkey,
+    pub beneficiary: Pubkey,
+    pub receipt_id: u64,
+    pub tokens_allocated: u64,
+    pub timestamp: i64,
+}
+
+impl CompressedReceipt {
+    pub fn leaf(&self) -> [u8; 32] {
+        keccak::hashv(&[
+            self.payer.as_ref(),
+            self.beneficiary.as_ref(),
+            &self.receipt_id.to_le_bytes(),
+            &self.tokens_allocated.to_le_bytes(),
+            &self.timestamp.to_le_bytes(),
+        ])
+        .0
+    }
+}
+
+/// Concurrent merkle tree of compressed purchase receipts, created by `init_receipt_tree`.
+/// The tree lives in an spl-account-compression account this PDA is the authority of; a
+/// fully claimed receipt's leaf is replaced with zeros, so it cannot be claimed twice.
+#[account]
+pub struct ReceiptTree {
+    pub merkle_tree: Pubkey, // spl-account-compression account holding the tree
+    pub leaf_count: u64,     // Receipts compressed so far; the next leaf's index
+    pub claimed_count: u64,  // Compressed receipts claimed so far
+}
+
+/// Per-wallet aggregate of all `buy_presale` purchases, created on the first purchase.
+/// Used for distribution after the presale.
+#[account]
+#[derive(Default)]
+pub struct BuyerInfo {
+    pub wallet: Pubkey,             // Buyer wallet
+    pub total_contributed: u64,     // Lamports contributed across all purchases
+    pub tokens_allocated: u64,      // Tokens allocated across all purchases
+    pub tokens_per_stage: [u64; 8], // Tokens allocated in each stage
+    pub purchases: u64,             // Number of purchases
+    pub first_purchase_at: i64,     // Timestamp of the first purchase
+    pub last_purchase_at: i64,      // Timestamp of the latest purchase
+    pub refunded: bool,             // Contribution refunded after a failed presale
+    pub usd_contributed: u64,       // USD contributed via stablecoins, 9 decimals
+    pub last_purchase_slot: u64,    // Slot of the latest purchase (one purchase per slot)
+    pub usd_sol_value: u64,         // `usd_contributed` valued in lamports at stage SOL prices
+}
+
+impl BuyerInfo {
+    /// Add a purchase to the buyer's totals.
+    pub fn record_purchase(
+        &mut self,
+        wallet: Pubkey,
+        quote: &PurchaseQuote,
+        payment_method: u8,
+        now: i64,
+        slot: u64,
+    ) {
+        if self.purchases == 0 {
+            self.wallet = wallet;
+            self.first_purchase_at = now;
+        }
+        let contributed = if payment_method == PAYMENT_METHOD_STABLECOIN {
+            &mut self.usd_contributed
+        } else {
+            &mut self.total_contributed
+        };
+        *contributed = contributed.checked_add(quote.lamports_used).unwrap();
+        if payment_method == PAYMENT_METHOD_STABLECOIN {
+            self.usd_sol_value = self.usd_sol_value.checked_add(quote.sol_value).unwrap();
+        }
+        self.tokens_allocated = self.tokens_allocated.checked_add(quote.tokens_allocated).unwrap();
+        for (index, tokens) in quote.tokens_per_stage.iter().enumerate() {
+            self.tokens_per_stage[index] =
+                self.tokens_per_stage[index].checked_add(*tokens).unwrap();
+        }
+        self.purchases = self.purchases.checked_add(1).unwrap();
+        self.last_purchase_at = now;
+        self.last_purchase_slot = slot;
+    }
+
+    /// Everything the buyer has contributed, in lamports; what the per-wallet cap applies to.
+    pub fn contributed_sol_value(&self) -> u64 {
+        self.total_contributed.checked_add(self.usd_sol_value).unwrap()
+    }
+}
+
+/// Referral stats and bonus balance of one referrer, created by `register_referrer`.
+#[account]
+pub struct ReferralAccount {
+    pub referrer: Pubkey,       // Referrer wallet
+    pub referrals: u64,         // Purchases made with this referrer
+    pub referred_lamports: u64, // Lamports spent by referred buyers
+    pub referred_usd: u64,      // USD spent by referred buyers via stablecoins, 9 decimals
+    pub rewards_accrued: u64,   // Bonus tokens earned so far
+    pub rewards_claimed: u64,   // Bonus tokens paid out from the referral pool
+}
+
+impl ReferralAccount {
+    /// Credit the referrer for a referred purchase.
+    pub fn record_referral(&mut self, quote: &PurchaseQuote, payment_method: u8, bonus_bps: u16) {
+        self.referrals = self.referrals.checked_add(1).unwrap();
+        let volume = if payment_method == PAYMENT_METHOD_STABLECOIN {
+            &mut self.referred_usd
+        } else {
+            &mut self.referred_lamports
+        };
+        *volume = volume.checked_add(quote.lamports_used).unwrap();
+        let bonus = (quote.tokens_allocated as u128) * (bonus_bps as u128) / 10_000;
+        self.rewards_accrued = self.rewards_accrued.checked_add(bonus as u64).unwrap();
+    }
+}
+
+/// A referral commission tier: referrers with at least `min_referred_lamports` of referred
+/// SOL volume earn `bonus_bps`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default)]
+pub struct ReferralTier {
+    pub min_referred_lamports: u64,
+    pub bonus_bps: u16,
+}
+
+/// A referrer's position on the referral leaderboard.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default)]
+pub struct LeaderboardEntry {
+    pub referrer: Pubkey,
+    pub referred_lamports: u64,
+}
+
+/// Referral commission tiers and the public referral leaderboard.
+#[account]
+pub struct ReferralProgram {
+    pub tiers: [ReferralTier; 4],            // Ascending thresholds; unused tiers have bonus_bps 0
+    pub leaderboard: [LeaderboardEntry; 10], // Top referrers by referred SOL volume, highest first
+}
+
+impl ReferralProgram {
+    /// Commission for a referrer with `referred_lamports` of volume: the highest tier reached,
+    /// or `base_bps` if no tier applies.
+    pub fn bonus_bps(&self, base_bps: u16, referred_lamports: u64) -> u16 {
+        self.tiers
+            .iter()
+            .filter(|tier| tier.bonus_bps > 0 && referred_lamports >= tier.min_referred_lamports)
+            .map(|tier| tier.bonus_bps)
+            .fold(base_bps, u16::max)
+    }
+
+    /// Insert or update the referrer's leaderboard entry, keeping the board sorted.
+    pub fn update_leaderboard(&mut self, referrer: Pubkey, referred_lamports: u64) {
+        let len = self.leaderboard.len();
+        let index = match self.leaderboard.iter().position(|entry| entry.referrer == referrer) {
+            Some(index) => index,
+            None if referred_lamports > self.leaderboard[len - 1].referred_lamports => len - 1,
+            None => return,
+        };
+        self.leaderboard[index] = LeaderboardEntry {
+            referrer,
+            referred_lamports,
+        };
+        self.leaderboard
+            .sort_by_key(|entry| std::cmp::Reverse(entry.referred_lamports));
+    }
+}
+
+/// A wallet's position on the staking leaderboard.
+#[zero_copy]
+pub struct StakingLeaderboardEntry {
+    pub staker: Pubkey,
+    pub total_staked: u64,
+}
+
+/// Top wallets by tokens staked across all their positions, highest first, for display
+/// without scanning every `StakeInfo`. Updated on stake, unstake and position changes once
+/// created; a wallet that drops off the board only returns once it beats the lowest entry.
+#[account(zero_copy)]
+pub struct StakingLeaderboard {
+    pub entries: [StakingLeaderboardEntry; STAKING_LEADERBOARD_SIZE],
+}
+
+impl StakingLeaderboard {
+    /// Record `staker`'s new total in the leaderboard in `leaderboard_info`, if it has been
+    /// created.
+    pub fn record<'info>(
+        leaderboard_info: &AccountInfo<'info>,
+        staker: Pubkey,
+        total_staked: u64,
+    ) -> Result<()> {
+        if leaderboard_info.data_is_empty() {
+            return Ok(());
+        }
+        load_zero_copy_mut::<StakingLeaderboard>(leaderboard_info)?.update(staker, total_staked);
+        Ok(())
+    }
+
+    /// Insert, update or (at zero) remove the staker's entry, keeping the board sorted.
+    pub fn update(&mut self, staker: Pubkey, total_staked: u64) {
+        let len = self.entries.len();
+        let index = match self.entries.iter().position(|entry| entry.staker == staker) {
+            Some(index) => index,
+            None if total_staked > self.entries[len - 1].total_staked => len - 1,
+            None => return,
+        };
+        self.entries[index] = StakingLeaderboardEntry {
+            staker: if total_staked == 0 { Pubkey::default() } else { staker },
+            total_staked,
+        };
+        self.entries.sort_by_key(|entry| std::cmp::Reverse(entry.total_staked));
+    }
+}
+
+/// A payment request issued by the admin to a specific payer.
+/// Paid in SOL to the treasury recorded at creation.
+#[account]
+pub struct Invoice {
+    pub invoice_id: u64,        // Admin-chosen id used in the invoice PDA seeds
+    pub payer: Pubkey,          // Wallet expected to pay the invoice
+    pub treasury: Pubkey,       // Treasury SOL account that receives the payment
+    pub amount: u64,            // Amount due in lamports
+    pub memo_hash: [u8; 32],    // Hash of the off-chain memo / invoice document
+    pub created_at: i64,        // Creation timestamp
+    pub expiry: i64,            // Invoice cannot be paid after this timestamp
+    pub is_paid: bool,
+    pub paid_at: Option<i64>,
+}
+
+#[account]
+pub struct StakeInfo {
+    pub owner: Pubkey,                       // Wallet that owns this position
+    pub position_id: u64,                    // Position index (0 for the primary position, split positions use their own id)
+    pub amount: u64,                         // Amount of tokens staked
+    pub start_time: i64,                     // Timestamp when staking started
+    pub last_claim_time: i64,                // Timestamp of last reward claim (or of the expiry cutoff)
+    pub is_frozen: bool,                     // Frozen by the admin (e.g. stolen-funds dispute)
+    pub freeze_reason_hash: [u8; 32],        // Hash of the off-chain freeze reason
+    pub frozen_at: Option<i64>,              // When the position was last frozen
+    pub unfrozen_at: Option<i64>,            // When the position was last unfrozen
+    pub last_activity: i64,                  // Last owner action (stake, claim, split); drives reward expiry
+    pub partner_reward_per_token_paid: u128, // Partner accumulator value at the last settlement
+    pub partner_rewards_owed: u64,           // Settled, unclaimed partner rewards
+    pub migrated: bool,                      // Converted to the new staking mint by `migrate_stake`
+    pub voting_locked_until: i64,            // Can't be split or unstaked before this (set by `cast_vote`)
+    pub mint: Pubkey,                        // Mint of the staked tokens; selects the stake vault
+    pub reward_debt: u128,                   // amount × acc_reward_per_share at the last settlement
+    pub rewards_owed: u64,                   // Settled, unclaimed rewards
+    pub unstake_queue_position: u64,         // Position in the unstake queue (0 = not queued)
+    pub unstake_window: i64,                 // Throttle window from which the queued unstake may proceed
+    pub locked_until: i64,                   // Can't be unstaked before this (auto-staked bonuses)
+    pub boost_nft: Pubkey,                   // Escrowed boost NFT mint (default = none)
+    pub boost_bps: u16,                      // Reward boost granted by the escrowed NFT
+    pub pending_withdrawal: u64,             // Unstaked tokens waiting for `withdraw_unstaked`
+    pub withdrawable_at: i64,                // When `pending_withdrawal` can be withdrawn
+}
+
+impl StakeInfo {
+    /// Unclaimed rewards as of `now`: settled rewards plus those accrued since the last
+    /// settlement, following any active APY ramp.
+    pub fn pending_rewards(
+        &self,
+        global_state: &GlobalState,
+        param_ramp: &ParamRamp,
+        emission_schedule: &EmissionSchedule,
+        now: i64,
+    ) -> u64 {
+        let acc_reward_per_share =
+            global_state.current_reward_per_share(param_ramp, emission_schedule, now);
+        self.rewards_owed
+            .checked_add(self.accrued_rewards(acc_reward_per_share))
+            .unwrap()
+    }
+
+    /// Rewards accrued since the last settlement, including any NFT boost. Nothing accrues
+    /// while the position is frozen.
+    pub fn accrued_rewards(&self, acc_reward_per_share: u128) -> u64 {
+        if self.is_frozen {
+            return 0;
+        }
+        let accrued = (self.amount as u128)
+            .checked_mul(acc_reward_per_share)
+            .unwrap()
+            .checked_sub(self.reward_debt)
+            .unwrap()
+            .checked_mul(10_000 + self.boost_bps as u128)
+            .unwrap()
+            / 10_000;
+        (accrued / REWARD_PER_TOKEN_SCALE) as u64
+    }
+
+    /// Move accrued rewards into `rewards_owed`. `acc_reward_per_share` must be up to date;
+    /// call before the position's amount changes and `reset_reward_debt` after.
+    pub fn settle_rewards(&mut self, acc_reward_per_share: u128) {
+        self.rewards_owed = self
+            .rewards_owed
+            .checked_add(self.accrued_rewards(acc_reward_per_share))
+            .unwrap();
+        self.reset_reward_debt(acc_reward_per_share);
+    }
+
+    /// Start accruing from `acc_reward_per_share` without crediting anything.
+    pub fn reset_reward_debt(&mut self, acc_reward_per_share: u128) {
+        self.reward_debt = (self.amount as u128)
+            .checked_mul(acc_reward_per_share)
+            .unwrap();
+    }
+}
+
+/// Secondary reward emissions in a partner SPL token, paid to stakers alongside BRATS.
+/// The funded amount is streamed at `emission_rate` tokens per second across all staked
+/// tokens until `emission_end`; positions track their share through `reward_per_token`.
+#[account]
+pub struct PartnerRewardPool {
+    pub mint: Pubkey,           // Partner reward token
+    pub emission_rate: u64,     // Partner tokens emitted per second across all stakers
+    pub emission_end: i64,      // Emissions stop at this timestamp
+    pub reward_per_token: u128, // Accumulated rewards per staked token, scaled by REWARD_PER_TOKEN_SCALE
+    pub last_update_time: i64,  // Timestamp the accumulator was last advanced to
+    pub total_funded: u64,      // Partner tokens deposited into the vault
+    pub total_claimed: u64,     // Partner tokens paid out to stakers
+}
+
+impl PartnerRewardPool {
+    /// Advance the accumulator to `now`, splitting the emissions since the last update over
+    /// `total_staked`. Nothing accrues while nothing is staked.
+    pub fn accrue(&mut self, total_staked: u64, now: i64) {
+        let until = now.min(self.emission_end);
+        if until > self.last_update_time && total_staked > 0 {
+            let emitted = (self.emission_rate as u128)
+                .checked_mul((until - self.last_update_time) as u128)
+                .unwrap();
+            self.reward_per_token = self
+                .reward_per_token
+                .checked_add(emitted * REWARD_PER_TOKEN_SCALE / total_staked as u128)
+                .unwrap();
+        }
+        self.last_update_time = self.last_update_time.max(now);
+    }
+
+    /// Move the position's rewards earned since its last settlement into `partner_rewards_owed`.
+    /// Must run before the position's amount changes.
+    pub fn settle(&self, stake_info: &mut StakeInfo) {
+        let delta = self
+            .reward_per_token
+            .checked_sub(stake_info.partner_reward_per_token_paid)
+            .unwrap();
+        let earned = (stake_info.amount as u128)
+            .checked_mul(delta)
+            .unwrap()
+            / REWARD_PER_TOKEN_SCALE;
+        stake_info.partner_rewards_owed = stake_info
+            .partner_rewards_owed
+            .checked_add(earned as u64)
+            .unwrap();
+        stake_info.partner_reward_per_token_paid = self.reward_per_token;
+    }
+
+    /// Accrue and settle `stake_info` against the pool in `pool_info`, if the pool exists.
+    /// `total_staked` is the global total before the position's amount changes.
+    pub fn checkpoint(
+        pool_info: &AccountInfo,
+        stake_info: &mut StakeInfo,
+        total_staked: u64,
+        now: i64,
+    ) -> Result<()> {
+        if pool_info.data_is_empty() {
+            return Ok(());
+        }
+        let mut pool: PartnerRewardPool = load_account(pool_info)?;
+        pool.accrue(total_staked, now);
+        pool.settle(stake_info);
+        store_account(pool_info, &pool)
+    }
+}
+
+/// Second staking pool where BRATS/SOL liquidity providers stake their LP tokens for BRATS.
+/// Funded rewards are emitted at `reward_rate` tokens per second across all staked LP tokens
+/// until `reward_pool` runs out; positions track their share through `acc_reward_per_share`.
+#[account]
+pub struct LpStakePool {
+    pub lp_mint: Pubkey,            // LP token staked in this pool
+    pub reward_rate: u64,           // BRATS emitted per second across all staked LP tokens
+    pub total_staked: u64,          // LP tokens staked
+    pub acc_reward_per_share: u128, // Accumulated rewards per staked LP token, scaled by REWARD_PER_TOKEN_SCALE
+    pub last_update_time: i64,      // Timestamp the accumulator was last advanced to
+    pub reward_pool: u64,           // Funded rewards not yet emitted
+    pub total_funded: u64,          // BRATS deposited into the LP reward vault
+    pub total_claimed: u64,         // BRATS paid out to LP stakers
+}
+
+impl LpStakePool {
+    /// Advance the accumulator to `now`, emitting at `reward_rate` out of `reward_pool`.
+    /// Nothing is emitted while nothing is staked.
+    pub fn update_rewards(&mut self, now: i64) -> u128 {
+        if now > self.last_update_time && self.total_staked > 0 {
+            let emitted = (self.reward_rate as u128)
+                .checked_mul((now - self.last_update_time) as u128)
+                .unwrap()
+                .min(self.reward_pool as u128);
+            self.acc_reward_per_share = self
+                .acc_reward_per_share
+                .checked_add(emitted * REWARD_PER_TOKEN_SCALE / self.total_staked as u128)
+                .unwrap();
+            self.reward_pool -= emitted as u64;
+        }
+        self.last_update_time = self.last_update_time.max(now);
+        self.acc_reward_per_share
+    }
+}
+
+/// A wallet's position in the LP staking pool.
+#[account]
+pub struct LpStakeInfo {
+    pub owner: Pubkey,
+    pub amount: u64,       // LP tokens staked
+    pub start_time: i64,   // When the position was first opened
+    pub reward_debt: u128, // amount × acc_reward_per_share at the last settlement
+    pub rewards_owed: u64, // Settled, unclaimed rewards
+}
+
+impl LpStakeInfo {
+    /// Move rewards accrued since the last settlement into `rewards_owed`. Call before the
+    /// amount changes and `reset_reward_debt` after.
+    pub fn settle_rewards(&mut self, acc_reward_per_share: u128) {
+        let accrued = (self.amount as u128)
+            .checked_mul(acc_reward_per_share)
+            .unwrap()
+            .checked_sub(self.reward_debt)
+            .unwrap()
+            / REWARD_PER_TOKEN_SCALE;
+        self.rewards_owed = self.rewards_owed.checked_add(accrued as u64).unwrap();
+        self.reset_reward_debt(acc_reward_per_share);
+    }
+
+    /// Start accruing from `acc_reward_per_share` without crediting anything.
+    pub fn reset_reward_debt(&mut self, acc_reward_per_share: u128) {
+        self.reward_debt = (self.amount as u128)
+            .checked_mul(acc_reward_per_share)
+            .unwrap();
+    }
+}
+
+/// An approved conversion of stake positions from the old staking mint to a new one
+/// (e.g. a Token-2022 re-issue) at a fixed `ratio_numerator / ratio_denominator`.
+/// The admin pre-funds the migration vault with new-mint tokens; each migrated position moves
+/// its converted amount into the new mint's stake vault, while its old tokens stay behind in
+/// the old stake vault to be retired.
+#[account]
+pub struct StakeMigration {
+    pub old_mint: Pubkey,
+    pub new_mint: Pubkey,
+    pub ratio_numerator: u64,
+    pub ratio_denominator: u64,
+    pub approved_at: i64,
+    pub positions_migrated: u64,  // Positions converted so far
+    pub old_amount_migrated: u64, // Old-mint tokens left in the old pool by migrated positions
+    pub new_amount_migrated: u64, // New-mint tokens moved into the new pool
+}
+
+impl StakeMigration {
+    /// New-mint amount for `amount` old-mint tokens.
+    pub fn convert(&self, amount: u64) -> u64 {
+        ((amount as u128) * (self.ratio_numerator as u128) / (self.ratio_denominator as u128)) as u64
+    }
+
+    /// Once a migration is approved, tag new positions with the mint they are staked in and
+    /// keep top-ups in the position's own mint, so positions cannot be converted twice.
+    pub fn check_stake_mint(
+        migration_info: &AccountInfo,
+        stake_info: &mut StakeInfo,
+        mint: Pubkey,
+    ) -> Result<()> {
+        if migration_info.data_is_empty() {
+            return Ok(());
+        }
+        let migration: StakeMigration = load_account(migration_info)?;
+        if stake_info.amount == 0 {
+            stake_info.migrated = mint == migration.new_mint;
+        }
+        let expected = if stake_info.migrated {
+            migration.new_mint
+        } else {
+            migration.old_mint
+        };
+        require!(mint == expected, ErrorCode::InvalidStakeMint);
+        Ok(())
+    }
+}
+
+/// This account holds the presale stage data. There are 8 stages.
+/// The `price` is stored as a fixed-point value with 8 decimals (e.g. 0.00021 is stored as 21000).
+/// Stages live inside the zero-copy `PresaleStageInfo`, so enums and flags are stored as `u8`,
+/// and the struct keeps its packed on-chain layout.
+#[zero_copy(unsafe)]
+#[repr(C)]
+pub struct PresaleStage {
+    pub stage: u8,
+    pub price: u64,            // SOL price per token, 8 decimals
+    pub usd_price: u64,        // USD price per token for stablecoin purchases, 8 decimals
+    pub allocation: u64,       // Tokens available for sale in this stage
+    pub tokens_sold: u64,
+    pub total_raised: u64,     // Lamports raised in this stage
+    pub total_raised_usd: u64, // USD raised in this stage via stablecoins, 9 decimals
+    pub vesting_preset: u8,    // `VestingPreset::index` of the terms for this stage
+    pub payment_methods: u8,   // Bitmask of `PAYMENT_METHOD_*` accepted in this stage
+    pub usd_target: u64,       // Stage ends early at this USD raise, 9 decimals (0 = off)
+    pub target_reached: u8,    // 1 once ended by `usd_target` before selling out
+}
+
+// SAFETY: the struct is packed and every field is plain old data, so it has no padding.
+unsafe impl bytemuck::Zeroable for PresaleStage {}
+unsafe impl bytemuck::Pod for PresaleStage {}
+
+impl PresaleStage {
+    /// Builds a stage that has not sold anything yet and accepts every payment method.
+    pub fn new(
+        stage: u8,
+        price: u64,
+        usd_price: u64,
+        allocation: u64,
+        vesting_preset: VestingPreset,
+    ) -> Self {
+        PresaleStage {
+            stage,
+            price,
+            usd_price,
+            allocation,
+            tokens_sold: 0,
+            total_raised: 0,
+            total_raised_usd: 0,
+            vesting_preset: vesting_preset.index() as u8,
+            payment_methods: PAYMENT_METHODS_ALL,
+            usd_target: 0,
+            target_reached: 0,
+        }
+    }
+
+    /// Vesting terms applied to tokens bought in this stage.
+    pub fn vesting_preset(&self) -> VestingPreset {
+        VestingPreset::ALL[self.vesting_preset as usize]
+    }
+
+    /// Whether the stage is still selling: neither sold out nor past its USD target.
+    pub fn is_open(&self) -> bool {
+        self.target_reached == 0 && self.tokens_sold < self.allocation
+    }
+
+    /// USD raised in this stage, 9 decimals: stablecoin payments plus SOL payments valued at
+    /// `sol_usd_price` (8 decimals).
+    pub fn usd_raised(&self, sol_usd_price: u64) -> u64 {
+        let sol_usd = (self.total_raised as u128) * (sol_usd_price as u128) / PRICE_SCALE as u128;
+        self.total_raised_usd.checked_add(sol_usd as u64).unwrap()
+    }
+
+    /// Tokens that can still be sold before the USD target is hit, for purchases paid with
+    /// `payment_method`. `None` when the target doesn't apply.
+    pub fn tokens_to_usd_target(&self, payment_method: u8, sol_usd_price: u64) -> Option<u128> {
+        if self.usd_target == 0 || sol_usd_price == 0 {
+            return None;
+        }
+        // USD price per token, 8 decimals.
+        let usd_price = if payment_method == PAYMENT_METHOD_STABLECOIN {
+            self.usd_price as u128
+        } else {
+            (self.price as u128) * (sol_usd_price as u128) / PRICE_SCALE as u128
+        };
+        if usd_price == 0 {
+            return None;
+        }
+        let usd_left = self.usd_target.saturating_sub(self.usd_raised(sol_usd_price)) as u128;
+        // Rounded up, so that selling them reaches the target.
+        Some((usd_left * PRICE_SCALE as u128).div_ceil(usd_price))
+    }
+
+    /// Price per token used for the given `PAYMENT_METHOD_*`.
+    pub fn price_for(&self, payment_method: u8) -> u64 {
+        if payment_method == PAYMENT_METHOD_STABLECOIN {
+            self.usd_price
+        } else {
+            self.price
+        }
+    }
+
+    /// Whether this stage accepts the given `PAYMENT_METHOD_*`.
+    pub fn accepts(&self, payment_method: u8) -> bool {
+        self.payment_methods & payment_method != 0
+    }
+
+    /// Percent of this stage's allocation that has been sold, in basis points.
+    pub fn percent_sold_bps(&self) -> u16 {
+        if self.allocation == 0 {
+            return 10_000;
+        }
+        let bps = (self.tokens_sold as u128) * 10_000 / (self.allocation as u128);
+        bps.min(10_000) as u16
+    }
+}
+
+/// Preset vesting templates, so frontends select a variant instead of encoding
+/// raw cliff/duration numbers.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum VestingPreset {
+    /// 100% unlocked at TGE.
+    FullUnlock,
+    /// 25% at TGE, remainder released linearly over 6 months.
+    Tge25Linear6Months,
+    /// 10% at TGE, 3-month cliff, remainder released linearly over 12 months.
+    Tge10Cliff3MonthsLinear12Months,
+    /// Nothing at TGE, 6-month cliff, remainder released linearly over 12 months.
+    Cliff6MonthsLinear12Months,
+}
+
+/// Raw vesting terms resolved from a `VestingPreset`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct VestingTerms {
+    pub tge_unlock_percent: u64, // Percent released at TGE
+    pub cliff_duration: i64,     // Seconds after TGE before linear release starts
+    pub linear_duration: i64,    // Seconds over which the remainder is released
+}
+
+impl VestingPreset {
+    pub const ALL: [VestingPreset; 4] = [
+        VestingPreset::FullUnlock,
+        VestingPreset::Tge25Linear6Months,
+        VestingPreset::Tge10Cliff3MonthsLinear12Months,
+        VestingPreset::Cliff6MonthsLinear12Months,
+    ];
+
+    /// Position of the preset in `VestingPreset::ALL`.
+    pub fn index(&self) -> usize {
+        match self {
+            VestingPreset::FullUnlock => 0,
+            VestingPreset::Tge25Linear6Months => 1,
+            VestingPreset::Tge10Cliff3MonthsLinear12Months => 2,
+            VestingPreset::Cliff6MonthsLinear12Months => 3,
+        }
+    }
+
+    pub fn terms(&self) -> VestingTerms {
+        match self {
+            VestingPreset::FullUnlock => VestingTerms {
+                tge_unlock_percent: 100,
+                cliff_duration: 0,
+                linear_duration: 0,
+            },
+            VestingPreset::Tge25Linear6Months => VestingTerms {
+                tge_unlock_percent: 25,
+                cliff_duration: 0,
+                linear_duration: 180 * 24 * 3600, // 6 months
+            },
+            VestingPreset::Tge10Cliff3MonthsLinear12Months => VestingTerms {
+                tge_unlock_percent: 10,
+                cliff_duration: 90 * 24 * 3600,   // 3 months
+                linear_duration: 365 * 24 * 3600, // 12 months
+            },
+            VestingPreset::Cliff6MonthsLinear12Months => VestingTerms {
+                tge_unlock_percent: 0,
+                cliff_duration: 180 * 24 * 3600,  // 6 months
+                linear_duration: 365 * 24 * 3600, // 12 months
+            },
+        }
+    }
+}
+
+/// Per-beneficiary vesting of presale allocations, released from the vesting vault
+/// starting at TGE (`PresaleState::launch_time`).
+#[account]
+pub struct VestingSchedule {
+    pub beneficiary: Pubkey,           // Wallet the tokens vest to
+    pub vesting_preset: VestingPreset, // Terms shared by every receipt in this schedule
+    pub total_amount: u64,             // Tokens vesting under this schedule
+    pub claimed_amount: u64,           // Tokens released so far
+}
+
+impl VestingSchedule {
+    /// Tokens vested at `now` for a TGE at `tge`: the TGE unlock, then linear release
+    /// of the remainder after the cliff.
+    pub fn vested_amount(&self, tge: i64, now: i64) -> u64 {
+        if now < tge {
+            return 0;
+        }
+        let terms = self.vesting_preset.terms();
+        let total = self.total_amount as u128;
+        let unlocked_at_tge = total * terms.tge_unlock_percent as u128 / 100;
+        let linear_start = tge + terms.cliff_duration;
+        if now < linear_start {
+            return unlocked_at_tge as u64;
+        }
+        let elapsed = now - linear_start;
+        if terms.linear_duration == 0 || elapsed >= terms.linear_duration {
+            return self.total_amount;
+        }
+        let linear = (total - unlocked_at_tge) * elapsed as u128 / terms.linear_duration as u128;
+        (unlocked_at_tge + linear) as u64
+    }
+
+    /// Vested tokens not yet released.
+    pub fn claimable(&self, tge: i64, now: i64) -> u64 {
+        self.vested_amount(tge, now).saturating_sub(self.claimed_amount)
+    }
+}
+
+/// A repeat buyer's presale allocations merged across rounds and stages by
+/// `consolidate_allocations`: one vesting schedule per set of vesting terms, all claimed
+/// together by `claim_consolidated_vesting`.
+#[account]
+pub struct AllocationAggregate {
+    pub beneficiary: Pubkey,
+    pub total_amounts: [u64; 4],   // Tokens vesting under each `VestingPreset::ALL` entry
+    pub claimed_amounts: [u64; 4], // Tokens released under each entry
+    pub receipts: u64,             // Receipts consolidated so far
+}
+
+impl AllocationAggregate {
+    /// The vesting schedule for the `index`th entry of `VestingPreset::ALL`.
+    pub fn schedule(&self, index: usize) -> VestingSchedule {
+        VestingSchedule {
+            beneficiary: self.beneficiary,
+            vesting_preset: VestingPreset::ALL[index],
+            total_amount: self.total_amounts[index],
+            claimed_amount: self.claimed_amounts[index],
+        }
+    }
+
+    /// Vested tokens not yet released, per entry of `VestingPreset::ALL`.
+    pub fn claimable(&self, tge: i64, now: i64) -> [u64; 4] {
+        let mut claimable = [0u64; 4];
+        for (index, amount) in claimable.iter_mut().enumerate() {
+            *amount = self.schedule(index).claimable(tge, now);
+        }
+        claimable
+    }
+}
+
+#[account(zero_copy)]
+pub struct PresaleStageInfo {
+    pub stages: [PresaleStage; 8],
+}
+
+impl PresaleStageInfo {
+    /// Work out what `amount_in` buys at the current stage prices, spilling over into
+    /// the following stages when the current one sells out. `amount_in` is in lamports for
+    /// SOL and in USD with 9 decimals for stablecoins. The purchase stops at the first
+    /// stage that does not accept `payment_method`. With a `sol_usd_price`, a stage with a
+    /// USD target also ends once the target is hit. Does not modify any state.
+    pub fn quote_purchase(
+        &self,
+        amount_in: u64,
+        payment_method: u8,
+        sol_usd_price: u64,
+    ) -> PurchaseQuote {
+        let mut quote = PurchaseQuote {
+            sol_usd_price,
+            ..Default::default()
+        };
+        let mut budget = amount_in as u128;
+        let first = match self.current_stage_index() {
+            Some(index) => index,
+            None => return quote,
+        };
+        for index in first..self.stages.len() {
+            let stage = &self.stages[index];
+            if !stage.accepts(payment_method) {
+                break;
+            }
+            if !stage.is_open() {
+                continue;
+            }
+            let mut remaining = stage.allocation.saturating_sub(stage.tokens_sold) as u128;
+            if let Some(tokens) = stage.tokens_to_usd_target(payment_method, sol_usd_price) {
+                remaining = remaining.min(tokens);
+            }
+            let price = stage.price_for(payment_method) as u128;
+            if remaining == 0 || price == 0 {
+                continue;
+            }
+            // Amount needed to buy out the rest of this stage (rounded up).
+            let cost_remaining =
+                (remaining * price).div_ceil(PRICE_SCALE as u128);
+            let (tokens, cost) = if budget >= cost_remaining {
+                (remaining, cost_remaining)
+            } else {
+                let tokens = budget * PRICE_SCALE as u128 / price;
+                (tokens, budget)
+            };
+            if tokens == 0 {
+                break;
+            }
+            if quote.first_stage == 0 {
+                quote.first_stage = stage.stage;
+            }
+            quote.last_stage = stage.stage;
+            quote.tokens_per_stage[index] = tokens as u64;
+            quote.lamports_per_stage[index] = cost as u64;
+            quote.tokens_allocated += tokens as u64;
+            quote.lamports_used += cost as u64;
+            quote.sol_value += if payment_method == PAYMENT_METHOD_SOL {
+                cost as u64
+            } else {
+                (tokens * stage.price as u128).div_ceil(PRICE_SCALE as u128)
+                    as u64
+            };
+            quote.remaining_in_current_stage = (remaining - tokens) as u64;
+            budget -= cost;
+            if budget == 0 {
+                break;
+            }
+        }
+        quote
+    }
+
+    /// Record a quoted purchase against the stages it was spread over, ending the stages
+    /// whose USD target it reached.
+    pub fn apply_purchase(&mut self, quote: &PurchaseQuote, payment_method: u8) {
+        for (index, stage) in self.stages.iter_mut().enumerate() {
+            stage.tokens_sold = stage
+                .tokens_sold
+                .checked_add(quote.tokens_per_stage[index])
+                .unwrap();
+            // Fields of a zero-copy stage are updated by value; the struct is packed.
+            if payment_method == PAYMENT_METHOD_STABLECOIN {
+                stage.total_raised_usd = stage
+                    .total_raised_usd
+                    .checked_add(quote.lamports_per_stage[index])
+                    .unwrap();
+            } else {
+                stage.total_raised = stage
+                    .total_raised
+                    .checked_add(quote.lamports_per_stage[index])
+                    .unwrap();
+            }
+            if stage.usd_target > 0
+                && quote.sol_usd_price > 0
+                && stage.usd_raised(quote.sol_usd_price) >= stage.usd_target
+            {
+                stage.target_reached = 1;
+            }
+        }
+    }
+
+    /// Index of the first stage that is still selling, if any.
+    pub fn current_stage_index(&self) -> Option<usize> {
+        self.stages.iter().position(|stage| stage.is_open())
+    }
+
+    /// SOL price of the last stage that sold anything, used to value tokens at launch.
+    pub fn listing_price(&self) -> u64 {
+        self.stages
+            .iter()
+            .rev()
+            .find(|stage| stage.tokens_sold > 0)
+            .unwrap_or(&self.stages[0])
+            .price
+    }
+
+    /// Total raised across all stages.
+    pub fn total_raised(&self) -> u64 {
+        self.stages
+            .iter()
+            .fold(0u64, |acc, stage| acc.checked_add(stage.total_raised).unwrap())
+    }
+}
+
+/// Immutable end-of-presale summary written once by `finalize_round` after governance approved
+/// it; the canonical on-chain record of the round for auditors and the community.
+#[account]
+pub struct RoundReport {
+    pub presale_state: Pubkey,
+    pub total_raised_lamports: u64,   // SOL raised across all stages
+    pub total_raised_usd: u64,        // USD raised via stablecoins, 9 decimals
+    pub tokens_sold: u64,
+    pub buyers: u64,                  // Wallets with at least one purchase
+    pub purchases: u64,
+    pub average_purchase_tokens: u64, // Tokens per purchase, across all payment methods
+    pub stage_prices: [u64; 8],       // Final SOL price of each stage, 8 decimals
+    pub stage_usd_prices: [u64; 8],   // Final USD price of each stage, 8 decimals
+    pub approval_proposal_id: u64,    // Governance proposal that approved the report
+    pub finalized_at: i64,
+}
+
+/// Breakdown of a presale purchase across stages, as computed by `quote_purchase`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default)]
+pub struct PurchaseQuote {
+    pub tokens_allocated: u64,           // Total tokens bought (base units)
+    pub lamports_used: u64,              // Amount actually spent (see `quote_purchase`); any excess is not charged
+    pub first_stage: u8,                 // 1-based stage the purchase started in (0 if nothing was bought)
+    pub last_stage: u8,                  // 1-based stage the purchase ended in
+    pub remaining_in_current_stage: u64, // Tokens left in `last_stage` after this purchase
+    pub tokens_per_stage: [u64; 8],      // Tokens bought in each stage
+    pub lamports_per_stage: [u64; 8],    // Amount spent in each stage
+    pub sol_usd_price: u64,              // SOL/USD price used for stage USD targets (0 = none)
+    pub launch_tax: u64,                 // Launch-tax tokens owed to the fee wallet, not in `tokens_allocated`
+    pub sol_value: u64,                  // Value in lamports at the stages' SOL prices
+}
+
+/// Confirmation details returned by purchase instructions via `set_return_data`,
+/// so wallets can show the result without refetching accounts.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct PurchaseResult {
+    pub tokens_allocated: u64,
+    pub first_stage: u8,
+    pub last_stage: u8,
+    pub remaining_in_current_stage: u64,
+}
+
+impl PurchaseQuote {
+    /// Token-weighted average stage of this purchase, ×100.
+    pub fn weighted_entry_stage(&self) -> u64 {
+        let total = self.tokens_allocated.checked_add(self.launch_tax).unwrap();
+        if total == 0 {
+            return 0;
+        }
+        let weighted: u128 = self
+            .tokens_per_stage
+            .iter()
+            .enumerate()
+            .map(|(index, tokens)| (index as u128 + 1) * 100 * (*tokens as u128))
+            .sum();
+        (weighted / total as u128) as u64
+    }
+
+    /// Publish the purchase result as the instruction's return data.
+    pub fn set_return_data(&self) -> Result<()> {
+        let result = PurchaseResult {
+            tokens_allocated: self.tokens_allocated,
+            first_stage: self.first_stage,
+            last_stage: self.last_stage,
+            remaining_in_current_stage: self.remaining_in_current_stage,
+        };
+        solana_program::program::set_return_data(&result.try_to_vec()?);
+        Ok(())
+    }
+}
+
+/// Compact presale summary for the website's progress bar, so it can read one
+/// small account instead of the whole stage array. Refreshed on every purchase
+/// and stage update.
+#[account]
+pub struct PresaleProgress {
+    pub current_stage: u8,           // 1-based stage currently selling (0 once all stages are sold out)
+    pub stage_percent_sold_bps: u16, // Percent of the current stage sold, in basis points
+    pub total_raised: u64,           // Total raised across all stages
+    pub presale_end_time: i64,       // Scheduled end of the presale
+    pub time_remaining: i64,         // Seconds left until `presale_end_time` as of `last_updated`
+    pub last_updated: i64,           // Timestamp of the last refresh
+}
+
+impl PresaleProgress {
+    pub fn refresh(&mut self, presale_stage_info: &PresaleStageInfo, now: i64) {
+        match presale_stage_info.current_stage_index() {
+            Some(index) => {
+                let stage = &presale_stage_info.stages[index];
+                self.current_stage = stage.stage;
+                self.stage_percent_sold_bps = stage.percent_sold_bps();
+            }
+            None => {
+                self.current_stage = 0;
+                self.stage_percent_sold_bps = 10_000;
+            }
+        }
+        self.total_raised = presale_stage_info.total_raised();
+        self.time_remaining = (self.presale_end_time - now).max(0);
+        self.last_updated = now;
+    }
+}
+
+/// Bounded allowance for the designated market maker to pull tokens and SOL from the
+/// dedicated market-making vaults, limited per epoch. Every pull emits `MarketMakerPull`;
+/// the admin or a governance proposal can revoke the allowance at any time.
+#[account]
+pub struct MarketMakerAllowance {
+    pub market_maker: Pubkey,            // Key allowed to pull from the market-making vaults
+    pub token_limit_per_epoch: u64,      // Max tokens pulled per epoch
+    pub lamport_limit_per_epoch: u64,    // Max lamports pulled per epoch
+    pub epoch: u64,                      // Epoch that the `*_this_epoch` counters refer to
+    pub tokens_pulled_this_epoch: u64,   // Tokens pulled in `epoch`
+    pub lamports_pulled_this_epoch: u64, // Lamports pulled in `epoch`
+    pub total_tokens_pulled: u64,        // Tokens pulled over the allowance's lifetime
+    pub total_lamports_pulled: u64,      // Lamports pulled over the allowance's lifetime
+    pub revoked: bool,                   // Pulls are rejected once revoked
+}
+
+impl MarketMakerAllowance {
+    /// Count a pull against the per-epoch limits, resetting the counters on a new epoch.
+    pub fn record_pull(&mut self, tokens: u64, lamports: u64, epoch: u64) -> Result<()> {
+        require!(!self.revoked, ErrorCode::MarketMakerRevoked);
+        if epoch != self.epoch {
+            self.epoch = epoch;
+            self.tokens_pulled_this_epoch = 0;
+            self.lamports_pulled_this_epoch = 0;
+        }
+        let tokens_pulled = self.tokens_pulled_this_epoch.checked_add(tokens).unwrap();
+        let lamports_pulled = self.lamports_pulled_this_epoch.checked_add(lamports).unwrap();
+        require!(
+            tokens_pulled <= self.token_limit_per_epoch
+                && lamports_pulled <= self.lamport_limit_per_epoch,
+            ErrorCode::MarketMakerLimitExceeded
+        );
+        self.tokens_pulled_this_epoch = tokens_pulled;
+        self.lamports_pulled_this_epoch = lamports_pulled;
+        self.total_tokens_pulled = self.total_tokens_pulled.checked_add(tokens).unwrap();
+        self.total_lamports_pulled = self.total_lamports_pulled.checked_add(lamports).unwrap();
+        Ok(())
+    }
+}
+
+/// What a SOL deposit into the treasury is for.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum DepositPurpose {
+    Donation,
+    LiquidityFunding,
+    Operations,
+}
+
+/// Treasury SOL inflows through `deposit_sol`, totalled per purpose. Each deposit is also
+/// logged with its attribution memo hash in a `SolDeposited` event.
+#[account]
+pub struct DepositLog {
+    pub deposit_count: u64,     // Deposits so far; the next deposit's id
+    pub donations: u64,         // Lamports deposited as donations
+    pub liquidity_funding: u64, // Lamports deposited to fund liquidity
+    pub operations: u64,        // Lamports deposited for operations
+}
+
+impl DepositLog {
+    /// Add a deposit of `amount` lamports to its purpose's total; returns the deposit's id.
+    pub fn record(&mut self, purpose: DepositPurpose, amount: u64) -> u64 {
+        let total = match purpose {
+            DepositPurpose::Donation => &mut self.donations,
+            DepositPurpose::LiquidityFunding => &mut self.liquidity_funding,
+            DepositPurpose::Operations => &mut self.operations,
+        };
+        *total = total.checked_add(amount).unwrap();
+        let deposit_id = self.deposit_count;
+        self.deposit_count = self.deposit_count.checked_add(1).unwrap();
+        deposit_id
+    }
+}
+
+/// Team buy-in program: team members buy tokens at market with their own SOL through the
+/// allow-listed swap program, the treasury adds `match_bps` of the tokens bought, and both
+/// halves vest from the team escrow vault.
+#[account]
+pub struct TeamBuyInConfig {
+    pub swap_program: Pubkey,  // DEX program buy-ins must swap through
+    pub match_bps: u16,        // Treasury match on tokens bought
+    pub match_budget: u64,     // Treasury tokens still available for matching
+    pub cliff_duration: i64,   // Seconds after a buy-in before any of it vests
+    pub vesting_duration: i64, // Seconds of linear vesting after the cliff
+    pub total_purchased: u64,  // Tokens bought by team members across all buy-ins
+    pub total_matched: u64,    // Tokens matched by the treasury across all buy-ins
+}
+
+/// A wallet admitted to the team buy-in program.
+#[account]
+pub struct TeamMember {
+    pub wallet: Pubkey,
+    pub buy_in_count: u64,   // Buy-ins so far; the next buy-in's id
+    pub lamports_spent: u64, // Wrapped SOL the member has spent on buy-ins
+    pub purchased: u64,      // Tokens the member has bought
+    pub matched: u64,        // Tokens the treasury has matched
+}
+
+/// Tokens from one team buy-in (bought plus matched), vesting on the terms in force at the time.
+#[account]
+pub struct TeamEscrow {
+    pub member: Pubkey,
+    pub buy_in_id: u64,
+    pub lamports_spent: u64, // Wrapped SOL spent on the swap
+    pub purchased: u64,      // Tokens received from the swap
+    pub matched: u64,        // Tokens added by the treasury
+    pub released: u64,       // Tokens released to the member so far
+    pub start_time: i64,     // Time of the buy-in
+    pub cliff_duration: i64,
+    pub vesting_duration: i64,
+}
+
+impl TeamEscrow {
+    /// Tokens vested at `now`: nothing before the cliff, then linear over `vesting_duration`.
+    pub fn vested_amount(&self, now: i64) -> u64 {
+        let total = self.purchased.checked_add(self.matched).unwrap();
+        let linear_start = self.start_time + self.cliff_duration;
+        if now < linear_start {
+            return 0;
+        }
+        let elapsed = now - linear_start;
+        if elapsed >= self.vesting_duration {
+            return total;
+        }
+        (total as u128 * elapsed as u128 / self.vesting_duration as u128) as u64
+    }
+
+    /// Vested tokens not yet released.
+    pub fn releasable(&self, now: i64) -> u64 {
+        self.vested_amount(now).saturating_sub(self.released)
+    }
+}
+
+//
+// HELPERS
+//
+
+/// Deserialize the program account held by `info`, checking its owner and discriminator like
+/// `Account<T>`. Used for accounts an instruction only reads or updates when they exist.
+pub fn load_account<T: AccountDeserialize + Owner>(info: &AccountInfo) -> Result<T> {
+    if info.owner != &T::owner() {
+        return Err(anchor_lang::error::ErrorCode::AccountOwnedByWrongProgram.into());
+    }
+    T::try_deserialize(&mut &info.try_borrow_data()?[..])
+}
+
+/// Load the token account in `info`, owned by either SPL Token or Token-2022.
+pub fn load_token_account(info: &AccountInfo) -> Result<TokenAccount> {
+    if !<TokenAccount as anchor_lang::Owners>::owners().contains(info.owner) {
+        return Err(anchor_lang::error::ErrorCode::AccountOwnedByWrongProgram.into());
+    }
+    TokenAccount::try_deserialize(&mut &info.try_borrow_data()?[..])
+}
+
+/// Move `amount` with `transfer_checked` and return what the destination actually received,
+/// which is less than `amount` while the mint charges a Token-2022 transfer fee. Inbound
+/// transfers credit program accounting with this so it matches what the vaults hold.
+pub fn transfer_received<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, TransferChecked<'info>>,
+    amount: u64,
+    decimals: u8,
+) -> Result<u64> {
+    let to = ctx.accounts.to.clone();
+    let before = load_token_account(&to)?.amount;
+    token_interface::transfer_checked(ctx, amount, decimals)?;
+    Ok(load_token_account(&to)?.amount.checked_sub(before).unwrap())
+}
+
+/// Write `account`, loaded with `load_account`, back into `info`.
+pub fn store_account<T: AccountSerialize>(info: &AccountInfo, account: &T) -> Result<()> {
+    let mut data = info.try_borrow_mut_data()?;
+    let mut writer: &mut [u8] = &mut data;
+    account.try_serialize(&mut writer)
+}
+
+/// Mutably borrow the zero-copy account held by `info`, checking its owner and discriminator
+/// like `AccountLoader<T>`.
+pub fn load_zero_copy_mut<'a, T: anchor_lang::ZeroCopy + Owner>(
+    info: &'a AccountInfo,
+) -> Result<std::cell::RefMut<'a, T>> {
+    if info.owner != &T::owner() {
+        return Err(anchor_lang::error::ErrorCode::AccountOwnedByWrongProgram.into());
+    }
+    let data = info.try_borrow_mut_data()?;
+    require!(
+        data.len() >= 8 + std::mem::size_of::<T>() && data[..8] == T::discriminator(),
+        anchor_lang::error::ErrorCode::AccountDiscriminatorMismatch
+    );
+    Ok(std::cell::RefMut::map(data, |data| {
+        bytemuck::from_bytes_mut(&mut data[8..8 + std::mem::size_of::<T>()])
+    }))
+}
+
+/// `a * b / c`, rounded down, for products that may not fit in a `u128` as long as
+/// `(a % c) * b` does.
+pub fn mul_div(a: u128, b: u128, c: u128) -> u128 {
+    (a / c)
+        .checked_mul(b)
+        .unwrap()
+        .checked_add((a % c).checked_mul(b).unwrap() / c)
+        .unwrap()
+}
+
+/// Verify a merkle proof for `leaf` against `root`. Pairs are hashed in sorted order,
+/// so proofs don't need to carry left/right flags.
+pub fn verify_merkle_proof(proof: &[[u8; 32]], root: [u8; 32], leaf: [u8; 32]) -> bool {
+    let mut computed = leaf;
+    for node in proof.iter() {
+        computed = if computed <= *node {
+            keccak::hashv(&[&computed, node]).0
+        } else {
+            keccak::hashv(&[node, &computed]).0
+        };
+    }
+    computed == root
+}
+
+/// Whether `info` is an initialized token account of `mint` owned by `owner`.
+pub fn is_token_account_of(info: &AccountInfo, owner: Pubkey, mint: Pubkey) -> bool {
+    match load_token_account(info) {
+        Ok(token_account) => token_account.owner == owner && token_account.mint == mint,
+        Err(_) => false,
+    }
+}
+
+//
+// PROGRAM
+//
+#[program]
+pub mod brats_contract {
+    use super::*;
+
+    /// Initialize the presale state, roles, global state, presale stages, staking config and the
+    /// distribution, stake and reward vaults at their canonical PDAs in one transaction.
+    /// `admin` may be a wallet or a multisig PDA (e.g. a Squads vault); admin-gated
+    /// instructions check it with `has_one = admin`. `vesting_preset` selects the vesting terms
+    /// applied to every stage (adjustable per stage later).
+    /// Idempotent: once initialized, calling it again changes nothing.
+    /// The treasury and presale vault PDAs are signer-only addresses and need no account.
+    pub fn initialize_all(
+        ctx: Context<InitializeAll>,
+        admin: Pubkey,
+        apy: u64,
+        transaction_fee_percent: u64,
+        vesting_preset: VestingPreset,
+    ) -> Result<()> {
+        if ctx.accounts.presale_state.admin != Pubkey::default() {
+            return Ok(());
+        }
+        require!(admin != Pubkey::default(), ErrorCode::Unauthorized);
+        require!(transaction_fee_percent <= 100, ErrorCode::InvalidFeeConfig);
+        require!(ctx.accounts.mint.decimals == 9, ErrorCode::InvalidMint);
+
+        let presale_state = &mut ctx.accounts.presale_state;
+        presale_state.is_presale_active = true;
+        presale_state.presale_end_time = None;
+        presale_state.launch_time = None;
+        presale_state.admin = admin;
+        presale_state.liquidity_locked = false;
+        presale_state.liquidity_lock_end_time = None;
+
+        // Both roles start with the admin until `set_roles` hands them out.
+        let roles = &mut ctx.accounts.roles;
+        roles.operator = admin;
+        roles.treasurer = admin;
+
+        let global_state = &mut ctx.accounts.global_state;
+        global_state.total_staked = 0;
+        global_state.reward_pool = 0;
+        global_state.apy = apy;
+        global_state.transaction_fee_percent = transaction_fee_percent;
+        global_state.unique_stakers = 0;
+        global_state.total_positions = 0;
+        global_state.early_unstake_penalty_percent = EARLY_UNSTAKE_PENALTY_PERCENT;
+        global_state.acc_reward_per_share = 0;
+        global_state.last_reward_update = Clock::get()?.unix_timestamp;
+
+        // `init_if_needed` leaves a re-run with an existing account, which `load_init` rejects.
+        let mut presale_stage_info = match ctx.accounts.presale_stage_info.load_mut() {
+            Ok(presale_stage_info) => presale_stage_info,
+            Err(_) => ctx.accounts.presale_stage_info.load_init()?,
+        };
+        presale_stage_info.stages = [
+            // Prices are stored with 8 decimals (e.g. 0.00021 -> 21000); allocations are in base units.
+            // SOL prices start equal to the USD prices and are retuned via `update_presale_stage`.
+            PresaleStage::new(1, 21000, 21000, 2_500_000_000 * TOKEN_UNIT, vesting_preset),
+            PresaleStage::new(2, 25000, 25000, 2_500_000_000 * TOKEN_UNIT, vesting_preset),
+            PresaleStage::new(3, 29000, 29000, 2_500_000_000 * TOKEN_UNIT, vesting_preset),
+            PresaleStage::new(4, 33000, 33000, 2_500_000_000 * TOKEN_UNIT, vesting_preset),
+            PresaleStage::new(5, 37000, 37000, 2_500_000_000 * TOKEN_UNIT, vesting_preset),
+            PresaleStage::new(6, 41000, 41000, 2_500_000_000 * TOKEN_UNIT, vesting_preset),
+            PresaleStage::new(7, 45000, 45000, 2_500_000_000 * TOKEN_UNIT, vesting_preset),
+            PresaleStage::new(8, 49000, 49000, 2_500_000_000 * TOKEN_UNIT, vesting_preset),
+        ];
+        Ok(())
+    }
+
+    /// Initialize the program config with the recovery key, inactivity period and the wallet
+    /// receiving fee portions (for both SOL and SPL tokens). (Admin only)
+    pub fn initialize_program_config(
+        ctx: Context<InitializeProgramConfig>,
+        recovery_key: Pubkey,
+        admin_inactivity_period: i64,
+        burn_cap_per_epoch: u64,
+        fee_wallet: Pubkey,
+    ) -> Result<()> {
+        require!(admin_inactivity_period > 0, ErrorCode::InvalidInactivityPeriod);
+        require!(fee_wallet != Pubkey::default(), ErrorCode::InvalidFeeWallet);
+        let clock = Clock::get()?;
+        let program_config = &mut ctx.accounts.program_config;
+        program_config.recovery_key = recovery_key;
+        program_config.admin_inactivity_period = admin_inactivity_period;
+        program_config.burn_cap_per_epoch = burn_cap_per_epoch;
+        program_config.burn_epoch = clock.epoch;
+        program_config.burned_this_epoch = 0;
+        program_config.fee_wallet = fee_wallet;
+        program_config.staking_lock_period = STAKING_DURATION;
+        program_config.early_unstake_period = EARLY_UNSTAKE_PERIOD;
+        program_config.liquidity_lock_period = LIQUIDITY_LOCK_PERIOD;
+        program_config.record_admin_activity(clock.unix_timestamp);
+        Ok(())
+    }
+
+    /// Pause or resume individual subsystems: `paused_operations` is the full set of `PAUSE_*`
+    /// bits that should be paused after the call. (Admin only)
+    pub fn set_paused_operations(ctx: Context<AdminConfig>, paused_operations: u8) -> Result<()> {
+        require!(paused_operations & !PAUSE_ALL == 0, ErrorCode::InvalidPauseFlags);
+        let program_config = &mut ctx.accounts.program_config;
+        let now = Clock::get()?.unix_timestamp;
+        program_config.record_admin_activity(now);
+        program_config.paused_operations = paused_operations;
+        emit!(PausedOperationsChanged {
+            paused_operations: program_config.paused_operations,
+            timestamp: now,
+        });
+        Ok(())
+    }
+
+    /// Circuit breaker: pause purchases, staking, unstaking, claims and liquidity operations
+    /// at once. (Admin only)
+    pub fn pause(ctx: Context<AdminConfig>) -> Result<()> {
+        let program_config = &mut ctx.accounts.program_config;
+        let now = Clock::get()?.unix_timestamp;
+        program_config.record_admin_activity(now);
+        program_config.paused_operations = PAUSE_ALL;
+        emit!(PausedOperationsChanged {
+            paused_operations: program_config.paused_operations,
+            timestamp: now,
+        });
+        Ok(())
+    }
+
+    /// Resume every paused subsystem. (Admin only)
+    pub fn unpause(ctx: Context<AdminConfig>) -> Result<()> {
+        let program_config = &mut ctx.accounts.program_config;
+        let now = Clock::get()?.unix_timestamp;
+        program_config.record_admin_activity(now);
+        program_config.paused_operations = 0;
+        emit!(PausedOperationsChanged {
+            paused_operations: program_config.paused_operations,
+            timestamp: now,
+        });
+        Ok(())
+    }
+
+    /// Enable or disable `emergency_unstake`. (Admin only)
+    pub fn set_emergency_unstake(ctx: Context<AdminConfig>, enabled: bool) -> Result<()> {
+        let program_config = &mut ctx.accounts.program_config;
+        program_config.record_admin_activity(Clock::get()?.unix_timestamp);
+        program_config.emergency_unstake_enabled = enabled;
+        Ok(())
+    }
+
+    /// Enable or disable `transfer_stake_position`. (Admin only)
+    pub fn set_position_transfers(ctx: Context<AdminConfig>, enabled: bool) -> Result<()> {
+        let program_config = &mut ctx.accounts.program_config;
+        program_config.record_admin_activity(Clock::get()?.unix_timestamp);
+        program_config.position_transfers_enabled = enabled;
+        Ok(())
+    }
+
+    /// Set the Pyth SOL/USD price feed used for stage USD targets and the max age of its
+    /// price; the default key turns USD targets off. (Admin only)
+    pub fn set_sol_usd_price_feed(
+        ctx: Context<AdminConfig>,
+        price_feed: Pubkey,
+        max_price_age: i64,
+    ) -> Result<()> {
+        require!(max_price_age > 0, ErrorCode::InvalidPriceFeed);
+        let program_config = &mut ctx.accounts.program_config;
+        program_config.record_admin_activity(Clock::get()?.unix_timestamp);
+        program_config.sol_usd_price_feed = price_feed;
+        program_config.max_price_age = max_price_age;
+        Ok(())
+    }
+
+    /// Allow-list the swap program (e.g. Jupiter) used by `buyback_and_burn`. (Admin only)
+    pub fn set_buyback_swap_program(
+        ctx: Context<AdminConfig>,
+        swap_program: Pubkey,
+    ) -> Result<()> {
+        let program_config = &mut ctx.accounts.program_config;
+        program_config.record_admin_activity(Clock::get()?.unix_timestamp);
+        program_config.buyback_swap_program = swap_program;
+        Ok(())
+    }
+
+    /// Configure the anti-whale limits applied to token payouts for `protection_period`
+    /// seconds after launch. A zero limit disables that check. (Admin only)
+    pub fn set_launch_limits(
+        ctx: Context<AdminConfig>,
+        max_tx_amount: u64,
+        max_wallet_bps: u16,
+        protection_period: i64,
+    ) -> Result<()> {
+        require!(max_wallet_bps <= 10_000, ErrorCode::InvalidFeeConfig);
+        require!(protection_period >= 0, ErrorCode::InvalidLockPeriod);
+        let program_config = &mut ctx.accounts.program_config;
+        program_config.record_admin_activity(Clock::get()?.unix_timestamp);
+        program_config.max_tx_amount = max_tx_amount;
+        program_config.max_wallet_bps = max_wallet_bps;
+        program_config.launch_protection_period = protection_period;
+        Ok(())
+    }
+
+    /// Lift the anti-whale limits ahead of the end of the launch window. (Admin only)
+    pub fn lift_launch_limits(ctx: Context<AdminConfig>) -> Result<()> {
+        let program_config = &mut ctx.accounts.program_config;
+        program_config.record_admin_activity(Clock::get()?.unix_timestamp);
+        program_config.max_tx_amount = 0;
+        program_config.max_wallet_bps = 0;
+        program_config.launch_protection_period = 0;
+        Ok(())
+    }
+
+    /// Configure the anti-bot launch guard. Presale purchases open at `presale_opens_at`,
+    /// each wallet may buy once per slot, and a `launch_tax_bps` tax on bought tokens decays
+    /// linearly to 0 over `launch_tax_duration` seconds; taxed tokens are owed to the fee
+    /// wallet and paid out by `claim_launch_tax`. At TGE, no allocation or vested tokens are
+    /// claimed before `trading_opens_at`, so snipers cannot front-run the trading open with
+    /// claimed tokens. (Admin only)
+    pub fn set_launch_guard(
+        ctx: Context<AdminConfig>,
+        presale_opens_at: i64,
+        trading_opens_at: i64,
+        launch_tax_bps: u16,
+        launch_tax_duration: i64,
+    ) -> Result<()> {
+        require!(launch_tax_bps <= 10_000, ErrorCode::InvalidFeeConfig);
+        require!(launch_tax_duration >= 0, ErrorCode::InvalidLockPeriod);
+        let program_config = &mut ctx.accounts.program_config;
+        program_config.record_admin_activity(Clock::get()?.unix_timestamp);
+        program_config.presale_opens_at = presale_opens_at;
+        program_config.trading_opens_at = trading_opens_at;
+        program_config.launch_tax_bps = launch_tax_bps;
+        program_config.launch_tax_duration = launch_tax_duration;
+        Ok(())
+    }
+
+    /// Pay the launch-tax tokens withheld from purchases out of the vesting vault to the fee
+    /// wallet, once the presale has launched and reached its soft cap. (Admin only)
+    pub fn claim_launch_tax(ctx: Context<ClaimLaunchTax>) -> Result<()> {
+        let presale_state = &ctx.accounts.presale_state;
+        require!(presale_state.launch_time.is_some(), ErrorCode::PresaleNotEnded);
+        require!(
+            presale_state.total_raised >= presale_state.soft_cap,
+            ErrorCode::SoftCapNotReached
+        );
+        let amount = presale_state.launch_tax_owed;
+        require!(amount > 0, ErrorCode::InvalidAmount);
+        ctx.accounts.program_config.record_admin_activity(Clock::get()?.unix_timestamp);
+
+        let bump = ctx.bumps.vesting_vault;
+        let seeds: &[&[u8]] = &[b"vesting_vault", &[bump]];
+        token_interface::transfer_checked(
+            ctx.accounts.launch_tax_transfer_context().with_signer(&[seeds]),
+            amount,
+            ctx.accounts.token_mint.decimals,
+        )?;
+        ctx.accounts.presale_state.launch_tax_owed = 0;
+        Ok(())
+    }
+
+    /// Set the $BRATS Token-2022 transfer fee to `TRANSFER_FEE_BPS + TRANSFER_BURN_BPS` and
+    /// route `reward_pool_share_bps` of the non-burned share to the reward pool on harvest,
+    /// the rest to the fee wallet. The mint must have been created with the transfer-fee
+    /// extension and the `fee_authority` PDA as its fee config and withdraw authority. Token-2022
+    /// applies a new fee two epochs after it is set. (Admin only)
+    pub fn set_transfer_fee(
+        ctx: Context<SetTransferFee>,
+        reward_pool_share_bps: u16,
+    ) -> Result<()> {
+        require!(reward_pool_share_bps <= 10_000, ErrorCode::InvalidFeeConfig);
+        let clock = Clock::get()?;
+        let program_config = &mut ctx.accounts.program_config;
+        program_config.record_admin_activity(clock.unix_timestamp);
+        program_config.transfer_fee_reward_bps = reward_pool_share_bps;
+
+        let basis_points = TRANSFER_FEE_BPS + TRANSFER_BURN_BPS;
+        let ix = transfer_fee::instruction::set_transfer_fee(
+            ctx.accounts.token_program.key,
+            &ctx.accounts.token_mint.key(),
+            ctx.accounts.fee_authority.key,
+            &[],
+            basis_points,
+            u64::MAX,
+        )?;
+        let bump = ctx.bumps.fee_authority;
+        let seeds: &[&[u8]] = &[b"fee_authority", &[bump]];
+        solana_program::program::invoke_signed(
+            &ix,
+            &[
+                ctx.accounts.token_mint.to_account_info(),
+                ctx.accounts.fee_authority.to_account_info(),
+            ],
+            &[seeds],
+        )?;
+        emit!(TransferFeeSet {
+            basis_points,
+            reward_pool_share_bps,
+            timestamp: clock.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Collect the transfer fees withheld on the $BRATS token accounts passed as remaining
+    /// accounts and on the mint into the reward vault, then burn the `TRANSFER_BURN_BPS` share
+    /// and split the rest between the reward pool and the fee wallet. Withdrawing withheld fees
+    /// is not itself charged, so the reward pool is credited in full; the fee wallet's share is
+    /// an ordinary transfer whose fee is collected again by a later harvest. Anyone may call this.
+    pub fn harvest_transfer_fees<'info>(
+        ctx: Context<'_, '_, '_, 'info, HarvestTransferFees<'info>>,
+    ) -> Result<()> {
+        let sources: Vec<&Pubkey> = ctx.remaining_accounts.iter().map(|info| info.key).collect();
+        let ix = transfer_fee::instruction::harvest_withheld_tokens_to_mint(
+            ctx.accounts.token_program.key,
+            &ctx.accounts.token_mint.key(),
+            &sources,
+        )?;
+        let mut infos = vec![ctx.accounts.token_mint.to_account_info()];
+        infos.extend(ctx.remaining_accounts.iter().cloned());
+        solana_program::program::invoke(&ix, &infos)?;
+
+        let vault_before = ctx.accounts.reward_vault.amount;
+        let ix = transfer_fee::instruction::withdraw_withheld_tokens_from_mint(
+            ctx.accounts.token_program.key,
+            &ctx.accounts.token_mint.key(),
+            &ctx.accounts.reward_vault.key(),
+            ctx.accounts.fee_authority.key,
+            &[],
+        )?;
+        let bump = ctx.bumps.fee_authority;
+        let seeds: &[&[u8]] = &[b"fee_authority", &[bump]];
+        solana_program::program::invoke_signed(
+            &ix,
+            &[
+                ctx.accounts.token_mint.to_account_info(),
+                ctx.accounts.reward_vault.to_account_info(),
+                ctx.accounts.fee_authority.to_account_info(),
+            ],
+            &[seeds],
+        )?;
+        ctx.accounts.reward_vault.reload()?;
+        let amount = ctx.accounts.reward_vault.amount.checked_sub(vault_before).unwrap();
+        require!(amount > 0, ErrorCode::InvalidAmount);
+
+        let burned = mul_div(
+            amount as u128,
+            TRANSFER_BURN_BPS as u128,
+            (TRANSFER_FEE_BPS + TRANSFER_BURN_BPS) as u128,
+        ) as u64;
+        let fees = amount.checked_sub(burned).unwrap();
+        let to_reward_pool = mul_div(
+            fees as u128,
+            ctx.accounts.program_config.transfer_fee_reward_bps as u128,
+            10_000,
+        ) as u64;
+        let to_fee_wallet = fees.checked_sub(to_reward_pool).unwrap();
+        let bump = ctx.bumps.reward_vault;
+        let seeds: &[&[u8]] = &[b"reward_vault", &[bump]];
+        if burned > 0 {
+            token_interface::burn(ctx.accounts.burn_context().with_signer(&[seeds]), burned)?;
+        }
+        if to_fee_wallet > 0 {
+            token_interface::transfer_checked(
+                ctx.accounts.fee_transfer_context().with_signer(&[seeds]),
+                to_fee_wallet,
+                ctx.accounts.token_mint.decimals,
+            )?;
+        }
+        ctx.accounts
+            .global_state
+            .credit_reward_pool(to_reward_pool, RewardSource::FeeShare);
+        emit!(TransferFeesHarvested {
+            amount,
+            burned,
+            to_reward_pool,
+            to_fee_wallet,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Set, per bonus campaign, how long claimed bonuses are locked in the claimant's bonus
+    /// stake position; 0 pays the campaign's bonuses out liquid. (Admin only)
+    pub fn set_bonus_auto_stake(
+        ctx: Context<AdminConfig>,
+        early_bird_lock_period: i64,
+        referral_lock_period: i64,
+    ) -> Result<()> {
+        require!(
+            early_bird_lock_period >= 0 && referral_lock_period >= 0,
+            ErrorCode::InvalidLockPeriod
+        );
+        let program_config = &mut ctx.accounts.program_config;
+        program_config.record_admin_activity(Clock::get()?.unix_timestamp);
+        program_config.early_bird_bonus_lock_period = early_bird_lock_period;
+        program_config.referral_bonus_lock_period = referral_lock_period;
+        Ok(())
+    }
+
+    /// Change the wallet that receives fees. (Admin only)
+    pub fn set_fee_wallet(ctx: Context<AdminConfig>, fee_wallet: Pubkey) -> Result<()> {
+        require!(fee_wallet != Pubkey::default(), ErrorCode::InvalidFeeWallet);
+        let program_config = &mut ctx.accounts.program_config;
+        program_config.record_admin_activity(Clock::get()?.unix_timestamp);
+        program_config.fee_wallet = fee_wallet;
+        Ok(())
+    }
+
+    /// Change the staking, early-unstake and liquidity lock periods, in seconds. Only affects
+    /// liquidity locked after the change. (Admin only)
+    pub fn set_lock_periods(
+        ctx: Context<AdminConfig>,
+        staking_lock_period: i64,
+        early_unstake_period: i64,
+        liquidity_lock_period: i64,
+    ) -> Result<()> {
+        require!(
+            staking_lock_period > 0 && early_unstake_period >= 0 && liquidity_lock_period > 0,
+            ErrorCode::InvalidLockPeriod
+        );
+        let program_config = &mut ctx.accounts.program_config;
+        program_config.record_admin_activity(Clock::get()?.unix_timestamp);
+        program_config.staking_lock_period = staking_lock_period;
+        program_config.early_unstake_period = early_unstake_period;
+        program_config.liquidity_lock_period = liquidity_lock_period;
+        Ok(())
+    }
+
+    /// Change the per-epoch cap on admin burns. (Admin only)
+    pub fn set_burn_cap(ctx: Context<AdminConfig>, burn_cap_per_epoch: u64) -> Result<()> {
+        let program_config = &mut ctx.accounts.program_config;
+        program_config.burn_cap_per_epoch = burn_cap_per_epoch;
+        program_config.record_admin_activity(Clock::get()?.unix_timestamp);
+        Ok(())
+    }
+
+    /// Set the SOL account that escrowed presale funds are released to. (Admin only)
+    pub fn set_presale_treasury(ctx: Context<AdminConfig>, treasury: Pubkey) -> Result<()> {
+        require!(treasury != Pubkey::default(), ErrorCode::InvalidTreasury);
+        let program_config = &mut ctx.accounts.program_config;
+        program_config.presale_treasury = treasury;
+        program_config.record_admin_activity(Clock::get()?.unix_timestamp);
+        Ok(())
+    }
+
+    /// Configure the payment fee discount for stakers holding at least `stake_threshold`
+    /// tokens in stake. `discount_bps` of 0 disables it. (Admin only)
+    pub fn set_fee_discount(
+        ctx: Context<AdminConfig>,
+        stake_threshold: u64,
+        discount_bps: u16,
+    ) -> Result<()> {
+        require!(discount_bps <= 10_000, ErrorCode::InvalidFeeConfig);
+        let program_config = &mut ctx.accounts.program_config;
+        program_config.fee_discount_stake_threshold = stake_threshold;
+        program_config.fee_discount_bps = discount_bps;
+        program_config.record_admin_activity(Clock::get()?.unix_timestamp);
+        Ok(())
+    }
+
+    /// Set the protocol fee (in lamports) charged by `transfer_allocation`. (Admin only)
+    pub fn set_allocation_transfer_fee(ctx: Context<AdminConfig>, fee: u64) -> Result<()> {
+        let program_config = &mut ctx.accounts.program_config;
+        program_config.allocation_transfer_fee = fee;
+        program_config.record_admin_activity(Clock::get()?.unix_timestamp);
+        Ok(())
+    }
+
+    /// Record the $BRATS mint. Can only be set once. (Admin only)
+    pub fn set_token_mint(ctx: Context<AdminConfig>, token_mint: Pubkey) -> Result<()> {
+        let program_config = &mut ctx.accounts.program_config;
+        require!(
+            program_config.token_mint == Pubkey::default() && token_mint != Pubkey::default(),
+            ErrorCode::InvalidTokenMint
+        );
+        program_config.token_mint = token_mint;
+        program_config.record_admin_activity(Clock::get()?.unix_timestamp);
+        Ok(())
+    }
+
+    /// Create the Metaplex metadata of the $BRATS mint with `TOKEN_NAME`, `TOKEN_SYMBOL` and
+    /// `uri`, once `set_token_mint` has run. The mint authority signs this once; the update
+    /// authority is the program's `metadata_authority` PDA, so later changes go through
+    /// `update_token_metadata`. (Admin only)
+    pub fn create_token_metadata(ctx: Context<CreateTokenMetadata>, uri: String) -> Result<()> {
+        require!(uri.len() <= MAX_URI_LEN, ErrorCode::UriTooLong);
+        ctx.accounts.program_config.record_admin_activity(Clock::get()?.unix_timestamp);
+        let ix = mpl_token_metadata::instructions::CreateMetadataAccountV3 {
+            metadata: ctx.accounts.metadata.key(),
+            mint: ctx.accounts.token_mint.key(),
+            mint_authority: ctx.accounts.mint_authority.key(),
+            payer: ctx.accounts.admin.key(),
+            update_authority: (ctx.accounts.metadata_authority.key(), true),
+            system_program: ctx.accounts.system_program.key(),
+            rent: Some(ctx.accounts.rent.key()),
+        }
+        .instruction(mpl_token_metadata::instructions::CreateMetadataAccountV3InstructionArgs {
+            data: mpl_token_metadata::types::DataV2 {
+                name: TOKEN_NAME.to_string(),
+                symbol: TOKEN_SYMBOL.to_string(),
+                uri,
+                seller_fee_basis_points: 0,
+                creators: None,
+                collection: None,
+                uses: None,
+            },
+            is_mutable: true,
+            collection_details: None,
+        });
+        let bump = ctx.bumps.metadata_authority;
+        let seeds: &[&[u8]] = &[b"metadata_authority", &[bump]];
+        solana_program::program::invoke_signed(
+            &ix,
+            &[
+                ctx.accounts.metadata.to_account_info(),
+                ctx.accounts.token_mint.to_account_info(),
+                ctx.accounts.mint_authority.to_account_info(),
+                ctx.accounts.admin.to_account_info(),
+                ctx.accounts.metadata_authority.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+                ctx.accounts.rent.to_account_info(),
+                ctx.accounts.token_metadata_program.to_account_info(),
+            ],
+            &[seeds],
+        )?;
+        Ok(())
+    }
+
+    /// Replace the name, symbol and URI in the $BRATS Metaplex metadata. (Admin only)
+    pub fn update_token_metadata(
+        ctx: Context<UpdateTokenMetadata>,
+        name: String,
+        symbol: String,
+        uri: String,
+    ) -> Result<()> {
+        require!(uri.len() <= MAX_URI_LEN, ErrorCode::UriTooLong);
+        ctx.accounts.program_config.record_admin_activity(Clock::get()?.unix_timestamp);
+        let data = mpl_token_metadata::types::DataV2 {
+            name,
+            symbol,
+            uri,
+            seller_fee_basis_points: 0,
+            creators: None,
+            collection: None,
+            uses: None,
+        };
+        let ix = mpl_token_metadata::instructions::UpdateMetadataAccountV2 {
+            metadata: ctx.accounts.metadata.key(),
+            update_authority: ctx.accounts.metadata_authority.key(),
+        }
+        .instruction(mpl_token_metadata::instructions::UpdateMetadataAccountV2InstructionArgs {
+            data: Some(data),
+            new_update_authority: None,
+            primary_sale_happened: None,
+            is_mutable: None,
+        });
+        let bump = ctx.bumps.metadata_authority;
+        let seeds: &[&[u8]] = &[b"metadata_authority", &[bump]];
+        solana_program::program::invoke_signed(
+            &ix,
+            &[
+                ctx.accounts.metadata.to_account_info(),
+                ctx.accounts.metadata_authority.to_account_info(),
+                ctx.accounts.token_metadata_program.to_account_info(),
+            ],
+            &[seeds],
+        )?;
+        Ok(())
+    }
+
+    /// Move tokens that were sent to a treasury-PDA-owned token account by mistake to the
+    /// presale treasury. $BRATS and mints in the accepted-mint registry are protected. (Admin only)
+    pub fn recover_foreign_tokens(ctx: Context<RecoverForeignTokens>, amount: u64) -> Result<()> {
+        ctx.accounts.program_config.record_admin_activity(Clock::get()?.unix_timestamp);
+        let program_config = &ctx.accounts.program_config;
+        let mint = ctx.accounts.source_token_account.mint;
+        require!(
+            program_config.token_mint != Pubkey::default() && mint != program_config.token_mint,
+            ErrorCode::ProtectedMint
+        );
+        require!(
+            ctx.accounts.accepted_mint.data_is_empty(),
+            ErrorCode::ProtectedMint
+        );
+        require!(
+            ctx.accounts.destination_token_account.owner == program_config.presale_treasury,
+            ErrorCode::InvalidTreasury
+        );
+
+        let bump = ctx.bumps.treasury_authority;
+        let seeds: &[&[u8]] = &[b"treasury", &[bump]];
+        token_interface::transfer_checked(
+            ctx.accounts.recovery_transfer_context().with_signer(&[seeds]),
+            amount,
+            ctx.accounts.mint.decimals,
+        )?;
+        Ok(())
+    }
+
+    /// Announce an upcoming program upgrade by the hash of its binary. The upgrade must not be
+    /// deployed before `earliest_upgrade_at`, at least `UPGRADE_NOTICE_PERIOD` from now.
+    /// Re-announcing replaces the pending announcement and restarts the notice. (Admin only)
+    pub fn announce_upgrade(
+        ctx: Context<AdminConfig>,
+        program_hash: [u8; 32],
+        earliest_upgrade_at: i64,
+    ) -> Result<()> {
+        let clock = Clock::get()?;
+        require!(program_hash != [0u8; 32], ErrorCode::InvalidUpgradeAnnouncement);
+        require!(
+            earliest_upgrade_at >= clock.unix_timestamp + UPGRADE_NOTICE_PERIOD,
+            ErrorCode::InvalidUpgradeAnnouncement
+        );
+        let program_config = &mut ctx.accounts.program_config;
+        program_config.announced_program_hash = program_hash;
+        program_config.earliest_upgrade_at = earliest_upgrade_at;
+        program_config.record_admin_activity(clock.unix_timestamp);
+        emit!(UpgradeAnnounced {
+            program_hash,
+            earliest_upgrade_at,
+            timestamp: clock.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Verifier check: succeeds only if `program_hash` is the announced upgrade and its
+    /// notice period has elapsed.
+    pub fn check_upgrade_announcement(
+        ctx: Context<CheckUpgradeAnnouncement>,
+        program_hash: [u8; 32],
+    ) -> Result<()> {
+        let program_config = &ctx.accounts.program_config;
+        require!(
+            program_hash != [0u8; 32] && program_hash == program_config.announced_program_hash,
+            ErrorCode::UpgradeNotAnnounced
+        );
+        require!(
+            Clock::get()?.unix_timestamp >= program_config.earliest_upgrade_at,
+            ErrorCode::UpgradeTimelockActive
+        );
+        Ok(())
+    }
+
+    /// Configure the automatic reward pool refill from the treasury. (Admin only)
+    /// Setting `top_up_amount` to 0 disables the crank.
+    pub fn set_reward_pool_refill_config(
+        ctx: Context<SetRewardPoolRefillConfig>,
+        low_water_mark: u64,
+        top_up_amount: u64,
+    ) -> Result<()> {
+        let program_config = &mut ctx.accounts.program_config;
+        program_config.reward_pool_low_water_mark = low_water_mark;
+        program_config.reward_pool_top_up_amount = top_up_amount;
+        program_config.record_admin_activity(Clock::get()?.unix_timestamp);
+        Ok(())
+    }
+
+    /// Permissionless crank: when the reward pool is below the low-water mark, move up to
+    /// `reward_pool_top_up_amount` tokens from the treasury token account (owned by the
+    /// treasury PDA) into the reward pool.
+    pub fn crank_reward_pool_refill(ctx: Context<CrankRewardPoolRefill>) -> Result<()> {
+        let program_config = &ctx.accounts.program_config;
+        require!(
+            ctx.accounts.global_state.reward_pool < program_config.reward_pool_low_water_mark,
+            ErrorCode::RewardPoolAboveLowWaterMark
+        );
+        let amount = program_config
+            .reward_pool_top_up_amount
+            .min(ctx.accounts.treasury_token_account.amount);
+        require!(amount > 0, ErrorCode::InsufficientFunds);
+
+        let bump = ctx.bumps.treasury_authority;
+        let seeds: &[&[u8]] = &[b"treasury", &[bump]];
+        let received = transfer_received(
+            ctx.accounts.treasury_transfer_context().with_signer(&[seeds]),
+            amount,
+            ctx.accounts.token_mint.decimals,
+        )?;
+        ctx.accounts
+            .global_state
+            .credit_reward_pool(received, RewardSource::TreasuryRefill);
+        Ok(())
+    }
+
+    /// Designate the market maker and its per-epoch pull limits, creating the allowance and
+    /// the market-making vaults on first use. Re-enables a revoked allowance. (Admin only)
+    pub fn set_market_maker_allowance(
+        ctx: Context<SetMarketMakerAllowance>,
+        market_maker: Pubkey,
+        token_limit_per_epoch: u64,
+        lamport_limit_per_epoch: u64,
+    ) -> Result<()> {
+        ctx.accounts.program_config.record_admin_activity(Clock::get()?.unix_timestamp);
+        let allowance = &mut ctx.accounts.market_maker_allowance;
+        allowance.market_maker = market_maker;
+        allowance.token_limit_per_epoch = token_limit_per_epoch;
+        allowance.lamport_limit_per_epoch = lamport_limit_per_epoch;
+        allowance.revoked = false;
+        Ok(())
+    }
+
+    /// Revoke the market-maker allowance immediately. (Admin only)
+    pub fn revoke_market_maker(ctx: Context<RevokeMarketMaker>) -> Result<()> {
+        ctx.accounts.program_config.record_admin_activity(Clock::get()?.unix_timestamp);
+        ctx.accounts.market_maker_allowance.revoked = true;
+        Ok(())
+    }
+
+    /// Fund the market-making vaults: `tokens` from the treasury token account (owned by the
+    /// treasury PDA) and `lamports` from the treasurer. (Treasurer only)
+    pub fn fund_market_maker(
+        ctx: Context<FundMarketMaker>,
+        tokens: u64,
+        lamports: u64,
+    ) -> Result<()> {
+        if tokens > 0 {
+            let bump = ctx.bumps.treasury_authority;
+            let seeds: &[&[u8]] = &[b"treasury", &[bump]];
+            token_interface::transfer_checked(
+                ctx.accounts.treasury_transfer_context().with_signer(&[seeds]),
+                tokens,
+                ctx.accounts.token_mint.decimals,
+            )?;
+        }
+        if lamports > 0 {
+            let ix = system_instruction::transfer(
+                ctx.accounts.treasurer.key,
+                ctx.accounts.mm_sol_vault.key,
+                lamports,
+            );
+            solana_program::program::invoke(
+                &ix,
+                &[
+                    ctx.accounts.treasurer.to_account_info(),
+                    ctx.accounts.mm_sol_vault.to_account_info(),
+                    ctx.accounts.system_program.to_account_info(),
+                ],
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Pull up to the per-epoch limits from the market-making vaults to the market maker's
+    /// own accounts. Every pull is logged with a `MarketMakerPull` event. (Market maker only)
+    pub fn market_maker_pull(
+        ctx: Context<PullMarketMakerFunds>,
+        tokens: u64,
+        lamports: u64,
+    ) -> Result<()> {
+        ctx.accounts.program_config.check_not_paused(PAUSE_LIQUIDITY)?;
+        require!(tokens > 0 || lamports > 0, ErrorCode::InvalidAmount);
+        let clock = Clock::get()?;
+        let allowance = &mut ctx.accounts.market_maker_allowance;
+        allowance.record_pull(tokens, lamports, clock.epoch)?;
+
+        if tokens > 0 {
+            let bump = ctx.bumps.mm_token_vault;
+            let seeds: &[&[u8]] = &[b"mm_token_vault", &[bump]];
+            token_interface::transfer_checked(
+                ctx.accounts.pull_transfer_context().with_signer(&[seeds]),
+                tokens,
+                ctx.accounts.token_mint.decimals,
+            )?;
+        }
+        if lamports > 0 {
+            let bump = ctx.bumps.mm_sol_vault;
+            let seeds: &[&[u8]] = &[b"mm_sol_vault", &[bump]];
+            let ix = system_instruction::transfer(
+                ctx.accounts.mm_sol_vault.key,
+                ctx.accounts.market_maker.key,
+                lamports,
+            );
+            solana_program::program::invoke_signed(
+                &ix,
+                &[
+                    ctx.accounts.mm_sol_vault.to_account_info(),
+                    ctx.accounts.market_maker.to_account_info(),
+                    ctx.accounts.system_program.to_account_info(),
+                ],
+                &[seeds],
+            )?;
+        }
+        let allowance = &ctx.accounts.market_maker_allowance;
+        emit!(MarketMakerPull {
+            market_maker: allowance.market_maker,
+            tokens,
+            lamports,
+            epoch: clock.epoch,
+            tokens_pulled_this_epoch: allowance.tokens_pulled_this_epoch,
+            lamports_pulled_this_epoch: allowance.lamports_pulled_this_epoch,
+            timestamp: clock.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Configure the team buy-in program: the swap program, the treasury match and budget, and
+    /// the vesting terms of new buy-ins. Creates the team escrow vault. (Admin only)
+    pub fn set_team_buy_in_config(
+        ctx: Context<SetTeamBuyInConfig>,
+        swap_program: Pubkey,
+        match_bps: u16,
+        match_budget: u64,
+        cliff_duration: i64,
+        vesting_duration: i64,
+    ) -> Result<()> {
+        require!(
+            match_bps <= 10_000 && cliff_duration >= 0 && vesting_duration > 0,
+            ErrorCode::InvalidTeamBuyInConfig
+        );
+        ctx.accounts.program_config.record_admin_activity(Clock::get()?.unix_timestamp);
+        let config = &mut ctx.accounts.team_buy_in_config;
+        config.swap_program = swap_program;
+        config.match_bps = match_bps;
+        config.match_budget = match_budget;
+        config.cliff_duration = cliff_duration;
+        config.vesting_duration = vesting_duration;
+        Ok(())
+    }
+
+    /// Admit a wallet to the team buy-in program. (Admin only)
+    pub fn register_team_member(
+        ctx: Context<RegisterTeamMember>,
+        wallet: Pubkey,
+    ) -> Result<()> {
+        ctx.accounts.program_config.record_admin_activity(Clock::get()?.unix_timestamp);
+        ctx.accounts.team_member.wallet = wallet;
+        Ok(())
+    }
+
+    /// Buy tokens at market with the member's wrapped SOL. `swap_data` and the remaining
+    /// accounts form the swap instruction, which must pay out into the team escrow vault; the
+    /// vault's balance change is what counts as bought. The treasury then matches `match_bps`
+    /// of it (up to the remaining budget) and both halves vest in a new `TeamEscrow`.
+    pub fn team_buy_in(
+        ctx: Context<TeamBuyIn>,
+        max_lamports: u64,
+        min_tokens_out: u64,
+        swap_data: Vec<u8>,
+    ) -> Result<()> {
+        let wsol_before = ctx.accounts.member_wsol_account.amount;
+        let vault_before = ctx.accounts.team_escrow_vault.amount;
+        let swap_ix = solana_program::instruction::Instruction {
+            program_id: ctx.accounts.swap_program.key(),
+            accounts: ctx
+                .remaining_accounts
+                .iter()
+                .map(|account| solana_program::instruction::AccountMeta {
+                    pubkey: account.key(),
+                    is_signer: account.is_signer,
+                    is_writable: account.is_writable,
+                })
+                .collect(),
+            data: swap_data,
+        };
+        solana_program::program::invoke(&swap_ix, ctx.remaining_accounts)?;
+
+        ctx.accounts.member_wsol_account.reload()?;
+        ctx.accounts.team_escrow_vault.reload()?;
+        let lamports_spent = wsol_before
+            .checked_sub(ctx.accounts.member_wsol_account.amount)
+            .ok_or(ErrorCode::InvalidAmount)?;
+        let purchased = ctx
+            .accounts
+            .team_escrow_vault
+            .amount
+            .checked_sub(vault_before)
+            .ok_or(ErrorCode::InvalidAmount)?;
+        require!(lamports_spent <= max_lamports, ErrorCode::SlippageExceeded);
+        require!(
+            purchased > 0 && purchased >= min_tokens_out,
+            ErrorCode::SlippageExceeded
+        );
+
+        let config = &mut ctx.accounts.team_buy_in_config;
+        let matched = (purchased as u128 * config.match_bps as u128 / 10_000) as u64;
+        let matched = matched.min(config.match_budget);
+        config.match_budget -= matched;
+        config.total_purchased = config.total_purchased.checked_add(purchased).unwrap();
+        config.total_matched = config.total_matched.checked_add(matched).unwrap();
+
+        // The escrow vests what it actually received after any transfer fee.
+        let matched = if matched > 0 {
+            let bump = ctx.bumps.treasury_authority;
+            let seeds: &[&[u8]] = &[b"treasury", &[bump]];
+            transfer_received(
+                ctx.accounts.match_transfer_context().with_signer(&[seeds]),
+                matched,
+                ctx.accounts.token_mint.decimals,
+            )?
+        } else {
+            0
+        };
+
+        let config = &ctx.accounts.team_buy_in_config;
+        let now = Clock::get()?.unix_timestamp;
+        let team_member = &mut ctx.accounts.team_member;
+        let team_escrow = &mut ctx.accounts.team_escrow;
+        team_escrow.member = team_member.wallet;
+        team_escrow.buy_in_id = team_member.buy_in_count;
+        team_escrow.lamports_spent = lamports_spent;
+        team_escrow.purchased = purchased;
+        team_escrow.matched = matched;
+        team_escrow.start_time = now;
+        team_escrow.cliff_duration = config.cliff_duration;
+        team_escrow.vesting_duration = config.vesting_duration;
+        team_member.buy_in_count = team_member.buy_in_count.checked_add(1).unwrap();
+        team_member.lamports_spent =
+            team_member.lamports_spent.checked_add(lamports_spent).unwrap();
+        team_member.purchased = team_member.purchased.checked_add(purchased).unwrap();
+        team_member.matched = team_member.matched.checked_add(matched).unwrap();
+        let event = TeamBuyInExecuted {
+            member: team_member.wallet,
+            buy_in_id: team_escrow.buy_in_id,
+            lamports_spent,
+            purchased,
+            matched,
+            vests_from: now + team_escrow.cliff_duration,
+            vests_until: now + team_escrow.cliff_duration + team_escrow.vesting_duration,
+            timestamp: now,
+        };
+        emit!(event);
+        Ok(())
+    }
+
+    /// Release the vested, unreleased tokens of one team buy-in to the member.
+    pub fn release_team_escrow(ctx: Context<ReleaseTeamEscrow>) -> Result<()> {
+        let team_escrow = &mut ctx.accounts.team_escrow;
+        let amount = team_escrow.releasable(Clock::get()?.unix_timestamp);
+        require!(amount > 0, ErrorCode::NothingVested);
+        team_escrow.released = team_escrow.released.checked_add(amount).unwrap();
+        let bump = ctx.bumps.team_escrow_vault;
+        let seeds: &[&[u8]] = &[b"team_escrow_vault", &[bump]];
+        token_interface::transfer_checked(
+            ctx.accounts.release_transfer_context().with_signer(&[seeds]),
+            amount,
+            ctx.accounts.token_mint.decimals,
+        )?;
+        Ok(())
+    }
+
+    /// Enable or disable the anti-sybil purchase check and set the attestor key. (Admin only)
+    pub fn set_sybil_check(
+        ctx: Context<AdminConfig>,
+        enabled: bool,
+        attestor: Pubkey,
+    ) -> Result<()> {
+        let program_config = &mut ctx.accounts.program_config;
+        program_config.sybil_check_enabled = enabled;
+        program_config.attestor = attestor;
+        program_config.record_admin_activity(Clock::get()?.unix_timestamp);
+        Ok(())
+    }
+
+    /// Write or update a wallet attestation. (Attestor only)
+    pub fn attest_wallet(
+        ctx: Context<AttestWallet>,
+        wallet: Pubkey,
+        first_seen_slot: u64,
+        has_history: bool,
+    ) -> Result<()> {
+        require!(
+            ctx.accounts.attestor.key() == ctx.accounts.program_config.attestor,
+            ErrorCode::Unauthorized
+        );
+        let attestation = &mut ctx.accounts.attestation;
+        attestation.wallet = wallet;
+        attestation.attestor = ctx.accounts.attestor.key();
+        attestation.first_seen_slot = first_seen_slot;
+        attestation.has_history = has_history;
+        attestation.updated_at = Clock::get()?.unix_timestamp;
+        Ok(())
+    }
+
+    /// Blacklist `wallet`, blocking its purchases, staking and claims. (Admin only)
+    pub fn add_to_blacklist(ctx: Context<UpdateBlacklist>, wallet: Pubkey) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        ctx.accounts.program_config.record_admin_activity(now);
+        let entry = &mut ctx.accounts.blacklist_entry;
+        entry.wallet = wallet;
+        entry.blocked = true;
+        entry.updated_at = now;
+        emit!(BlacklistUpdated { wallet, blocked: true, timestamp: now });
+        Ok(())
+    }
+
+    /// Clear `wallet`'s blacklist flag. (Admin only)
+    pub fn remove_from_blacklist(ctx: Context<UpdateBlacklist>, wallet: Pubkey) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        ctx.accounts.program_config.record_admin_activity(now);
+        let entry = &mut ctx.accounts.blacklist_entry;
+        entry.wallet = wallet;
+        entry.blocked = false;
+        entry.updated_at = now;
+        emit!(BlacklistUpdated { wallet, blocked: false, timestamp: now });
+        Ok(())
+    }
+
+    /// Set the withdrawal co-signer and the amount above which it must co-sign. (Admin only)
+    pub fn set_withdrawal_cosigner(
+        ctx: Context<AdminConfig>,
+        cosigner: Pubkey,
+        cosign_threshold: u64,
+    ) -> Result<()> {
+        let program_config = &mut ctx.accounts.program_config;
+        program_config.withdrawal_cosigner = cosigner;
+        program_config.cosign_threshold = cosign_threshold;
+        program_config.record_admin_activity(Clock::get()?.unix_timestamp);
+        Ok(())
+    }
+
+    /// Change the recovery key and inactivity period. (Admin only)
+    pub fn set_recovery_config(
+        ctx: Context<AdminConfig>,
+        recovery_key: Pubkey,
+        admin_inactivity_period: i64,
+    ) -> Result<()> {
+        require!(admin_inactivity_period > 0, ErrorCode::InvalidInactivityPeriod);
+        let program_config = &mut ctx.accounts.program_config;
+        program_config.recovery_key = recovery_key;
+        program_config.admin_inactivity_period = admin_inactivity_period;
+        program_config.record_admin_activity(Clock::get()?.unix_timestamp);
+        Ok(())
+    }
+
+    /// Prove the admin key is still alive without changing anything. (Admin only)
+    pub fn admin_heartbeat(ctx: Context<AdminConfig>) -> Result<()> {
+        ctx.accounts.program_config.record_admin_activity(Clock::get()?.unix_timestamp);
+        Ok(())
+    }
+
+    /// Claim admin rights with the recovery key once the admin has been inactive
+    /// for the configured period.
+    pub fn recover_admin(ctx: Context<RecoverAdmin>) -> Result<()> {
+        let program_config = &mut ctx.accounts.program_config;
+        require!(
+            ctx.accounts.recovery_key.key() == program_config.recovery_key,
+            ErrorCode::Unauthorized
+        );
+        let clock = Clock::get()?;
+        require!(
+            clock.unix_timestamp
+                >= program_config.last_admin_activity + program_config.admin_inactivity_period,
+            ErrorCode::AdminStillActive
+        );
+        let presale_state = &mut ctx.accounts.presale_state;
+        presale_state.admin = program_config.recovery_key;
+        presale_state.pending_admin = Pubkey::default();
+        program_config.record_admin_activity(clock.unix_timestamp);
+        Ok(())
+    }
+
+    /// Create the guardian set that can rotate the admin key in an emergency; later changes
+    /// go through `set_guardians`. (Admin only)
+    pub fn initialize_guardians(
+        ctx: Context<InitializeGuardians>,
+        guardians: Vec<Pubkey>,
+        threshold: u8,
+    ) -> Result<()> {
+        ctx.accounts.program_config.record_admin_activity(Clock::get()?.unix_timestamp);
+        ctx.accounts.guardian_set.configure(&guardians, threshold)
+    }
+
+    /// Replace the guardians and threshold, e.g. to rotate out a lost or compromised guardian
+    /// key. Not while a rotation is open, so the admin cannot swap out the guardians that are
+    /// rotating it. (Admin only)
+    pub fn set_guardians(
+        ctx: Context<SetGuardians>,
+        guardians: Vec<Pubkey>,
+        threshold: u8,
+    ) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let guardian_set = &mut ctx.accounts.guardian_set;
+        require!(!guardian_set.rotation_open(now), ErrorCode::AdminRotationPending);
+        guardian_set.configure(&guardians, threshold)?;
+        ctx.accounts.program_config.record_admin_activity(now);
+        emit!(GuardiansUpdated {
+            guardian_count: guardian_set.guardian_count,
+            threshold,
+            timestamp: now,
+        });
+        Ok(())
+    }
+
+    /// Propose rotating the admin key to `new_admin`, counting as the proposer's approval.
+    /// Only one rotation can be open at a time. (Guardian only)
+    pub fn propose_admin_rotation(
+        ctx: Context<GuardianAction>,
+        new_admin: Pubkey,
+    ) -> Result<()> {
+        require!(new_admin != Pubkey::default(), ErrorCode::InvalidGuardianConfig);
+        let now = Clock::get()?.unix_timestamp;
+        let guardian = ctx.accounts.guardian.key();
+        let guardian_set = &mut ctx.accounts.guardian_set;
+        let index = guardian_set
+            .guardian_index(guardian)
+            .ok_or(ErrorCode::NotGuardian)?;
+        require!(!guardian_set.rotation_open(now), ErrorCode::AdminRotationPending);
+        guardian_set.proposed_admin = new_admin;
+        guardian_set.proposed_at = now;
+        guardian_set.approvals = 0;
+        guardian_set.executable_at = 0;
+        emit!(AdminRotationProposed {
+            guardian,
+            new_admin,
+            timestamp: now,
+        });
+        guardian_set.approve(guardian, index, now);
+        Ok(())
+    }
+
+    /// Approve the open admin rotation. Once `threshold` guardians have approved, it can be
+    /// executed after `ADMIN_ROTATION_DELAY`. (Guardian only)
+    pub fn approve_admin_rotation(ctx: Context<GuardianAction>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let guardian = ctx.accounts.guardian.key();
+        let guardian_set = &mut ctx.accounts.guardian_set;
+        let index = guardian_set
+            .guardian_index(guardian)
+            .ok_or(ErrorCode::NotGuardian)?;
+        require!(guardian_set.rotation_open(now), ErrorCode::NoAdminRotation);
+        require!(
+            guardian_set.approvals & (1 << index) == 0,
+            ErrorCode::AlreadyApproved
+        );
+        guardian_set.approve(guardian, index, now);
+        Ok(())
+    }
+
+    /// Make the approved key the admin once the rotation delay has passed. Permissionless.
+    pub fn execute_admin_rotation(ctx: Context<ExecuteAdminRotation>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let guardian_set = &mut ctx.accounts.guardian_set;
+        require!(
+            guardian_set.proposed_admin != Pubkey::default() && guardian_set.executable_at != 0,
+            ErrorCode::NoAdminRotation
+        );
+        require!(now >= guardian_set.executable_at, ErrorCode::AdminRotationNotReady);
+        let presale_state = &mut ctx.accounts.presale_state;
+        let previous_admin = presale_state.admin;
+        presale_state.admin = guardian_set.proposed_admin;
+        presale_state.pending_admin = Pubkey::default();
+        ctx.accounts.program_config.record_admin_activity(now);
+        emit!(AdminRotationExecuted {
+            previous_admin,
+            new_admin: guardian_set.proposed_admin,
+            timestamp: now,
+        });
+        guardian_set.clear_rotation();
+        Ok(())
+    }
+
+    /// Cancel the open admin rotation, including during `ADMIN_ROTATION_DELAY` after quorum,
+    /// e.g. when a guardian key turns out to be compromised. (Admin or guardian)
+    pub fn cancel_admin_rotation(ctx: Context<CancelAdminRotation>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let authority = ctx.accounts.authority.key();
+        let guardian_set = &mut ctx.accounts.guardian_set;
+        require!(
+            authority == ctx.accounts.presale_state.admin
+                || guardian_set.guardian_index(authority).is_some(),
+            ErrorCode::Unauthorized
+        );
+        require!(guardian_set.rotation_open(now), ErrorCode::NoAdminRotation);
+        emit!(AdminRotationCancelled {
+            cancelled_by: authority,
+            new_admin: guardian_set.proposed_admin,
+            timestamp: now,
+        });
+        guardian_set.clear_rotation();
+        Ok(())
+    }
+
+    /// End the presale and mark the launch time.
+    /// After this, staking is disabled.
+    /// Launching enables token claims, so it requires the launch liquidity vaults to hold at
+    /// least the governance-set `min_launch_liquidity_percent` of the SOL raised: their SOL
+    /// plus their tokens valued at the listing price.
+    pub fn end_presale(ctx: Context<EndPresale>) -> Result<()> {
+        ctx.accounts.check_launch_liquidity()?;
+        let presale_state = &mut ctx.accounts.presale_state;
+        require!(presale_state.is_presale_active, ErrorCode::PresaleAlreadyEnded);
+        ctx.accounts.program_config.record_admin_activity(Clock::get()?.unix_timestamp);
+        let clock = Clock::get()?;
+        presale_state.is_presale_active = false;
+        presale_state.presale_end_time = Some(clock.unix_timestamp);
+        presale_state.launch_time = Some(clock.unix_timestamp);
+        presale_state.liquidity_lock_end_time =
+            Some(clock.unix_timestamp + ctx.accounts.program_config.liquidity_lock_period);
+        emit!(PresaleEnded {
+            total_raised: presale_state.total_raised,
+            buyer_count: presale_state.buyer_count,
+            timestamp: clock.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Write the round's `RoundReport` once the presale has ended and a governance proposal
+    /// approving it (`ApproveRoundReport`) has been executed. The report is never changed
+    /// afterwards. Permissionless.
+    pub fn finalize_round(ctx: Context<FinalizeRound>) -> Result<()> {
+        let presale_state = &ctx.accounts.presale_state;
+        require!(!presale_state.is_presale_active, ErrorCode::PresaleNotEnded);
+        let proposal = &ctx.accounts.proposal;
+        require!(
+            proposal.executed && proposal.action == ProposalAction::ApproveRoundReport,
+            ErrorCode::ProposalNotPassed
+        );
+
+        let presale_stage_info = ctx.accounts.presale_stage_info.load()?;
+        let stages = &presale_stage_info.stages;
+        let report = &mut ctx.accounts.round_report;
+        report.presale_state = presale_state.key();
+        report.total_raised_lamports = presale_stage_info.total_raised();
+        report.total_raised_usd = stages
+            .iter()
+            .fold(0u64, |acc, stage| acc.checked_add(stage.total_raised_usd).unwrap());
+        report.tokens_sold = stages
+            .iter()
+            .fold(0u64, |acc, stage| acc.checked_add(stage.tokens_sold).unwrap());
+        report.buyers = presale_state.buyer_count;
+        report.purchases = presale_state.purchase_count;
+        report.average_purchase_tokens = report
+            .tokens_sold
+            .checked_div(presale_state.purchase_count)
+            .unwrap_or(0);
+        for (index, stage) in stages.iter().enumerate() {
+            report.stage_prices[index] = stage.price;
+            report.stage_usd_prices[index] = stage.usd_price;
+        }
+        report.approval_proposal_id = proposal.id;
+        report.finalized_at = Clock::get()?.unix_timestamp;
+        Ok(())
+    }
+
+    /// Accept payment in either SOL or an SPL token from the accepted-mint registry.
+    /// A fee is deducted and sent to the fee wallet (flat 3 lamports for SOL, per-mint rules for SPL).
+    /// The remaining amount is transferred to the treasury.
+    pub fn accept_payment(
+        ctx: Context<AcceptPayment>,
+        amount: u64,
+        token_mint: Pubkey,
+    ) -> Result<()> {
+        ctx.accounts.process_payment(amount, token_mint)
+    }
+
+    /// Buy presale tokens with SOL on behalf of a beneficiary (gifts, payroll-in-tokens).
+    /// Priced, capped and escrowed exactly like `buy_presale` for the payer, who also keeps
+    /// the refund rights; the receipt records both wallets, and the beneficiary owns the
+    /// allocation and its claim rights.
+    pub fn accept_gift_payment(
+        ctx: Context<AcceptGiftPayment>,
+        lamports_in: u64,
+        receipt_id: u64,
+        beneficiary: Pubkey,
+        proof: Vec<[u8; 32]>,
+        referrer: Option<Pubkey>,
+    ) -> Result<()> {
+        require!(beneficiary != Pubkey::default(), ErrorCode::InvalidBeneficiary);
+        let purchase = &mut ctx.accounts.purchase;
+        let quote = purchase.quote_purchase(lamports_in, PAYMENT_METHOD_SOL, &proof)?;
+        purchase.escrow_sol(quote.lamports_used)?;
+        purchase.record_purchase(
+            &quote,
+            PAYMENT_METHOD_SOL,
+            receipt_id,
+            Pubkey::default(),
+            quote.lamports_used,
+            beneficiary,
+            referrer,
+        )?;
+
+        let receipt_key = ctx.accounts.purchase.receipt.key();
+        PositionManager::update(&ctx.accounts.beneficiary_position_manager, |manager| {
+            manager.add_receipt(receipt_key)
+        })?;
+        quote.set_return_data()
+    }
+
+    /// Create an invoice that a specific payer can settle with `pay_invoice`. (Admin only)
+    pub fn create_invoice(
+        ctx: Context<CreateInvoice>,
+        invoice_id: u64,
+        payer: Pubkey,
+        amount: u64,
+        expiry: i64,
+        memo_hash: [u8; 32],
+    ) -> Result<()> {
+        ctx.accounts.program_config.record_admin_activity(Clock::get()?.unix_timestamp);
+        require!(amount > 0, ErrorCode::InvalidAmount);
+        let clock = Clock::get()?;
+        require!(expiry > clock.unix_timestamp, ErrorCode::InvoiceExpired);
+
+        let invoice = &mut ctx.accounts.invoice;
+        invoice.invoice_id = invoice_id;
+        invoice.payer = payer;
+        invoice.treasury = ctx.accounts.treasury_sol_account.key();
+        invoice.amount = amount;
+        invoice.memo_hash = memo_hash;
+        invoice.created_at = clock.unix_timestamp;
+        invoice.expiry = expiry;
+        invoice.is_paid = false;
+        invoice.paid_at = None;
+        Ok(())
+    }
+
+    /// Pay an open invoice. Only the designated payer can pay, and only before expiry.
+    pub fn pay_invoice(ctx: Context<PayInvoice>) -> Result<()> {
+        ctx.accounts.program_config.check_not_paused(PAUSE_PURCHASES)?;
+        let invoice = &ctx.accounts.invoice;
+        require!(!invoice.is_paid, ErrorCode::InvoiceAlreadyPaid);
+        require!(
+            ctx.accounts.payer.key() == invoice.payer,
+            ErrorCode::Unauthorized
+        );
+        require!(
+            ctx.accounts.treasury_sol_account.key() == invoice.treasury,
+            ErrorCode::InvalidTreasury
+        );
+        let clock = Clock::get()?;
+        require!(clock.unix_timestamp <= invoice.expiry, ErrorCode::InvoiceExpired);
+
+        let ix = system_instruction::transfer(
+            ctx.accounts.payer.key,
+            ctx.accounts.treasury_sol_account.key,
+            invoice.amount,
+        );
+        solana_program::program::invoke(
+            &ix,
+            &[
+                ctx.accounts.payer.to_account_info(),
+                ctx.accounts.treasury_sol_account.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+            ],
+        )?;
+
+        let invoice = &mut ctx.accounts.invoice;
+        invoice.is_paid = true;
+        invoice.paid_at = Some(clock.unix_timestamp);
+        Ok(())
+    }
+
+    /// Buy presale tokens with SOL at the current stage price, spilling over into later
+    /// stages if the current one sells out. Only the lamports actually needed are charged.
+    /// Payments are escrowed in the presale vault until the soft cap is reached.
+    /// The allocation is recorded on a new receipt owned by the buyer. `quote_buy_presale`
+    /// simulates a purchase without creating any account.
+    /// While whitelist mode is on, purchases starting in the early stages need a merkle
+    /// `proof` that the buyer is whitelisted.
+    /// With a `referrer`, their registered `ReferralAccount` must be passed as
+    /// `referral_account` and is credited with the referral bonus.
+    pub fn buy_presale(
+        ctx: Context<BuyPresale>,
+        lamports_in: u64,
+        receipt_id: u64,
+        proof: Vec<[u8; 32]>,
+        referrer: Option<Pubkey>,
+    ) -> Result<()> {
+        let quote = ctx
+            .accounts
+            .quote_purchase(lamports_in, PAYMENT_METHOD_SOL, &proof)?;
+        ctx.accounts.escrow_sol(quote.lamports_used)?;
+        let buyer = ctx.accounts.buyer.key();
+        ctx.accounts.record_purchase(
+            &quote,
+            PAYMENT_METHOD_SOL,
+            receipt_id,
+            Pubkey::default(),
+            quote.lamports_used,
+            buyer,
+            referrer,
+        )?;
+        quote.set_return_data()
+    }
+
+    /// Buy presale tokens with SOL like `buy_presale`, appending the receipt straight to the
+    /// receipt tree as a `CompressedReceipt` leaf instead of creating a receipt account, so
+    /// the buyer pays no receipt rent; the allocation is claimed with
+    /// `claim_compressed_receipt`. Only for purchases starting in a full-unlock stage while no
+    /// early-bird bonus is configured, as vesting and the bonus are tracked on receipt
+    /// accounts; compressed receipts cannot be adjusted in the dispute window either. The
+    /// purchase events carry the default address as the receipt.
+    pub fn buy_presale_compressed(
+        ctx: Context<BuyPresaleCompressed>,
+        lamports_in: u64,
+        receipt_id: u64,
+        proof: Vec<[u8; 32]>,
+        referrer: Option<Pubkey>,
+    ) -> Result<()> {
+        require!(
+            ctx.accounts.program_config.early_bird_bonus_bps == 0,
+            ErrorCode::CompressedPurchaseUnavailable
+        );
+        let quote = ctx
+            .accounts
+            .quote_purchase(lamports_in, PAYMENT_METHOD_SOL, &proof)?;
+        ctx.accounts.escrow_sol(quote.lamports_used)?;
+        let vesting_preset = ctx.accounts.purchase_book().book(
+            &quote,
+            PAYMENT_METHOD_SOL,
+            Pubkey::default(),
+            Pubkey::default(),
+            quote.lamports_used,
+            referrer,
+        )?;
+        require!(
+            vesting_preset == VestingPreset::FullUnlock,
+            ErrorCode::CompressedPurchaseUnavailable
+        );
+
+        let now = Clock::get()?.unix_timestamp;
+        let compressed = CompressedReceipt {
+            payer: ctx.accounts.buyer.key(),
+            beneficiary: ctx.accounts.buyer.key(),
+            receipt_id,
+            tokens_allocated: quote.tokens_allocated,
+            timestamp: now,
+        };
+        let bump = ctx.bumps.receipt_tree;
+        let seeds: &[&[u8]] = &[b"receipt_tree", &[bump]];
+        spl_account_compression::cpi::append(
+            ctx.accounts.modify_tree_context().with_signer(&[seeds]),
+            compressed.leaf(),
+        )?;
+        let receipt_tree = &mut ctx.accounts.receipt_tree;
+        let leaf_index = receipt_tree.leaf_count;
+        receipt_tree.leaf_count = leaf_index.checked_add(1).unwrap();
+        emit!(ReceiptCompressed {
+            receipt: Pubkey::default(),
+            leaf_index,
+            compressed,
+            timestamp: now,
+        });
+        quote.set_return_data()
+    }
+
+    /// Buy presale tokens with an accepted stablecoin, valued at $1 against the stages'
+    /// USD prices. Payment is escrowed in the mint's stablecoin vault until the presale
+    /// succeeds, and refunded per receipt by `claim_stablecoin_refund` if it fails. For the
+    /// caps and limits, the tokens bought are valued at the stages' SOL prices.
+    /// `proof` and `referrer` behave as in `buy_presale`; `quote_buy_presale_with_stablecoin`
+    /// simulates a purchase.
+    pub fn buy_presale_with_stablecoin(
+        ctx: Context<BuyPresaleWithStablecoin>,
+        amount: u64,
+        receipt_id: u64,
+        proof: Vec<[u8; 32]>,
+        referrer: Option<Pubkey>,
+    ) -> Result<()> {
+        let accepted_mint = &ctx.accounts.accepted_mint;
+        require!(
+            accepted_mint.enabled && accepted_mint.is_stablecoin,
+            ErrorCode::MintNotAccepted
+        );
+        require!(
+            ctx.accounts.buyer_token_account.mint == accepted_mint.mint,
+            ErrorCode::InvalidTokenMint
+        );
+
+        // Price in 9-decimal USD, then convert the spend back (rounded up).
+        let usd_in = accepted_mint.base_units_to_usd(amount);
+        let quote = ctx
+            .accounts
+            .purchase
+            .quote_purchase(usd_in, PAYMENT_METHOD_STABLECOIN, &proof)?;
+        let charged = accepted_mint.usd_to_base_units(quote.lamports_used);
+
+        token_interface::transfer_checked(
+            ctx.accounts.stablecoin_transfer_context(),
+            charged,
+            ctx.accounts.stablecoin_mint.decimals,
+        )?;
+        let mint = ctx.accounts.accepted_mint.mint;
+        let buyer = ctx.accounts.purchase.buyer.key();
+        ctx.accounts.purchase.record_purchase(
+            &quote,
+            PAYMENT_METHOD_STABLECOIN,
+            receipt_id,
+            mint,
+            charged,
+            buyer,
+            referrer,
+        )?;
+        quote.set_return_data()
+    }
+
+    /// Simulate `buy_presale` for `buyer`: run the same checks and pricing and return the
+    /// same result as return data, without writing or creating any account.
+    pub fn quote_buy_presale(
+        ctx: Context<QuotePurchase>,
+        lamports_in: u64,
+        proof: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        ctx.accounts
+            .quote_purchase(lamports_in, PAYMENT_METHOD_SOL, &proof)?
+            .set_return_data()
+    }
+
+    /// Simulate `buy_presale_with_stablecoin` for `buyer`, like `quote_buy_presale`.
+    pub fn quote_buy_presale_with_stablecoin(
+        ctx: Context<QuoteStablecoinPurchase>,
+        amount: u64,
+        proof: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        let accepted_mint = &ctx.accounts.accepted_mint;
+        require!(
+            accepted_mint.enabled && accepted_mint.is_stablecoin,
+            ErrorCode::MintNotAccepted
+        );
+        let usd_in = accepted_mint.base_units_to_usd(amount);
+        ctx.accounts
+            .quote
+            .quote_purchase(usd_in, PAYMENT_METHOD_STABLECOIN, &proof)?
+            .set_return_data()
+    }
+
+    /// Set the presale soft and hard caps, in lamports. 0 disables a cap. (Admin only)
+    pub fn set_presale_caps(
+        ctx: Context<UpdatePresaleState>,
+        soft_cap: u64,
+        hard_cap: u64,
+    ) -> Result<()> {
+        ctx.accounts.program_config.record_admin_activity(Clock::get()?.unix_timestamp);
+        require!(
+            ctx.accounts.presale_state.is_presale_active,
+            ErrorCode::PresaleAlreadyEnded
+        );
+        require!(hard_cap == 0 || hard_cap >= soft_cap, ErrorCode::InvalidPresaleCaps);
+        let presale_state = &mut ctx.accounts.presale_state;
+        presale_state.soft_cap = soft_cap;
+        presale_state.hard_cap = hard_cap;
+        Ok(())
+    }
+
+    /// Set the per-purchase minimum and per-wallet maximum, in lamports; stablecoin purchases
+    /// are valued at the stages' SOL prices. 0 disables either limit. (Admin only)
+    pub fn set_purchase_limits(
+        ctx: Context<UpdatePresaleState>,
+        min_purchase: u64,
+        max_purchase_per_wallet: u64,
+    ) -> Result<()> {
+        ctx.accounts.program_config.record_admin_activity(Clock::get()?.unix_timestamp);
+        require!(
+            max_purchase_per_wallet == 0 || max_purchase_per_wallet >= min_purchase,
+            ErrorCode::InvalidPresaleCaps
+        );
+        let presale_state = &mut ctx.accounts.presale_state;
+        presale_state.min_purchase = min_purchase;
+        presale_state.max_purchase_per_wallet = max_purchase_per_wallet;
+        Ok(())
+    }
+
+    /// Set the early-stage whitelist merkle root; all zeros turns whitelist mode off. (Admin only)
+    pub fn set_whitelist_root(
+        ctx: Context<UpdatePresaleState>,
+        whitelist_root: [u8; 32],
+    ) -> Result<()> {
+        ctx.accounts.program_config.record_admin_activity(Clock::get()?.unix_timestamp);
+        ctx.accounts.presale_state.whitelist_root = whitelist_root;
+        Ok(())
+    }
+
+    /// Nominate `new_admin` as the next admin; it takes over once it calls `accept_admin`.
+    /// Nominating the default pubkey cancels a pending transfer. (Admin only)
+    pub fn propose_admin(ctx: Context<UpdatePresaleState>, new_admin: Pubkey) -> Result<()> {
+        ctx.accounts.program_config.record_admin_activity(Clock::get()?.unix_timestamp);
+        ctx.accounts.presale_state.pending_admin = new_admin;
+        Ok(())
+    }
+
+    /// Accept a pending admin nomination. Must be signed by the nominated key.
+    pub fn accept_admin(ctx: Context<AcceptAdmin>) -> Result<()> {
+        let presale_state = &mut ctx.accounts.presale_state;
+        presale_state.admin = presale_state.pending_admin;
+        presale_state.pending_admin = Pubkey::default();
+        ctx.accounts.program_config.record_admin_activity(Clock::get()?.unix_timestamp);
+        Ok(())
+    }
+
+    /// Assign the operator and treasurer roles. (Admin only)
+    pub fn set_roles(ctx: Context<SetRoles>, operator: Pubkey, treasurer: Pubkey) -> Result<()> {
+        ctx.accounts.program_config.record_admin_activity(Clock::get()?.unix_timestamp);
+        let roles = &mut ctx.accounts.roles;
+        roles.operator = operator;
+        roles.treasurer = treasurer;
+        Ok(())
+    }
+
+    /// Create the token vault for launch liquidity. It and the `liquidity_sol_vault` PDA are
+    /// funded with regular transfers; both count toward the launch liquidity check. (Admin only)
+    pub fn initialize_liquidity_vault(ctx: Context<InitializeLiquidityVault>) -> Result<()> {
+        ctx.accounts.program_config.record_admin_activity(Clock::get()?.unix_timestamp);
+        Ok(())
+    }
+
+    /// After launch, release `lamports` and `tokens` from the launch liquidity vaults to the
+    /// admin to seed the BRATS/SOL pool. (Admin only)
+    pub fn release_launch_liquidity(
+        ctx: Context<ReleaseLaunchLiquidity>,
+        lamports: u64,
+        tokens: u64,
+    ) -> Result<()> {
+        ctx.accounts.program_config.record_admin_activity(Clock::get()?.unix_timestamp);
+        require!(
+            ctx.accounts.presale_state.launch_time.is_some(),
+            ErrorCode::PresaleNotEnded
+        );
+        if lamports > 0 {
+            let bump = ctx.bumps.liquidity_sol_vault;
+            let seeds: &[&[u8]] = &[b"liquidity_sol_vault", &[bump]];
+            let ix = system_instruction::transfer(
+                ctx.accounts.liquidity_sol_vault.key,
+                ctx.accounts.admin.key,
+                lamports,
+            );
+            solana_program::program::invoke_signed(
+                &ix,
+                &[
+                    ctx.accounts.liquidity_sol_vault.to_account_info(),
+                    ctx.accounts.admin.to_account_info(),
+                    ctx.accounts.system_program.to_account_info(),
+                ],
+                &[seeds],
+            )?;
+        }
+        if tokens > 0 {
+            let bump = ctx.bumps.liquidity_token_vault;
+            let seeds: &[&[u8]] = &[b"liquidity_token_vault", &[bump]];
+            token_interface::transfer_checked(
+                ctx.accounts.release_transfer_context().with_signer(&[seeds]),
+                tokens,
+                ctx.accounts.token_mint.decimals,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Release escrowed presale funds to the presale treasury once the presale has ended
+    /// with the soft cap reached. (Admin only)
+    pub fn release_presale_funds(ctx: Context<ReleasePresaleFunds>, amount: u64) -> Result<()> {
+        ctx.accounts.program_config.record_admin_activity(Clock::get()?.unix_timestamp);
+        let presale_state = &ctx.accounts.presale_state;
+        require!(!presale_state.is_presale_active, ErrorCode::PresaleNotEnded);
+        require!(
+            presale_state.total_raised >= presale_state.soft_cap,
+            ErrorCode::SoftCapNotReached
+        );
+        require!(
+            ctx.accounts.treasury_sol_account.key() == ctx.accounts.program_config.presale_treasury,
+            ErrorCode::InvalidTreasury
+        );
+
+        let bump = ctx.bumps.presale_vault;
+        let seeds: &[&[u8]] = &[b"presale_vault", &[bump]];
+        let ix = system_instruction::transfer(
+            ctx.accounts.presale_vault.key,
+            ctx.accounts.treasury_sol_account.key,
+            amount,
+        );
+        solana_program::program::invoke_signed(
+            &ix,
+            &[
+                ctx.accounts.presale_vault.to_account_info(),
+                ctx.accounts.treasury_sol_account.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+            ],
+            &[seeds],
+        )?;
+        emit!(FundsWithdrawn {
+            authority: ctx.accounts.admin.key(),
+            destination: ctx.accounts.treasury_sol_account.key(),
+            amount,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Release escrowed stablecoin payments to the presale treasury's token account once the
+    /// presale has ended with the soft cap reached. (Admin only)
+    pub fn release_stablecoin_funds(
+        ctx: Context<ReleaseStablecoinFunds>,
+        amount: u64,
+    ) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        ctx.accounts.program_config.record_admin_activity(now);
+        let presale_state = &ctx.accounts.presale_state;
+        require!(!presale_state.is_presale_active, ErrorCode::PresaleNotEnded);
+        require!(
+            presale_state.total_raised >= presale_state.soft_cap,
+            ErrorCode::SoftCapNotReached
+        );
+        require!(
+            ctx.accounts.treasury_token_account.owner
+                == ctx.accounts.program_config.presale_treasury,
+            ErrorCode::InvalidTreasury
+        );
+
+        let mint = ctx.accounts.stablecoin_vault.mint;
+        let bump = ctx.bumps.stablecoin_vault;
+        let seeds: &[&[u8]] = &[b"stablecoin_vault", mint.as_ref(), &[bump]];
+        token_interface::transfer_checked(
+            ctx.accounts.release_transfer_context().with_signer(&[seeds]),
+            amount,
+            ctx.accounts.stablecoin_mint.decimals,
+        )?;
+        emit!(FundsWithdrawn {
+            authority: ctx.accounts.admin.key(),
+            destination: ctx.accounts.treasury_token_account.key(),
+            amount,
+            timestamp: now,
+        });
+        Ok(())
+    }
+
+    /// Refund a stablecoin purchase from escrow to its payer when the presale ended below
+    /// the soft cap. SOL contributions are refunded by `claim_refund`.
+    pub fn claim_stablecoin_refund(ctx: Context<ClaimStablecoinRefund>) -> Result<()> {
+        let presale_state = &ctx.accounts.presale_state;
+        require!(!presale_state.is_presale_active, ErrorCode::PresaleNotEnded);
+        require!(
+            presale_state.total_raised < presale_state.soft_cap,
+            ErrorCode::SoftCapReached
+        );
+        let receipt = &mut ctx.accounts.receipt;
+        require!(
+            receipt.payer == ctx.accounts.payer.key(),
+            ErrorCode::Unauthorized
+        );
+        require!(
+            receipt.token_mint == ctx.accounts.stablecoin_vault.mint,
+            ErrorCode::InvalidTokenMint
+        );
+        require!(!receipt.refunded, ErrorCode::AlreadyRefunded);
+        let amount = receipt.amount;
+        require!(amount > 0, ErrorCode::InvalidAmount);
+        receipt.refunded = true;
+
+        let mint = ctx.accounts.stablecoin_vault.mint;
+        let bump = ctx.bumps.stablecoin_vault;
+        let seeds: &[&[u8]] = &[b"stablecoin_vault", mint.as_ref(), &[bump]];
+        token_interface::transfer_checked(
+            ctx.accounts.refund_transfer_context().with_signer(&[seeds]),
+            amount,
+            ctx.accounts.stablecoin_mint.decimals,
+        )?;
+        emit!(RefundClaimed {
+            buyer: ctx.accounts.payer.key(),
+            amount,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Refund a buyer's full contribution from the presale vault when the presale ended
+    /// below the soft cap.
+    pub fn claim_refund(ctx: Context<ClaimRefund>) -> Result<()> {
+        let presale_state = &ctx.accounts.presale_state;
+        require!(!presale_state.is_presale_active, ErrorCode::PresaleNotEnded);
+        require!(
+            presale_state.total_raised < presale_state.soft_cap,
+            ErrorCode::SoftCapReached
+        );
+        let buyer_info = &ctx.accounts.buyer_info;
+        require!(!buyer_info.refunded, ErrorCode::AlreadyRefunded);
+        let amount = buyer_info.total_contributed;
+        require!(amount > 0, ErrorCode::InvalidAmount);
+
+        let bump = ctx.bumps.presale_vault;
+        let seeds: &[&[u8]] = &[b"presale_vault", &[bump]];
+        let ix = system_instruction::transfer(
+            ctx.accounts.presale_vault.key,
+            ctx.accounts.buyer.key,
+            amount,
+        );
+        solana_program::program::invoke_signed(
+            &ix,
+            &[
+                ctx.accounts.presale_vault.to_account_info(),
+                ctx.accounts.buyer.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+            ],
+            &[seeds],
+        )?;
+        ctx.accounts.buyer_info.refunded = true;
+        emit!(RefundClaimed {
+            buyer: ctx.accounts.buyer.key(),
+            amount,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Correct a receipt's token allocation by `delta` within the dispute window, e.g. after a
+    /// mispriced purchase. Needs both the admin and the beneficiary; the change is capped at
+    /// `RECEIPT_MAX_ADJUSTMENT_BPS` of the allocation. The delta is booked against the stage
+    /// the receipt was bought in, so the stage's tokens sold and the progress bar stay in sync.
+    pub fn adjust_receipt(
+        ctx: Context<AdjustReceipt>,
+        delta: i64,
+        reason_hash: [u8; 32],
+    ) -> Result<()> {
+        let clock = Clock::get()?;
+        ctx.accounts.program_config.record_admin_activity(clock.unix_timestamp);
+        let receipt = &mut ctx.accounts.receipt;
+        require!(
+            receipt.beneficiary == ctx.accounts.beneficiary.key(),
+            ErrorCode::Unauthorized
+        );
+        require!(
+            clock.unix_timestamp <= receipt.timestamp + RECEIPT_DISPUTE_WINDOW,
+            ErrorCode::DisputeWindowClosed
+        );
+        require!(
+            !receipt.vested && receipt.tokens_paid == 0,
+            ErrorCode::AllocationAlreadyClaimed
+        );
+        let max_delta = receipt.tokens_allocated * RECEIPT_MAX_ADJUSTMENT_BPS / 10_000;
+        require!(
+            delta != 0 && delta.unsigned_abs() <= max_delta,
+            ErrorCode::InvalidReceiptAdjustment
+        );
+
+        let old_tokens_allocated = receipt.tokens_allocated;
+        let buyer_info = &mut ctx.accounts.buyer_info;
+        let mut presale_stage_info = ctx.accounts.presale_stage_info.load_mut()?;
+        let stage_index = receipt.entry_stage_index(presale_stage_info.stages.len());
+        let stage = &mut presale_stage_info.stages[stage_index];
+        if delta > 0 {
+            receipt.tokens_allocated = old_tokens_allocated.checked_add(delta as u64).unwrap();
+            buyer_info.tokens_allocated =
+                buyer_info.tokens_allocated.checked_add(delta as u64).unwrap();
+            stage.tokens_sold = stage.tokens_sold.checked_add(delta as u64).unwrap();
+        } else {
+            receipt.tokens_allocated =
+                old_tokens_allocated.checked_sub(delta.unsigned_abs()).unwrap();
+            buyer_info.tokens_allocated = buyer_info
+                .tokens_allocated
+                .checked_sub(delta.unsigned_abs())
+                .unwrap();
+            stage.tokens_sold = stage.tokens_sold.saturating_sub(delta.unsigned_abs());
+        }
+        ctx.accounts.presale_progress.refresh(&presale_stage_info, clock.unix_timestamp);
+
+        emit!(ReceiptAdjusted {
+            receipt: receipt.key(),
+            beneficiary: receipt.beneficiary,
+            old_tokens_allocated,
+            new_tokens_allocated: receipt.tokens_allocated,
+            reason_hash,
+            timestamp: clock.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Move a receipt's allocation to a new beneficiary before launch.
+    /// Both the current and the new beneficiary sign; the new one pays the protocol fee.
+    /// Neither may be blacklisted.
+    pub fn transfer_allocation(ctx: Context<TransferAllocation>) -> Result<()> {
+        BlacklistEntry::check(&ctx.accounts.blacklist_entry)?;
+        BlacklistEntry::check(&ctx.accounts.new_blacklist_entry)?;
+        require!(
+            ctx.accounts.presale_state.launch_time.is_none(),
+            ErrorCode::PresaleAlreadyEnded
+        );
+        require!(
+            ctx.accounts.receipt.beneficiary == ctx.accounts.beneficiary.key(),
+            ErrorCode::Unauthorized
+        );
+        require!(
+            ctx.accounts.new_beneficiary.key() != ctx.accounts.beneficiary.key(),
+            ErrorCode::InvalidBeneficiary
+        );
+        require!(
+            ctx.accounts.fee_wallet_sol_account.key() == ctx.accounts.program_config.fee_wallet,
+            ErrorCode::InvalidFeeWallet
+        );
+
+        let fee = ctx.accounts.program_config.allocation_transfer_fee;
+        if fee > 0 {
+            let ix = system_instruction::transfer(
+                ctx.accounts.new_beneficiary.key,
+                ctx.accounts.fee_wallet_sol_account.key,
+                fee,
+            );
+            solana_program::program::invoke(
+                &ix,
+                &[
+                    ctx.accounts.new_beneficiary.to_account_info(),
+                    ctx.accounts.fee_wallet_sol_account.to_account_info(),
+                    ctx.accounts.system_program.to_account_info(),
+                ],
+            )?;
+        }
+
+        ctx.accounts.receipt.beneficiary = ctx.accounts.new_beneficiary.key();
+        Ok(())
+    }
+
+    /// Create the referral pool that referral bonuses are paid from, along with the
+    /// referral tiers and leaderboard account, and set the base bonus rate.
+    /// The admin funds the pool with regular token transfers. (Admin only)
+    pub fn initialize_referral_pool(
+        ctx: Context<InitializeReferralPool>,
+        referral_bonus_bps: u16,
+    ) -> Result<()> {
+        require!(referral_bonus_bps <= 10_000, ErrorCode::InvalidFeeConfig);
+        let program_config = &mut ctx.accounts.program_config;
+        program_config.record_admin_activity(Clock::get()?.unix_timestamp);
+        program_config.referral_bonus_bps = referral_bonus_bps;
+        Ok(())
+    }
+
+    /// Set the referral commission tiers. Thresholds must be ascending. (Admin only)
+    pub fn set_referral_tiers(
+        ctx: Context<SetReferralTiers>,
+        tiers: [ReferralTier; 4],
+    ) -> Result<()> {
+        ctx.accounts.program_config.record_admin_activity(Clock::get()?.unix_timestamp);
+        let ascending = tiers
+            .windows(2)
+            .all(|pair| pair[0].min_referred_lamports <= pair[1].min_referred_lamports);
+        require!(
+            ascending && tiers.iter().all(|tier| tier.bonus_bps <= 10_000),
+            ErrorCode::InvalidReferralTiers
+        );
+        ctx.accounts.referral_program.tiers = tiers;
+        Ok(())
+    }
+
+    /// Create the caller's `ReferralAccount` so purchases can name them as referrer.
+    pub fn register_referrer(ctx: Context<RegisterReferrer>) -> Result<()> {
+        let referral_account = &mut ctx.accounts.referral_account;
+        referral_account.referrer = ctx.accounts.referrer.key();
+        let referral_account_key = referral_account.key();
+        PositionManager::update(&ctx.accounts.position_manager, |manager| {
+            manager.referral_account = referral_account_key
+        })?;
+        Ok(())
+    }
+
+    /// Pay the referrer's unclaimed referral bonus from the referral pool.
+    /// Fails while referral bonuses are auto-staked; use `claim_referral_rewards_staked` then.
+    pub fn claim_referral_rewards(ctx: Context<ClaimReferralRewards>) -> Result<()> {
+        require!(
+            ctx.accounts.program_config.referral_bonus_lock_period == 0,
+            ErrorCode::BonusMustBeStaked
+        );
+        let amount = ctx.accounts.take_rewards()?;
+
+        let bump = ctx.bumps.referral_pool;
+        let seeds: &[&[u8]] = &[b"referral_pool", &[bump]];
+        token_interface::transfer_checked(
+            ctx.accounts.referral_transfer_context().with_signer(&[seeds]),
+            amount,
+            ctx.accounts.token_mint.decimals,
+        )?;
+        emit!(ReferralRewardsClaimed {
+            referrer: ctx.accounts.referrer.key(),
+            amount,
+            staked: false,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Claim the referrer's unclaimed referral bonus into their locked bonus stake position,
+    /// for `referral_bonus_lock_period` seconds from now.
+    pub fn claim_referral_rewards_staked(
+        ctx: Context<ClaimReferralRewardsStaked>,
+    ) -> Result<()> {
+        let lock_period = ctx.accounts.claim.program_config.referral_bonus_lock_period;
+        require!(lock_period > 0, ErrorCode::BonusAutoStakeDisabled);
+        require!(
+            ctx.accounts.bonus_stake.owner.key() == ctx.accounts.claim.referrer.key(),
+            ErrorCode::Unauthorized
+        );
+        let amount = ctx.accounts.claim.take_rewards()?;
+
+        let bump = ctx.bumps.claim.referral_pool;
+        let seeds: &[&[u8]] = &[b"referral_pool", &[bump]];
+        let amount = transfer_received(
+            ctx.accounts.stake_transfer_context().with_signer(&[seeds]),
+            amount,
+            ctx.accounts.claim.token_mint.decimals,
+        )?;
+        ctx.accounts.bonus_stake.stake_bonus(amount, lock_period)?;
+        emit!(ReferralRewardsClaimed {
+            referrer: ctx.accounts.claim.referrer.key(),
+            amount,
+            staked: true,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Register (or update) an SPL payment mint with its price feed and fee rules. (Admin only)
+    /// The fee is `fee_bps` of the amount, at least `min_fee` base units of that mint.
+    pub fn add_accepted_mint(
+        ctx: Context<AddAcceptedMint>,
+        price_feed: Pubkey,
+        fee_bps: u16,
+        min_fee: u64,
+        is_stablecoin: bool,
+    ) -> Result<()> {
+        ctx.accounts.program_config.record_admin_activity(Clock::get()?.unix_timestamp);
+        require!(fee_bps <= 10_000, ErrorCode::InvalidFeeConfig);
+        let accepted_mint = &mut ctx.accounts.accepted_mint;
+        accepted_mint.mint = ctx.accounts.mint.key();
+        accepted_mint.price_feed = price_feed;
+        accepted_mint.decimals = ctx.accounts.mint.decimals;
+        accepted_mint.fee_bps = fee_bps;
+        accepted_mint.min_fee = min_fee;
+        accepted_mint.enabled = true;
+        accepted_mint.is_stablecoin = is_stablecoin;
+        Ok(())
+    }
+
+    /// Stop accepting payments in a mint. The entry is kept and can be re-enabled with
+    /// `add_accepted_mint`. (Admin only)
+    pub fn remove_accepted_mint(ctx: Context<RemoveAcceptedMint>) -> Result<()> {
+        ctx.accounts.program_config.record_admin_activity(Clock::get()?.unix_timestamp);
+        ctx.accounts.accepted_mint.enabled = false;
+        Ok(())
+    }
+
+    /// Deposit SOL into the treasury.
+    /// This is a dedicated deposit instruction for SOL. The deposit is categorized by `purpose`
+    /// in the deposit log, and `memo_hash` (hash of an off-chain attribution memo) is emitted
+    /// with it.
+    pub fn deposit_sol(
+        ctx: Context<DepositSol>,
+        amount: u64,
+        purpose: DepositPurpose,
+        memo_hash: [u8; 32],
+    ) -> Result<()> {
+        require!(amount > 0, ErrorCode::InvalidAmount);
+        let ix = system_instruction::transfer(
+            ctx.accounts.payer.key,
+            ctx.accounts.treasury_sol_account.key,
+            amount,
+        );
+        solana_program::program::invoke(
+            &ix,
+            &[
+                ctx.accounts.payer.to_account_info(),
+                ctx.accounts.treasury_sol_account.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+            ],
+        )?;
+        let deposit_id = ctx.accounts.deposit_log.record(purpose, amount);
+        emit!(SolDeposited {
+            deposit_id,
+            depositor: ctx.accounts.payer.key(),
+            purpose,
+            amount,
+            memo_hash,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Stake tokens during the presale into the wallet's primary position (the `StakeInfo` PDA
+    /// at `[b"stake", wallet]`), creating it on the first stake.
+    /// Staking is allowed only while the presale is active and if rewards are available.
+    pub fn stake_tokens(ctx: Context<StakeTokens>, amount: u64) -> Result<()> {
+        ctx.accounts.program_config.check_not_paused(PAUSE_STAKING)?;
+        BlacklistEntry::check(&ctx.accounts.blacklist_entry)?;
+        // Allow staking only if presale is active.
+        require!(
+            ctx.accounts.presale_state.is_presale_active,
+            ErrorCode::StakingClosed
+        );
+        // Also, ensure the reward pool is not empty.
+        require!(
+            ctx.accounts.global_state.reward_pool > 0,
+            ErrorCode::StakingRewardsExhausted
+        );
+        require!(amount > 0, ErrorCode::InvalidAmount);
+
+        // Transfer tokens from the user's account to the stake vault; the position is
+        // credited with what the vault received after any transfer fee.
+        let amount = transfer_received(
+            ctx.accounts.stake_transfer_context(),
+            amount,
+            ctx.accounts.token_mint.decimals,
+        )?;
+
+        let stake_info = &mut ctx.accounts.stake_info;
+        let global_state = &mut ctx.accounts.global_state;
+        // Record the owner on first stake; afterwards only the owner may top up (the position
+        // may have been transferred away).
+        if stake_info.owner == Pubkey::default() {
+            stake_info.owner = ctx.accounts.payer.key();
+        }
+        require!(
+            stake_info.owner == ctx.accounts.payer.key(),
+            ErrorCode::Unauthorized
+        );
+        require!(!stake_info.is_frozen, ErrorCode::PositionFrozen);
+        require!(
+            stake_info.unstake_queue_position == 0,
+            ErrorCode::PositionQueuedForUnstake
+        );
+        let mint = ctx.accounts.user_token_account.mint;
+        StakeMigration::check_stake_mint(&ctx.accounts.stake_migration, stake_info, mint)?;
+        if stake_info.amount == 0 {
+            stake_info.mint = mint;
+        }
+        require!(stake_info.mint == mint, ErrorCode::InvalidStakeMint);
+        let clock = Clock::get()?;
+        PartnerRewardPool::checkpoint(
+            &ctx.accounts.partner_reward_pool,
+            stake_info,
+            global_state.total_staked,
+            clock.unix_timestamp,
+        )?;
+        // Rewards earned so far are kept; the top-up only restarts the lock period.
+        let acc_reward_per_share = global_state.update_rewards(
+            &ctx.accounts.param_ramp,
+            &ctx.accounts.emission_schedule,
+            clock.unix_timestamp,
+        );
+        stake_info.settle_rewards(acc_reward_per_share);
+        // An empty position becomes open with this stake.
+        let staker_record = &mut ctx.accounts.staker_record;
+        if staker_record.owner == Pubkey::default() {
+            Stats::update(&ctx.accounts.stats, clock.unix_timestamp, |stats| {
+                stats.total_stakers = stats.total_stakers.checked_add(1).unwrap()
+            })?;
+        }
+        staker_record.owner = ctx.accounts.payer.key();
+        if stake_info.amount == 0 {
+            global_state.open_position(staker_record);
+            let position = stake_info.key();
+            PositionManager::update(&ctx.accounts.position_manager, |manager| {
+                manager.add_stake_position(position)
+            })?;
+        }
+        // Enforce the per-wallet cap across all of the wallet's positions.
+        staker_record.total_staked = staker_record.total_staked.checked_add(amount).unwrap();
+        let max_stake_per_wallet = ctx.accounts.staking_config.max_stake_per_wallet;
+        require!(
+            max_stake_per_wallet == 0 || staker_record.total_staked <= max_stake_per_wallet,
+            ErrorCode::WalletStakeCapExceeded
+        );
+        StakingLeaderboard::record(
+            &ctx.accounts.staking_leaderboard,
+            staker_record.owner,
+            staker_record.total_staked,
+        )?;
+        if stake_info.amount == 0 {
+            stake_info.last_claim_time = clock.unix_timestamp;
+        }
+        stake_info.amount = stake_info.amount.checked_add(amount).unwrap();
+        stake_info.reset_reward_debt(acc_reward_per_share);
+        global_state.total_staked = global_state.total_staked.checked_add(amount).unwrap();
+        stake_info.start_time = clock.unix_timestamp;
+        stake_info.last_activity = clock.unix_timestamp;
+        emit!(Staked {
+            owner: ctx.accounts.payer.key(),
+            position: ctx.accounts.stake_info.key(),
+            amount,
+            position_amount: ctx.accounts.stake_info.amount,
+            timestamp: clock.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Request to unstake `amount` tokens from a position; the remainder keeps accruing under
+    /// the position's original start time. The unstaked tokens stop earning at once and can
+    /// be withdrawn with `withdraw_unstaked` after the unstake cooldown; a new request restarts
+    /// the cooldown for everything pending.
+    /// If the staking lock period has been met, the full amount becomes withdrawable and the
+    /// position's settled rewards are paid out, if the reward pool covers them.
+    /// Otherwise, if early unstaking is used (allowed only after the early-unstake period),
+    /// the early-unstake penalty (20% by default, set by governance) is applied to the
+    /// unstaked amount: the user can withdraw (100 - penalty)% of it, and the penalty goes to
+    /// the reward pool (the governance-set redistribution share) or is burned.
+    pub fn request_unstake(ctx: Context<RequestUnstake>, amount: u64) -> Result<()> {
+        ctx.accounts.program_config.check_not_paused(PAUSE_UNSTAKING)?;
+        let stake_info = &mut ctx.accounts.stake_info;
+        let global_state = &mut ctx.accounts.global_state;
+        let clock = Clock::get()?;
+        let staking_duration = clock.unix_timestamp - stake_info.start_time;
+        require!(!stake_info.is_frozen, ErrorCode::PositionFrozen);
+        require!(
+            clock.unix_timestamp >= stake_info.voting_locked_until,
+            ErrorCode::PositionVoteLocked
+        );
+        require!(
+            clock.unix_timestamp >= stake_info.locked_until,
+            ErrorCode::PositionLocked
+        );
+
+        // Check that early unstaking is allowed (7 days after launch by default)
+        if let Some(launch_time) = ctx.accounts.presale_state.launch_time {
+            let early_unstake_period = ctx.accounts.program_config.early_unstake_period;
+            if clock.unix_timestamp < launch_time + early_unstake_period {
+                return Err(ErrorCode::UnstakingNotAllowedBefore7Days.into());
+            }
+        }
+
+        require!(
+            amount > 0 && amount <= stake_info.amount,
+            ErrorCode::InvalidAmount
+        );
+        // Whale-exit throttle: a queued position waits for its window; otherwise the unstake
+        // must fit in the current window's remaining capacity (else use `queue_unstake`).
+        let staking_config = &mut ctx.accounts.staking_config;
+        if stake_info.unstake_queue_position != 0 {
+            require!(
+                staking_config.unstake_throttle_bps == 0
+                    || clock.unix_timestamp / UNSTAKE_THROTTLE_WINDOW >= stake_info.unstake_window,
+                ErrorCode::UnstakeThrottled
+            );
+            stake_info.unstake_queue_position = 0;
+            stake_info.unstake_window = 0;
+        } else if staking_config.unstake_throttle_bps > 0 {
+            let window = staking_config.reserve_unstake(
+                amount,
+                global_state.total_staked,
+                clock.unix_timestamp,
+            );
+            require!(
+                window == clock.unix_timestamp / UNSTAKE_THROTTLE_WINDOW,
+                ErrorCode::UnstakeThrottled
+            );
+        }
+        // Rewards earned so far stay claimable after the position is closed.
+        PartnerRewardPool::checkpoint(
+            &ctx.accounts.partner_reward_pool,
+            stake_info,
+            global_state.total_staked,
+            clock.unix_timestamp,
+        )?;
+        let acc_reward_per_share = global_state.update_rewards(
+            &ctx.accounts.param_ramp,
+            &ctx.accounts.emission_schedule,
+            clock.unix_timestamp,
+        );
+        stake_info.settle_rewards(acc_reward_per_share);
+        let staker_record = &mut ctx.accounts.staker_record;
+        staker_record.total_staked = staker_record.total_staked.checked_sub(amount).unwrap();
+        StakingLeaderboard::record(
+            &ctx.accounts.staking_leaderboard,
+            stake_info.owner,
+            staker_record.total_staked,
+        )?;
+        stake_info.amount = stake_info.amount.checked_sub(amount).unwrap();
+        stake_info.reset_reward_debt(acc_reward_per_share);
+        global_state.total_staked = global_state.total_staked.checked_sub(amount).unwrap();
+        if stake_info.amount == 0 {
+            global_state.close_position(staker_record);
+            let position = stake_info.key();
+            PositionManager::update(&ctx.accounts.position_manager, |manager| {
+                manager.remove_stake_position(position)
+            })?;
+        }
+        // A governance penalty waiver (e.g. after a serious incident) counts as a completed period.
+        let period_complete = staking_duration >= ctx.accounts.program_config.staking_lock_period
+            || clock.unix_timestamp < global_state.penalty_waiver_until;
+        // After a completed period, settled rewards are paid out with the principal when the
+        // pool covers them; otherwise they stay claimable through `claim_rewards`.
+        // Blacklisted wallets get their principal back but no rewards.
+        let rewards = stake_info.rewards_owed;
+        let auto_claim = period_complete
+            && rewards > 0
+            && ctx.accounts.program_config.paused_operations & PAUSE_CLAIMS == 0
+            && !BlacklistEntry::is_blocked(&ctx.accounts.blacklist_entry)?
+            && ctx.accounts.reward_vault.mint == stake_info.mint
+            && ctx.accounts.reward_vault.amount >= rewards
+            && global_state.reward_pool >= rewards;
+        if auto_claim {
+            global_state.reward_pool = global_state.reward_pool.checked_sub(rewards).unwrap();
+            stake_info.rewards_owed = 0;
+            stake_info.last_claim_time = clock.unix_timestamp;
+        }
+        // Early unstake: the penalty on the withdrawn amount is split between the reward pool
+        // (the governance-set redistribution share) and a burn.
+        let penalty_amount = if period_complete {
+            0
+        } else {
+            amount
+                .checked_mul(global_state.early_unstake_penalty_percent)
+                .unwrap()
+                .checked_div(100)
+                .unwrap()
+        };
+        let redistributed_amount = if ctx.accounts.reward_vault.mint == stake_info.mint {
+            penalty_amount
+                .checked_mul(global_state.penalty_redistribute_percent)
+                .unwrap()
+                .checked_div(100)
+                .unwrap()
+        } else {
+            0
+        };
+        let paid_rewards = if auto_claim { rewards } else { 0 };
+        Stats::update(&ctx.accounts.stats, clock.unix_timestamp, |stats| {
+            stats.total_rewards_distributed =
+                stats.total_rewards_distributed.checked_add(paid_rewards).unwrap();
+            stats.total_penalties_burned = stats
+                .total_penalties_burned
+                .checked_add(penalty_amount - redistributed_amount)
+                .unwrap();
+        })?;
+        // The unstaked tokens stay in the stake vault until the cooldown has passed.
+        stake_info.pending_withdrawal = stake_info
+            .pending_withdrawal
+            .checked_add(amount.checked_sub(penalty_amount).unwrap())
+            .unwrap();
+        stake_info.withdrawable_at = clock
+            .unix_timestamp
+            .checked_add(staking_config.unstake_cooldown)
+            .unwrap();
+        let mint = stake_info.mint;
+        let bump = ctx.bumps.stake_vault;
+        let seeds: &[&[u8]] = &[b"stake_vault", mint.as_ref(), &[bump]];
+        if period_complete {
+            if auto_claim {
+                let bump = ctx.bumps.reward_vault;
+                let seeds: &[&[u8]] = &[b"reward_vault", &[bump]];
+                token_interface::transfer_checked(
+                    ctx.accounts.reward_transfer_context().with_signer(&[seeds]),
+                    rewards,
+                    ctx.accounts.mint.decimals,
+                )?;
+            }
+        } else {
+            if redistributed_amount > 0 {
+                // The pool is credited with what the reward vault received after any fee.
+                let received = transfer_received(
+                    ctx.accounts.penalty_redistribution_context().with_signer(&[seeds]),
+                    redistributed_amount,
+                    ctx.accounts.mint.decimals,
+                )?;
+                ctx.accounts
+                    .global_state
+                    .credit_reward_pool(received, RewardSource::Penalty);
+            }
+            // Burn the rest of the penalty.
+            token_interface::burn(
+                ctx.accounts.early_unstake_burn_context().with_signer(&[seeds]),
+                penalty_amount - redistributed_amount,
+            )?;
+        }
+        let stake_info = &ctx.accounts.stake_info;
+        emit!(Unstaked {
+            owner: stake_info.owner,
+            position: stake_info.key(),
+            amount,
+            penalty: penalty_amount,
+            withdrawable_at: stake_info.withdrawable_at,
+            timestamp: clock.unix_timestamp,
+        });
+        if auto_claim {
+            emit!(RewardsClaimed {
+                owner: stake_info.owner,
+                position: stake_info.key(),
+                amount: rewards,
+                timestamp: clock.unix_timestamp,
+            });
+        }
+        Ok(())
+    }
+
+    /// Withdraw a position's unstaked tokens once the unstake cooldown has passed.
+    pub fn withdraw_unstaked(ctx: Context<WithdrawUnstaked>) -> Result<()> {
+        ctx.accounts.program_config.check_not_paused(PAUSE_UNSTAKING)?;
+        let stake_info = &mut ctx.accounts.stake_info;
+        require!(
+            stake_info.owner == ctx.accounts.owner.key(),
+            ErrorCode::Unauthorized
+        );
+        require!(!stake_info.is_frozen, ErrorCode::PositionFrozen);
+        let amount = stake_info.pending_withdrawal;
+        require!(amount > 0, ErrorCode::NoPendingWithdrawal);
+        require!(
+            Clock::get()?.unix_timestamp >= stake_info.withdrawable_at,
+            ErrorCode::UnstakeCooldownActive
+        );
+        stake_info.pending_withdrawal = 0;
+
+        let mint = stake_info.mint;
+        let bump = ctx.bumps.stake_vault;
+        let seeds: &[&[u8]] = &[b"stake_vault", mint.as_ref(), &[bump]];
+        token_interface::transfer_checked(
+            ctx.accounts.withdraw_transfer_context().with_signer(&[seeds]),
+            amount,
+            ctx.accounts.token_mint.decimals,
+        )?;
+        emit!(UnstakeWithdrawn {
+            owner: ctx.accounts.owner.key(),
+            position: ctx.accounts.stake_info.key(),
+            amount,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Hand a stake position to `new_owner`, e.g. for an OTC sale of a locked position. The
+    /// start time, locks and unclaimed rewards move with it. Neither wallet may be blacklisted.
+    /// (Position owner only, while position transfers are enabled)
+    pub fn transfer_stake_position(
+        ctx: Context<TransferStakePosition>,
+        new_owner: Pubkey,
+    ) -> Result<()> {
+        require!(
+            ctx.accounts.program_config.position_transfers_enabled,
+            ErrorCode::PositionTransfersDisabled
+        );
+        BlacklistEntry::check(&ctx.accounts.blacklist_entry)?;
+        BlacklistEntry::check(&ctx.accounts.new_blacklist_entry)?;
+        let stake_info = &mut ctx.accounts.stake_info;
+        require!(!stake_info.is_frozen, ErrorCode::PositionFrozen);
+        require!(
+            stake_info.unstake_queue_position == 0 && stake_info.pending_withdrawal == 0,
+            ErrorCode::PositionQueuedForUnstake
+        );
+        require!(stake_info.amount > 0, ErrorCode::InvalidAmount);
+        // Bonus positions are found by their owner's address; they stay with that wallet.
+        let (bonus_stake, _) = Pubkey::find_program_address(
+            &[b"bonus_stake", stake_info.owner.as_ref()],
+            ctx.program_id,
+        );
+        require!(stake_info.key() != bonus_stake, ErrorCode::PositionNotTransferable);
+        require!(stake_info.boost_nft == Pubkey::default(), ErrorCode::NftBoostActive);
+
+        let amount = stake_info.amount;
+        let global_state = &mut ctx.accounts.global_state;
+        let staker_record = &mut ctx.accounts.staker_record;
+        staker_record.total_staked = staker_record.total_staked.checked_sub(amount).unwrap();
+        global_state.close_position(staker_record);
+        let now = Clock::get()?.unix_timestamp;
+        let new_staker_record = &mut ctx.accounts.new_staker_record;
+        if new_staker_record.owner == Pubkey::default() {
+            Stats::update(&ctx.accounts.stats, now, |stats| {
+                stats.total_stakers = stats.total_stakers.checked_add(1).unwrap()
+            })?;
+        }
+        new_staker_record.owner = new_owner;
+        new_staker_record.total_staked =
+            new_staker_record.total_staked.checked_add(amount).unwrap();
+        let max_stake_per_wallet = ctx.accounts.staking_config.max_stake_per_wallet;
+        require!(
+            max_stake_per_wallet == 0 || new_staker_record.total_staked <= max_stake_per_wallet,
+            ErrorCode::WalletStakeCapExceeded
+        );
+        global_state.open_position(new_staker_record);
+        StakingLeaderboard::record(
+            &ctx.accounts.staking_leaderboard,
+            stake_info.owner,
+            staker_record.total_staked,
+        )?;
+        StakingLeaderboard::record(
+            &ctx.accounts.staking_leaderboard,
+            new_owner,
+            new_staker_record.total_staked,
+        )?;
+
+        let position = stake_info.key();
+        PositionManager::update(&ctx.accounts.position_manager, |manager| {
+            manager.remove_stake_position(position)
+        })?;
+        PositionManager::update(&ctx.accounts.new_position_manager, |manager| {
+            manager.add_stake_position(position)
+        })?;
+        let previous_owner = stake_info.owner;
+        stake_info.owner = new_owner;
+        emit!(StakePositionTransferred {
+            position: stake_info.key(),
+            previous_owner,
+            new_owner,
+            amount,
+            timestamp: now,
+        });
+        Ok(())
+    }
+
+    /// Create the caller's `PositionManager`. Accounts opened before it existed are listed by
+    /// passing them in: the caller's open stake positions and purchase receipts as remaining
+    /// accounts, plus the vesting schedule and referral account PDAs if they exist.
+    pub fn initialize_position_manager(ctx: Context<InitializePositionManager>) -> Result<()> {
+        let owner = ctx.accounts.owner.key();
+        let manager = &mut ctx.accounts.position_manager;
+        manager.owner = owner;
+        if !ctx.accounts.vesting_schedule.data_is_empty() {
+            manager.vesting_schedule = ctx.accounts.vesting_schedule.key();
+        }
+        if !ctx.accounts.referral_account.data_is_empty() {
+            manager.referral_account = ctx.accounts.referral_account.key();
+        }
+        for info in ctx.remaining_accounts {
+            if let Ok(stake_info) = load_account::<StakeInfo>(info) {
+                require!(stake_info.owner == owner, ErrorCode::Unauthorized);
+                if stake_info.amount > 0 && !manager.stake_positions.contains(info.key) {
+                    manager.add_stake_position(info.key());
+                }
+            } else {
+                let receipt: PurchaseReceipt = load_account(info)?;
+                require!(receipt.beneficiary == owner, ErrorCode::Unauthorized);
+                if !manager.receipts.contains(info.key) {
+                    manager.add_receipt(info.key());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Take a place in the unstake queue when the whale-exit throttle is on: the position's
+    /// amount is reserved in the earliest throttle window with capacity left, and
+    /// `request_unstake` succeeds from that window on. (Position owner only)
+    pub fn queue_unstake(ctx: Context<QueueUnstake>) -> Result<()> {
+        let staking_config = &mut ctx.accounts.staking_config;
+        require!(
+            staking_config.unstake_throttle_bps > 0,
+            ErrorCode::UnstakeThrottleDisabled
+        );
+        let stake_info = &mut ctx.accounts.stake_info;
+        require!(
+            stake_info.owner == ctx.accounts.owner.key(),
+            ErrorCode::Unauthorized
+        );
+        require!(stake_info.amount > 0, ErrorCode::InvalidAmount);
+        require!(
+            stake_info.unstake_queue_position == 0,
+            ErrorCode::PositionQueuedForUnstake
+        );
+        let now = Clock::get()?.unix_timestamp;
+        stake_info.unstake_window = staking_config.reserve_unstake(
+            stake_info.amount,
+            ctx.accounts.global_state.total_staked,
+            now,
+        );
+        staking_config.unstake_queue_length =
+            staking_config.unstake_queue_length.checked_add(1).unwrap();
+        stake_info.unstake_queue_position = staking_config.unstake_queue_length;
+        Ok(())
+    }
+
+    /// Withdraw a position's full principal while emergency unstaking is enabled, without any
+    /// reward or penalty math; accrued rewards are forfeited. Not subject to the pause flags,
+    /// so principal stays withdrawable even if reward accounting is broken. Vote locks still
+    /// apply, a queued position leaves the unstake queue, and the reward accumulators are
+    /// advanced before the total changes so other stakers keep what they accrued.
+    pub fn emergency_unstake(ctx: Context<EmergencyUnstake>) -> Result<()> {
+        require!(
+            ctx.accounts.program_config.emergency_unstake_enabled,
+            ErrorCode::EmergencyUnstakeDisabled
+        );
+        let stake_info = &mut ctx.accounts.stake_info;
+        require!(
+            stake_info.owner == ctx.accounts.payer.key(),
+            ErrorCode::Unauthorized
+        );
+        require!(!stake_info.is_frozen, ErrorCode::PositionFrozen);
+        let now = Clock::get()?.unix_timestamp;
+        require!(
+            now >= stake_info.voting_locked_until,
+            ErrorCode::PositionVoteLocked
+        );
+        let amount = stake_info.amount;
+        require!(amount > 0, ErrorCode::InvalidAmount);
+        if stake_info.unstake_queue_position != 0 {
+            ctx.accounts
+                .staking_config
+                .release_unstake(amount, stake_info.unstake_window);
+            stake_info.unstake_queue_position = 0;
+            stake_info.unstake_window = 0;
+        }
+        // Advance the accumulators at the old total; the position's own share is forfeited.
+        PartnerRewardPool::checkpoint(
+            &ctx.accounts.partner_reward_pool,
+            stake_info,
+            ctx.accounts.global_state.total_staked,
+            now,
+        )?;
+        ctx.accounts.global_state.update_rewards(
+            &ctx.accounts.param_ramp,
+            &ctx.accounts.emission_schedule,
+            now,
+        );
+
+        // Saturating updates: the aggregates may be the thing that is broken.
+        let staker_record = &mut ctx.accounts.staker_record;
+        staker_record.total_staked = staker_record.total_staked.saturating_sub(amount);
+        staker_record.open_positions = staker_record.open_positions.saturating_sub(1);
+        StakingLeaderboard::record(
+            &ctx.accounts.staking_leaderboard,
+            stake_info.owner,
+            staker_record.total_staked,
+        )?;
+        let global_state = &mut ctx.accounts.global_state;
+        if staker_record.open_positions == 0 {
+            global_state.unique_stakers = global_state.unique_stakers.saturating_sub(1);
+        }
+        global_state.total_positions = global_state.total_positions.saturating_sub(1);
+        global_state.total_staked = global_state.total_staked.saturating_sub(amount);
+        stake_info.amount = 0;
+        stake_info.reward_debt = 0;
+        stake_info.rewards_owed = 0;
+        stake_info.last_claim_time = now;
+        let position = stake_info.key();
+        PositionManager::update(&ctx.accounts.position_manager, |manager| {
+            manager.remove_stake_position(position)
+        })?;
+
+        let mint = stake_info.mint;
+        let bump = ctx.bumps.stake_vault;
+        let seeds: &[&[u8]] = &[b"stake_vault", mint.as_ref(), &[bump]];
+        token_interface::transfer_checked(
+            ctx.accounts.emergency_transfer_context().with_signer(&[seeds]),
+            amount,
+            ctx.accounts.token_mint.decimals,
+        )?;
+        // Paid out at once, without a penalty.
+        emit!(Unstaked {
+            owner: ctx.accounts.payer.key(),
+            position,
+            amount,
+            penalty: 0,
+            withdrawable_at: now,
+            timestamp: now,
+        });
+        Ok(())
+    }
+
+    /// Split a stake position into two.
+    /// `amount` tokens are moved into a new position PDA; the remainder stays in the original.
+    /// Rewards accrued so far are settled on the original position, and both positions keep
+    /// the same start time, so the early-unstake penalty on each half adds up to the original's.
+    pub fn split_position(
+        ctx: Context<SplitPosition>,
+        amount: u64,
+        new_position_id: u64,
+    ) -> Result<()> {
+        let stake_info = &mut ctx.accounts.stake_info;
+        require!(
+            stake_info.owner == ctx.accounts.owner.key(),
+            ErrorCode::Unauthorized
+        );
+        require!(!stake_info.is_frozen, ErrorCode::PositionFrozen);
+        require!(
+            amount > 0 && amount < stake_info.amount,
+            ErrorCode::InvalidSplitAmount
+        );
+        require!(
+            stake_info.unstake_queue_position == 0,
+            ErrorCode::PositionQueuedForUnstake
+        );
+        let now = Clock::get()?.unix_timestamp;
+        require!(
+            now >= stake_info.voting_locked_until,
+            ErrorCode::PositionVoteLocked
+        );
+
+        let acc_reward_per_share = ctx.accounts.global_state.update_rewards(
+            &ctx.accounts.param_ramp,
+            &ctx.accounts.emission_schedule,
+            now,
+        );
+        stake_info.settle_rewards(acc_reward_per_share);
+
+        let new_stake_info = &mut ctx.accounts.new_stake_info;
+        new_stake_info.owner = stake_info.owner;
+        new_stake_info.position_id = new_position_id;
+        new_stake_info.amount = amount;
+        new_stake_info.start_time = stake_info.start_time;
+        new_stake_info.last_claim_time = stake_info.last_claim_time;
+        // Both halves accrue partner rewards from the same accumulator value; already settled
+        // rewards stay with the original position.
+        new_stake_info.partner_reward_per_token_paid = stake_info.partner_reward_per_token_paid;
+        new_stake_info.migrated = stake_info.migrated;
+        new_stake_info.mint = stake_info.mint;
+        new_stake_info.locked_until = stake_info.locked_until;
+        new_stake_info.last_activity = now;
+        stake_info.last_activity = now;
+
+        new_stake_info.reset_reward_debt(acc_reward_per_share);
+
+        stake_info.amount = stake_info.amount.checked_sub(amount).unwrap();
+        stake_info.reset_reward_debt(acc_reward_per_share);
+        ctx.accounts
+            .global_state
+            .open_position(&mut ctx.accounts.staker_record);
+        let position = ctx.accounts.new_stake_info.key();
+        PositionManager::update(&ctx.accounts.position_manager, |manager| {
+            manager.add_stake_position(position)
+        })?;
+        Ok(())
+    }
+
+    /// Approve migrating stake positions to `new_mint` at `ratio_numerator / ratio_denominator`
+    /// new tokens per old token, and create the migration vault to pre-fund and the new mint's
+    /// stake vault. (Admin only)
+    pub fn approve_stake_migration(
+        ctx: Context<ApproveStakeMigration>,
+        ratio_numerator: u64,
+        ratio_denominator: u64,
+    ) -> Result<()> {
+        require!(
+            ratio_numerator > 0 && ratio_denominator > 0,
+            ErrorCode::InvalidMigrationRatio
+        );
+        let now = Clock::get()?.unix_timestamp;
+        ctx.accounts.program_config.record_admin_activity(now);
+        let stake_migration = &mut ctx.accounts.stake_migration;
+        stake_migration.old_mint = ctx.accounts.old_mint.key();
+        stake_migration.new_mint = ctx.accounts.new_mint.key();
+        stake_migration.ratio_numerator = ratio_numerator;
+        stake_migration.ratio_denominator = ratio_denominator;
+        stake_migration.approved_at = now;
+        Ok(())
+    }
+
+    /// Convert a stake position to the new mint in place. The start time and last claim time
+    /// are kept, so lock progress and pending rewards carry over (rewards are paid on the
+    /// converted amount) with no unstake penalty. Positions with a queued unstake or a
+    /// pending withdrawal must settle it first.
+    pub fn migrate_stake(ctx: Context<MigrateStake>) -> Result<()> {
+        let stake_info = &mut ctx.accounts.stake_info;
+        require!(
+            stake_info.owner == ctx.accounts.owner.key(),
+            ErrorCode::Unauthorized
+        );
+        require!(!stake_info.is_frozen, ErrorCode::PositionFrozen);
+        require!(!stake_info.migrated, ErrorCode::PositionAlreadyMigrated);
+        require!(stake_info.amount > 0, ErrorCode::InvalidAmount);
+        // Pending withdrawals are paid from the vault of `stake_info.mint`, so they must
+        // be settled in the old mint first.
+        require!(
+            stake_info.unstake_queue_position == 0 && stake_info.pending_withdrawal == 0,
+            ErrorCode::PositionQueuedForUnstake
+        );
+        let old_amount = stake_info.amount;
+        let converted = ctx.accounts.stake_migration.convert(old_amount);
+        require!(converted > 0, ErrorCode::InvalidAmount);
+
+        // The position holds what the new stake vault received after any transfer fee.
+        let bump = ctx.bumps.migration_vault;
+        let seeds: &[&[u8]] = &[b"migration_vault", &[bump]];
+        let new_amount = transfer_received(
+            ctx.accounts.migration_transfer_context().with_signer(&[seeds]),
+            converted,
+            ctx.accounts.new_mint.decimals,
+        )?;
+
+        let stake_info = &mut ctx.accounts.stake_info;
+        let global_state = &mut ctx.accounts.global_state;
+        let now = Clock::get()?.unix_timestamp;
+        PartnerRewardPool::checkpoint(
+            &ctx.accounts.partner_reward_pool,
+            stake_info,
+            global_state.total_staked,
+            now,
+        )?;
+        let acc_reward_per_share = global_state.update_rewards(
+            &ctx.accounts.param_ramp,
+            &ctx.accounts.emission_schedule,
+            now,
+        );
+        stake_info.settle_rewards(acc_reward_per_share);
+        let stake_migration = &mut ctx.accounts.stake_migration;
+
+        global_state.total_staked = global_state
+            .total_staked
+            .checked_sub(old_amount)
+            .unwrap()
+            .checked_add(new_amount)
+            .unwrap();
+        let staker_record = &mut ctx.accounts.staker_record;
+        staker_record.total_staked = staker_record
+            .total_staked
+            .checked_sub(old_amount)
+            .unwrap()
+            .checked_add(new_amount)
+            .unwrap();
+        stake_info.amount = new_amount;
+        stake_info.reset_reward_debt(acc_reward_per_share);
+        stake_info.migrated = true;
+        stake_info.mint = stake_migration.new_mint;
+
+        stake_migration.positions_migrated = stake_migration.positions_migrated.checked_add(1).unwrap();
+        stake_migration.old_amount_migrated =
+            stake_migration.old_amount_migrated.checked_add(old_amount).unwrap();
+        stake_migration.new_amount_migrated =
+            stake_migration.new_amount_migrated.checked_add(converted).unwrap();
+        Ok(())
+    }
+
+    /// Freeze a stake position, suspending reward accrual and unstaking (e.g. during a
+    /// stolen-funds dispute). (Admin only)
+    pub fn freeze_position(ctx: Context<FreezePosition>, reason_hash: [u8; 32]) -> Result<()> {
+        let clock = Clock::get()?;
+        ctx.accounts.program_config.record_admin_activity(clock.unix_timestamp);
+        let stake_info = &mut ctx.accounts.stake_info;
+        require!(!stake_info.is_frozen, ErrorCode::PositionFrozen);
+        let acc_reward_per_share = ctx.accounts.global_state.update_rewards(
+            &ctx.accounts.param_ramp,
+            &ctx.accounts.emission_schedule,
+            clock.unix_timestamp,
+        );
+        stake_info.settle_rewards(acc_reward_per_share);
+        stake_info.is_frozen = true;
+        stake_info.freeze_reason_hash = reason_hash;
+        stake_info.frozen_at = Some(clock.unix_timestamp);
+        Ok(())
+    }
+
+    /// Unfreeze a stake position. Rewards resume from now; the frozen period earns nothing. (Admin only)
+    pub fn unfreeze_position(ctx: Context<FreezePosition>) -> Result<()> {
+        let clock = Clock::get()?;
+        ctx.accounts.program_config.record_admin_activity(clock.unix_timestamp);
+        let stake_info = &mut ctx.accounts.stake_info;
+        require!(stake_info.is_frozen, ErrorCode::PositionNotFrozen);
+        // Accrue from now on, skipping the frozen period.
+        let acc_reward_per_share = ctx.accounts.global_state.update_rewards(
+            &ctx.accounts.param_ramp,
+            &ctx.accounts.emission_schedule,
+            clock.unix_timestamp,
+        );
+        stake_info.reset_reward_debt(acc_reward_per_share);
+        stake_info.is_frozen = false;
+        stake_info.unfrozen_at = Some(clock.unix_timestamp);
+        Ok(())
+    }
+
+    /// Create a merkle airdrop and fund its vault from the admin's token account. (Admin only)
+    pub fn create_airdrop(
+        ctx: Context<CreateAirdrop>,
+        distributor_id: u64,
+        merkle_root: [u8; 32],
+        total_amount: u64,
+        claim_deadline: i64,
+    ) -> Result<()> {
+        let clock = Clock::get()?;
+        ctx.accounts.program_config.record_admin_activity(clock.unix_timestamp);
+        require!(total_amount > 0, ErrorCode::InvalidAmount);
+        require!(claim_deadline > clock.unix_timestamp, ErrorCode::InvalidClaimWindow);
+
+        let distributor = &mut ctx.accounts.distributor;
+        distributor.distributor_id = distributor_id;
+        distributor.bump = ctx.bumps.distributor;
+        distributor.merkle_root = merkle_root;
+        distributor.vault = ctx.accounts.vault.key();
+        distributor.sweep_destination = ctx.accounts.sweep_destination.key();
+        distributor.total_amount = total_amount;
+        distributor.claim_deadline = claim_deadline;
+        distributor.total_claimed = 0;
+        distributor.num_claimants = 0;
+        distributor.swept_amount = 0;
+        distributor.swept_at = None;
+
+        // Record what the vault actually holds after any transfer fee.
+        let received = transfer_received(
+            ctx.accounts.fund_vault_context(),
+            total_amount,
+            ctx.accounts.mint.decimals,
+        )?;
+        ctx.accounts.distributor.total_amount = received;
+        Ok(())
+    }
+
+    /// Claim an airdrop allocation with a merkle proof, before the claim deadline.
+    pub fn claim_airdrop(
+        ctx: Context<ClaimAirdrop>,
+        amount: u64,
+        proof: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        ctx.accounts.program_config.check_not_paused(PAUSE_CLAIMS)?;
+        BlacklistEntry::check(&ctx.accounts.blacklist_entry)?;
+        let clock = Clock::get()?;
+        let distributor = &ctx.accounts.distributor;
+        require!(
+            clock.unix_timestamp < distributor.claim_deadline,
+            ErrorCode::AirdropClaimExpired
+        );
+        let leaf = keccak::hashv(&[
+            ctx.accounts.claimant.key().as_ref(),
+            &amount.to_le_bytes(),
+        ])
+        .0;
+        require!(
+            verify_merkle_proof(&proof, distributor.merkle_root, leaf),
+            ErrorCode::InvalidMerkleProof
+        );
+
+        let distributor_id = distributor.distributor_id.to_le_bytes();
+        let seeds: &[&[u8]] = &[b"airdrop", &distributor_id, &[distributor.bump]];
+        token_interface::transfer_checked(
+            ctx.accounts.claim_transfer_context().with_signer(&[seeds]),
+            amount,
+            ctx.accounts.mint.decimals,
+        )?;
+
+        let airdrop_claim = &mut ctx.accounts.airdrop_claim;
+        airdrop_claim.distributor = ctx.accounts.distributor.key();
+        airdrop_claim.claimant = ctx.accounts.claimant.key();
+        airdrop_claim.amount = amount;
+        airdrop_claim.claimed_at = clock.unix_timestamp;
+
+        let distributor = &mut ctx.accounts.distributor;
+        distributor.total_claimed = distributor.total_claimed.checked_add(amount).unwrap();
+        distributor.num_claimants = distributor.num_claimants.checked_add(1).unwrap();
+        emit!(AirdropClaimed {
+            distributor: distributor.key(),
+            claimant: ctx.accounts.claimant.key(),
+            amount,
+            timestamp: clock.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// After the claim deadline, return every unclaimed token to the community pool. (Admin only)
+    pub fn sweep_unclaimed(ctx: Context<SweepUnclaimed>) -> Result<()> {
+        let clock = Clock::get()?;
+        ctx.accounts.program_config.record_admin_activity(clock.unix_timestamp);
+        let distributor = &ctx.accounts.distributor;
+        require!(
+            clock.unix_timestamp >= distributor.claim_deadline,
+            ErrorCode::AirdropStillClaimable
+        );
+        require!(distributor.swept_at.is_none(), ErrorCode::AirdropAlreadySwept);
+
+        let amount = ctx.accounts.vault.amount;
+        let distributor_id = distributor.distributor_id.to_le_bytes();
+        let seeds: &[&[u8]] = &[b"airdrop", &distributor_id, &[distributor.bump]];
+        if amount > 0 {
+            token_interface::transfer_checked(
+                ctx.accounts.sweep_transfer_context().with_signer(&[seeds]),
+                amount,
+                ctx.accounts.mint.decimals,
+            )?;
+        }
+
+        let distributor = &mut ctx.accounts.distributor;
+        distributor.swept_amount = amount;
+        distributor.swept_at = Some(clock.unix_timestamp);
+        Ok(())
+    }
+
+    /// Create the reflection pool and its vault. (Admin only)
+    pub fn initialize_reflections(ctx: Context<InitializeReflections>) -> Result<()> {
+        ctx.accounts.program_config.record_admin_activity(Clock::get()?.unix_timestamp);
+        Ok(())
+    }
+
+    /// Add `amount` tokens to the reflection vault, e.g. the holder share of collected fees.
+    /// Anyone can deposit.
+    pub fn deposit_reflections(ctx: Context<DepositReflections>, amount: u64) -> Result<()> {
+        require!(amount > 0, ErrorCode::InvalidAmount);
+        let amount = transfer_received(
+            ctx.accounts.deposit_transfer_context(),
+            amount,
+            ctx.accounts.token_mint.decimals,
+        )?;
+        let pool = &mut ctx.accounts.reflection_pool;
+        pool.total_deposited = pool.total_deposited.checked_add(amount).unwrap();
+        Ok(())
+    }
+
+    /// Post a holder-balance snapshot as a merkle root of keccak(wallet || balance) leaves and
+    /// distribute every token in the vault not owed to an earlier open snapshot to it,
+    /// claimable until `claim_deadline`. (Admin only)
+    pub fn post_reflection_snapshot(
+        ctx: Context<PostReflectionSnapshot>,
+        merkle_root: [u8; 32],
+        total_balance: u64,
+        claim_deadline: i64,
+    ) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        ctx.accounts.program_config.record_admin_activity(now);
+        require!(total_balance > 0, ErrorCode::InvalidAmount);
+        require!(claim_deadline > now, ErrorCode::InvalidClaimWindow);
+        let pool = &mut ctx.accounts.reflection_pool;
+        let amount = ctx.accounts.reflection_vault.amount.saturating_sub(pool.reserved);
+        require!(amount > 0, ErrorCode::NothingToDistribute);
+
+        let snapshot = &mut ctx.accounts.snapshot;
+        snapshot.snapshot_id = pool.snapshot_count;
+        snapshot.merkle_root = merkle_root;
+        snapshot.total_balance = total_balance;
+        snapshot.amount = amount;
+        snapshot.claim_deadline = claim_deadline;
+        pool.snapshot_count = pool.snapshot_count.checked_add(1).unwrap();
+        pool.reserved = pool.reserved.checked_add(amount).unwrap();
+        emit!(ReflectionSnapshotPosted {
+            snapshot_id: snapshot.snapshot_id,
+            amount,
+            total_balance,
+            claim_deadline,
+            timestamp: now,
+        });
+        Ok(())
+    }
+
+    /// Claim the holder's reflection share of a snapshot with a merkle proof of their
+    /// snapshot `balance`, before the claim deadline.
+    pub fn claim_reflection(
+        ctx: Context<ClaimReflection>,
+        balance: u64,
+        proof: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        ctx.accounts.program_config.check_not_paused(PAUSE_CLAIMS)?;
+        BlacklistEntry::check(&ctx.accounts.blacklist_entry)?;
+        let now = Clock::get()?.unix_timestamp;
+        let snapshot = &mut ctx.accounts.snapshot;
+        require!(now < snapshot.claim_deadline, ErrorCode::ReflectionClaimExpired);
+        let leaf = keccak::hashv(&[
+            ctx.accounts.holder.key().as_ref(),
+            &balance.to_le_bytes(),
+        ])
+        .0;
+        require!(
+            verify_merkle_proof(&proof, snapshot.merkle_root, leaf),
+            ErrorCode::InvalidMerkleProof
+        );
+        let amount = snapshot.share(balance);
+        require!(amount > 0, ErrorCode::InvalidAmount);
+        snapshot.claimed = snapshot.claimed.checked_add(amount).unwrap();
+        let pool = &mut ctx.accounts.reflection_pool;
+        pool.reserved = pool.reserved.checked_sub(amount).unwrap();
+        pool.total_claimed = pool.total_claimed.checked_add(amount).unwrap();
+
+        let reflection_claim = &mut ctx.accounts.reflection_claim;
+        reflection_claim.snapshot = snapshot.key();
+        reflection_claim.holder = ctx.accounts.holder.key();
+        reflection_claim.amount = amount;
+        reflection_claim.claimed_at = now;
+
+        let bump = ctx.bumps.reflection_vault;
+        let seeds: &[&[u8]] = &[b"reflection_vault", &[bump]];
+        token_interface::transfer_checked(
+            ctx.accounts.claim_transfer_context().with_signer(&[seeds]),
+            amount,
+            ctx.accounts.token_mint.decimals,
+        )?;
+        emit!(ReflectionClaimed {
+            snapshot_id: ctx.accounts.snapshot.snapshot_id,
+            holder: ctx.accounts.holder.key(),
+            amount,
+            timestamp: now,
+        });
+        Ok(())
+    }
+
+    /// After a snapshot's claim deadline, release its unclaimed tokens so the next snapshot
+    /// distributes them. Permissionless.
+    pub fn expire_reflection_snapshot(ctx: Context<ExpireReflectionSnapshot>) -> Result<()> {
+        let snapshot = &mut ctx.accounts.snapshot;
+        require!(
+            Clock::get()?.unix_timestamp >= snapshot.claim_deadline,
+            ErrorCode::ReflectionStillClaimable
+        );
+        require!(!snapshot.expired, ErrorCode::ReflectionStillClaimable);
+        snapshot.expired = true;
+        let unclaimed = snapshot.amount.checked_sub(snapshot.claimed).unwrap();
+        let pool = &mut ctx.accounts.reflection_pool;
+        pool.reserved = pool.reserved.checked_sub(unclaimed).unwrap();
+        Ok(())
+    }
+
+    /// Create the early-bird bonus pool (a token account owned by its own PDA) and set the
+    /// bonus rate. (Admin only)
+    pub fn initialize_bonus_pool(
+        ctx: Context<InitializeBonusPool>,
+        early_bird_bonus_bps: u16,
+    ) -> Result<()> {
+        require!(early_bird_bonus_bps <= 10_000, ErrorCode::InvalidFeeConfig);
+        let program_config = &mut ctx.accounts.program_config;
+        program_config.record_admin_activity(Clock::get()?.unix_timestamp);
+        program_config.early_bird_bonus_bps = early_bird_bonus_bps;
+        Ok(())
+    }
+
+    /// After launch, pay the receipt's beneficiary an early-bird bonus from the bonus pool,
+    /// inversely proportional to the weighted average stage they bought in.
+    /// Fails while early-bird bonuses are auto-staked; use `claim_early_bird_bonus_staked` then.
+    pub fn claim_early_bird_bonus(ctx: Context<ClaimEarlyBirdBonus>) -> Result<()> {
+        require!(
+            ctx.accounts.program_config.early_bird_bonus_lock_period == 0,
+            ErrorCode::BonusMustBeStaked
+        );
+        let bonus = ctx.accounts.take_bonus()?;
+
+        let bump = ctx.bumps.bonus_pool;
+        let seeds: &[&[u8]] = &[b"bonus_pool", &[bump]];
+        token_interface::transfer_checked(
+            ctx.accounts.bonus_transfer_context().with_signer(&[seeds]),
+            bonus,
+            ctx.accounts.token_mint.decimals,
+        )?;
+        emit!(EarlyBirdBonusClaimed {
+            beneficiary: ctx.accounts.beneficiary.key(),
+            receipt: ctx.accounts.receipt.key(),
+            amount: bonus,
+            staked: false,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Claim the receipt's early-bird bonus into the beneficiary's locked bonus stake position,
+    /// for `early_bird_bonus_lock_period` seconds from now.
+    pub fn claim_early_bird_bonus_staked(
+        ctx: Context<ClaimEarlyBirdBonusStaked>,
+    ) -> Result<()> {
+        let lock_period = ctx.accounts.claim.program_config.early_bird_bonus_lock_period;
+        require!(lock_period > 0, ErrorCode::BonusAutoStakeDisabled);
+        require!(
+            ctx.accounts.bonus_stake.owner.key() == ctx.accounts.claim.beneficiary.key(),
+            ErrorCode::Unauthorized
+        );
+        let bonus = ctx.accounts.claim.take_bonus()?;
+
+        let bump = ctx.bumps.claim.bonus_pool;
+        let seeds: &[&[u8]] = &[b"bonus_pool", &[bump]];
+        let bonus = transfer_received(
+            ctx.accounts.stake_transfer_context().with_signer(&[seeds]),
+            bonus,
+            ctx.accounts.claim.token_mint.decimals,
+        )?;
+        ctx.accounts.bonus_stake.stake_bonus(bonus, lock_period)?;
+        emit!(EarlyBirdBonusClaimed {
+            beneficiary: ctx.accounts.claim.beneficiary.key(),
+            receipt: ctx.accounts.claim.receipt.key(),
+            amount: bonus,
+            staked: true,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Create the vault that presale allocations are distributed from, both by
+    /// `claim_presale_tokens` and vesting claims. (Admin only)
+    /// The admin funds it with regular token transfers.
+    pub fn initialize_vesting_vault(ctx: Context<InitializeVestingVault>) -> Result<()> {
+        ctx.accounts.program_config.record_admin_activity(Clock::get()?.unix_timestamp);
+        Ok(())
+    }
+
+    /// After the presale ends, add a receipt's allocation to its beneficiary's vesting
+    /// schedule. All receipts in one schedule must share the same vesting terms.
+    pub fn add_receipt_to_vesting(ctx: Context<AddReceiptToVesting>) -> Result<()> {
+        let presale_state = &ctx.accounts.presale_state;
+        require!(!presale_state.is_presale_active, ErrorCode::PresaleNotEnded);
+        require!(
+            presale_state.total_raised >= presale_state.soft_cap,
+            ErrorCode::SoftCapNotReached
+        );
+        let receipt = &mut ctx.accounts.receipt;
+        require!(
+            receipt.beneficiary == ctx.accounts.beneficiary.key(),
+            ErrorCode::Unauthorized
+        );
+        require!(!receipt.vested, ErrorCode::ReceiptAlreadyVested);
+        require!(receipt.tokens_paid == 0, ErrorCode::AllocationAlreadyClaimed);
+        require!(receipt.tokens_allocated > 0, ErrorCode::InvalidAmount);
+
+        let vesting_schedule = &mut ctx.accounts.vesting_schedule;
+        if vesting_schedule.total_amount == 0 {
+            vesting_schedule.beneficiary = receipt.beneficiary;
+            vesting_schedule.vesting_preset = receipt.vesting_preset;
+        }
+        require!(
+            vesting_schedule.vesting_preset == receipt.vesting_preset,
+            ErrorCode::VestingTermsMismatch
+        );
+        vesting_schedule.total_amount = vesting_schedule
+            .total_amount
+            .checked_add(receipt.tokens_allocated)
+            .unwrap();
+        receipt.vested = true;
+        let vesting_schedule_key = vesting_schedule.key();
+        PositionManager::update(&ctx.accounts.position_manager, |manager| {
+            manager.vesting_schedule = vesting_schedule_key
+        })?;
+        Ok(())
+    }
+
+    /// After the presale ends, merge the beneficiary's receipts (passed as writable remaining
+    /// accounts) into their allocation aggregate, one vesting schedule per set of vesting terms.
+    /// Can be called again for receipts bought later or left out.
+    pub fn consolidate_allocations(ctx: Context<ConsolidateAllocations>) -> Result<()> {
+        let presale_state = &ctx.accounts.presale_state;
+        require!(!presale_state.is_presale_active, ErrorCode::PresaleNotEnded);
+        require!(
+            presale_state.total_raised >= presale_state.soft_cap,
+            ErrorCode::SoftCapNotReached
+        );
+        require!(!ctx.remaining_accounts.is_empty(), ErrorCode::InvalidRemainingAccounts);
+        let beneficiary = ctx.accounts.beneficiary.key();
+        let aggregate = &mut ctx.accounts.allocation_aggregate;
+        aggregate.beneficiary = beneficiary;
+        for info in ctx.remaining_accounts {
+            let mut receipt: PurchaseReceipt = load_account(info)?;
+            require!(receipt.beneficiary == beneficiary, ErrorCode::Unauthorized);
+            require!(!receipt.vested, ErrorCode::ReceiptAlreadyVested);
+            require!(receipt.tokens_paid == 0, ErrorCode::AllocationAlreadyClaimed);
+            require!(receipt.tokens_allocated > 0, ErrorCode::InvalidAmount);
+            let index = receipt.vesting_preset.index();
+            aggregate.total_amounts[index] = aggregate.total_amounts[index]
+                .checked_add(receipt.tokens_allocated)
+                .unwrap();
+            aggregate.receipts = aggregate.receipts.checked_add(1).unwrap();
+            receipt.vested = true;
+            store_account(info, &receipt)?;
+        }
+        Ok(())
+    }
+
+    /// Release everything vested across the beneficiary's consolidated schedules in one
+    /// claim. Goes through the claim portal like other vesting claims.
+    pub fn claim_consolidated_vesting(ctx: Context<ClaimConsolidatedVesting>) -> Result<()> {
+        BlacklistEntry::check(&ctx.accounts.blacklist_entry)?;
+        let tge = ctx
+            .accounts
+            .presale_state
+            .launch_time
+            .ok_or(ErrorCode::PresaleNotEnded)?;
+        let clock = Clock::get()?;
+        ctx.accounts.claim_portal.assert_claimable(clock.unix_timestamp)?;
+
+        let aggregate = &mut ctx.accounts.allocation_aggregate;
+        let claimable = aggregate.claimable(tge, clock.unix_timestamp);
+        let vested = claimable
+            .iter()
+            .fold(0u64, |acc, amount| acc.checked_add(*amount).unwrap());
+        require!(vested > 0, ErrorCode::NothingVested);
+
+        let claim_record = &mut ctx.accounts.claim_record;
+        claim_record.wallet = ctx.accounts.beneficiary.key();
+        let amount = ctx.accounts.claim_portal.record_claim(
+            claim_record,
+            vested,
+            clock.unix_timestamp,
+            clock.slot,
+        )?;
+        require!(
+            ctx.accounts.vesting_vault.amount >= amount,
+            ErrorCode::InsufficientFunds
+        );
+        ctx.accounts.program_config.check_launch_limits(
+            tge,
+            clock.unix_timestamp,
+            amount,
+            ctx.accounts.beneficiary_token_account.amount.checked_add(amount).unwrap(),
+            ctx.accounts.token_mint.supply,
+        )?;
+        // A throttled claim is booked against the schedules in order; the rest stays vested.
+        let mut unbooked = amount;
+        for (index, claimed) in aggregate.claimed_amounts.iter_mut().enumerate() {
+            let booked = claimable[index].min(unbooked);
+            *claimed = claimed.checked_add(booked).unwrap();
+            unbooked -= booked;
+        }
+
+        let bump = ctx.bumps.vesting_vault;
+        let seeds: &[&[u8]] = &[b"vesting_vault", &[bump]];
+        token_interface::transfer_checked(
+            ctx.accounts.vesting_transfer_context().with_signer(&[seeds]),
+            amount,
+            ctx.accounts.token_mint.decimals,
+        )?;
+        emit!(TokensClaimed {
+            beneficiary: ctx.accounts.beneficiary.key(),
+            amount,
+            vested: true,
+            timestamp: clock.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// After launch, pay out a receipt's allocation to its beneficiary from the vesting
+    /// vault. Only for allocations without vesting; others go through
+    /// `add_receipt_to_vesting`. Goes through the claim portal like vesting claims; when its
+    /// throughput limit pays only part of the allocation, the rest stays on the receipt.
+    pub fn claim_presale_tokens(ctx: Context<ClaimPresaleTokens>) -> Result<()> {
+        BlacklistEntry::check(&ctx.accounts.blacklist_entry)?;
+        let presale_state = &ctx.accounts.presale_state;
+        let launch_time = presale_state.launch_time.ok_or(ErrorCode::PresaleNotEnded)?;
+        require!(
+            presale_state.total_raised >= presale_state.soft_cap,
+            ErrorCode::SoftCapNotReached
+        );
+        let clock = Clock::get()?;
+        ctx.accounts.claim_portal.assert_claimable(clock.unix_timestamp)?;
+
+        let receipt = &mut ctx.accounts.receipt;
+        require!(
+            receipt.beneficiary == ctx.accounts.beneficiary.key(),
+            ErrorCode::Unauthorized
+        );
+        require!(!receipt.tokens_claimed, ErrorCode::AllocationAlreadyClaimed);
+        require!(!receipt.vested, ErrorCode::ReceiptAlreadyVested);
+        require!(
+            receipt.vesting_preset == VestingPreset::FullUnlock,
+            ErrorCode::AllocationIsVesting
+        );
+        let remaining = receipt.tokens_allocated.checked_sub(receipt.tokens_paid).unwrap();
+        require!(remaining > 0, ErrorCode::InvalidAmount);
+
+        let claim_record = &mut ctx.accounts.claim_record;
+        claim_record.wallet = ctx.accounts.beneficiary.key();
+        let amount = ctx.accounts.claim_portal.record_claim(
+            claim_record,
+            remaining,
+            clock.unix_timestamp,
+            clock.slot,
+        )?;
+        require!(
+            ctx.accounts.vesting_vault.amount >= amount,
+            ErrorCode::InsufficientFunds
+        );
+        ctx.accounts.program_config.check_launch_limits(
+            launch_time,
+            clock.unix_timestamp,
+            amount,
+            ctx.accounts.beneficiary_token_account.amount.checked_add(amount).unwrap(),
+            ctx.accounts.token_mint.supply,
+        )?;
+        receipt.tokens_paid = receipt.tokens_paid.checked_add(amount).unwrap();
+        receipt.tokens_claimed = receipt.tokens_paid == receipt.tokens_allocated;
+
+        let bump = ctx.bumps.vesting_vault;
+        let seeds: &[&[u8]] = &[b"vesting_vault", &[bump]];
+        token_interface::transfer_checked(
+            ctx.accounts.distribution_transfer_context().with_signer(&[seeds]),
+            amount,
+            ctx.accounts.token_mint.decimals,
+        )?;
+        emit!(TokensClaimed {
+            beneficiary: ctx.accounts.beneficiary.key(),
+            amount,
+            vested: false,
+            timestamp: clock.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Create the receipt tree. `merkle_tree` must be allocated beforehand, owned by the
+    /// spl-account-compression program and sized for `max_depth` and `max_buffer_size`.
+    /// (Admin only)
+    pub fn init_receipt_tree(
+        ctx: Context<InitReceiptTree>,
+        max_depth: u32,
+        max_buffer_size: u32,
+    ) -> Result<()> {
+        ctx.accounts.program_config.record_admin_activity(Clock::get()?.unix_timestamp);
+        let receipt_tree = &mut ctx.accounts.receipt_tree;
+        receipt_tree.merkle_tree = ctx.accounts.merkle_tree.key();
+        receipt_tree.leaf_count = 0;
+        receipt_tree.claimed_count = 0;
+
+        let bump = ctx.bumps.receipt_tree;
+        let seeds: &[&[u8]] = &[b"receipt_tree", &[bump]];
+        spl_account_compression::cpi::init_empty_merkle_tree(
+            ctx.accounts.init_tree_context().with_signer(&[seeds]),
+            max_depth,
+            max_buffer_size,
+        )
+    }
+
+    /// Migrate an existing receipt account to the receipt tree: close it, refunding its rent
+    /// to the payer, and append it as a leaf; the allocation is then claimed with
+    /// `claim_compressed_receipt`. New purchases can skip the receipt account entirely with
+    /// `buy_presale_compressed`. Only for unclaimed, non-vesting receipts whose dispute window
+    /// has closed and whose early-bird bonus has been claimed; stablecoin receipts, which are
+    /// refunded per receipt, only once the presale has succeeded. (Beneficiary only)
+    pub fn compress_receipt(ctx: Context<CompressReceipt>) -> Result<()> {
+        let clock = Clock::get()?;
+        let receipt = &ctx.accounts.receipt;
+        require!(receipt.tokens_paid == 0, ErrorCode::AllocationAlreadyClaimed);
+        require!(!receipt.vested, ErrorCode::ReceiptAlreadyVested);
+        require!(!receipt.refunded, ErrorCode::AlreadyRefunded);
+        require!(
+            receipt.vesting_preset == VestingPreset::FullUnlock,
+            ErrorCode::AllocationIsVesting
+        );
+        require!(
+            clock.unix_timestamp >= receipt.timestamp.checked_add(RECEIPT_DISPUTE_WINDOW).unwrap(),
+            ErrorCode::DisputeWindowOpen
+        );
+        require!(
+            receipt.early_bird_bonus_claimed
+                || receipt.early_bird_bonus(ctx.accounts.program_config.early_bird_bonus_bps) == 0,
+            ErrorCode::BonusNotClaimed
+        );
+        if receipt.token_mint != Pubkey::default() {
+            let presale_state = &ctx.accounts.presale_state;
+            require!(
+                presale_state.launch_time.is_some()
+                    && presale_state.total_raised >= presale_state.soft_cap,
+                ErrorCode::SoftCapNotReached
+            );
+        }
+        let compressed = CompressedReceipt {
+            payer: receipt.payer,
+            beneficiary: receipt.beneficiary,
+            receipt_id: receipt.receipt_id,
+            tokens_allocated: receipt.tokens_allocated,
+            timestamp: receipt.timestamp,
+        };
+
+        let bump = ctx.bumps.receipt_tree;
+        let seeds: &[&[u8]] = &[b"receipt_tree", &[bump]];
+        spl_account_compression::cpi::append(
+            ctx.accounts.modify_tree_context().with_signer(&[seeds]),
+            compressed.leaf(),
+        )?;
+        let receipt_tree = &mut ctx.accounts.receipt_tree;
+        let leaf_index = receipt_tree.leaf_count;
+        receipt_tree.leaf_count = leaf_index.checked_add(1).unwrap();
+        emit!(ReceiptCompressed {
+            receipt: ctx.accounts.receipt.key(),
+            leaf_index,
+            compressed,
+            timestamp: clock.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Pay out a compressed receipt's allocation to its beneficiary, like
+    /// `claim_presale_tokens`. The merkle proof of its leaf against `root` is passed as
+    /// remaining accounts, less the nodes cached in the tree's canopy. When the claim portal's
+    /// throughput limit pays only part of the allocation, the leaf is replaced with the same
+    /// receipt holding the rest as `tokens_allocated`.
+    pub fn claim_compressed_receipt<'info>(
+        ctx: Context<'_, '_, '_, 'info, ClaimCompressedReceipt<'info>>,
+        compressed: CompressedReceipt,
+        root: [u8; 32],
+        leaf_index: u32,
+    ) -> Result<()> {
+        BlacklistEntry::check(&ctx.accounts.blacklist_entry)?;
+        let presale_state = &ctx.accounts.presale_state;
+        let launch_time = presale_state.launch_time.ok_or(ErrorCode::PresaleNotEnded)?;
+        require!(
+            presale_state.total_raised >= presale_state.soft_cap,
+            ErrorCode::SoftCapNotReached
+        );
+        let clock = Clock::get()?;
+        ctx.accounts.claim_portal.assert_claimable(clock.unix_timestamp)?;
+        require!(
+            compressed.beneficiary == ctx.accounts.beneficiary.key(),
+            ErrorCode::Unauthorized
+        );
+        require!(compressed.tokens_allocated > 0, ErrorCode::InvalidAmount);
+
+        let claim_record = &mut ctx.accounts.claim_record;
+        claim_record.wallet = ctx.accounts.beneficiary.key();
+        let amount = ctx.accounts.claim_portal.record_claim(
+            claim_record,
+            compressed.tokens_allocated,
+            clock.unix_timestamp,
+            clock.slot,
+        )?;
+        require!(
+            ctx.accounts.vesting_vault.amount >= amount,
+            ErrorCode::InsufficientFunds
+        );
+        ctx.accounts.program_config.check_launch_limits(
+            launch_time,
+            clock.unix_timestamp,
+            amount,
+            ctx.accounts.beneficiary_token_account.amount.checked_add(amount).unwrap(),
+            ctx.accounts.token_mint.supply,
+        )?;
+
+        // Verifies the proof and clears the leaf once fully claimed, so the receipt cannot be
+        // claimed again; a partial claim leaves the rest in the leaf.
+        let remaining = compressed.tokens_allocated - amount;
+        let new_leaf = if remaining == 0 {
+            [0u8; 32]
+        } else {
+            CompressedReceipt { tokens_allocated: remaining, ..compressed }.leaf()
+        };
+        let bump = ctx.bumps.receipt_tree;
+        let seeds: &[&[u8]] = &[b"receipt_tree", &[bump]];
+        spl_account_compression::cpi::replace_leaf(
+            ctx.accounts
+                .modify_tree_context()
+                .with_remaining_accounts(ctx.remaining_accounts.to_vec())
+                .with_signer(&[seeds]),
+            root,
+            compressed.leaf(),
+            new_leaf,
+            leaf_index,
+        )?;
+        if remaining == 0 {
+            let receipt_tree = &mut ctx.accounts.receipt_tree;
+            receipt_tree.claimed_count = receipt_tree.claimed_count.checked_add(1).unwrap();
+        }
+
+        let bump = ctx.bumps.vesting_vault;
+        let seeds: &[&[u8]] = &[b"vesting_vault", &[bump]];
+        token_interface::transfer_checked(
+            ctx.accounts.distribution_transfer_context().with_signer(&[seeds]),
+            amount,
+            ctx.accounts.token_mint.decimals,
+        )?;
+        emit!(TokensClaimed {
+            beneficiary: ctx.accounts.beneficiary.key(),
+            amount,
+            vested: false,
+            timestamp: clock.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Release the vested, unclaimed part of the caller's schedule from the vesting vault.
+    /// Goes through the claim portal, so its window, pause and throughput limit apply.
+    pub fn claim_vested_tokens(ctx: Context<ClaimVestedTokens>) -> Result<()> {
+        BlacklistEntry::check(&ctx.accounts.blacklist_entry)?;
+        let tge = ctx
+            .accounts
+            .presale_state
+            .launch_time
+            .ok_or(ErrorCode::PresaleNotEnded)?;
+        let clock = Clock::get()?;
+        ctx.accounts.claim_portal.assert_claimable(clock.unix_timestamp)?;
+
+        let vesting_schedule = &mut ctx.accounts.vesting_schedule;
+        let vested = vesting_schedule.claimable(tge, clock.unix_timestamp);
+        require!(vested > 0, ErrorCode::NothingVested);
+
+        let claim_record = &mut ctx.accounts.claim_record;
+        claim_record.wallet = ctx.accounts.beneficiary.key();
+        let amount = ctx.accounts.claim_portal.record_claim(
+            claim_record,
+            vested,
+            clock.unix_timestamp,
+            clock.slot,
+        )?;
+        require!(
+            ctx.accounts.vesting_vault.amount >= amount,
+            ErrorCode::InsufficientFunds
+        );
+        ctx.accounts.program_config.check_launch_limits(
+            tge,
+            clock.unix_timestamp,
+            amount,
+            ctx.accounts.beneficiary_token_account.amount.checked_add(amount).unwrap(),
+            ctx.accounts.token_mint.supply,
+        )?;
+        vesting_schedule.claimed_amount = vesting_schedule.claimed_amount.checked_add(amount).unwrap();
+
+        let bump = ctx.bumps.vesting_vault;
+        let seeds: &[&[u8]] = &[b"vesting_vault", &[bump]];
+        token_interface::transfer_checked(
+            ctx.accounts.vesting_transfer_context().with_signer(&[seeds]),
+            amount,
+            ctx.accounts.token_mint.decimals,
+        )?;
+        emit!(TokensClaimed {
+            beneficiary: ctx.accounts.beneficiary.key(),
+            amount,
+            vested: true,
+            timestamp: clock.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Lock liquidity by transferring liquidity tokens to a vault.
+    /// This function should be called (by admin or automatically) while liquidity is still locked.
+    pub fn lock_liquidity(ctx: Context<LockLiquidity>) -> Result<()> {
+        ctx.accounts.program_config.check_not_paused(PAUSE_LIQUIDITY)?;
+        let clock = Clock::get()?;
+        if let Some(lock_end) = ctx.accounts.presale_state.liquidity_lock_end_time {
+            if clock.unix_timestamp < lock_end {
+                let amount = ctx.accounts.liquidity_token_account.amount;
+                require!(amount > 0, ErrorCode::InvalidAmount);
+                token_interface::transfer_checked(
+                    ctx.accounts.liquidity_lock_transfer_context(),
+                    amount,
+                    ctx.accounts.liquidity_mint.decimals,
+                )?;
+                ctx.accounts.presale_state.liquidity_locked = true;
+                return Ok(());
+            }
+        }
+        Err(ErrorCode::LiquidityLockError.into())
+    }
+
+    /// Claim staking rewards.
+    /// Rewards accrue per staked token through `GlobalState::acc_reward_per_share` at the APY in
+    /// effect over time; settled rewards of a closed position remain claimable.
+    pub fn claim_rewards(ctx: Context<ClaimRewards>) -> Result<()> {
+        ctx.accounts.program_config.check_not_paused(PAUSE_CLAIMS)?;
+        BlacklistEntry::check(&ctx.accounts.blacklist_entry)?;
+        let stake_info = &mut ctx.accounts.stake_info;
+        let global_state = &mut ctx.accounts.global_state;
+        let clock = Clock::get()?;
+        require!(!stake_info.is_frozen, ErrorCode::PositionFrozen);
+
+        let acc_reward_per_share = global_state.update_rewards(
+            &ctx.accounts.param_ramp,
+            &ctx.accounts.emission_schedule,
+            clock.unix_timestamp,
+        );
+        stake_info.settle_rewards(acc_reward_per_share);
+        let reward_amount = stake_info.rewards_owed;
+        require!(reward_amount > 0, ErrorCode::NoRewardsAvailable);
+        // Vote records of the claimer, one per recent proposal, are passed as remaining accounts.
+        let bonus = Governance::participation_bonus(
+            &ctx.accounts.governance,
+            stake_info.owner,
+            ctx.remaining_accounts,
+            reward_amount,
+        )?;
+        let reward_amount = reward_amount.checked_add(bonus).unwrap();
+
+        require!(
+            ctx.accounts.reward_vault.amount >= reward_amount,
+            ErrorCode::InsufficientRewards
+        );
+
+        global_state.reward_pool = global_state.reward_pool.checked_sub(reward_amount).unwrap();
+        stake_info.rewards_owed = 0;
+        stake_info.last_claim_time = clock.unix_timestamp;
+        stake_info.last_activity = clock.unix_timestamp;
+        Stats::update(&ctx.accounts.stats, clock.unix_timestamp, |stats| {
+            stats.total_rewards_distributed =
+                stats.total_rewards_distributed.checked_add(reward_amount).unwrap()
+        })?;
+        let bump = ctx.bumps.reward_vault;
+        let seeds: &[&[u8]] = &[b"reward_vault", &[bump]];
+        token_interface::transfer_checked(
+            ctx.accounts.reward_transfer_context().with_signer(&[seeds]),
+            reward_amount,
+            ctx.accounts.token_mint.decimals,
+        )?;
+        emit!(RewardsClaimed {
+            owner: ctx.accounts.stake_info.owner,
+            position: ctx.accounts.stake_info.key(),
+            amount: reward_amount,
+            timestamp: clock.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Compound pending rewards into the position: they move from the reward vault into the
+    /// stake vault and are added to the staked amount. The position's start time is kept.
+    pub fn compound_rewards(ctx: Context<CompoundRewards>) -> Result<()> {
+        let program_config = &ctx.accounts.program_config;
+        program_config.check_not_paused(PAUSE_CLAIMS)?;
+        program_config.check_not_paused(PAUSE_STAKING)?;
+        BlacklistEntry::check(&ctx.accounts.blacklist_entry)?;
+        let stake_info = &mut ctx.accounts.stake_info;
+        require!(
+            stake_info.owner == ctx.accounts.owner.key(),
+            ErrorCode::Unauthorized
+        );
+        require!(!stake_info.is_frozen, ErrorCode::PositionFrozen);
+        require!(
+            stake_info.unstake_queue_position == 0,
+            ErrorCode::PositionQueuedForUnstake
+        );
+        require!(
+            stake_info.mint == ctx.accounts.reward_vault.mint,
+            ErrorCode::InvalidStakeMint
+        );
+        let global_state = &mut ctx.accounts.global_state;
+        let clock = Clock::get()?;
+        PartnerRewardPool::checkpoint(
+            &ctx.accounts.partner_reward_pool,
+            stake_info,
+            global_state.total_staked,
+            clock.unix_timestamp,
+        )?;
+        let acc_reward_per_share = global_state.update_rewards(
+            &ctx.accounts.param_ramp,
+            &ctx.accounts.emission_schedule,
+            clock.unix_timestamp,
+        );
+        stake_info.settle_rewards(acc_reward_per_share);
+        let reward_amount = stake_info.rewards_owed;
+        require!(reward_amount > 0, ErrorCode::NoRewardsAvailable);
+        require!(
+            ctx.accounts.reward_vault.amount >= reward_amount,
+            ErrorCode::InsufficientRewards
+        );
+
+        global_state.reward_pool = global_state.reward_pool.checked_sub(reward_amount).unwrap();
+        stake_info.rewards_owed = 0;
+
+        // The position grows by what the stake vault received after any transfer fee.
+        let bump = ctx.bumps.reward_vault;
+        let seeds: &[&[u8]] = &[b"reward_vault", &[bump]];
+        let staked = transfer_received(
+            ctx.accounts.compound_transfer_context().with_signer(&[seeds]),
+            reward_amount,
+            ctx.accounts.token_mint.decimals,
+        )?;
+
+        let stake_info = &mut ctx.accounts.stake_info;
+        let global_state = &mut ctx.accounts.global_state;
+        global_state.total_staked = global_state.total_staked.checked_add(staked).unwrap();
+        let staker_record = &mut ctx.accounts.staker_record;
+        staker_record.total_staked = staker_record.total_staked.checked_add(staked).unwrap();
+        StakingLeaderboard::record(
+            &ctx.accounts.staking_leaderboard,
+            stake_info.owner,
+            staker_record.total_staked,
+        )?;
+        stake_info.amount = stake_info.amount.checked_add(staked).unwrap();
+        stake_info.reset_reward_debt(acc_reward_per_share);
+        stake_info.last_claim_time = clock.unix_timestamp;
+        stake_info.last_activity = clock.unix_timestamp;
+        Stats::update(&ctx.accounts.stats, clock.unix_timestamp, |stats| {
+            stats.total_rewards_distributed =
+                stats.total_rewards_distributed.checked_add(reward_amount).unwrap()
+        })?;
+        emit!(RewardsCompounded {
+            owner: ctx.accounts.owner.key(),
+            position: ctx.accounts.stake_info.key(),
+            amount: reward_amount,
+            position_amount: ctx.accounts.stake_info.amount,
+            timestamp: clock.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Set the NFT collection eligible for the staking boost and the boost granted to newly
+    /// registered NFTs; a zero boost closes registration. (Admin only)
+    pub fn set_nft_boost(
+        ctx: Context<SetNftBoost>,
+        collection: Pubkey,
+        boost_bps: u16,
+    ) -> Result<()> {
+        require!(boost_bps <= 10_000, ErrorCode::InvalidNftBoost);
+        ctx.accounts.program_config.record_admin_activity(Clock::get()?.unix_timestamp);
+        let nft_boost_config = &mut ctx.accounts.nft_boost_config;
+        nft_boost_config.collection = collection;
+        nft_boost_config.boost_bps = boost_bps;
+        Ok(())
+    }
+
+    /// Escrow an NFT from the boost collection against a position; its rewards are boosted
+    /// from now until the NFT is withdrawn.
+    pub fn register_boost_nft(ctx: Context<RegisterBoostNft>) -> Result<()> {
+        let nft_boost_config = &ctx.accounts.nft_boost_config;
+        require!(nft_boost_config.boost_bps > 0, ErrorCode::InvalidNftBoost);
+        let nft_mint = &ctx.accounts.nft_mint;
+        require!(
+            nft_mint.decimals == 0 && nft_mint.supply == 1,
+            ErrorCode::NftNotInCollection
+        );
+        nft_boost_config.check_collection(nft_mint.key(), &ctx.accounts.nft_metadata)?;
+        let stake_info = &mut ctx.accounts.stake_info;
+        require!(
+            stake_info.owner == ctx.accounts.owner.key(),
+            ErrorCode::Unauthorized
+        );
+        require!(!stake_info.is_frozen, ErrorCode::PositionFrozen);
+        require!(stake_info.boost_nft == Pubkey::default(), ErrorCode::NftBoostActive);
+
+        // Rewards up to now are settled at the unboosted rate.
+        let now = Clock::get()?.unix_timestamp;
+        let acc_reward_per_share = ctx.accounts.global_state.update_rewards(
+            &ctx.accounts.param_ramp,
+            &ctx.accounts.emission_schedule,
+            now,
+        );
+        stake_info.settle_rewards(acc_reward_per_share);
+        stake_info.boost_nft = nft_mint.key();
+        stake_info.boost_bps = nft_boost_config.boost_bps;
+        stake_info.last_activity = now;
+        token_interface::transfer_checked(
+            ctx.accounts.escrow_transfer_context(),
+            1,
+            ctx.accounts.nft_mint.decimals,
+        )?;
+        Ok(())
+    }
+
+    /// Withdraw a position's boost NFT from escrow, ending the boost.
+    pub fn unregister_boost_nft(ctx: Context<UnregisterBoostNft>) -> Result<()> {
+        let stake_info = &mut ctx.accounts.stake_info;
+        require!(
+            stake_info.owner == ctx.accounts.owner.key(),
+            ErrorCode::Unauthorized
+        );
+        require!(!stake_info.is_frozen, ErrorCode::PositionFrozen);
+        require!(
+            stake_info.boost_nft == ctx.accounts.nft_escrow.mint,
+            ErrorCode::NoNftBoost
+        );
+
+        // Rewards up to now are settled at the boosted rate.
+        let now = Clock::get()?.unix_timestamp;
+        let acc_reward_per_share = ctx.accounts.global_state.update_rewards(
+            &ctx.accounts.param_ramp,
+            &ctx.accounts.emission_schedule,
+            now,
+        );
+        stake_info.settle_rewards(acc_reward_per_share);
+        stake_info.boost_nft = Pubkey::default();
+        stake_info.boost_bps = 0;
+        stake_info.last_activity = now;
+
+        let position = stake_info.key();
+        let bump = ctx.bumps.nft_escrow;
+        let seeds: &[&[u8]] = &[b"nft_escrow", position.as_ref(), &[bump]];
+        token_interface::transfer_checked(
+            ctx.accounts.release_transfer_context().with_signer(&[seeds]),
+            1,
+            ctx.accounts.nft_mint.decimals,
+        )?;
+        token_interface::close_account(ctx.accounts.close_escrow_context().with_signer(&[seeds]))?;
+        Ok(())
+    }
+
+    /// Claim a position's pending rewards into a fresh position instead of paying them out:
+    /// the rewards plus the restake bonus move from the reward vault into the stake vault and
+    /// stay locked for the restake lock period.
+    pub fn restake_rewards(ctx: Context<RestakeRewards>, new_position_id: u64) -> Result<()> {
+        let program_config = &ctx.accounts.program_config;
+        program_config.check_not_paused(PAUSE_CLAIMS)?;
+        program_config.check_not_paused(PAUSE_STAKING)?;
+        BlacklistEntry::check(&ctx.accounts.blacklist_entry)?;
+        let stake_info = &mut ctx.accounts.stake_info;
+        require!(
+            stake_info.owner == ctx.accounts.owner.key(),
+            ErrorCode::Unauthorized
+        );
+        require!(!stake_info.is_frozen, ErrorCode::PositionFrozen);
+        require!(
+            stake_info.mint == ctx.accounts.reward_vault.mint,
+            ErrorCode::InvalidStakeMint
+        );
+        let global_state = &mut ctx.accounts.global_state;
+        let now = Clock::get()?.unix_timestamp;
+        // Brings the position's partner accumulator up to date for the new position to copy.
+        PartnerRewardPool::checkpoint(
+            &ctx.accounts.partner_reward_pool,
+            stake_info,
+            global_state.total_staked,
+            now,
+        )?;
+        let acc_reward_per_share = global_state.update_rewards(
+            &ctx.accounts.param_ramp,
+            &ctx.accounts.emission_schedule,
+            now,
+        );
+        stake_info.settle_rewards(acc_reward_per_share);
+        let reward_amount = stake_info.rewards_owed;
+        require!(reward_amount > 0, ErrorCode::NoRewardsAvailable);
+        let staking_config = &ctx.accounts.staking_config;
+        let bonus = (reward_amount as u128) * (staking_config.restake_bonus_bps as u128) / 10_000;
+        let amount = reward_amount.checked_add(bonus as u64).unwrap();
+        require!(
+            ctx.accounts.reward_vault.amount >= amount && global_state.reward_pool >= amount,
+            ErrorCode::InsufficientRewards
+        );
+        stake_info.rewards_owed = 0;
+        stake_info.last_claim_time = now;
+        stake_info.last_activity = now;
+        Stats::update(&ctx.accounts.stats, now, |stats| {
+            stats.total_rewards_distributed =
+                stats.total_rewards_distributed.checked_add(amount).unwrap()
+        })?;
+        global_state.reward_pool = global_state.reward_pool.checked_sub(amount).unwrap();
+
+        // The new position holds what the stake vault received after any transfer fee.
+        let bump = ctx.bumps.reward_vault;
+        let seeds: &[&[u8]] = &[b"reward_vault", &[bump]];
+        let staked = transfer_received(
+            ctx.accounts.restake_transfer_context().with_signer(&[seeds]),
+            amount,
+            ctx.accounts.token_mint.decimals,
+        )?;
+
+        let stake_info = &ctx.accounts.stake_info;
+        let global_state = &mut ctx.accounts.global_state;
+        let staking_config = &ctx.accounts.staking_config;
+        let new_stake_info = &mut ctx.accounts.new_stake_info;
+        new_stake_info.owner = stake_info.owner;
+        new_stake_info.position_id = new_position_id;
+        new_stake_info.amount = staked;
+        new_stake_info.start_time = now;
+        new_stake_info.last_claim_time = now;
+        new_stake_info.partner_reward_per_token_paid = stake_info.partner_reward_per_token_paid;
+        new_stake_info.mint = stake_info.mint;
+        new_stake_info.locked_until = now.checked_add(staking_config.restake_lock_period).unwrap();
+        new_stake_info.last_activity = now;
+        new_stake_info.reset_reward_debt(acc_reward_per_share);
+
+        global_state.total_staked = global_state.total_staked.checked_add(staked).unwrap();
+        let staker_record = &mut ctx.accounts.staker_record;
+        staker_record.total_staked = staker_record.total_staked.checked_add(staked).unwrap();
+        StakingLeaderboard::record(
+            &ctx.accounts.staking_leaderboard,
+            stake_info.owner,
+            staker_record.total_staked,
+        )?;
+        global_state.open_position(staker_record);
+        let position = new_stake_info.key();
+        PositionManager::update(&ctx.accounts.position_manager, |manager| {
+            manager.add_stake_position(position)
+        })?;
+        emit!(RewardsRestaked {
+            owner: ctx.accounts.owner.key(),
+            position: ctx.accounts.stake_info.key(),
+            new_position: position,
+            amount: reward_amount,
+            bonus: amount - reward_amount,
+            locked_until: ctx.accounts.new_stake_info.locked_until,
+            timestamp: now,
+        });
+        Ok(())
+    }
+
+    /// Create the staking leaderboard. Wallets appear on it as they next stake or unstake.
+    /// (Admin only)
+    pub fn initialize_staking_leaderboard(
+        ctx: Context<InitializeStakingLeaderboard>,
+    ) -> Result<()> {
+        ctx.accounts.program_config.record_admin_activity(Clock::get()?.unix_timestamp);
+        ctx.accounts.staking_leaderboard.load_init()?;
+        Ok(())
+    }
+
+    /// Create the partner reward pool and its vault for `mint`. (Admin only)
+    pub fn initialize_partner_rewards(ctx: Context<InitializePartnerRewards>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        ctx.accounts.program_config.record_admin_activity(now);
+        let pool = &mut ctx.accounts.partner_reward_pool;
+        pool.mint = ctx.accounts.mint.key();
+        pool.last_update_time = now;
+        pool.emission_end = now;
+        Ok(())
+    }
+
+    /// Deposit `amount` partner tokens and emit them, plus any not yet emitted, evenly over
+    /// the next `duration` seconds. (Admin only)
+    pub fn fund_partner_rewards(
+        ctx: Context<FundPartnerRewards>,
+        amount: u64,
+        duration: i64,
+    ) -> Result<()> {
+        require!(amount > 0 && duration > 0, ErrorCode::InvalidAmount);
+        let now = Clock::get()?.unix_timestamp;
+        ctx.accounts.program_config.record_admin_activity(now);
+        let amount = transfer_received(
+            ctx.accounts.fund_transfer_context(),
+            amount,
+            ctx.accounts.token_mint.decimals,
+        )?;
+
+        let pool = &mut ctx.accounts.partner_reward_pool;
+        pool.accrue(ctx.accounts.global_state.total_staked, now);
+        let remaining = if pool.emission_end > now {
+            pool.emission_rate
+                .checked_mul((pool.emission_end - now) as u64)
+                .unwrap()
+        } else {
+            0
+        };
+        pool.emission_rate = remaining.checked_add(amount).unwrap() / duration as u64;
+        pool.emission_end = now + duration;
+        pool.total_funded = pool.total_funded.checked_add(amount).unwrap();
+        Ok(())
+    }
+
+    /// Claim the partner token rewards earned by a stake position.
+    pub fn claim_partner_rewards(ctx: Context<ClaimPartnerRewards>) -> Result<()> {
+        ctx.accounts.program_config.check_not_paused(PAUSE_CLAIMS)?;
+        BlacklistEntry::check(&ctx.accounts.blacklist_entry)?;
+        let stake_info = &mut ctx.accounts.stake_info;
+        require!(
+            stake_info.owner == ctx.accounts.owner.key(),
+            ErrorCode::Unauthorized
+        );
+        require!(!stake_info.is_frozen, ErrorCode::PositionFrozen);
+        let now = Clock::get()?.unix_timestamp;
+        let pool = &mut ctx.accounts.partner_reward_pool;
+        pool.accrue(ctx.accounts.global_state.total_staked, now);
+        pool.settle(stake_info);
+        let amount = stake_info.partner_rewards_owed;
+        require!(amount > 0, ErrorCode::NoRewardsAvailable);
+        require!(
+            ctx.accounts.partner_reward_vault.amount >= amount,
+            ErrorCode::InsufficientRewards
+        );
+        stake_info.partner_rewards_owed = 0;
+        pool.total_claimed = pool.total_claimed.checked_add(amount).unwrap();
+        Stats::update(&ctx.accounts.stats, now, |stats| {
+            stats.total_rewards_distributed =
+                stats.total_rewards_distributed.checked_add(amount).unwrap()
+        })?;
+
+        let bump = ctx.bumps.partner_reward_vault;
+        let seeds: &[&[u8]] = &[b"partner_reward_vault", &[bump]];
+        token_interface::transfer_checked(
+            ctx.accounts.partner_transfer_context().with_signer(&[seeds]),
+            amount,
+            ctx.accounts.token_mint.decimals,
+        )?;
+        Ok(())
+    }
+
+    /// Create the LP staking pool for `lp_mint` with its LP stake vault and BRATS reward
+    /// vault. (Admin only)
+    pub fn initialize_lp_stake_pool(
+        ctx: Context<InitializeLpStakePool>,
+        reward_rate: u64,
+    ) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        ctx.accounts.program_config.record_admin_activity(now);
+        let pool = &mut ctx.accounts.lp_stake_pool;
+        pool.lp_mint = ctx.accounts.lp_mint.key();
+        pool.reward_rate = reward_rate;
+        pool.last_update_time = now;
+        Ok(())
+    }
+
+    /// Set the LP pool's emission rate, in BRATS per second. (Admin only)
+    pub fn set_lp_reward_rate(ctx: Context<UpdateLpStakePool>, reward_rate: u64) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        ctx.accounts.program_config.record_admin_activity(now);
+        let pool = &mut ctx.accounts.lp_stake_pool;
+        pool.update_rewards(now);
+        pool.reward_rate = reward_rate;
+        Ok(())
+    }
+
+    /// Deposit `amount` BRATS into the LP reward vault to be emitted to LP stakers. (Admin only)
+    pub fn fund_lp_rewards(ctx: Context<FundLpRewards>, amount: u64) -> Result<()> {
+        require!(amount > 0, ErrorCode::InvalidAmount);
+        let now = Clock::get()?.unix_timestamp;
+        ctx.accounts.program_config.record_admin_activity(now);
+        let amount = transfer_received(
+            ctx.accounts.fund_transfer_context(),
+            amount,
+            ctx.accounts.token_mint.decimals,
+        )?;
+        let pool = &mut ctx.accounts.lp_stake_pool;
+        pool.update_rewards(now);
+        pool.reward_pool = pool.reward_pool.checked_add(amount).unwrap();
+        pool.total_funded = pool.total_funded.checked_add(amount).unwrap();
+        Ok(())
+    }
+
+    /// Stake BRATS/SOL LP tokens in the LP pool, adding to the wallet's LP position.
+    pub fn stake_lp(ctx: Context<StakeLp>, amount: u64) -> Result<()> {
+        ctx.accounts.program_config.check_not_paused(PAUSE_STAKING)?;
+        BlacklistEntry::check(&ctx.accounts.blacklist_entry)?;
+        require!(amount > 0, ErrorCode::InvalidAmount);
+        let now = Clock::get()?.unix_timestamp;
+        let pool = &mut ctx.accounts.lp_stake_pool;
+        let acc_reward_per_share = pool.update_rewards(now);
+        let lp_stake_info = &mut ctx.accounts.lp_stake_info;
+        if lp_stake_info.owner == Pubkey::default() {
+            lp_stake_info.owner = ctx.accounts.owner.key();
+        }
+        if lp_stake_info.amount == 0 {
+            lp_stake_info.start_time = now;
+        }
+        lp_stake_info.settle_rewards(acc_reward_per_share);
+        lp_stake_info.amount = lp_stake_info.amount.checked_add(amount).unwrap();
+        lp_stake_info.reset_reward_debt(acc_reward_per_share);
+        pool.total_staked = pool.total_staked.checked_add(amount).unwrap();
+        token_interface::transfer_checked(
+            ctx.accounts.stake_transfer_context(),
+            amount,
+            ctx.accounts.lp_mint.decimals,
+        )?;
+        emit!(LpStaked {
+            owner: ctx.accounts.owner.key(),
+            amount,
+            position_amount: ctx.accounts.lp_stake_info.amount,
+            timestamp: now,
+        });
+        Ok(())
+    }
+
+    /// Withdraw staked LP tokens. Rewards earned so far stay claimable.
+    pub fn unstake_lp(ctx: Context<UnstakeLp>, amount: u64) -> Result<()> {
+        ctx.accounts.program_config.check_not_paused(PAUSE_UNSTAKING)?;
+        let lp_stake_info = &mut ctx.accounts.lp_stake_info;
+        require!(
+            amount > 0 && amount <= lp_stake_info.amount,
+            ErrorCode::InvalidAmount
+        );
+        let pool = &mut ctx.accounts.lp_stake_pool;
+        let acc_reward_per_share = pool.update_rewards(Clock::get()?.unix_timestamp);
+        lp_stake_info.settle_rewards(acc_reward_per_share);
+        lp_stake_info.amount = lp_stake_info.amount.checked_sub(amount).unwrap();
+        lp_stake_info.reset_reward_debt(acc_reward_per_share);
+        pool.total_staked = pool.total_staked.checked_sub(amount).unwrap();
+
+        let bump = ctx.bumps.lp_stake_vault;
+        let seeds: &[&[u8]] = &[b"lp_stake_vault", &[bump]];
+        token_interface::transfer_checked(
+            ctx.accounts.unstake_transfer_context().with_signer(&[seeds]),
+            amount,
+            ctx.accounts.lp_mint.decimals,
+        )?;
+        emit!(LpUnstaked {
+            owner: ctx.accounts.lp_stake_info.owner,
+            amount,
+            position_amount: ctx.accounts.lp_stake_info.amount,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Claim the BRATS rewards earned by the wallet's LP position.
+    pub fn claim_lp_rewards(ctx: Context<ClaimLpRewards>) -> Result<()> {
+        ctx.accounts.program_config.check_not_paused(PAUSE_CLAIMS)?;
+        BlacklistEntry::check(&ctx.accounts.blacklist_entry)?;
+        let now = Clock::get()?.unix_timestamp;
+        let pool = &mut ctx.accounts.lp_stake_pool;
+        let acc_reward_per_share = pool.update_rewards(now);
+        let lp_stake_info = &mut ctx.accounts.lp_stake_info;
+        lp_stake_info.settle_rewards(acc_reward_per_share);
+        let amount = lp_stake_info.rewards_owed;
+        require!(amount > 0, ErrorCode::NoRewardsAvailable);
+        require!(
+            ctx.accounts.lp_reward_vault.amount >= amount,
+            ErrorCode::InsufficientRewards
+        );
+        lp_stake_info.rewards_owed = 0;
+        pool.total_claimed = pool.total_claimed.checked_add(amount).unwrap();
+        Stats::update(&ctx.accounts.stats, now, |stats| {
+            stats.total_rewards_distributed =
+                stats.total_rewards_distributed.checked_add(amount).unwrap()
+        })?;
+
+        let bump = ctx.bumps.lp_reward_vault;
+        let seeds: &[&[u8]] = &[b"lp_reward_vault", &[bump]];
+        token_interface::transfer_checked(
+            ctx.accounts.reward_transfer_context().with_signer(&[seeds]),
+            amount,
+            ctx.accounts.token_mint.decimals,
+        )?;
+        emit!(LpRewardsClaimed {
+            owner: ctx.accounts.lp_stake_info.owner,
+            amount,
+            timestamp: now,
+        });
+        Ok(())
+    }
+
+    /// Recompute a wallet's notification flags. Permissionless.
+    /// Pass the wallet's stake positions as remaining accounts to evaluate
+    /// `FLAG_POSITION_UNLOCKABLE`, and the most recent proposal as `latest_proposal`.
+    pub fn refresh_user_flags(ctx: Context<RefreshUserFlags>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let wallet = ctx.accounts.wallet.key();
+        let program_config = &ctx.accounts.program_config;
+        let mut flags = 0u8;
+
+        if ctx.accounts.global_state.reward_pool < program_config.reward_pool_low_water_mark {
+            flags |= FLAG_REWARD_POOL_LOW;
+        }
+        for info in ctx.remaining_accounts.iter() {
+            require!(info.owner == &crate::ID, ErrorCode::Unauthorized);
+            let stake_info = StakeInfo::try_deserialize(&mut &info.data.borrow()[..])?;
+            require!(stake_info.owner == wallet, ErrorCode::Unauthorized);
+            if stake_info.amount > 0
+                && now - stake_info.start_time >= program_config.staking_lock_period
+            {
+                flags |= FLAG_POSITION_UNLOCKABLE;
+            }
+        }
+        if !ctx.accounts.claim_portal.data_is_empty() {
+            let claim_portal: ClaimPortal = load_account(&ctx.accounts.claim_portal)?;
+            if claim_portal.assert_claimable(now).is_ok() {
+                flags |= FLAG_CLAIM_PORTAL_OPEN;
+            }
+        }
+        if !ctx.accounts.governance.data_is_empty() {
+            let governance: Governance = load_account(&ctx.accounts.governance)?;
+            if governance.proposal_count > 0 {
+                let proposal: Proposal = load_account(&ctx.accounts.latest_proposal)?;
+                require!(
+                    proposal.id == governance.proposal_count - 1,
+                    ErrorCode::InvalidProposal
+                );
+                if now < proposal.voting_ends_at {
+                    flags |= FLAG_PROPOSAL_ACTIVE;
+                }
+            }
+        }
+
+        let user_flags = &mut ctx.accounts.user_flags;
+        user_flags.wallet = wallet;
+        user_flags.flags = flags;
+        user_flags.updated_at = now;
+        Ok(())
+    }
+
+    /// Permissionless crank enforcing the reward expiry policy: if the position has been
+    /// inactive for longer than the grace period, rewards accrued before the grace window are
+    /// forfeited and stay in the reward pool.
+    pub fn expire_unclaimed_rewards(ctx: Context<ExpireUnclaimedRewards>) -> Result<()> {
+        let staking_config = &ctx.accounts.staking_config;
+        require!(
+            staking_config.reward_expiry_enabled,
+            ErrorCode::RewardExpiryDisabled
+        );
+        let clock = Clock::get()?;
+        let cutoff = clock.unix_timestamp - staking_config.reward_expiry_grace_period;
+        let stake_info = &mut ctx.accounts.stake_info;
+        require!(
+            stake_info.last_activity < cutoff && stake_info.last_claim_time < cutoff,
+            ErrorCode::NoRewardsAvailable
+        );
+
+        let global_state = &mut ctx.accounts.global_state;
+        let param_ramp = &ctx.accounts.param_ramp;
+        let emission_schedule = &ctx.accounts.emission_schedule;
+        let acc_reward_per_share = global_state.update_rewards(
+            param_ramp,
+            emission_schedule,
+            clock.unix_timestamp,
+        );
+        stake_info.settle_rewards(acc_reward_per_share);
+        // Only what accrued inside the grace window survives.
+        let kept = if stake_info.is_frozen {
+            0
+        } else {
+            ((stake_info.amount as u128)
+                .checked_mul(global_state.reward_per_share_between(
+                    param_ramp,
+                    emission_schedule,
+                    cutoff,
+                    clock.unix_timestamp,
+                ))
+                .unwrap()
+                / REWARD_PER_TOKEN_SCALE) as u64
+        };
+        let kept = kept.min(stake_info.rewards_owed);
+        let expired = stake_info.rewards_owed - kept;
+        stake_info.rewards_owed = kept;
+        stake_info.last_claim_time = cutoff;
+        global_state.total_rewards_expired =
+            global_state.total_rewards_expired.checked_add(expired).unwrap();
+        Ok(())
+    }
+
+    /// Calculate rewards for display (off‑chain) without transferring tokens.
+    /// The `RewardsEstimate` is published via `set_return_data` for simulation.
+    pub fn calculate_rewards(ctx: Context<CalculateRewards>) -> Result<()> {
+        let stake_info = &ctx.accounts.stake_info;
+        let global_state = &ctx.accounts.global_state;
+        let clock = Clock::get()?;
+        let rewards = stake_info.pending_rewards(
+            global_state,
+            &ctx.accounts.param_ramp,
+            &ctx.accounts.emission_schedule,
+            clock.unix_timestamp,
+        );
+        let estimate = RewardsEstimate {
+            rewards,
+            apy: global_state.current_apy(
+                &ctx.accounts.param_ramp,
+                &ctx.accounts.emission_schedule,
+                clock.unix_timestamp,
+            ),
+            elapsed: (clock.unix_timestamp - stake_info.last_claim_time).max(0),
+            pool_covers: global_state.reward_pool >= rewards,
+        };
+        solana_program::program::set_return_data(&estimate.try_to_vec()?);
+        Ok(())
+    }
+
+    /// Return the standardized collateral metadata of a stake position
+    /// (amount, unlock timestamp, APY) so lending protocols can price it without custom indexing.
+    pub fn get_stake_collateral_metadata(
+        ctx: Context<GetStakeCollateralMetadata>,
+    ) -> Result<StakeCollateralMetadata> {
+        let stake_info = &ctx.accounts.stake_info;
+        let staking_lock_period = ctx.accounts.program_config.staking_lock_period;
+        let clock = Clock::get()?;
+        Ok(StakeCollateralMetadata {
+            position: stake_info.key(),
+            owner: stake_info.owner,
+            amount: stake_info.amount,
+            unlock_timestamp: stake_info.start_time + staking_lock_period,
+            apy: ctx
+                .accounts
+                .global_state
+                .current_apy(
+                    &ctx.accounts.param_ramp,
+                    &ctx.accounts.emission_schedule,
+                    clock.unix_timestamp,
+                ),
+        })
+    }
+
+    /// Return the price of one whole token in the current stage in lamports, USDC and USDT
+    /// base units, computed exactly as a purchase of that size would be charged.
+    pub fn get_stage_prices(ctx: Context<GetStagePrices>) -> Result<StagePrices> {
+        let presale_stage_info = ctx.accounts.presale_stage_info.load()?;
+        let index = presale_stage_info
+            .current_stage_index()
+            .ok_or(ErrorCode::PresaleSoldOut)?;
+        let stage = &presale_stage_info.stages[index];
+        // Same rounding (up) as the per-stage cost in `PresaleStageInfo::quote_purchase`.
+        let cost = |price: u64| {
+            (TOKEN_UNIT as u128 * price as u128).div_ceil(PRICE_SCALE as u128) as u64
+        };
+        let usd = cost(stage.usd_price);
+        Ok(StagePrices {
+            stage: stage.stage,
+            lamports: cost(stage.price),
+            usdc: ctx.accounts.usdc.usd_to_base_units(usd),
+            usdt: ctx.accounts.usdt.usd_to_base_units(usd),
+        })
+    }
+
+    /// Watchdog: check the live accounts the configuration depends on and return a bitmask of
+    /// `DRIFT_*` flags (0 = no drift). The remaining accounts are triples of (`AcceptedMint`,
+    /// its price feed, the fee wallet's token account for that mint).
+    // `is_multiple_of` is newer than the Solana platform tools' Rust.
+    #[allow(clippy::manual_is_multiple_of)]
+    pub fn verify_config(ctx: Context<VerifyConfig>) -> Result<u32> {
+        let program_config = &ctx.accounts.program_config;
+        let token_mint = program_config.token_mint;
+        let mut drift = 0;
+
+        let fee_wallet = &ctx.accounts.fee_wallet;
+        if fee_wallet.key() != program_config.fee_wallet
+            || *fee_wallet.owner != solana_program::system_program::ID
+        {
+            drift |= DRIFT_FEE_WALLET;
+        }
+        let treasury_token_account = &ctx.accounts.treasury_token_account;
+        let treasury_authority = ctx.accounts.treasury_authority.key();
+        if !is_token_account_of(treasury_token_account, treasury_authority, token_mint)
+            || program_config.presale_treasury == Pubkey::default()
+        {
+            drift |= DRIFT_TREASURY;
+        }
+        let stake_vault = &ctx.accounts.stake_vault;
+        if !is_token_account_of(stake_vault, stake_vault.key(), token_mint) {
+            drift |= DRIFT_STAKE_VAULT;
+        }
+        let reward_vault = &ctx.accounts.reward_vault;
+        if !is_token_account_of(reward_vault, reward_vault.key(), token_mint) {
+            drift |= DRIFT_REWARD_VAULT;
+        }
+
+        require!(
+            ctx.remaining_accounts.len() % 3 == 0,
+            ErrorCode::InvalidRemainingAccounts
+        );
+        for accounts in ctx.remaining_accounts.chunks(3) {
+            let accepted_mint: AcceptedMint = load_account(&accounts[0])?;
+            let price_feed = &accounts[1];
+            if price_feed.key() != accepted_mint.price_feed
+                || price_feed.data_is_empty()
+                || *price_feed.owner == solana_program::system_program::ID
+            {
+                drift |= DRIFT_ORACLE;
+            }
+            if !is_token_account_of(&accounts[2], program_config.fee_wallet, accepted_mint.mint) {
+                drift |= DRIFT_FEE_TOKEN_ACCOUNT;
+            }
+        }
+        Ok(drift)
+    }
+
+    /// Return protocol health metrics (unique stakers, open positions, average position size).
+    pub fn get_global_stats(ctx: Context<GetGlobalStats>) -> Result<GlobalStats> {
+        let global_state = &ctx.accounts.global_state;
+        Ok(GlobalStats {
+            total_staked: global_state.total_staked,
+            reward_pool: global_state.reward_pool,
+            unique_stakers: global_state.unique_stakers,
+            total_positions: global_state.total_positions,
+            average_position_size: global_state.average_position_size(),
+            reward_inflow_admin: global_state.reward_inflow_admin,
+            reward_inflow_treasury: global_state.reward_inflow_treasury,
+            reward_inflow_fees: global_state.reward_inflow_fees,
+            reward_inflow_penalties: global_state.reward_inflow_penalties,
+        })
+    }
+
+    /// Create the cumulative `Stats` account. The amounts raised so far are carried over from
+    /// the presale stages; the other totals count from now on. (Admin only)
+    pub fn initialize_stats(ctx: Context<InitializeStats>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        ctx.accounts.program_config.record_admin_activity(now);
+        let presale_stage_info = ctx.accounts.presale_stage_info.load()?;
+        let stats = &mut ctx.accounts.stats;
+        stats.total_sol_raised = presale_stage_info.total_raised();
+        stats.total_usd_raised = presale_stage_info
+            .stages
+            .iter()
+            .fold(0u64, |acc, stage| acc.checked_add(stage.total_raised_usd).unwrap());
+        stats.last_updated = now;
+        Ok(())
+    }
+
+    /// Burn tokens from a source account. (Admin only)
+    /// Burns are limited to `burn_cap_per_epoch` per epoch and always emit a `TokensBurned` event.
+    pub fn burn_tokens(ctx: Context<BurnTokens>, amount: u64) -> Result<()> {
+        require!(amount > 0, ErrorCode::InvalidAmount);
+        let clock = Clock::get()?;
+        let program_config = &mut ctx.accounts.program_config;
+        program_config.record_admin_activity(clock.unix_timestamp);
+        program_config.record_burn(amount, clock.epoch)?;
+        let burned_this_epoch = program_config.burned_this_epoch;
+        Stats::update(&ctx.accounts.stats, clock.unix_timestamp, |stats| {
+            stats.total_tokens_burned = stats.total_tokens_burned.checked_add(amount).unwrap()
+        })?;
+
+        token_interface::burn(ctx.accounts.burn_context(), amount)?;
+        emit!(TokensBurned {
+            admin: ctx.accounts.admin.key(),
+            source: ctx.accounts.source.key(),
+            amount,
+            epoch: clock.epoch,
+            burned_this_epoch,
+            timestamp: clock.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Swap up to `max_sol` of the treasury's wrapped SOL for $BRATS through the allow-listed
+    /// buyback swap program and burn everything bought, which must be at least
+    /// `min_tokens_out`. `swap_data` and the remaining accounts are the swap instruction built
+    /// off-chain; the treasury PDA signs it, so the route may only move treasury funds.
+    /// (Admin only)
+    pub fn buyback_and_burn(
+        ctx: Context<BuybackAndBurn>,
+        max_sol: u64,
+        min_tokens_out: u64,
+        swap_data: Vec<u8>,
+    ) -> Result<()> {
+        require!(max_sol > 0, ErrorCode::InvalidAmount);
+        let now = Clock::get()?.unix_timestamp;
+        ctx.accounts.program_config.record_admin_activity(now);
+        let wsol_before = ctx.accounts.treasury_wsol_account.amount;
+        let tokens_before = ctx.accounts.treasury_token_account.amount;
+        let treasury_authority = ctx.accounts.treasury_authority.key();
+        let swap_ix = solana_program::instruction::Instruction {
+            program_id: ctx.accounts.swap_program.key(),
+            accounts: ctx
+                .remaining_accounts
+                .iter()
+                .map(|account| solana_program::instruction::AccountMeta {
+                    pubkey: account.key(),
+                    is_signer: account.is_signer || account.key() == treasury_authority,
+                    is_writable: account.is_writable,
+                })
+                .collect(),
+            data: swap_data,
+        };
+        let bump = ctx.bumps.treasury_authority;
+        let seeds: &[&[u8]] = &[b"treasury", &[bump]];
+        solana_program::program::invoke_signed(&swap_ix, ctx.remaining_accounts, &[seeds])?;
+
+        ctx.accounts.treasury_wsol_account.reload()?;
+        ctx.accounts.treasury_token_account.reload()?;
+        let sol_spent = wsol_before
+            .checked_sub(ctx.accounts.treasury_wsol_account.amount)
+            .ok_or(ErrorCode::InvalidAmount)?;
+        let purchased = ctx
+            .accounts
+            .treasury_token_account
+            .amount
+            .checked_sub(tokens_before)
+            .ok_or(ErrorCode::InvalidAmount)?;
+        require!(sol_spent <= max_sol, ErrorCode::SlippageExceeded);
+        require!(
+            purchased > 0 && purchased >= min_tokens_out,
+            ErrorCode::SlippageExceeded
+        );
+
+        token_interface::burn(ctx.accounts.burn_context().with_signer(&[seeds]), purchased)?;
+        Stats::update(&ctx.accounts.stats, now, |stats| {
+            stats.total_buyback_sol = stats.total_buyback_sol.checked_add(sol_spent).unwrap();
+            stats.total_buyback_burned =
+                stats.total_buyback_burned.checked_add(purchased).unwrap();
+        })?;
+        emit!(BuybackBurned {
+            sol_spent,
+            tokens_burned: purchased,
+            timestamp: now,
+        });
+        Ok(())
+    }
+
+    /// Configure the scheduled burns, creating the schedule and its burn reserve vault on
+    /// first use: every `interval` seconds from `first_burn_ts`, burn `supply_bps` of the
+    /// mint supply or, with `supply_bps` 0, a fixed `amount`. Fund the reserve with regular
+    /// token transfers. (Admin only)
+    pub fn set_burn_schedule(
+        ctx: Context<SetBurnSchedule>,
+        interval: i64,
+        amount: u64,
+        supply_bps: u16,
+        first_burn_ts: i64,
+    ) -> Result<()> {
+        require!(
+            interval > 0 && supply_bps <= 10_000 && (amount > 0 || supply_bps > 0),
+            ErrorCode::InvalidBurnSchedule
+        );
+        ctx.accounts.program_config.record_admin_activity(Clock::get()?.unix_timestamp);
+        let burn_schedule = &mut ctx.accounts.burn_schedule;
+        burn_schedule.interval = interval;
+        burn_schedule.amount = amount;
+        burn_schedule.supply_bps = supply_bps;
+        burn_schedule.next_burn_ts = first_burn_ts;
+        Ok(())
+    }
+
+    /// Execute the scheduled burn that is due, from the burn reserve vault. Burns what is
+    /// left in the reserve if it holds less than the scheduled amount. Permissionless.
+    pub fn execute_scheduled_burn(ctx: Context<ExecuteScheduledBurn>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let burn_schedule = &mut ctx.accounts.burn_schedule;
+        require!(now >= burn_schedule.next_burn_ts, ErrorCode::BurnNotDue);
+        let amount = burn_schedule
+            .burn_amount(ctx.accounts.token_mint.supply)
+            .min(ctx.accounts.burn_reserve.amount);
+        require!(amount > 0, ErrorCode::InsufficientFunds);
+        burn_schedule.advance(now);
+        burn_schedule.total_burned = burn_schedule.total_burned.checked_add(amount).unwrap();
+        burn_schedule.burns_executed = burn_schedule.burns_executed.checked_add(1).unwrap();
+        let next_burn_ts = burn_schedule.next_burn_ts;
+
+        let bump = ctx.bumps.burn_reserve;
+        let seeds: &[&[u8]] = &[b"burn_reserve", &[bump]];
+        token_interface::burn(ctx.accounts.burn_context().with_signer(&[seeds]), amount)?;
+        emit!(ScheduledBurnExecuted {
+            amount,
+            total_burned: ctx.accounts.burn_schedule.total_burned,
+            next_burn_ts,
+            timestamp: now,
+        });
+        Ok(())
+    }
+
+    /// Refill the reward pool by transferring tokens into the reward pool account. (Operator only)
+    pub fn refill_reward_pool(ctx: Context<RefillRewardPool>, amount: u64) -> Result<()> {
+        let received = transfer_received(
+            ctx.accounts.refill_transfer_context(),
+            amount,
+            ctx.accounts.token_mint.decimals,
+        )?;
+        ctx.accounts
+            .global_state
+            .credit_reward_pool(received, RewardSource::AdminRefill);
+        Ok(())
+    }
+
+    /// Create or update the staking limits. `max_stake_per_wallet` of 0 disables the cap. (Admin only)
+    pub fn set_staking_config(
+        ctx: Context<SetStakingConfig>,
+        max_stake_per_wallet: u64,
+    ) -> Result<()> {
+        ctx.accounts.program_config.record_admin_activity(Clock::get()?.unix_timestamp);
+        ctx.accounts.staking_config.max_stake_per_wallet = max_stake_per_wallet;
+        Ok(())
+    }
+
+    /// Set the cooldown between `request_unstake` and `withdraw_unstaked`, in seconds.
+    /// Applies to requests made from now on. (Admin only)
+    pub fn set_unstake_cooldown(ctx: Context<SetStakingConfig>, cooldown: i64) -> Result<()> {
+        require!(cooldown >= 0, ErrorCode::InvalidAmount);
+        ctx.accounts.program_config.record_admin_activity(Clock::get()?.unix_timestamp);
+        ctx.accounts.staking_config.unstake_cooldown = cooldown;
+        Ok(())
+    }
+
+    /// Set the bonus and lock period for positions created by `restake_rewards`. (Admin only)
+    pub fn set_restake_terms(
+        ctx: Context<SetStakingConfig>,
+        bonus_bps: u16,
+        lock_period: i64,
+    ) -> Result<()> {
+        require!(bonus_bps <= 10_000 && lock_period >= 0, ErrorCode::InvalidAmount);
+        ctx.accounts.program_config.record_admin_activity(Clock::get()?.unix_timestamp);
+        let staking_config = &mut ctx.accounts.staking_config;
+        staking_config.restake_bonus_bps = bonus_bps;
+        staking_config.restake_lock_period = lock_period;
+        Ok(())
+    }
+
+    /// Set the unclaimed reward expiry policy. (Admin only)
+    pub fn set_reward_expiry_policy(
+        ctx: Context<SetStakingConfig>,
+        enabled: bool,
+        grace_period: i64,
+    ) -> Result<()> {
+        ctx.accounts.program_config.record_admin_activity(Clock::get()?.unix_timestamp);
+        require!(grace_period > 0, ErrorCode::InvalidExpiryPolicy);
+        let staking_config = &mut ctx.accounts.staking_config;
+        staking_config.reward_expiry_enabled = enabled;
+        staking_config.reward_expiry_grace_period = grace_period;
+        Ok(())
+    }
+
+    /// Configure the APY controller: bounds and the target reward pool runway. (Admin only)
+    pub fn set_apy_controller(
+        ctx: Context<SetStakingConfig>,
+        enabled: bool,
+        min_apy: u64,
+        max_apy: u64,
+        target_runway: i64,
+    ) -> Result<()> {
+        ctx.accounts.program_config.record_admin_activity(Clock::get()?.unix_timestamp);
+        require!(
+            min_apy <= max_apy && target_runway > 0,
+            ErrorCode::InvalidApyController
+        );
+        let staking_config = &mut ctx.accounts.staking_config;
+        staking_config.apy_controller_enabled = enabled;
+        staking_config.apy_controller_min = min_apy;
+        staking_config.apy_controller_max = max_apy;
+        staking_config.target_runway = target_runway;
+        Ok(())
+    }
+
+    /// Switch the utilization-curve APY on or off and set its shape: the APY falls linearly
+    /// from `max_apy` with nothing staked to `min_apy` once `target_tvl` tokens are staked.
+    /// While on, it replaces the fixed APY, ramps and the APY controller. (Admin only)
+    pub fn set_apy_curve(
+        ctx: Context<SetApyCurve>,
+        enabled: bool,
+        min_apy: u64,
+        max_apy: u64,
+        target_tvl: u64,
+    ) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        ctx.accounts.program_config.record_admin_activity(now);
+        require!(min_apy <= max_apy && target_tvl > 0, ErrorCode::InvalidApyCurve);
+        let global_state = &mut ctx.accounts.global_state;
+        global_state.update_rewards(
+            &ctx.accounts.param_ramp,
+            &ctx.accounts.emission_schedule,
+            now,
+        );
+        global_state.apy_curve_enabled = enabled;
+        global_state.apy_curve_min = min_apy;
+        global_state.apy_curve_max = max_apy;
+        global_state.apy_curve_target_tvl = target_tvl;
+        Ok(())
+    }
+
+    /// Permissionless crank, once per epoch: set the APY to the highest value within the
+    /// controller bounds that keeps the reward pool funded for the target runway.
+    pub fn adjust_apy(ctx: Context<AdjustApy>) -> Result<()> {
+        let staking_config = &mut ctx.accounts.staking_config;
+        require!(
+            staking_config.apy_controller_enabled,
+            ErrorCode::ApyControllerDisabled
+        );
+        let epoch = Clock::get()?.epoch;
+        require!(
+            epoch > staking_config.apy_adjusted_epoch,
+            ErrorCode::ApyAlreadyAdjusted
+        );
+        let global_state = &mut ctx.accounts.global_state;
+        global_state.update_rewards(
+            &ctx.accounts.param_ramp,
+            &ctx.accounts.emission_schedule,
+            Clock::get()?.unix_timestamp,
+        );
+        global_state.apy =
+            staking_config.runway_apy(global_state.total_staked, global_state.reward_pool);
+        staking_config.apy_adjusted_epoch = epoch;
+        Ok(())
+    }
+
+    /// Create the parameter timelock with a delay of at least `MIN_PARAMS_DELAY`. (Admin only)
+    pub fn initialize_pending_params(
+        ctx: Context<InitializePendingParams>,
+        delay: i64,
+    ) -> Result<()> {
+        require!(delay >= MIN_PARAMS_DELAY, ErrorCode::InvalidTimelockDelay);
+        ctx.accounts.program_config.record_admin_activity(Clock::get()?.unix_timestamp);
+        ctx.accounts.pending_params.delay = delay;
+        Ok(())
+    }
+
+    /// Queue a new APY and transaction fee percent, executable after the timelock delay.
+    /// Replaces any change already queued. (Admin only)
+    pub fn queue_parameters(
         ctx: Context<UpdateParameters>,
         new_apy: u64,
         new_fee_percent: u64,
-    ) -> ProgramResult {
+    ) -> Result<()> {
+        let clock = Clock::get()?;
+        ctx.accounts.program_config.record_admin_activity(clock.unix_timestamp);
+        let pending_params = &mut ctx.accounts.pending_params;
+        pending_params.is_queued = true;
+        pending_params.new_apy = new_apy;
+        pending_params.new_fee_percent = new_fee_percent;
+        pending_params.eta = clock.unix_timestamp + pending_params.delay;
+        emit!(ParametersQueued {
+            new_apy,
+            new_fee_percent,
+            eta: pending_params.eta,
+            timestamp: clock.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Apply the queued parameter change once its eta has passed. Permissionless.
+    pub fn execute_parameters(ctx: Context<ExecuteParameters>) -> Result<()> {
+        let pending_params = &mut ctx.accounts.pending_params;
+        require!(pending_params.is_queued, ErrorCode::NoQueuedParameters);
+        let now = Clock::get()?.unix_timestamp;
+        require!(now >= pending_params.eta, ErrorCode::TimelockNotExpired);
+        let global_state = &mut ctx.accounts.global_state;
+        global_state.update_rewards(
+            &ctx.accounts.param_ramp,
+            &ctx.accounts.emission_schedule,
+            now,
+        );
+        global_state.apy = pending_params.new_apy;
+        global_state.transaction_fee_percent = pending_params.new_fee_percent;
+        pending_params.is_queued = false;
+        Ok(())
+    }
+
+    /// Create or update the staker governance settings. (Admin only)
+    pub fn set_governance_config(
+        ctx: Context<SetGovernanceConfig>,
+        voting_period: i64,
+        quorum: u64,
+        proposal_threshold: u64,
+    ) -> Result<()> {
+        require!(voting_period > 0, ErrorCode::InvalidGovernanceConfig);
+        ctx.accounts.program_config.record_admin_activity(Clock::get()?.unix_timestamp);
+        let governance = &mut ctx.accounts.governance;
+        governance.voting_period = voting_period;
+        governance.quorum = quorum;
+        governance.proposal_threshold = proposal_threshold;
+        Ok(())
+    }
+
+    /// Set the claim-time reward boost for wallets that voted on each of the last `window`
+    /// proposals. A `boost_bps` of 0 turns it off. (Admin only)
+    pub fn set_participation_boost(
+        ctx: Context<SetGovernanceConfig>,
+        window: u64,
+        boost_bps: u16,
+    ) -> Result<()> {
+        require!(
+            window <= MAX_PARTICIPATION_WINDOW && boost_bps <= 10_000,
+            ErrorCode::InvalidGovernanceConfig
+        );
+        ctx.accounts.program_config.record_admin_activity(Clock::get()?.unix_timestamp);
+        let governance = &mut ctx.accounts.governance;
+        governance.participation_window = window;
+        governance.participation_boost_bps = boost_bps;
+        Ok(())
+    }
+
+    /// Open a proposal to change the APY, transaction fee or early-unstake penalty, or to
+    /// waive the early-unstake penalty until a given time.
+    /// The proposer must have at least `proposal_threshold` tokens staked.
+    pub fn create_proposal(ctx: Context<CreateProposal>, action: ProposalAction) -> Result<()> {
+        match action {
+            ProposalAction::SetApy(_)
+            | ProposalAction::SetPenaltyWaiverUntil(_)
+            | ProposalAction::RevokeMarketMaker
+            | ProposalAction::ApproveRoundReport => {}
+            ProposalAction::SetTransactionFeePercent(percent)
+            | ProposalAction::SetEarlyUnstakePenaltyPercent(percent)
+            | ProposalAction::SetPenaltyRedistributePercent(percent)
+            | ProposalAction::SetMinLaunchLiquidityPercent(percent) => {
+                require!(percent <= 100, ErrorCode::InvalidFeeConfig);
+            }
+            ProposalAction::SetUnstakeThrottleBps(bps) => {
+                require!(bps <= 10_000, ErrorCode::InvalidUnstakeThrottle);
+            }
+        }
+        let governance = &mut ctx.accounts.governance;
+        require!(
+            ctx.accounts.staker_record.total_staked >= governance.proposal_threshold,
+            ErrorCode::InsufficientVotingPower
+        );
+        let now = Clock::get()?.unix_timestamp;
+        let proposal = &mut ctx.accounts.proposal;
+        proposal.id = governance.proposal_count;
+        proposal.proposer = ctx.accounts.proposer.key();
+        proposal.action = action;
+        proposal.created_at = now;
+        proposal.voting_ends_at = now + governance.voting_period;
+        governance.proposal_count = governance.proposal_count.checked_add(1).unwrap();
+        emit!(ProposalCreated {
+            proposal_id: proposal.id,
+            proposer: proposal.proposer,
+            action,
+            voting_ends_at: proposal.voting_ends_at,
+            timestamp: now,
+        });
+        Ok(())
+    }
+
+    /// Vote on a proposal with a stake position; the weight is the staked amount.
+    /// The position can't be split or unstaked until voting ends, so its tokens can't vote twice.
+    pub fn cast_vote(ctx: Context<CastVote>, support: bool) -> Result<()> {
+        let proposal = &mut ctx.accounts.proposal;
+        let now = Clock::get()?.unix_timestamp;
+        require!(now < proposal.voting_ends_at, ErrorCode::VotingClosed);
+        let stake_info = &mut ctx.accounts.stake_info;
+        require!(
+            stake_info.owner == ctx.accounts.voter.key(),
+            ErrorCode::Unauthorized
+        );
+        require!(!stake_info.is_frozen, ErrorCode::PositionFrozen);
+        let weight = stake_info.amount;
+        require!(weight > 0, ErrorCode::InsufficientVotingPower);
+
+        if support {
+            proposal.votes_for = proposal.votes_for.checked_add(weight).unwrap();
+        } else {
+            proposal.votes_against = proposal.votes_against.checked_add(weight).unwrap();
+        }
+        stake_info.voting_locked_until = stake_info.voting_locked_until.max(proposal.voting_ends_at);
+
+        let vote_record = &mut ctx.accounts.vote_record;
+        vote_record.proposal = proposal.key();
+        vote_record.stake_info = stake_info.key();
+        vote_record.weight = weight;
+        vote_record.support = support;
+        vote_record.voter = ctx.accounts.voter.key();
+        vote_record.proposal_id = proposal.id;
+
+        let staker_record = &mut ctx.accounts.staker_record;
+        staker_record.votes_cast = staker_record.votes_cast.checked_add(1).unwrap();
+        emit!(VoteCast {
+            proposal_id: proposal.id,
+            voter: ctx.accounts.voter.key(),
+            position: stake_info.key(),
+            support,
+            weight,
+            timestamp: now,
+        });
+        Ok(())
+    }
+
+    /// Apply a passed proposal once voting has ended. Permissionless.
+    pub fn execute_proposal(ctx: Context<ExecuteProposal>) -> Result<()> {
+        let proposal = &mut ctx.accounts.proposal;
+        let now = Clock::get()?.unix_timestamp;
+        require!(now >= proposal.voting_ends_at, ErrorCode::VotingNotEnded);
+        require!(!proposal.executed, ErrorCode::ProposalAlreadyExecuted);
+        require!(
+            proposal.passed(ctx.accounts.governance.quorum),
+            ErrorCode::ProposalNotPassed
+        );
+        let global_state = &mut ctx.accounts.global_state;
+        match proposal.action {
+            ProposalAction::SetApy(apy) => {
+                global_state.update_rewards(
+                    &ctx.accounts.param_ramp,
+                    &ctx.accounts.emission_schedule,
+                    now,
+                );
+                global_state.apy = apy
+            }
+            ProposalAction::SetTransactionFeePercent(percent) => {
+                global_state.transaction_fee_percent = percent
+            }
+            ProposalAction::SetEarlyUnstakePenaltyPercent(percent) => {
+                global_state.early_unstake_penalty_percent = percent
+            }
+            ProposalAction::SetPenaltyWaiverUntil(timestamp) => {
+                global_state.penalty_waiver_until = timestamp
+            }
+            ProposalAction::SetPenaltyRedistributePercent(percent) => {
+                global_state.penalty_redistribute_percent = percent
+            }
+            ProposalAction::SetMinLaunchLiquidityPercent(percent) => {
+                global_state.min_launch_liquidity_percent = percent
+            }
+            ProposalAction::RevokeMarketMaker => {
+                let mut allowance: MarketMakerAllowance =
+                    load_account(&ctx.accounts.market_maker_allowance)?;
+                allowance.revoked = true;
+                store_account(&ctx.accounts.market_maker_allowance, &allowance)?;
+            }
+            ProposalAction::SetUnstakeThrottleBps(bps) => {
+                ctx.accounts.staking_config.unstake_throttle_bps = bps
+            }
+            // Consumed by `finalize_round`.
+            ProposalAction::ApproveRoundReport => {}
+        }
+        proposal.executed = true;
+        emit!(ProposalExecuted {
+            proposal_id: proposal.id,
+            action: proposal.action,
+            votes_for: proposal.votes_for,
+            votes_against: proposal.votes_against,
+            timestamp: now,
+        });
+        Ok(())
+    }
+
+    /// Drop the queued parameter change. (Admin only)
+    pub fn cancel_parameters(ctx: Context<UpdateParameters>) -> Result<()> {
+        ctx.accounts.program_config.record_admin_activity(Clock::get()?.unix_timestamp);
+        let pending_params = &mut ctx.accounts.pending_params;
+        require!(pending_params.is_queued, ErrorCode::NoQueuedParameters);
+        pending_params.is_queued = false;
+        Ok(())
+    }
+
+    /// Create the (inactive) APY ramp account. (Admin only)
+    pub fn initialize_param_ramp(ctx: Context<InitializeParamRamp>) -> Result<()> {
+        ctx.accounts.program_config.record_admin_activity(Clock::get()?.unix_timestamp);
+        let param_ramp = &mut ctx.accounts.param_ramp;
+        param_ramp.is_active = false;
+        param_ramp.start_apy = 0;
+        param_ramp.end_apy = 0;
+        param_ramp.start_time = 0;
+        param_ramp.end_time = 0;
+        Ok(())
+    }
+
+    /// Schedule a gradual APY transition to `end_apy` between `start_time` and `end_time`. (Admin only)
+    /// The ramp starts from the APY currently in effect, so replacing a running ramp is seamless.
+    pub fn schedule_apy_ramp(
+        ctx: Context<UpdateParamRamp>,
+        end_apy: u64,
+        start_time: i64,
+        end_time: i64,
+    ) -> Result<()> {
+        ctx.accounts.program_config.record_admin_activity(Clock::get()?.unix_timestamp);
+        let clock = Clock::get()?;
+        require!(
+            start_time >= clock.unix_timestamp && end_time > start_time,
+            ErrorCode::InvalidRampSchedule
+        );
+        let param_ramp = &mut ctx.accounts.param_ramp;
+        let global_state = &mut ctx.accounts.global_state;
+        global_state.update_rewards(
+            param_ramp,
+            &ctx.accounts.emission_schedule,
+            clock.unix_timestamp,
+        );
+        let start_apy = param_ramp.current_apy(global_state.apy, start_time);
+        param_ramp.is_active = true;
+        param_ramp.start_apy = start_apy;
+        param_ramp.end_apy = end_apy;
+        param_ramp.start_time = start_time;
+        param_ramp.end_time = end_time;
+        Ok(())
+    }
+
+    /// Stop the active ramp, freezing the APY at its current interpolated value. (Admin only)
+    pub fn cancel_apy_ramp(ctx: Context<UpdateParamRamp>) -> Result<()> {
+        ctx.accounts.program_config.record_admin_activity(Clock::get()?.unix_timestamp);
+        let clock = Clock::get()?;
+        let param_ramp = &mut ctx.accounts.param_ramp;
+        let global_state = &mut ctx.accounts.global_state;
+        global_state.update_rewards(
+            param_ramp,
+            &ctx.accounts.emission_schedule,
+            clock.unix_timestamp,
+        );
+        global_state.apy = param_ramp.current_apy(global_state.apy, clock.unix_timestamp);
+        param_ramp.is_active = false;
+        Ok(())
+    }
+
+    /// Create the (disabled) emission schedule. (Admin only)
+    pub fn initialize_emission_schedule(
+        ctx: Context<InitializeEmissionSchedule>,
+    ) -> Result<()> {
+        ctx.accounts.program_config.record_admin_activity(Clock::get()?.unix_timestamp);
+        let emission_schedule = &mut ctx.accounts.emission_schedule;
+        emission_schedule.enabled = false;
+        emission_schedule.decay_bps = 10_000;
+        Ok(())
+    }
+
+    /// Configure the emission schedule: from `start_time`, the reward rate is multiplied by
+    /// `decay_bps` every `epochs_per_step` epochs of `epoch_length` seconds; a step lasts at
+    /// least `MIN_EMISSION_STEP_LENGTH`. Rewards accrued until now keep the old schedule.
+    /// (Admin only)
+    pub fn set_emission_schedule(
+        ctx: Context<UpdateEmissionSchedule>,
+        enabled: bool,
+        start_time: i64,
+        epoch_length: i64,
+        epochs_per_step: u64,
+        decay_bps: u16,
+    ) -> Result<()> {
+        let step_length = i64::try_from(epochs_per_step)
+            .ok()
+            .and_then(|epochs| epoch_length.checked_mul(epochs));
+        require!(
+            epoch_length > 0
+                && step_length.is_some_and(|length| length >= MIN_EMISSION_STEP_LENGTH)
+                && decay_bps <= 10_000,
+            ErrorCode::InvalidEmissionSchedule
+        );
+        let now = Clock::get()?.unix_timestamp;
+        ctx.accounts.program_config.record_admin_activity(now);
+        let emission_schedule = &mut ctx.accounts.emission_schedule;
+        ctx.accounts.global_state.update_rewards(&ctx.accounts.param_ramp, emission_schedule, now);
+        emission_schedule.enabled = enabled;
+        emission_schedule.start_time = start_time;
+        emission_schedule.epoch_length = epoch_length;
+        emission_schedule.epochs_per_step = epochs_per_step;
+        emission_schedule.decay_bps = decay_bps;
+        Ok(())
+    }
+
+    /// Create the claim portal (closed until opened by the admin). (Admin only)
+    pub fn initialize_claim_portal(ctx: Context<InitializeClaimPortal>) -> Result<()> {
+        ctx.accounts.program_config.record_admin_activity(Clock::get()?.unix_timestamp);
+        let claim_portal = &mut ctx.accounts.claim_portal;
+        claim_portal.is_open = false;
+        claim_portal.is_paused = false;
+        claim_portal.opens_at = 0;
+        claim_portal.closes_at = None;
+        claim_portal.total_claimed = 0;
+        claim_portal.claimants = 0;
+        claim_portal.max_claim_per_slot = 0;
+        claim_portal.throttle_slot = 0;
+        claim_portal.claimed_this_slot = 0;
+        Ok(())
+    }
+
+    /// Open the claim portal from `opens_at`, optionally closing at `closes_at`. (Admin only)
+    pub fn open_claim_portal(
+        ctx: Context<UpdateClaimPortal>,
+        opens_at: i64,
+        closes_at: Option<i64>,
+    ) -> Result<()> {
+        ctx.accounts.program_config.record_admin_activity(Clock::get()?.unix_timestamp);
+        if let Some(closes_at) = closes_at {
+            require!(closes_at > opens_at, ErrorCode::InvalidClaimWindow);
+        }
+        let claim_portal = &mut ctx.accounts.claim_portal;
+        claim_portal.is_open = true;
+        claim_portal.opens_at = opens_at;
+        claim_portal.closes_at = closes_at;
+        Ok(())
+    }
+
+    /// Close the claim portal. (Admin only)
+    pub fn close_claim_portal(ctx: Context<UpdateClaimPortal>) -> Result<()> {
+        ctx.accounts.program_config.record_admin_activity(Clock::get()?.unix_timestamp);
+        ctx.accounts.claim_portal.is_open = false;
+        Ok(())
+    }
+
+    /// Pause or resume claims without changing the claim window. (Admin only)
+    pub fn set_claim_portal_paused(ctx: Context<UpdateClaimPortal>, paused: bool) -> Result<()> {
+        ctx.accounts.program_config.record_admin_activity(Clock::get()?.unix_timestamp);
+        ctx.accounts.claim_portal.is_paused = paused;
+        Ok(())
+    }
+
+    /// Limit the tokens claimable per slot across all wallets, to smooth launch-day
+    /// sell pressure. A claim larger than what is left in the slot is paid in part and the
+    /// rest stays claimable in a later slot. 0 disables the limit. (Admin only)
+    pub fn set_claim_rate_limit(
+        ctx: Context<UpdateClaimPortal>,
+        max_claim_per_slot: u64,
+    ) -> Result<()> {
+        ctx.accounts.program_config.record_admin_activity(Clock::get()?.unix_timestamp);
+        ctx.accounts.claim_portal.max_claim_per_slot = max_claim_per_slot;
+        Ok(())
+    }
+
+    /// Create or update the project links account. (Admin only)
+    pub fn set_project_info(
+        ctx: Context<SetProjectInfo>,
+        website: ProjectLink,
+        audits: ProjectLink,
+        tokenomics: ProjectLink,
+    ) -> Result<()> {
+        let clock = Clock::get()?;
+        ctx.accounts.program_config.record_admin_activity(clock.unix_timestamp);
+        for link in [&website, &audits, &tokenomics].iter() {
+            require!(link.uri.len() <= MAX_URI_LEN, ErrorCode::UriTooLong);
+        }
+        let project_info = &mut ctx.accounts.project_info;
+        project_info.website = website;
+        project_info.audits = audits;
+        project_info.tokenomics = tokenomics;
+        project_info.updated_at = clock.unix_timestamp;
+        Ok(())
+    }
+
+    /// Allow the treasurer to withdraw funds from the treasury SOL account during the presale.
+    /// Amounts above the configured threshold also require the withdrawal co-signer.
+    pub fn withdraw_funds(ctx: Context<WithdrawFunds>, amount: u64) -> Result<()> {
+        // Only allow withdrawal while presale is active.
+        require!(
+            ctx.accounts.presale_state.is_presale_active,
+            ErrorCode::WithdrawalNotAllowedAfterPresale
+        );
+        ctx.accounts
+            .program_config
+            .check_withdrawal_cosigner(amount, &ctx.accounts.cosigner)?;
+        let ix = system_instruction::transfer(
+            ctx.accounts.treasury_sol_account.key,
+            ctx.accounts.treasurer.key,
+            amount,
+        );
+        solana_program::program::invoke(
+            &ix,
+            &[
+                ctx.accounts.treasury_sol_account.clone(),
+                ctx.accounts.treasurer.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+            ],
+        )?;
+        emit!(FundsWithdrawn {
+            authority: ctx.accounts.treasurer.key(),
+            destination: ctx.accounts.treasurer.key(),
+            amount,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Update a specific presale stage (Operator only).
+    /// `stage_index` is 0-based (i.e. 0 for Stage 1, 1 for Stage 2, etc.)
+    #[allow(clippy::too_many_arguments)]
+    pub fn update_presale_stage(
+        ctx: Context<UpdatePresaleStage>,
+        stage_index: u8,
+        price: u64,
+        usd_price: u64,
+        allocation: u64,
+        tokens_sold: u64,
+        total_raised: u64,
+        vesting_preset: VestingPreset,
+        payment_methods: u8,
+    ) -> Result<()> {
+        let mut presale_stage_info = ctx.accounts.presale_stage_info.load_mut()?;
+        require!(
+            (stage_index as usize) < presale_stage_info.stages.len(),
+            ErrorCode::InvalidStageIndex
+        );
+        require!(
+            payment_methods != 0 && payment_methods & !PAYMENT_METHODS_ALL == 0,
+            ErrorCode::InvalidPaymentMethods
+        );
+        let previous = presale_stage_info.stages[stage_index as usize];
+        presale_stage_info.stages[stage_index as usize] = PresaleStage {
+            stage: stage_index + 1,
+            price,
+            usd_price,
+            allocation,
+            tokens_sold,
+            total_raised,
+            total_raised_usd: previous.total_raised_usd,
+            vesting_preset: vesting_preset.index() as u8,
+            payment_methods,
+            usd_target: previous.usd_target,
+            target_reached: previous.target_reached,
+        };
+        let clock = Clock::get()?;
+        ctx.accounts
+            .presale_progress
+            .refresh(&presale_stage_info, clock.unix_timestamp);
+        emit!(StageUpdated::new(
+            &presale_stage_info.stages[stage_index as usize],
+            clock.unix_timestamp
+        ));
+        Ok(())
+    }
+
+    /// Set the USD raise at which a stage ends even if its allocation hasn't sold out, in USD
+    /// with 9 decimals; 0 removes the target. Needs a SOL/USD price feed to take effect.
+    /// Setting a target reopens a stage that was ended by its previous one. (Operator only)
+    pub fn set_stage_usd_target(
+        ctx: Context<UpdatePresaleStage>,
+        stage_index: u8,
+        usd_target: u64,
+    ) -> Result<()> {
+        let mut presale_stage_info = ctx.accounts.presale_stage_info.load_mut()?;
+        require!(
+            (stage_index as usize) < presale_stage_info.stages.len(),
+            ErrorCode::InvalidStageIndex
+        );
+        let stage = &mut presale_stage_info.stages[stage_index as usize];
+        stage.usd_target = usd_target;
+        stage.target_reached = 0;
+        let clock = Clock::get()?;
+        ctx.accounts
+            .presale_progress
+            .refresh(&presale_stage_info, clock.unix_timestamp);
+        emit!(StageUpdated::new(
+            &presale_stage_info.stages[stage_index as usize],
+            clock.unix_timestamp
+        ));
+        Ok(())
+    }
+
+    /// Create the presale progress account (Admin only).
+    /// `presale_end_time` is the scheduled end used for the time-remaining countdown.
+    pub fn initialize_presale_progress(
+        ctx: Context<InitializePresaleProgress>,
+        presale_end_time: i64,
+    ) -> Result<()> {
+        ctx.accounts.program_config.record_admin_activity(Clock::get()?.unix_timestamp);
+        let clock = Clock::get()?;
+        let presale_progress = &mut ctx.accounts.presale_progress;
+        presale_progress.presale_end_time = presale_end_time;
+        presale_progress.refresh(&*ctx.accounts.presale_stage_info.load()?, clock.unix_timestamp);
+        Ok(())
+    }
+}
+
+//
+// EVENTS
+//
+
+#[event]
+pub struct TokensBurned {
+    pub admin: Pubkey,
+    pub source: Pubkey,
+    pub amount: u64,
+    pub epoch: u64,
+    pub burned_this_epoch: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ReceiptAdjusted {
+    pub receipt: Pubkey,
+    pub beneficiary: Pubkey,
+    pub old_tokens_allocated: u64,
+    pub new_tokens_allocated: u64,
+    pub reason_hash: [u8; 32],
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct UpgradeAnnounced {
+    pub program_hash: [u8; 32],
+    pub earliest_upgrade_at: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ParametersQueued {
+    pub new_apy: u64,
+    pub new_fee_percent: u64,
+    pub eta: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct MarketMakerPull {
+    pub market_maker: Pubkey,
+    pub tokens: u64,
+    pub lamports: u64,
+    pub epoch: u64,
+    pub tokens_pulled_this_epoch: u64,
+    pub lamports_pulled_this_epoch: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct SolDeposited {
+    pub deposit_id: u64,
+    pub depositor: Pubkey,
+    pub purpose: DepositPurpose,
+    pub amount: u64,
+    pub memo_hash: [u8; 32],
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct StakePositionTransferred {
+    pub position: Pubkey,
+    pub previous_owner: Pubkey,
+    pub new_owner: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TeamBuyInExecuted {
+    pub member: Pubkey,
+    pub buy_in_id: u64,
+    pub lamports_spent: u64,
+    pub purchased: u64,
+    pub matched: u64,
+    pub vests_from: i64,
+    pub vests_until: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AdminRotationProposed {
+    pub guardian: Pubkey,
+    pub new_admin: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AdminRotationApproved {
+    pub guardian: Pubkey,
+    pub new_admin: Pubkey,
+    pub approvals: u8,
+    pub executable_at: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AdminRotationExecuted {
+    pub previous_admin: Pubkey,
+    pub new_admin: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AdminRotationCancelled {
+    pub cancelled_by: Pubkey,
+    pub new_admin: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct GuardiansUpdated {
+    pub guardian_count: u8,
+    pub threshold: u8,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PausedOperationsChanged {
+    pub paused_operations: u8,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PaymentAccepted {
+    pub payer: Pubkey,
+    pub token_mint: Pubkey,
+    pub amount: u64,
+    pub fee: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TokensPurchased {
+    pub buyer: Pubkey,
+    pub receipt: Pubkey,
+    pub payment_method: u8,
+    pub token_mint: Pubkey,
+    pub amount_paid: u64,
+    pub tokens_allocated: u64,
+    pub launch_tax: u64,
+    pub first_stage: u8,
+    pub last_stage: u8,
+    pub referrer: Option<Pubkey>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct StageUpdated {
+    pub stage: u8,
+    pub price: u64,
+    pub usd_price: u64,
+    pub allocation: u64,
+    pub tokens_sold: u64,
+    pub payment_methods: u8,
+    pub usd_target: u64,
+    pub timestamp: i64,
+}
+
+impl StageUpdated {
+    pub fn new(stage: &PresaleStage, timestamp: i64) -> Self {
+        StageUpdated {
+            stage: stage.stage,
+            price: stage.price,
+            usd_price: stage.usd_price,
+            allocation: stage.allocation,
+            tokens_sold: stage.tokens_sold,
+            payment_methods: stage.payment_methods,
+            usd_target: stage.usd_target,
+            timestamp,
+        }
+    }
+}
+
+#[event]
+pub struct PresaleEnded {
+    pub total_raised: u64,
+    pub buyer_count: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct FundsWithdrawn {
+    pub authority: Pubkey,
+    pub destination: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RefundClaimed {
+    pub buyer: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TokensClaimed {
+    pub beneficiary: Pubkey,
+    pub amount: u64,
+    pub vested: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ReferralRewardsClaimed {
+    pub referrer: Pubkey,
+    pub amount: u64,
+    pub staked: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct EarlyBirdBonusClaimed {
+    pub beneficiary: Pubkey,
+    pub receipt: Pubkey,
+    pub amount: u64,
+    pub staked: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AirdropClaimed {
+    pub distributor: Pubkey,
+    pub claimant: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct Staked {
+    pub owner: Pubkey,
+    pub position: Pubkey,
+    pub amount: u64,
+    pub position_amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct Unstaked {
+    pub owner: Pubkey,
+    pub position: Pubkey,
+    pub amount: u64,
+    pub penalty: u64,
+    pub withdrawable_at: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct UnstakeWithdrawn {
+    pub owner: Pubkey,
+    pub position: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RewardsClaimed {
+    pub owner: Pubkey,
+    pub position: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RewardsCompounded {
+    pub owner: Pubkey,
+    pub position: Pubkey,
+    pub amount: u64,
+    pub position_amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RewardsRestaked {
+    pub owner: Pubkey,
+    pub position: Pubkey,
+    pub new_position: Pubkey,
+    pub amount: u64,
+    pub bonus: u64,
+    pub locked_until: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct LpStaked {
+    pub owner: Pubkey,
+    pub amount: u64,
+    pub position_amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct LpUnstaked {
+    pub owner: Pubkey,
+    pub amount: u64,
+    pub position_amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct LpRewardsClaimed {
+    pub owner: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ProposalCreated {
+    pub proposal_id: u64,
+    pub proposer: Pubkey,
+    pub action: ProposalAction,
+    pub voting_ends_at: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct VoteCast {
+    pub proposal_id: u64,
+    pub voter: Pubkey,
+    pub position: Pubkey,
+    pub support: bool,
+    pub weight: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ProposalExecuted {
+    pub proposal_id: u64,
+    pub action: ProposalAction,
+    pub votes_for: u64,
+    pub votes_against: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct BuybackBurned {
+    pub sol_spent: u64,
+    pub tokens_burned: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ScheduledBurnExecuted {
+    pub amount: u64,
+    pub total_burned: u64,
+    pub next_burn_ts: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ReflectionSnapshotPosted {
+    pub snapshot_id: u64,
+    pub amount: u64,
+    pub total_balance: u64,
+    pub claim_deadline: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ReflectionClaimed {
+    pub snapshot_id: u64,
+    pub holder: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct BlacklistUpdated {
+    pub wallet: Pubkey,
+    pub blocked: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TransferFeeSet {
+    pub basis_points: u16,
+    pub reward_pool_share_bps: u16,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TransferFeesHarvested {
+    pub amount: u64,
+    pub burned: u64,
+    pub to_reward_pool: u64,
+    pub to_fee_wallet: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ReceiptCompressed {
+    pub receipt: Pubkey,
+    pub leaf_index: u64,
+    pub compressed: CompressedReceipt,
+    pub timestamp: i64,
+}
+
+//
+// ERROR CODES
+//
+#[error_code]
+pub enum ErrorCode {
+    #[msg("Presale has not ended yet. Staking is only allowed during the presale.")]
+    PresaleNotEnded,
+    #[msg("Presale already ended.")]
+    PresaleAlreadyEnded,
+    #[msg("Unstaking not allowed during the early-unstake period after launch.")]
+    UnstakingNotAllowedBefore7Days,
+    #[msg("Liquidity lock error.")]
+    LiquidityLockError,
+    #[msg("Invalid payment or stake amount.")]
+    InvalidAmount,
+    #[msg("Insufficient funds for SPL token transfer.")]
+    InsufficientFunds,
+    #[msg("No rewards available to claim yet.")]
+    NoRewardsAvailable,
+    #[msg("Invalid token mint address.")]
+    InvalidTokenMint,
+    #[msg("Not enough rewards in the pool.")]
+    InsufficientRewards,
+    #[msg("Unauthorized.")]
+    Unauthorized,
+    #[msg("Fee wallet provided is invalid.")]
+    InvalidFeeWallet,
+    #[msg("Staking is only allowed during the presale.")]
+    StakingClosed,
+    #[msg("Staking rewards pool is exhausted.")]
+    StakingRewardsExhausted,
+    #[msg("Withdrawal allowed only during presale.")]
+    WithdrawalNotAllowedAfterPresale,
+    #[msg("Invalid presale stage index.")]
+    InvalidStageIndex,
+    #[msg("Split amount must be greater than zero and less than the staked amount.")]
+    InvalidSplitAmount,
+    #[msg("Invalid beneficiary wallet.")]
+    InvalidBeneficiary,
+    #[msg("Invoice has expired.")]
+    InvoiceExpired,
+    #[msg("Invoice has already been paid.")]
+    InvoiceAlreadyPaid,
+    #[msg("Treasury account does not match.")]
+    InvalidTreasury,
+    #[msg("Invalid APY ramp schedule.")]
+    InvalidRampSchedule,
+    #[msg("Admin inactivity period must be positive.")]
+    InvalidInactivityPeriod,
+    #[msg("Admin has been active within the inactivity period.")]
+    AdminStillActive,
+    #[msg("Burn exceeds the per-epoch burn cap.")]
+    BurnCapExceeded,
+    #[msg("Reward pool is not below the low-water mark.")]
+    RewardPoolAboveLowWaterMark,
+    #[msg("Claim portal is closed.")]
+    ClaimPortalClosed,
+    #[msg("Claims are paused.")]
+    ClaimPortalPaused,
+    #[msg("Invalid claim window.")]
+    InvalidClaimWindow,
+    #[msg("Purchase requires a wallet attestation.")]
+    MissingAttestation,
+    #[msg("Wallet failed the anti-sybil check.")]
+    SybilCheckFailed,
+    #[msg("Invalid fee configuration.")]
+    InvalidFeeConfig,
+    #[msg("Payments in this mint are not accepted.")]
+    MintNotAccepted,
+    #[msg("Stake position is frozen.")]
+    PositionFrozen,
+    #[msg("Stake position is not frozen.")]
+    PositionNotFrozen,
+    #[msg("Airdrop claim deadline has passed.")]
+    AirdropClaimExpired,
+    #[msg("Invalid merkle proof.")]
+    InvalidMerkleProof,
+    #[msg("Airdrop can still be claimed.")]
+    AirdropStillClaimable,
+    #[msg("Airdrop has already been swept.")]
+    AirdropAlreadySwept,
+    #[msg("Bonus has already been claimed.")]
+    BonusAlreadyClaimed,
+    #[msg("Withdrawal above the threshold requires the co-signer.")]
+    CosignerRequired,
+    #[msg("URI is too long.")]
+    UriTooLong,
+    #[msg("Stake exceeds the per-wallet staking cap.")]
+    WalletStakeCapExceeded,
+    #[msg("Reward expiry is disabled.")]
+    RewardExpiryDisabled,
+    #[msg("Invalid reward expiry policy.")]
+    InvalidExpiryPolicy,
+    #[msg("Presale is sold out.")]
+    PresaleSoldOut,
+    #[msg("Claim throughput limit reached for this slot; retry in a later slot.")]
+    ClaimThrottled,
+    #[msg("Purchase would exceed the presale hard cap.")]
+    HardCapExceeded,
+    #[msg("Invalid presale caps.")]
+    InvalidPresaleCaps,
+    #[msg("Presale soft cap not reached.")]
+    SoftCapNotReached,
+    #[msg("Presale soft cap reached; refunds are not available.")]
+    SoftCapReached,
+    #[msg("Contribution already refunded.")]
+    AlreadyRefunded,
+    #[msg("Buyer is not on the presale whitelist.")]
+    NotWhitelisted,
+    #[msg("Invalid upgrade announcement.")]
+    InvalidUpgradeAnnouncement,
+    #[msg("This program hash has not been announced.")]
+    UpgradeNotAnnounced,
+    #[msg("Upgrade notice period has not elapsed.")]
+    UpgradeTimelockActive,
+    #[msg("Tokens of this mint are protected and cannot be recovered.")]
+    ProtectedMint,
+    #[msg("Receipt has already been added to a vesting schedule.")]
+    ReceiptAlreadyVested,
+    #[msg("Receipt vesting terms differ from the vesting schedule.")]
+    VestingTermsMismatch,
+    #[msg("No vested tokens to claim.")]
+    NothingVested,
+    #[msg("This payment method is not accepted in the current stage.")]
+    PaymentMethodNotAllowed,
+    #[msg("Invalid payment method mask.")]
+    InvalidPaymentMethods,
+    #[msg("Invalid APY controller configuration.")]
+    InvalidApyController,
+    #[msg("APY controller is disabled.")]
+    ApyControllerDisabled,
+    #[msg("APY already adjusted this epoch.")]
+    ApyAlreadyAdjusted,
+    #[msg("Invalid referrer.")]
+    InvalidReferrer,
+    #[msg("Invalid referral tiers.")]
+    InvalidReferralTiers,
+    #[msg("Allocation already claimed.")]
+    AllocationAlreadyClaimed,
+    #[msg("Allocation vests; add the receipt to a vesting schedule instead.")]
+    AllocationIsVesting,
+    #[msg("Purchase is below the minimum.")]
+    PurchaseBelowMinimum,
+    #[msg("Purchase would exceed the per-wallet contribution cap.")]
+    WalletContributionCapExceeded,
+    #[msg("Receipt dispute window has closed.")]
+    DisputeWindowClosed,
+    #[msg("Invalid receipt adjustment.")]
+    InvalidReceiptAdjustment,
+    #[msg("Invalid migration ratio.")]
+    InvalidMigrationRatio,
+    #[msg("Position already migrated to the new mint.")]
+    PositionAlreadyMigrated,
+    #[msg("Tokens are not in the position's staking mint.")]
+    InvalidStakeMint,
+    #[msg("Timelock delay is too short.")]
+    InvalidTimelockDelay,
+    #[msg("No parameter change is queued.")]
+    NoQueuedParameters,
+    #[msg("Timelock has not expired yet.")]
+    TimelockNotExpired,
+    #[msg("Invalid token mint.")]
+    InvalidMint,
+    #[msg("Invalid lock period.")]
+    InvalidLockPeriod,
+    #[msg("This operation is paused.")]
+    OperationPaused,
+    #[msg("Invalid pause flags.")]
+    InvalidPauseFlags,
+    #[msg("Emergency unstaking is not enabled.")]
+    EmergencyUnstakeDisabled,
+    #[msg("Invalid proposal account.")]
+    InvalidProposal,
+    #[msg("Position is locked until the proposals it voted on close.")]
+    PositionVoteLocked,
+    #[msg("Invalid governance configuration.")]
+    InvalidGovernanceConfig,
+    #[msg("Not enough staked tokens.")]
+    InsufficientVotingPower,
+    #[msg("Voting on this proposal has closed.")]
+    VotingClosed,
+    #[msg("Voting on this proposal has not ended yet.")]
+    VotingNotEnded,
+    #[msg("Proposal did not pass.")]
+    ProposalNotPassed,
+    #[msg("Proposal already executed.")]
+    ProposalAlreadyExecuted,
+    #[msg("The market-maker allowance has been revoked.")]
+    MarketMakerRevoked,
+    #[msg("Pull exceeds the market maker's per-epoch limit.")]
+    MarketMakerLimitExceeded,
+    #[msg("Unstake throughput limit reached; queue the unstake and retry in its window.")]
+    UnstakeThrottled,
+    #[msg("Position is queued for unstaking.")]
+    PositionQueuedForUnstake,
+    #[msg("The unstake throttle is not enabled.")]
+    UnstakeThrottleDisabled,
+    #[msg("Invalid unstake throttle.")]
+    InvalidUnstakeThrottle,
+    #[msg("This bonus is auto-staked; claim it into the bonus stake position.")]
+    BonusMustBeStaked,
+    #[msg("Auto-staking is not enabled for this bonus.")]
+    BonusAutoStakeDisabled,
+    #[msg("Position is locked.")]
+    PositionLocked,
+    #[msg("Invalid vote record.")]
+    InvalidVoteRecord,
+    #[msg("Invalid APY curve.")]
+    InvalidApyCurve,
+    #[msg("Invalid team buy-in configuration.")]
+    InvalidTeamBuyInConfig,
+    #[msg("Swap result is outside the given limits.")]
+    SlippageExceeded,
+    #[msg("Invalid emission schedule.")]
+    InvalidEmissionSchedule,
+    #[msg("Unexpected number of remaining accounts.")]
+    InvalidRemainingAccounts,
+    #[msg("Stake position transfers are not enabled.")]
+    PositionTransfersDisabled,
+    #[msg("This position can't be transferred.")]
+    PositionNotTransferable,
+    #[msg("Invalid NFT boost.")]
+    InvalidNftBoost,
+    #[msg("Invalid NFT metadata account.")]
+    InvalidNftMetadata,
+    #[msg("The NFT is not part of the boost collection.")]
+    NftNotInCollection,
+    #[msg("The position has a boost NFT registered.")]
+    NftBoostActive,
+    #[msg("The position has no boost NFT registered.")]
+    NoNftBoost,
+    #[msg("Invalid price feed.")]
+    InvalidPriceFeed,
+    #[msg("The oracle price is too old.")]
+    StalePrice,
+    #[msg("No unstaked tokens are waiting to be withdrawn.")]
+    NoPendingWithdrawal,
+    #[msg("The unstake cooldown has not passed yet.")]
+    UnstakeCooldownActive,
+    #[msg("Not enough liquidity has been committed for launch.")]
+    InsufficientLaunchLiquidity,
+    #[msg("Invalid guardian configuration.")]
+    InvalidGuardianConfig,
+    #[msg("Signer is not a guardian.")]
+    NotGuardian,
+    #[msg("An admin rotation is already open.")]
+    AdminRotationPending,
+    #[msg("No admin rotation is open.")]
+    NoAdminRotation,
+    #[msg("The admin rotation delay has not passed yet.")]
+    AdminRotationNotReady,
+    #[msg("Already approved.")]
+    AlreadyApproved,
+    #[msg("Invalid burn schedule.")]
+    InvalidBurnSchedule,
+    #[msg("The next scheduled burn is not due yet.")]
+    BurnNotDue,
+    #[msg("No undistributed reflections in the vault.")]
+    NothingToDistribute,
+    #[msg("Reflection claim deadline has passed.")]
+    ReflectionClaimExpired,
+    #[msg("Reflection snapshot can still be claimed or has already expired.")]
+    ReflectionStillClaimable,
+    #[msg("Amount exceeds the per-transaction limit during the launch window.")]
+    MaxTransactionExceeded,
+    #[msg("Recipient balance would exceed the max wallet size during the launch window.")]
+    MaxWalletExceeded,
+    #[msg("Wallet is blacklisted.")]
+    WalletBlacklisted,
+    #[msg("The presale has not opened yet.")]
+    PresaleNotOpen,
+    #[msg("Only one purchase per wallet per slot.")]
+    PurchaseRateLimited,
+    #[msg("The receipt can still be adjusted; wait for its dispute window to close.")]
+    DisputeWindowOpen,
+    #[msg("Claim the receipt's early-bird bonus first.")]
+    BonusNotClaimed,
+    #[msg("Merkle tree does not match the receipt tree.")]
+    InvalidReceiptTree,
+    #[msg("Compressed purchases need a full-unlock stage and no early-bird bonus.")]
+    CompressedPurchaseUnavailable,
+    #[msg("Trading has not opened yet.")]
+    TradingNotOpen,
+}
+
+//
+// CONTEXTS & HELPER FUNCTIONS
+//
+
+// ---------- InitializeAll ----------
+#[derive(Accounts)]
+pub struct InitializeAll<'info> {
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + std::mem::size_of::<PresaleState>(),
+        seeds = [b"presale_state"],
+        bump
+    )]
+    pub presale_state: Box<Account<'info, PresaleState>>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + std::mem::size_of::<Roles>(),
+        seeds = [b"roles"],
+        bump
+    )]
+    pub roles: Box<Account<'info, Roles>>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + std::mem::size_of::<GlobalState>(),
+        seeds = [b"global_state"],
+        bump
+    )]
+    pub global_state: Box<Account<'info, GlobalState>>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + std::mem::size_of::<PresaleStageInfo>(),
+        seeds = [b"presale_stage_info"],
+        bump
+    )]
+    pub presale_stage_info: AccountLoader<'info, PresaleStageInfo>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + std::mem::size_of::<StakingConfig>(),
+        seeds = [b"staking_config"],
+        bump
+    )]
+    pub staking_config: Box<Account<'info, StakingConfig>>,
+    /// Vault holding presale tokens for distribution; the token account is its own authority.
+    #[account(
+        init_if_needed,
+        payer = payer,
+        token::mint = mint,
+        token::authority = vesting_vault,
+        seeds = [b"vesting_vault"],
+        bump
+    )]
+    pub vesting_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+    /// Vault custodying staked tokens of this mint; the token account is its own authority.
+    #[account(
+        init_if_needed,
+        payer = payer,
+        token::mint = mint,
+        token::authority = stake_vault,
+        seeds = [b"stake_vault", mint.key().as_ref()],
+        bump
+    )]
+    pub stake_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+    /// Vault holding staking rewards; the token account is its own authority.
+    #[account(
+        init_if_needed,
+        payer = payer,
+        token::mint = mint,
+        token::authority = reward_vault,
+        seeds = [b"reward_vault"],
+        bump
+    )]
+    pub reward_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+    /// The $BRATS mint.
+    pub mint: Box<InterfaceAccount<'info, Mint>>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+// ---------- InitializeProgramConfig ----------
+#[derive(Accounts)]
+pub struct InitializeProgramConfig<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + std::mem::size_of::<ProgramConfig>(),
+        seeds = [b"config"],
+        bump
+    )]
+    pub program_config: Account<'info, ProgramConfig>,
+    #[account(has_one = admin @ ErrorCode::Unauthorized)]
+    pub presale_state: Account<'info, PresaleState>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+// ---------- AdminConfig ----------
+#[derive(Accounts)]
+pub struct AdminConfig<'info> {
+    #[account(mut, seeds = [b"config"], bump)]
+    pub program_config: Account<'info, ProgramConfig>,
+    #[account(has_one = admin @ ErrorCode::Unauthorized)]
+    pub presale_state: Account<'info, PresaleState>,
+    pub admin: Signer<'info>,
+}
+
+// ---------- CreateTokenMetadata ----------
+#[derive(Accounts)]
+pub struct CreateTokenMetadata<'info> {
+    /// CHECK: Metadata PDA of the $BRATS mint; derived and created by the metadata program.
+    #[account(mut)]
+    pub metadata: AccountInfo<'info>,
+    #[account(constraint = token_mint.key() == program_config.token_mint @ ErrorCode::InvalidTokenMint)]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+    pub mint_authority: Signer<'info>,
+    /// CHECK: PDA set as the metadata's update authority; signs metadata updates.
+    #[account(seeds = [b"metadata_authority"], bump)]
+    pub metadata_authority: AccountInfo<'info>,
+    #[account(has_one = admin @ ErrorCode::Unauthorized)]
+    pub presale_state: Account<'info, PresaleState>,
+    #[account(mut, seeds = [b"config"], bump)]
+    pub program_config: Account<'info, ProgramConfig>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    /// CHECK: Metaplex Token Metadata program.
+    #[account(address = mpl_token_metadata::ID)]
+    pub token_metadata_program: AccountInfo<'info>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+// ---------- UpdateTokenMetadata ----------
+#[derive(Accounts)]
+pub struct UpdateTokenMetadata<'info> {
+    /// CHECK: Metadata PDA of the $BRATS mint; the metadata program checks the update authority.
+    #[account(mut)]
+    pub metadata: AccountInfo<'info>,
+    /// CHECK: PDA set as the metadata's update authority by `create_token_metadata`.
+    #[account(seeds = [b"metadata_authority"], bump)]
+    pub metadata_authority: AccountInfo<'info>,
+    #[account(has_one = admin @ ErrorCode::Unauthorized)]
+    pub presale_state: Account<'info, PresaleState>,
+    #[account(mut, seeds = [b"config"], bump)]
+    pub program_config: Account<'info, ProgramConfig>,
+    pub admin: Signer<'info>,
+    /// CHECK: Metaplex Token Metadata program.
+    #[account(address = mpl_token_metadata::ID)]
+    pub token_metadata_program: AccountInfo<'info>,
+}
+
+// ---------- RecoverForeignTokens ----------
+#[derive(Accounts)]
+pub struct RecoverForeignTokens<'info> {
+    #[account(has_one = admin @ ErrorCode::Unauthorized)]
+    pub presale_state: Account<'info, PresaleState>,
+    #[account(mut, seeds = [b"config"], bump)]
+    pub program_config: Account<'info, ProgramConfig>,
+    /// CHECK: PDA that owns the source token account; only used as a signer.
+    #[account(seeds = [b"treasury"], bump)]
+    pub treasury_authority: AccountInfo<'info>,
+    /// Token account holding the stray tokens, owned by the treasury PDA.
+    #[account(mut, constraint = source_token_account.owner == treasury_authority.key())]
+    pub source_token_account: InterfaceAccount<'info, TokenAccount>,
+    /// Presale treasury's token account for the same mint.
+    #[account(mut, constraint = destination_token_account.mint == source_token_account.mint)]
+    pub destination_token_account: InterfaceAccount<'info, TokenAccount>,
+    /// CHECK: Accepted-mint PDA for the source mint; must not exist.
+    #[account(seeds = [b"accepted_mint", source_token_account.mint.as_ref()], bump)]
+    pub accepted_mint: AccountInfo<'info>,
+    pub admin: Signer<'info>,
+    /// Mint of the tokens being recovered.
+    #[account(address = source_token_account.mint)]
+    pub mint: InterfaceAccount<'info, Mint>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+impl<'info> RecoverForeignTokens<'info> {
+    /// Returns a CPI context for moving the stray tokens to the presale treasury.
+    pub fn recovery_transfer_context(
+        &self,
+    ) -> CpiContext<'_, '_, '_, 'info, TransferChecked<'info>> {
+        let cpi_accounts = TransferChecked {
+            from: self.source_token_account.to_account_info(),
+            mint: self.mint.to_account_info(),
+            to: self.destination_token_account.to_account_info(),
+            authority: self.treasury_authority.to_account_info(),
+        };
+        CpiContext::new(self.token_program.to_account_info(), cpi_accounts)
+    }
+}
+
+// ---------- CheckUpgradeAnnouncement ----------
+#[derive(Accounts)]
+pub struct CheckUpgradeAnnouncement<'info> {
+    #[account(seeds = [b"config"], bump)]
+    pub program_config: Account<'info, ProgramConfig>,
+}
+
+// ---------- SetRewardPoolRefillConfig ----------
+#[derive(Accounts)]
+pub struct SetRewardPoolRefillConfig<'info> {
+    #[account(mut, seeds = [b"config"], bump)]
+    pub program_config: Account<'info, ProgramConfig>,
+    #[account(has_one = admin @ ErrorCode::Unauthorized)]
+    pub presale_state: Account<'info, PresaleState>,
+    pub admin: Signer<'info>,
+}
+
+// ---------- CrankRewardPoolRefill ----------
+#[derive(Accounts)]
+pub struct CrankRewardPoolRefill<'info> {
+    #[account(seeds = [b"config"], bump)]
+    pub program_config: Account<'info, ProgramConfig>,
+    #[account(mut)]
+    pub global_state: Account<'info, GlobalState>,
+    /// CHECK: PDA that owns the treasury token account; only used as a signer.
+    #[account(seeds = [b"treasury"], bump)]
+    pub treasury_authority: AccountInfo<'info>,
+    /// The treasury token account (source), owned by the treasury PDA.
+    #[account(mut, constraint = treasury_token_account.owner == treasury_authority.key())]
+    pub treasury_token_account: InterfaceAccount<'info, TokenAccount>,
+    /// The reward vault (destination).
+    #[account(mut, seeds = [b"reward_vault"], bump)]
+    pub reward_vault: InterfaceAccount<'info, TokenAccount>,
+    /// The $BRATS mint.
+    #[account(address = reward_vault.mint)]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+impl<'info> CrankRewardPoolRefill<'info> {
+    /// Returns a CPI context for transferring tokens from the treasury into the reward pool.
+    pub fn treasury_transfer_context(
+        &self,
+    ) -> CpiContext<'_, '_, '_, 'info, TransferChecked<'info>> {
+        let cpi_accounts = TransferChecked {
+            from: self.treasury_token_account.to_account_info(),
+            mint: self.token_mint.to_account_info(),
+            to: self.reward_vault.to_account_info(),
+            authority: self.treasury_authority.to_account_info(),
+        };
+        CpiContext::new(self.token_program.to_account_info(), cpi_accounts)
+    }
+}
+
+// ---------- SetMarketMakerAllowance ----------
+#[derive(Accounts)]
+pub struct SetMarketMakerAllowance<'info> {
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = 8 + std::mem::size_of::<MarketMakerAllowance>(),
+        seeds = [b"market_maker"],
+        bump
+    )]
+    pub market_maker_allowance: Account<'info, MarketMakerAllowance>,
+    /// Market-making token vault; the token account is its own authority.
+    #[account(
+        init_if_needed,
+        payer = admin,
+        token::mint = mint,
+        token::authority = mm_token_vault,
+        seeds = [b"mm_token_vault"],
+        bump
+    )]
+    pub mm_token_vault: InterfaceAccount<'info, TokenAccount>,
+    #[account(constraint = mint.key() == program_config.token_mint @ ErrorCode::InvalidTokenMint)]
+    pub mint: InterfaceAccount<'info, Mint>,
+    #[account(has_one = admin @ ErrorCode::Unauthorized)]
+    pub presale_state: Account<'info, PresaleState>,
+    #[account(mut, seeds = [b"config"], bump)]
+    pub program_config: Account<'info, ProgramConfig>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+// ---------- RevokeMarketMaker ----------
+#[derive(Accounts)]
+pub struct RevokeMarketMaker<'info> {
+    #[account(mut, seeds = [b"market_maker"], bump)]
+    pub market_maker_allowance: Account<'info, MarketMakerAllowance>,
+    #[account(has_one = admin @ ErrorCode::Unauthorized)]
+    pub presale_state: Account<'info, PresaleState>,
+    #[account(mut, seeds = [b"config"], bump)]
+    pub program_config: Account<'info, ProgramConfig>,
+    pub admin: Signer<'info>,
+}
+
+// ---------- FundMarketMaker ----------
+#[derive(Accounts)]
+pub struct FundMarketMaker<'info> {
+    #[account(seeds = [b"roles"], bump, has_one = treasurer @ ErrorCode::Unauthorized)]
+    pub roles: Account<'info, Roles>,
+    /// CHECK: PDA that owns the treasury token account; only used as a signer.
+    #[account(seeds = [b"treasury"], bump)]
+    pub treasury_authority: AccountInfo<'info>,
+    /// The treasury token account (source), owned by the treasury PDA.
+    #[account(mut, constraint = treasury_token_account.owner == treasury_authority.key())]
+    pub treasury_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, seeds = [b"mm_token_vault"], bump)]
+    pub mm_token_vault: InterfaceAccount<'info, TokenAccount>,
+    /// CHECK: SOL vault PDA for market making.
+    #[account(mut, seeds = [b"mm_sol_vault"], bump)]
+    pub mm_sol_vault: AccountInfo<'info>,
+    #[account(mut)]
+    pub treasurer: Signer<'info>,
+    /// The $BRATS mint.
+    #[account(address = mm_token_vault.mint)]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> FundMarketMaker<'info> {
+    /// Returns a CPI context for moving treasury tokens into the market-making vault.
+    pub fn treasury_transfer_context(
+        &self,
+    ) -> CpiContext<'_, '_, '_, 'info, TransferChecked<'info>> {
+        let cpi_accounts = TransferChecked {
+            from: self.treasury_token_account.to_account_info(),
+            mint: self.token_mint.to_account_info(),
+            to: self.mm_token_vault.to_account_info(),
+            authority: self.treasury_authority.to_account_info(),
+        };
+        CpiContext::new(self.token_program.to_account_info(), cpi_accounts)
+    }
+}
+
+// ---------- PullMarketMakerFunds ----------
+#[derive(Accounts)]
+pub struct PullMarketMakerFunds<'info> {
+    #[account(
+        mut,
+        seeds = [b"market_maker"],
+        bump,
+        has_one = market_maker @ ErrorCode::Unauthorized
+    )]
+    pub market_maker_allowance: Account<'info, MarketMakerAllowance>,
+    #[account(seeds = [b"config"], bump)]
+    pub program_config: Account<'info, ProgramConfig>,
+    #[account(mut, seeds = [b"mm_token_vault"], bump)]
+    pub mm_token_vault: InterfaceAccount<'info, TokenAccount>,
+    /// CHECK: SOL vault PDA for market making.
+    #[account(mut, seeds = [b"mm_sol_vault"], bump)]
+    pub mm_sol_vault: AccountInfo<'info>,
+    /// The market maker's token account (destination).
+    #[account(mut)]
+    pub market_maker_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut)]
+    pub market_maker: Signer<'info>,
+    /// The $BRATS mint.
+    #[account(address = mm_token_vault.mint)]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> PullMarketMakerFunds<'info> {
+    /// Returns a CPI context for moving tokens from the market-making vault to the market maker.
+    pub fn pull_transfer_context(
+        &self,
+    ) -> CpiContext<'_, '_, '_, 'info, TransferChecked<'info>> {
+        let cpi_accounts = TransferChecked {
+            from: self.mm_token_vault.to_account_info(),
+            mint: self.token_mint.to_account_info(),
+            to: self.market_maker_token_account.to_account_info(),
+            authority: self.mm_token_vault.to_account_info(),
+        };
+        CpiContext::new(self.token_program.to_account_info(), cpi_accounts)
+    }
+}
+
+// ---------- SetTeamBuyInConfig ----------
+#[derive(Accounts)]
+pub struct SetTeamBuyInConfig<'info> {
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = 8 + std::mem::size_of::<TeamBuyInConfig>(),
+        seeds = [b"team_buy_in"],
+        bump
+    )]
+    pub team_buy_in_config: Account<'info, TeamBuyInConfig>,
+    /// Team escrow vault; the token account is its own authority.
+    #[account(
+        init_if_needed,
+        payer = admin,
+        token::mint = mint,
+        token::authority = team_escrow_vault,
+        seeds = [b"team_escrow_vault"],
+        bump
+    )]
+    pub team_escrow_vault: InterfaceAccount<'info, TokenAccount>,
+    #[account(constraint = mint.key() == program_config.token_mint @ ErrorCode::InvalidTokenMint)]
+    pub mint: InterfaceAccount<'info, Mint>,
+    #[account(has_one = admin @ ErrorCode::Unauthorized)]
+    pub presale_state: Account<'info, PresaleState>,
+    #[account(mut, seeds = [b"config"], bump)]
+    pub program_config: Account<'info, ProgramConfig>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+// ---------- RegisterTeamMember ----------
+#[derive(Accounts)]
+#[instruction(wallet: Pubkey)]
+pub struct RegisterTeamMember<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + std::mem::size_of::<TeamMember>(),
+        seeds = [b"team_member", wallet.as_ref()],
+        bump
+    )]
+    pub team_member: Account<'info, TeamMember>,
+    #[account(has_one = admin @ ErrorCode::Unauthorized)]
+    pub presale_state: Account<'info, PresaleState>,
+    #[account(mut, seeds = [b"config"], bump)]
+    pub program_config: Account<'info, ProgramConfig>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+// ---------- TeamBuyIn ----------
+#[derive(Accounts)]
+pub struct TeamBuyIn<'info> {
+    #[account(mut, seeds = [b"team_buy_in"], bump)]
+    pub team_buy_in_config: Box<Account<'info, TeamBuyInConfig>>,
+    #[account(mut, seeds = [b"team_member", member.key().as_ref()], bump)]
+    pub team_member: Box<Account<'info, TeamMember>>,
+    #[account(
+        init,
+        payer = member,
+        space = 8 + std::mem::size_of::<TeamEscrow>(),
+        seeds = [
+            b"team_escrow",
+            member.key().as_ref(),
+            &team_member.buy_in_count.to_le_bytes()
+        ],
+        bump
+    )]
+    pub team_escrow: Box<Account<'info, TeamEscrow>>,
+    #[account(mut, seeds = [b"team_escrow_vault"], bump)]
+    pub team_escrow_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+    /// The member's wrapped SOL account the swap spends from.
+    #[account(
+        mut,
+        constraint = member_wsol_account.owner == member.key() @ ErrorCode::Unauthorized,
+        constraint = member_wsol_account.mint == Pubkey::from_str(NATIVE_MINT).unwrap()
+            @ ErrorCode::InvalidTokenMint
+    )]
+    pub member_wsol_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    /// CHECK: PDA that owns the treasury token account; only used as a signer.
+    #[account(seeds = [b"treasury"], bump)]
+    pub treasury_authority: AccountInfo<'info>,
+    /// The treasury token account funding the match, owned by the treasury PDA.
+    #[account(mut, constraint = treasury_token_account.owner == treasury_authority.key())]
+    pub treasury_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    /// CHECK: The allow-listed swap program.
+    #[account(executable, address = team_buy_in_config.swap_program @ ErrorCode::Unauthorized)]
+    pub swap_program: AccountInfo<'info>,
+    #[account(mut)]
+    pub member: Signer<'info>,
+    /// The $BRATS mint.
+    #[account(address = team_escrow_vault.mint)]
+    pub token_mint: Box<InterfaceAccount<'info, Mint>>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> TeamBuyIn<'info> {
+    /// Returns a CPI context for moving the treasury match into the team escrow vault.
+    pub fn match_transfer_context(
+        &self,
+    ) -> CpiContext<'_, '_, '_, 'info, TransferChecked<'info>> {
+        let cpi_accounts = TransferChecked {
+            from: self.treasury_token_account.to_account_info(),
+            mint: self.token_mint.to_account_info(),
+            to: self.team_escrow_vault.to_account_info(),
+            authority: self.treasury_authority.to_account_info(),
+        };
+        CpiContext::new(self.token_program.to_account_info(), cpi_accounts)
+    }
+}
+
+// ---------- ReleaseTeamEscrow ----------
+#[derive(Accounts)]
+pub struct ReleaseTeamEscrow<'info> {
+    #[account(
+        mut,
+        seeds = [
+            b"team_escrow",
+            member.key().as_ref(),
+            &team_escrow.buy_in_id.to_le_bytes()
+        ],
+        bump,
+        has_one = member @ ErrorCode::Unauthorized
+    )]
+    pub team_escrow: Account<'info, TeamEscrow>,
+    #[account(mut, seeds = [b"team_escrow_vault"], bump)]
+    pub team_escrow_vault: InterfaceAccount<'info, TokenAccount>,
+    /// The member's token account receiving the released tokens.
+    #[account(mut)]
+    pub member_token_account: InterfaceAccount<'info, TokenAccount>,
+    pub member: Signer<'info>,
+    /// The $BRATS mint.
+    #[account(address = team_escrow_vault.mint)]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+impl<'info> ReleaseTeamEscrow<'info> {
+    /// Returns a CPI context for releasing tokens from the team escrow vault.
+    pub fn release_transfer_context(
+        &self,
+    ) -> CpiContext<'_, '_, '_, 'info, TransferChecked<'info>> {
+        let cpi_accounts = TransferChecked {
+            from: self.team_escrow_vault.to_account_info(),
+            mint: self.token_mint.to_account_info(),
+            to: self.member_token_account.to_account_info(),
+            authority: self.team_escrow_vault.to_account_info(),
+        };
+        CpiContext::new(self.token_program.to_account_info(), cpi_accounts)
+    }
+}
+
+// ---------- AttestWallet ----------
+#[derive(Accounts)]
+#[instruction(wallet: Pubkey)]
+pub struct AttestWallet<'info> {
+    #[account(seeds = [b"config"], bump)]
+    pub program_config: Account<'info, ProgramConfig>,
+    #[account(
+        init_if_needed,
+        payer = attestor,
+        space = 8 + std::mem::size_of::<Attestation>(),
+        seeds = [b"attestation", wallet.as_ref()],
+        bump
+    )]
+    pub attestation: Account<'info, Attestation>,
+    #[account(mut)]
+    pub attestor: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+// ---------- UpdateBlacklist ----------
+#[derive(Accounts)]
+#[instruction(wallet: Pubkey)]
+pub struct UpdateBlacklist<'info> {
+    #[account(has_one = admin @ ErrorCode::Unauthorized)]
+    pub presale_state: Account<'info, PresaleState>,
+    #[account(mut, seeds = [b"config"], bump)]
+    pub program_config: Account<'info, ProgramConfig>,
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = 8 + std::mem::size_of::<BlacklistEntry>(),
+        seeds = [b"blacklist", wallet.as_ref()],
+        bump
+    )]
+    pub blacklist_entry: Account<'info, BlacklistEntry>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+// ---------- RecoverAdmin ----------
+#[derive(Accounts)]
+pub struct RecoverAdmin<'info> {
+    #[account(mut, seeds = [b"config"], bump)]
+    pub program_config: Account<'info, ProgramConfig>,
+    #[account(mut)]
+    pub presale_state: Account<'info, PresaleState>,
+    pub recovery_key: Signer<'info>,
+}
+
+// ---------- InitializeGuardians ----------
+#[derive(Accounts)]
+pub struct InitializeGuardians<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + std::mem::size_of::<GuardianSet>(),
+        seeds = [b"guardians"],
+        bump
+    )]
+    pub guardian_set: Account<'info, GuardianSet>,
+    #[account(has_one = admin @ ErrorCode::Unauthorized)]
+    pub presale_state: Account<'info, PresaleState>,
+    #[account(mut, seeds = [b"config"], bump)]
+    pub program_config: Account<'info, ProgramConfig>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+// ---------- GuardianAction ----------
+#[derive(Accounts)]
+pub struct GuardianAction<'info> {
+    #[account(mut, seeds = [b"guardians"], bump)]
+    pub guardian_set: Account<'info, GuardianSet>,
+    pub guardian: Signer<'info>,
+}
+
+// ---------- SetGuardians ----------
+#[derive(Accounts)]
+pub struct SetGuardians<'info> {
+    #[account(mut, seeds = [b"guardians"], bump)]
+    pub guardian_set: Account<'info, GuardianSet>,
+    #[account(has_one = admin @ ErrorCode::Unauthorized)]
+    pub presale_state: Account<'info, PresaleState>,
+    #[account(mut, seeds = [b"config"], bump)]
+    pub program_config: Account<'info, ProgramConfig>,
+    pub admin: Signer<'info>,
+}
+
+// ---------- CancelAdminRotation ----------
+#[derive(Accounts)]
+pub struct CancelAdminRotation<'info> {
+    #[account(mut, seeds = [b"guardians"], bump)]
+    pub guardian_set: Account<'info, GuardianSet>,
+    pub presale_state: Account<'info, PresaleState>,
+    /// The admin or one of the guardians.
+    pub authority: Signer<'info>,
+}
+
+// ---------- ExecuteAdminRotation ----------
+#[derive(Accounts)]
+pub struct ExecuteAdminRotation<'info> {
+    #[account(mut, seeds = [b"guardians"], bump)]
+    pub guardian_set: Account<'info, GuardianSet>,
+    #[account(mut)]
+    pub presale_state: Account<'info, PresaleState>,
+    #[account(mut, seeds = [b"config"], bump)]
+    pub program_config: Account<'info, ProgramConfig>,
+}
+
+// ---------- EndPresale ----------
+#[derive(Accounts)]
+pub struct EndPresale<'info> {
+    #[account(mut, has_one = admin @ ErrorCode::Unauthorized)]
+    pub presale_state: Account<'info, PresaleState>,
+    #[account(mut, seeds = [b"config"], bump)]
+    pub program_config: Account<'info, ProgramConfig>,
+    pub global_state: Account<'info, GlobalState>,
+    pub presale_stage_info: AccountLoader<'info, PresaleStageInfo>,
+    /// CHECK: SOL vault PDA for launch liquidity; only its balance is read.
+    #[account(seeds = [b"liquidity_sol_vault"], bump)]
+    pub liquidity_sol_vault: AccountInfo<'info>,
+    /// CHECK: Launch liquidity token vault PDA; only read when a minimum is set.
+    #[account(seeds = [b"liquidity_token_vault"], bump)]
+    pub liquidity_token_vault: AccountInfo<'info>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+}
+
+impl<'info> EndPresale<'info> {
+    /// Fail unless the launch liquidity vaults hold `min_launch_liquidity_percent` of the SOL
+    /// raised, counting their tokens at the listing price.
+    pub fn check_launch_liquidity(&self) -> Result<()> {
+        let min_percent = self.global_state.min_launch_liquidity_percent;
+        if min_percent == 0 {
+            return Ok(());
+        }
+        let required = (self.presale_state.total_raised as u128) * (min_percent as u128) / 100;
+        let token_value = if self.liquidity_token_vault.data_is_empty() {
+            0
+        } else {
+            let vault = load_token_account(&self.liquidity_token_vault)?;
+            (vault.amount as u128) * (self.presale_stage_info.load()?.listing_price() as u128)
+                / PRICE_SCALE as u128
+        };
+        let committed = (self.liquidity_sol_vault.lamports() as u128) + token_value;
+        require!(committed >= required, ErrorCode::InsufficientLaunchLiquidity);
+        Ok(())
+    }
+}
+
+// ---------- FinalizeRound ----------
+#[derive(Accounts)]
+pub struct FinalizeRound<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + std::mem::size_of::<RoundReport>(),
+        seeds = [b"round_report", presale_state.key().as_ref()],
+        bump
+    )]
+    pub round_report: Account<'info, RoundReport>,
+    pub presale_state: Account<'info, PresaleState>,
+    pub presale_stage_info: AccountLoader<'info, PresaleStageInfo>,
+    #[account(seeds = [b"proposal", &proposal.id.to_le_bytes()], bump)]
+    pub proposal: Account<'info, Proposal>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+// ---------- AcceptPayment ----------
+/// This context includes accounts for both SOL and SPL branches.
+/// (Unused accounts for one branch can be ignored.)
+#[derive(Accounts)]
+pub struct AcceptPayment<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    // SPL token accounts
+    #[account(mut)]
+    pub payer_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut)]
+    pub treasury_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut)]
+    pub fee_wallet_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut)]
+    pub reward_pool_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut)]
+    pub mint: InterfaceAccount<'info, Mint>,
+    pub mint_authority: Signer<'info>,
+
+    // SOL accounts (for SOL payments)
+    /// CHECK: Treasury SOL account (must be a non‑executable wallet)
+    #[account(mut)]
+    pub treasury_sol_account: AccountInfo<'info>,
+    /// CHECK: Fee wallet SOL account (must be a non‑executable wallet)
+    #[account(mut)]
+    pub fee_wallet_sol_account: AccountInfo<'info>,
+    /// CHECK: Reward pool SOL account
+    #[account(mut)]
+    pub reward_pool_sol_account: AccountInfo<'info>,
+
+    // Global state (holds fee parameters and reward pool tracker)
+    #[account(mut)]
+    pub global_state: Account<'info, GlobalState>,
+
+    /// CHECK: `AcceptedMint` config of the payment mint; deserialized and validated in the SPL branch.
+    pub accepted_mint: AccountInfo<'info>,
+
+    // Anti-sybil check (see `ProgramConfig::check_purchase_attestation`)
+    #[account(seeds = [b"config"], bump)]
+    pub program_config: Account<'info, ProgramConfig>,
+    /// CHECK: Buyer's attestation PDA; only deserialized and validated when the check is enabled.
+    #[account(seeds = [b"attestation", payer.key().as_ref()], bump)]
+    pub attestation: AccountInfo<'info>,
+    /// CHECK: Payer's `StakerRecord` PDA; read for the staker fee discount if it exists.
+    #[account(seeds = [b"staker", payer.key().as_ref()], bump)]
+    pub staker_record: AccountInfo<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> AcceptPayment<'info> {
+    /// Validate the fee wallet, then split the payment between the treasury and the fee wallet.
+    pub fn process_payment(&self, amount: u64, token_mint: Pubkey) -> Result<()> {
+        self.program_config.check_not_paused(PAUSE_PURCHASES)?;
+        self.program_config.check_purchase_attestation(
+            self.payer.key(),
+            &self.attestation,
+            Clock::get()?.slot,
+        )?;
+
+        // Check that the fee wallet accounts belong to the configured fee wallet.
+        let fee_wallet_pubkey = self.program_config.fee_wallet;
+        require!(
+            *self.fee_wallet_sol_account.key == fee_wallet_pubkey,
+            ErrorCode::InvalidFeeWallet
+        );
+        require!(
+            self.fee_wallet_token_account.owner == fee_wallet_pubkey,
+            ErrorCode::InvalidFeeWallet
+        );
+
+        let fee = if token_mint == Pubkey::default() {
+            // SOL branch.
+            // Ensure the amount is greater than the flat fee of 3.
+            require!(amount > 3, ErrorCode::InvalidAmount);
+            let fee = self
+                .program_config
+                .discounted_fee(3, self.payer.key(), &self.staker_record);
+            let net_amount = amount.checked_sub(fee).unwrap();
+
+            // Transfer net_amount from payer to treasury (SOL)
+            let ix1 = system_instruction::transfer(
+                self.payer.key,
+                self.treasury_sol_account.key,
+                net_amount,
+            );
+            solana_program::program::invoke(
+                &ix1,
+                &[
+                    self.payer.to_account_info(),
+                    self.treasury_sol_account.clone(),
+                    self.system_program.to_account_info(),
+                ],
+            )?;
+
+            // Transfer fee from payer to fee wallet (SOL)
+            let ix2 = system_instruction::transfer(
+                self.payer.key,
+                self.fee_wallet_sol_account.key,
+                fee,
+            );
+            solana_program::program::invoke(
+                &ix2,
+                &[
+                    self.payer.to_account_info(),
+                    self.fee_wallet_sol_account.clone(),
+                    self.system_program.to_account_info(),
+                ],
+            )?;
+            fee
+        } else {
+            // SPL branch for any mint in the accepted-mint registry.
+            require!(
+                self.payer_token_account.mint == token_mint,
+                ErrorCode::InvalidTokenMint
+            );
+            require!(
+                self.payer_token_account.amount >= amount,
+                ErrorCode::InsufficientFunds
+            );
+            // The fee is configured per mint, in that mint's own decimals.
+            let accepted_mint: AcceptedMint = load_account(&self.accepted_mint)
+                .map_err(|_| ErrorCode::InvalidTokenMint)?;
+            require!(accepted_mint.mint == token_mint, ErrorCode::InvalidTokenMint);
+            require!(accepted_mint.enabled, ErrorCode::MintNotAccepted);
+            let fee = self.program_config.discounted_fee(
+                accepted_mint.fee_for(amount),
+                self.payer.key(),
+                &self.staker_record,
+            );
+            require!(amount > fee, ErrorCode::InvalidAmount);
+            let net_amount = amount.checked_sub(fee).unwrap();
+
+            // Transfer net_amount from payer to treasury (SPL)
+            token_interface::transfer_checked(
+                self.stake_transfer_context_generic(
+                    self.payer_token_account.to_account_info(),
+                    self.treasury_token_account.to_account_info(),
+                ),
+                net_amount,
+                self.mint.decimals,
+            )?;
+            // Transfer fee from payer to fee wallet (SPL)
+            token_interface::transfer_checked(
+                self.stake_transfer_context_generic(
+                    self.payer_token_account.to_account_info(),
+                    self.fee_wallet_token_account.to_account_info(),
+                ),
+                fee,
+                self.mint.decimals,
+            )?;
+            fee
+        };
+        emit!(PaymentAccepted {
+            payer: self.payer.key(),
+            token_mint,
+            amount,
+            fee,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// A generic transfer context used for SPL token transfers.
+    pub fn stake_transfer_context_generic(
+        &self,
+        from: AccountInfo<'info>,
+        to: AccountInfo<'info>,
+    ) -> CpiContext<'_, '_, '_, 'info, TransferChecked<'info>> {
+        let cpi_accounts = TransferChecked {
+            from,
+            mint: self.mint.to_account_info(),
+            to,
+            authority: self.payer.to_account_info(),
+        };
+        CpiContext::new(self.token_program.to_account_info(), cpi_accounts)
+    }
+}
+
+// ---------- AcceptGiftPayment ----------
+#[derive(Accounts)]
+#[instruction(lamports_in: u64, receipt_id: u64, beneficiary: Pubkey)]
+pub struct AcceptGiftPayment<'info> {
+    pub purchase: BuyPresale<'info>,
+    /// CHECK: Beneficiary's `PositionManager` PDA; updated only if it has been created.
+    #[account(mut, seeds = [b"position_manager", beneficiary.as_ref()], bump)]
+    pub beneficiary_position_manager: AccountInfo<'info>,
+}
+
+// ---------- BuyPresale ----------
+#[derive(Accounts)]
+#[instruction(lamports_in: u64, receipt_id: u64)]
+pub struct BuyPresale<'info> {
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+    /// CHECK: `BlacklistEntry` PDA of the buyer; checked only if it has been created.
+    #[account(seeds = [b"blacklist", buyer.key().as_ref()], bump)]
+    pub blacklist_entry: AccountInfo<'info>,
+    #[account(mut)]
+    pub presale_state: Account<'info, PresaleState>,
+    #[account(mut)]
+    pub presale_stage_info: AccountLoader<'info, PresaleStageInfo>,
+    #[account(mut, seeds = [b"presale_progress"], bump)]
+    pub presale_progress: Account<'info, PresaleProgress>,
+    #[account(
+        init,
+        payer = buyer,
+        space = 8 + std::mem::size_of::<PurchaseReceipt>(),
+        seeds = [b"receipt", buyer.key().as_ref(), &receipt_id.to_le_bytes()],
+        bump
+    )]
+    pub receipt: Account<'info, PurchaseReceipt>,
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        space = 8 + std::mem::size_of::<BuyerInfo>(),
+        seeds = [b"buyer", presale_state.key().as_ref(), buyer.key().as_ref()],
+        bump
+    )]
+    pub buyer_info: Account<'info, BuyerInfo>,
+    #[account(seeds = [b"config"], bump)]
+    pub program_config: Account<'info, ProgramConfig>,
+    /// CHECK: Buyer's attestation PDA; only deserialized and validated when the check is enabled.
+    #[account(seeds = [b"attestation", buyer.key().as_ref()], bump)]
+    pub attestation: AccountInfo<'info>,
+    /// CHECK: SOL escrow PDA holding presale payments until release or refund.
+    #[account(mut, seeds = [b"presale_vault"], bump)]
+    pub presale_vault: AccountInfo<'info>,
+    /// CHECK: Referrer's `ReferralAccount`; only deserialized and validated when a referrer
+    /// is given. Pass any writable account (e.g. the buyer) otherwise.
+    #[account(mut)]
+    pub referral_account: AccountInfo<'info>,
+    /// CHECK: `ReferralProgram` PDA; only deserialized when a referrer is given.
+    #[account(mut, seeds = [b"referral_program"], bump)]
+    pub referral_program: AccountInfo<'info>,
+    /// CHECK: Buyer's `PositionManager` PDA; updated only if it has been created.
+    #[account(mut, seeds = [b"position_manager", buyer.key().as_ref()], bump)]
+    pub position_manager: AccountInfo<'info>,
+    /// CHECK: SOL/USD price feed; checked against `ProgramConfig::sol_usd_price_feed` when set.
+    pub sol_usd_price_feed: AccountInfo<'info>,
+    /// CHECK: `Stats` PDA; updated only if it has been created.
+    #[account(mut, seeds = [b"stats"], bump)]
+    pub stats: AccountInfo<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+/// The accounts a presale purchase is checked and priced against, borrowed from `BuyPresale`
+/// for purchases and from `QuotePurchase` for simulations, so both run the same checks.
+pub struct PurchaseQuoter<'a, 'info> {
+    pub buyer: Pubkey,
+    pub buyer_info: &'a BuyerInfo,
+    pub blacklist_entry: &'a AccountInfo<'info>,
+    pub presale_state: &'a PresaleState,
+    pub presale_stage_info: &'a AccountLoader<'info, PresaleStageInfo>,
+    pub program_config: &'a ProgramConfig,
+    pub attestation: &'a AccountInfo<'info>,
+    pub sol_usd_price_feed: &'a AccountInfo<'info>,
+}
+
+impl<'a, 'info> PurchaseQuoter<'a, 'info> {
+    /// Run the purchase checks and price `amount_in` (see `PresaleStageInfo::quote_purchase`).
+    pub fn quote(
+        &self,
+        amount_in: u64,
+        payment_method: u8,
+        proof: &[[u8; 32]],
+    ) -> Result<PurchaseQuote> {
+        self.program_config.check_not_paused(PAUSE_PURCHASES)?;
+        BlacklistEntry::check(self.blacklist_entry)?;
+        require!(
+            self.presale_state.is_presale_active && self.presale_state.launch_time.is_none(),
+            ErrorCode::PresaleAlreadyEnded
+        );
+        require!(amount_in > 0, ErrorCode::InvalidAmount);
+        let clock = Clock::get()?;
+        require!(
+            clock.unix_timestamp >= self.program_config.presale_opens_at,
+            ErrorCode::PresaleNotOpen
+        );
+        require!(
+            self.buyer_info.purchases == 0 || self.buyer_info.last_purchase_slot != clock.slot,
+            ErrorCode::PurchaseRateLimited
+        );
+        self.program_config.check_purchase_attestation(
+            self.buyer,
+            self.attestation,
+            clock.slot,
+        )?;
+
+        let presale_stage_info = self.presale_stage_info.load()?;
+        let current_stage = presale_stage_info
+            .current_stage_index()
+            .ok_or(ErrorCode::PresaleSoldOut)?;
+        require!(
+            presale_stage_info.stages[current_stage].accepts(payment_method),
+            ErrorCode::PaymentMethodNotAllowed
+        );
+        let sol_usd_price = self
+            .program_config
+            .sol_usd_price(self.sol_usd_price_feed, clock.unix_timestamp)?;
+        let mut quote =
+            presale_stage_info.quote_purchase(amount_in, payment_method, sol_usd_price);
+        require!(quote.tokens_allocated > 0, ErrorCode::PresaleSoldOut);
+        let tax_bps = self.program_config.current_launch_tax_bps(clock.unix_timestamp);
+        quote.launch_tax = (quote.tokens_allocated as u128 * tax_bps as u128 / 10_000) as u64;
+        quote.tokens_allocated -= quote.launch_tax;
+
+        let whitelist_root = self.presale_state.whitelist_root;
+        if whitelist_root != [0u8; 32] && quote.first_stage <= WHITELIST_LAST_STAGE {
+            let leaf = keccak::hashv(&[self.buyer.as_ref()]).0;
+            require!(
+                verify_merkle_proof(proof, whitelist_root, leaf),
+                ErrorCode::NotWhitelisted
+            );
+        }
+        self.check_purchase_limits(&quote)?;
+        Ok(quote)
+    }
+
+    /// Enforce the hard cap, the minimum purchase and the per-wallet cap on `quote`. All three
+    /// are in lamports, so every payment method is valued via `PurchaseQuote::sol_value`.
+    pub fn check_purchase_limits(&self, quote: &PurchaseQuote) -> Result<()> {
+        let presale_state = self.presale_state;
+        let total_raised = presale_state.total_raised.checked_add(quote.sol_value).unwrap();
+        require!(
+            presale_state.hard_cap == 0 || total_raised <= presale_state.hard_cap,
+            ErrorCode::HardCapExceeded
+        );
+        require!(
+            quote.sol_value >= presale_state.min_purchase,
+            ErrorCode::PurchaseBelowMinimum
+        );
+        let contributed = self
+            .buyer_info
+            .contributed_sol_value()
+            .checked_add(quote.sol_value)
+            .unwrap();
+        require!(
+            presale_state.max_purchase_per_wallet == 0
+                || contributed <= presale_state.max_purchase_per_wallet,
+            ErrorCode::WalletContributionCapExceeded
+        );
+        Ok(())
+    }
+}
+
+impl<'info> BuyPresale<'info> {
+    /// Run the purchase checks and price `amount_in` (see `PurchaseQuoter::quote`).
+    pub fn quote_purchase(
+        &self,
+        amount_in: u64,
+        payment_method: u8,
+        proof: &[[u8; 32]],
+    ) -> Result<PurchaseQuote> {
+        PurchaseQuoter {
+            buyer: self.buyer.key(),
+            buyer_info: &self.buyer_info,
+            blacklist_entry: &self.blacklist_entry,
+            presale_state: &self.presale_state,
+            presale_stage_info: &self.presale_stage_info,
+            program_config: &self.program_config,
+            attestation: &self.attestation,
+            sol_usd_price_feed: &self.sol_usd_price_feed,
+        }
+        .quote(amount_in, payment_method, proof)
+    }
+
+    /// Escrow `lamports` of SOL from the buyer in the presale vault.
+    pub fn escrow_sol(&self, lamports: u64) -> Result<()> {
+        let ix = system_instruction::transfer(self.buyer.key, self.presale_vault.key, lamports);
+        solana_program::program::invoke(
+            &ix,
+            &[
+                self.buyer.to_account_info(),
+                self.presale_vault.to_account_info(),
+                self.system_program.to_account_info(),
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Returns the accounts a purchase is booked on (see `PurchaseBook::book`).
+    pub fn purchase_book(&mut self) -> PurchaseBook<'_, 'info> {
+        PurchaseBook {
+            buyer: self.buyer.key(),
+            presale_state: &mut self.presale_state,
+            presale_stage_info: &self.presale_stage_info,
+            presale_progress: &mut self.presale_progress,
+            buyer_info: &mut self.buyer_info,
+            program_config: &self.program_config,
+            referral_account: &self.referral_account,
+            referral_program: &self.referral_program,
+            stats: &self.stats,
+        }
+    }
+
+    /// Book a paid purchase (see `PurchaseBook::book`) and record it on the receipt. The
+    /// receipt's allocation belongs to `beneficiary`, which is the buyer except for gifts.
+    #[allow(clippy::too_many_arguments)]
+    pub fn record_purchase(
+        &mut self,
+        quote: &PurchaseQuote,
+        payment_method: u8,
+        receipt_id: u64,
+        token_mint: Pubkey,
+        amount_paid: u64,
+        beneficiary: Pubkey,
+        referrer: Option<Pubkey>,
+    ) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let receipt_key = self.receipt.key();
+        let vesting_preset = self.purchase_book().book(
+            quote,
+            payment_method,
+            receipt_key,
+            token_mint,
+            amount_paid,
+            referrer,
+        )?;
+
+        let receipt = &mut self.receipt;
+        receipt.payer = self.buyer.key();
+        receipt.beneficiary = beneficiary;
+        receipt.receipt_id = receipt_id;
+        receipt.token_mint = token_mint;
+        receipt.amount = amount_paid;
+        receipt.timestamp = now;
+        receipt.early_bird_bonus_claimed = false;
+        receipt.vesting_preset = vesting_preset;
+        receipt.vested = false;
+        receipt.tokens_claimed = false;
+        receipt.tokens_paid = 0;
+        receipt.record_allocation(quote);
+        if beneficiary == self.buyer.key() {
+            PositionManager::update(&self.position_manager, |manager| {
+                manager.add_receipt(receipt_key)
+            })?;
+        }
+        Ok(())
+    }
+}
+
+/// The accounts a paid purchase is booked on, borrowed from `BuyPresale` for purchases
+/// recorded on a receipt account and from `BuyPresaleCompressed` for compressed ones.
+pub struct PurchaseBook<'a, 'info> {
+    pub buyer: Pubkey,
+    pub presale_state: &'a mut PresaleState,
+    pub presale_stage_info: &'a AccountLoader<'info, PresaleStageInfo>,
+    pub presale_progress: &'a mut PresaleProgress,
+    pub buyer_info: &'a mut BuyerInfo,
+    pub program_config: &'a ProgramConfig,
+    pub referral_account: &'a AccountInfo<'info>,
+    pub referral_program: &'a AccountInfo<'info>,
+    pub stats: &'a AccountInfo<'info>,
+}
+
+impl<'a, 'info> PurchaseBook<'a, 'info> {
+    /// Book a paid purchase on the stages, the progress account, the presale totals, the
+    /// buyer's totals and the referrer's account, and emit `TokensPurchased` for `receipt`.
+    /// Returns the vesting terms of the stage the purchase started in.
+    pub fn book(
+        &mut self,
+        quote: &PurchaseQuote,
+        payment_method: u8,
+        receipt: Pubkey,
+        token_mint: Pubkey,
+        amount_paid: u64,
+        referrer: Option<Pubkey>,
+    ) -> Result<VestingPreset> {
+        let clock = Clock::get()?;
+        let now = clock.unix_timestamp;
+        let vesting_preset = {
+            let mut presale_stage_info = self.presale_stage_info.load_mut()?;
+            presale_stage_info.apply_purchase(quote, payment_method);
+            self.presale_progress.refresh(&presale_stage_info, now);
+            presale_stage_info.stages[(quote.first_stage - 1) as usize].vesting_preset()
+        };
+
+        let presale_state = &mut *self.presale_state;
+        presale_state.total_raised =
+            presale_state.total_raised.checked_add(quote.sol_value).unwrap();
+        if self.buyer_info.purchases == 0 {
+            presale_state.buyer_count = presale_state.buyer_count.checked_add(1).unwrap();
+        }
+        presale_state.purchase_count = presale_state.purchase_count.checked_add(1).unwrap();
+        presale_state.launch_tax_owed =
+            presale_state.launch_tax_owed.checked_add(quote.launch_tax).unwrap();
+        Stats::update(self.stats, now, |stats| {
+            if payment_method == PAYMENT_METHOD_STABLECOIN {
+                stats.total_usd_raised =
+                    stats.total_usd_raised.checked_add(quote.lamports_used).unwrap();
+            } else {
+                stats.total_sol_raised =
+                    stats.total_sol_raised.checked_add(quote.lamports_used).unwrap();
+            }
+        })?;
+        self.buyer_info
+            .record_purchase(self.buyer, quote, payment_method, now, clock.slot);
+
+        if let Some(referrer) = referrer {
+            require!(referrer != self.buyer, ErrorCode::InvalidReferrer);
+            let mut referral_account: ReferralAccount =
+                load_account(self.referral_account).map_err(|_| ErrorCode::InvalidReferrer)?;
+            require!(referral_account.referrer == referrer, ErrorCode::InvalidReferrer);
+            let mut referral_program: ReferralProgram =
+                load_account(self.referral_program)?;
+            let bonus_bps = referral_program.bonus_bps(
+                self.program_config.referral_bonus_bps,
+                referral_account.referred_lamports,
+            );
+            referral_account.record_referral(quote, payment_method, bonus_bps);
+            referral_program.update_leaderboard(referrer, referral_account.referred_lamports);
+            store_account(self.referral_account, &referral_account)?;
+            store_account(self.referral_program, &referral_program)?;
+        }
+        emit!(TokensPurchased {
+            buyer: self.buyer,
+            receipt,
+            payment_method,
+            token_mint,
+            amount_paid,
+            tokens_allocated: quote.tokens_allocated,
+            launch_tax: quote.launch_tax,
+            first_stage: quote.first_stage,
+            last_stage: quote.last_stage,
+            referrer,
+            timestamp: now,
+        });
+        Ok(vesting_preset)
+    }
+}
+
+// ---------- BuyPresaleCompressed ----------
+#[derive(Accounts)]
+pub struct BuyPresaleCompressed<'info> {
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+    /// CHECK: `BlacklistEntry` PDA of the buyer; checked only if it has been created.
+    #[account(seeds = [b"blacklist", buyer.key().as_ref()], bump)]
+    pub blacklist_entry: AccountInfo<'info>,
+    #[account(mut)]
+    pub presale_state: Account<'info, PresaleState>,
+    #[account(mut)]
+    pub presale_stage_info: AccountLoader<'info, PresaleStageInfo>,
+    #[account(mut, seeds = [b"presale_progress"], bump)]
+    pub presale_progress: Account<'info, PresaleProgress>,
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        space = 8 + std::mem::size_of::<BuyerInfo>(),
+        seeds = [b"buyer", presale_state.key().as_ref(), buyer.key().as_ref()],
+        bump
+    )]
+    pub buyer_info: Account<'info, BuyerInfo>,
+    #[account(seeds = [b"config"], bump)]
+    pub program_config: Account<'info, ProgramConfig>,
+    /// CHECK: Buyer's attestation PDA; only deserialized and validated when the check is enabled.
+    #[account(seeds = [b"attestation", buyer.key().as_ref()], bump)]
+    pub attestation: AccountInfo<'info>,
+    /// CHECK: SOL escrow PDA holding presale payments until release or refund.
+    #[account(mut, seeds = [b"presale_vault"], bump)]
+    pub presale_vault: AccountInfo<'info>,
+    /// CHECK: Referrer's `ReferralAccount`; only deserialized and validated when a referrer
+    /// is given. Pass any writable account (e.g. the buyer) otherwise.
+    #[account(mut)]
+    pub referral_account: AccountInfo<'info>,
+    /// CHECK: `ReferralProgram` PDA; only deserialized when a referrer is given.
+    #[account(mut, seeds = [b"referral_program"], bump)]
+    pub referral_program: AccountInfo<'info>,
+    /// CHECK: SOL/USD price feed; checked against `ProgramConfig::sol_usd_price_feed` when set.
+    pub sol_usd_price_feed: AccountInfo<'info>,
+    /// CHECK: `Stats` PDA; updated only if it has been created.
+    #[account(mut, seeds = [b"stats"], bump)]
+    pub stats: AccountInfo<'info>,
+    #[account(mut, seeds = [b"receipt_tree"], bump)]
+    pub receipt_tree: Account<'info, ReceiptTree>,
+    /// CHECK: The receipt tree's spl-account-compression account.
+    #[account(mut, address = receipt_tree.merkle_tree @ ErrorCode::InvalidReceiptTree)]
+    pub merkle_tree: AccountInfo<'info>,
+    pub compression_program: Program<'info, SplAccountCompression>,
+    pub noop_program: Program<'info, Noop>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> BuyPresaleCompressed<'info> {
+    /// Run the purchase checks and price `amount_in` (see `PurchaseQuoter::quote`).
+    pub fn quote_purchase(
+        &self,
+        amount_in: u64,
+        payment_method: u8,
+        proof: &[[u8; 32]],
+    ) -> Result<PurchaseQuote> {
+        PurchaseQuoter {
+            buyer: self.buyer.key(),
+            buyer_info: &self.buyer_info,
+            blacklist_entry: &self.blacklist_entry,
+            presale_state: &self.presale_state,
+            presale_stage_info: &self.presale_stage_info,
+            program_config: &self.program_config,
+            attestation: &self.attestation,
+            sol_usd_price_feed: &self.sol_usd_price_feed,
+        }
+        .quote(amount_in, payment_method, proof)
+    }
+
+    /// Escrow `lamports` of SOL from the buyer in the presale vault.
+    pub fn escrow_sol(&self, lamports: u64) -> Result<()> {
+        let ix = system_instruction::transfer(self.buyer.key, self.presale_vault.key, lamports);
+        solana_program::program::invoke(
+            &ix,
+            &[
+                self.buyer.to_account_info(),
+                self.presale_vault.to_account_info(),
+                self.system_program.to_account_info(),
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Returns the accounts a purchase is booked on (see `PurchaseBook::book`).
+    pub fn purchase_book(&mut self) -> PurchaseBook<'_, 'info> {
+        PurchaseBook {
+            buyer: self.buyer.key(),
+            presale_state: &mut self.presale_state,
+            presale_stage_info: &self.presale_stage_info,
+            presale_progress: &mut self.presale_progress,
+            buyer_info: &mut self.buyer_info,
+            program_config: &self.program_config,
+            referral_account: &self.referral_account,
+            referral_program: &self.referral_program,
+            stats: &self.stats,
+        }
+    }
+
+    /// Returns a CPI context for appending to the receipt tree.
+    pub fn modify_tree_context(
+        &self,
+    ) -> CpiContext<'_, '_, '_, 'info, spl_account_compression::cpi::accounts::Modify<'info>> {
+        let cpi_accounts = spl_account_compression::cpi::accounts::Modify {
+            merkle_tree: self.merkle_tree.to_account_info(),
+            authority: self.receipt_tree.to_account_info(),
+            noop: self.noop_program.to_account_info(),
+        };
+        CpiContext::new(self.compression_program.to_account_info(), cpi_accounts)
+    }
+}
+
+// ---------- BuyPresaleWithStablecoin ----------
+#[derive(Accounts)]
+#[instruction(amount: u64, receipt_id: u64)]
+pub struct BuyPresaleWithStablecoin<'info> {
+    pub purchase: BuyPresale<'info>,
+    #[account(seeds = [b"accepted_mint", buyer_token_account.mint.as_ref()], bump)]
+    pub accepted_mint: Account<'info, AcceptedMint>,
+    #[account(mut, constraint = buyer_token_account.owner == purchase.buyer.key())]
+    pub buyer_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, seeds = [b"stablecoin_vault", buyer_token_account.mint.as_ref()], bump)]
+    pub stablecoin_vault: InterfaceAccount<'info, TokenAccount>,
+    /// Mint of the stablecoin paid.
+    #[account(address = buyer_token_account.mint)]
+    pub stablecoin_mint: InterfaceAccount<'info, Mint>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+impl<'info> BuyPresaleWithStablecoin<'info> {
+    /// Returns a CPI context for paying the stablecoin into escrow.
+    pub fn stablecoin_transfer_context(
+        &self,
+    ) -> CpiContext<'_, '_, '_, 'info, TransferChecked<'info>> {
+        let cpi_accounts = TransferChecked {
+            from: self.buyer_token_account.to_account_info(),
+            mint: self.stablecoin_mint.to_account_info(),
+            to: self.stablecoin_vault.to_account_info(),
+            authority: self.purchase.buyer.to_account_info(),
+        };
+        CpiContext::new(self.token_program.to_account_info(), cpi_accounts)
+    }
+}
+
+// ---------- QuotePurchase ----------
+#[derive(Accounts)]
+pub struct QuotePurchase<'info> {
+    /// CHECK: Wallet the purchase is simulated for; need not sign.
+    pub buyer: AccountInfo<'info>,
+    /// CHECK: `BlacklistEntry` PDA of the buyer; checked only if it has been created.
+    #[account(seeds = [b"blacklist", buyer.key().as_ref()], bump)]
+    pub blacklist_entry: AccountInfo<'info>,
+    pub presale_state: Account<'info, PresaleState>,
+    pub presale_stage_info: AccountLoader<'info, PresaleStageInfo>,
+    /// CHECK: Buyer's `BuyerInfo` PDA; read if it exists, a first purchase is assumed otherwise.
+    #[account(seeds = [b"buyer", presale_state.key().as_ref(), buyer.key().as_ref()], bump)]
+    pub buyer_info: AccountInfo<'info>,
+    #[account(seeds = [b"config"], bump)]
+    pub program_config: Account<'info, ProgramConfig>,
+    /// CHECK: Buyer's attestation PDA; only deserialized and validated when the check is enabled.
+    #[account(seeds = [b"attestation", buyer.key().as_ref()], bump)]
+    pub attestation: AccountInfo<'info>,
+    /// CHECK: SOL/USD price feed; checked against `ProgramConfig::sol_usd_price_feed` when set.
+    pub sol_usd_price_feed: AccountInfo<'info>,
+}
+
+impl<'info> QuotePurchase<'info> {
+    /// Run the purchase checks and price `amount_in` exactly as `BuyPresale::quote_purchase`.
+    pub fn quote_purchase(
+        &self,
+        amount_in: u64,
+        payment_method: u8,
+        proof: &[[u8; 32]],
+    ) -> Result<PurchaseQuote> {
+        let buyer_info = if self.buyer_info.data_is_empty() {
+            BuyerInfo::default()
+        } else {
+            load_account::<BuyerInfo>(&self.buyer_info)?
+        };
+        PurchaseQuoter {
+            buyer: self.buyer.key(),
+            buyer_info: &buyer_info,
+            blacklist_entry: &self.blacklist_entry,
+            presale_state: &self.presale_state,
+            presale_stage_info: &self.presale_stage_info,
+            program_config: &self.program_config,
+            attestation: &self.attestation,
+            sol_usd_price_feed: &self.sol_usd_price_feed,
+        }
+        .quote(amount_in, payment_method, proof)
+    }
+}
+
+// ---------- QuoteStablecoinPurchase ----------
+#[derive(Accounts)]
+pub struct QuoteStablecoinPurchase<'info> {
+    pub quote: QuotePurchase<'info>,
+    #[account(seeds = [b"accepted_mint", accepted_mint.mint.as_ref()], bump)]
+    pub accepted_mint: Account<'info, AcceptedMint>,
+}
+
+// ---------- UpdatePresaleState ----------
+#[derive(Accounts)]
+pub struct UpdatePresaleState<'info> {
+    #[account(mut, has_one = admin @ ErrorCode::Unauthorized)]
+    pub presale_state: Account<'info, PresaleState>,
+    #[account(mut, seeds = [b"config"], bump)]
+    pub program_config: Account<'info, ProgramConfig>,
+    pub admin: Signer<'info>,
+}
+
+// ---------- AcceptAdmin ----------
+#[derive(Accounts)]
+pub struct AcceptAdmin<'info> {
+    #[account(
+        mut,
+        constraint = presale_state.pending_admin == new_admin.key() @ ErrorCode::Unauthorized
+    )]
+    pub presale_state: Account<'info, PresaleState>,
+    #[account(mut, seeds = [b"config"], bump)]
+    pub program_config: Account<'info, ProgramConfig>,
+    pub new_admin: Signer<'info>,
+}
+
+// ---------- SetRoles ----------
+#[derive(Accounts)]
+pub struct SetRoles<'info> {
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = 8 + std::mem::size_of::<Roles>(),
+        seeds = [b"roles"],
+        bump
+    )]
+    pub roles: Account<'info, Roles>,
+    #[account(has_one = admin @ ErrorCode::Unauthorized)]
+    pub presale_state: Account<'info, PresaleState>,
+    #[account(mut, seeds = [b"config"], bump)]
+    pub program_config: Account<'info, ProgramConfig>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+// ---------- ReleasePresaleFunds ----------
+#[derive(Accounts)]
+pub struct ReleasePresaleFunds<'info> {
+    #[account(has_one = admin @ ErrorCode::Unauthorized)]
+    pub presale_state: Account<'info, PresaleState>,
+    #[account(mut, seeds = [b"config"], bump)]
+    pub program_config: Account<'info, ProgramConfig>,
+    /// CHECK: SOL escrow PDA holding presale payments.
+    #[account(mut, seeds = [b"presale_vault"], bump)]
+    pub presale_vault: AccountInfo<'info>,
+    /// CHECK: Presale treasury; must match `program_config.presale_treasury`.
+    #[account(mut)]
+    pub treasury_sol_account: AccountInfo<'info>,
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+// ---------- InitializeLiquidityVault ----------
+#[derive(Accounts)]
+pub struct InitializeLiquidityVault<'info> {
+    /// Vault holding tokens for launch liquidity; the token account is its own authority.
+    #[account(
+        init,
+        payer = admin,
+        token::mint = mint,
+        token::authority = liquidity_token_vault,
+        seeds = [b"liquidity_token_vault"],
+        bump
+    )]
+    pub liquidity_token_vault: InterfaceAccount<'info, TokenAccount>,
+    #[account(constraint = mint.key() == program_config.token_mint @ ErrorCode::InvalidTokenMint)]
+    pub mint: InterfaceAccount<'info, Mint>,
+    #[account(has_one = admin @ ErrorCode::Unauthorized)]
+    pub presale_state: Account<'info, PresaleState>,
+    #[account(mut, seeds = [b"config"], bump)]
+    pub program_config: Account<'info, ProgramConfig>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+// ---------- ReleaseLaunchLiquidity ----------
+#[derive(Accounts)]
+pub struct ReleaseLaunchLiquidity<'info> {
+    #[account(has_one = admin @ ErrorCode::Unauthorized)]
+    pub presale_state: Account<'info, PresaleState>,
+    #[account(mut, seeds = [b"config"], bump)]
+    pub program_config: Account<'info, ProgramConfig>,
+    /// CHECK: SOL vault PDA for launch liquidity.
+    #[account(mut, seeds = [b"liquidity_sol_vault"], bump)]
+    pub liquidity_sol_vault: AccountInfo<'info>,
+    #[account(mut, seeds = [b"liquidity_token_vault"], bump)]
+    pub liquidity_token_vault: InterfaceAccount<'info, TokenAccount>,
+    /// The admin's token account receiving the tokens.
+    #[account(mut)]
+    pub admin_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    /// The $BRATS mint.
+    #[account(address = liquidity_token_vault.mint)]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> ReleaseLaunchLiquidity<'info> {
+    /// Returns a CPI context for releasing tokens from the launch liquidity vault.
+    pub fn release_transfer_context(
+        &self,
+    ) -> CpiContext<'_, '_, '_, 'info, TransferChecked<'info>> {
+        let cpi_accounts = TransferChecked {
+            from: self.liquidity_token_vault.to_account_info(),
+            mint: self.token_mint.to_account_info(),
+            to: self.admin_token_account.to_account_info(),
+            authority: self.liquidity_token_vault.to_account_info(),
+        };
+        CpiContext::new(self.token_program.to_account_info(), cpi_accounts)
+    }
+}
+
+// ---------- ReleaseStablecoinFunds ----------
+#[derive(Accounts)]
+pub struct ReleaseStablecoinFunds<'info> {
+    #[account(has_one = admin @ ErrorCode::Unauthorized)]
+    pub presale_state: Account<'info, PresaleState>,
+    #[account(mut, seeds = [b"config"], bump)]
+    pub program_config: Account<'info, ProgramConfig>,
+    #[account(mut, seeds = [b"stablecoin_vault", stablecoin_vault.mint.as_ref()], bump)]
+    pub stablecoin_vault: InterfaceAccount<'info, TokenAccount>,
+    /// Presale treasury's token account for the stablecoin.
+    #[account(mut, constraint = treasury_token_account.mint == stablecoin_vault.mint)]
+    pub treasury_token_account: InterfaceAccount<'info, TokenAccount>,
+    pub admin: Signer<'info>,
+    /// Mint of the escrowed stablecoin.
+    #[account(address = stablecoin_vault.mint)]
+    pub stablecoin_mint: InterfaceAccount<'info, Mint>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+impl<'info> ReleaseStablecoinFunds<'info> {
+    /// Returns a CPI context for releasing stablecoins from escrow to the treasury.
+    pub fn release_transfer_context(
+        &self,
+    ) -> CpiContext<'_, '_, '_, 'info, TransferChecked<'info>> {
+        let cpi_accounts = TransferChecked {
+            from: self.stablecoin_vault.to_account_info(),
+            mint: self.stablecoin_mint.to_account_info(),
+            to: self.treasury_token_account.to_account_info(),
+            authority: self.stablecoin_vault.to_account_info(),
+        };
+        CpiContext::new(self.token_program.to_account_info(), cpi_accounts)
+    }
+}
+
+// ---------- ClaimStablecoinRefund ----------
+#[derive(Accounts)]
+pub struct ClaimStablecoinRefund<'info> {
+    pub presale_state: Account<'info, PresaleState>,
+    #[account(mut)]
+    pub receipt: Account<'info, PurchaseReceipt>,
+    #[account(mut, seeds = [b"stablecoin_vault", stablecoin_vault.mint.as_ref()], bump)]
+    pub stablecoin_vault: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = payer_token_account.owner == payer.key(),
+        constraint = payer_token_account.mint == stablecoin_vault.mint
+    )]
+    pub payer_token_account: InterfaceAccount<'info, TokenAccount>,
+    pub payer: Signer<'info>,
+    /// Mint of the escrowed stablecoin.
+    #[account(address = stablecoin_vault.mint)]
+    pub stablecoin_mint: InterfaceAccount<'info, Mint>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+impl<'info> ClaimStablecoinRefund<'info> {
+    /// Returns a CPI context for refunding stablecoins from escrow to the payer.
+    pub fn refund_transfer_context(&self) -> CpiContext<'_, '_, '_, 'info, TransferChecked<'info>> {
+        let cpi_accounts = TransferChecked {
+            from: self.stablecoin_vault.to_account_info(),
+            mint: self.stablecoin_mint.to_account_info(),
+            to: self.payer_token_account.to_account_info(),
+            authority: self.stablecoin_vault.to_account_info(),
+        };
+        CpiContext::new(self.token_program.to_account_info(), cpi_accounts)
+    }
+}
+
+// ---------- ClaimRefund ----------
+#[derive(Accounts)]
+pub struct ClaimRefund<'info> {
+    pub presale_state: Account<'info, PresaleState>,
+    #[account(
+        mut,
+        seeds = [b"buyer", presale_state.key().as_ref(), buyer.key().as_ref()],
+        bump
+    )]
+    pub buyer_info: Account<'info, BuyerInfo>,
+    /// CHECK: SOL escrow PDA holding presale payments.
+    #[account(mut, seeds = [b"presale_vault"], bump)]
+    pub presale_vault: AccountInfo<'info>,
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+// ---------- AdjustReceipt ----------
+#[derive(Accounts)]
+pub struct AdjustReceipt<'info> {
+    #[account(mut)]
+    pub receipt: Account<'info, PurchaseReceipt>,
+    /// Aggregate of the wallet that made the purchase.
+    #[account(
+        mut,
+        seeds = [b"buyer", presale_state.key().as_ref(), receipt.payer.as_ref()],
+        bump
+    )]
+    pub buyer_info: Account<'info, BuyerInfo>,
+    #[account(has_one = admin @ ErrorCode::Unauthorized)]
+    pub presale_state: Account<'info, PresaleState>,
+    #[account(mut, seeds = [b"presale_stage_info"], bump)]
+    pub presale_stage_info: AccountLoader<'info, PresaleStageInfo>,
+    #[account(mut, seeds = [b"presale_progress"], bump)]
+    pub presale_progress: Account<'info, PresaleProgress>,
+    #[account(mut, seeds = [b"config"], bump)]
+    pub program_config: Account<'info, ProgramConfig>,
+    pub admin: Signer<'info>,
+    pub beneficiary: Signer<'info>,
+}
+
+// ---------- TransferAllocation ----------
+#[derive(Accounts)]
+pub struct TransferAllocation<'info> {
+    #[account(mut)]
+    pub receipt: Account<'info, PurchaseReceipt>,
+    pub presale_state: Account<'info, PresaleState>,
+    #[account(seeds = [b"config"], bump)]
+    pub program_config: Account<'info, ProgramConfig>,
+    pub beneficiary: Signer<'info>,
+    #[account(mut)]
+    pub new_beneficiary: Signer<'info>,
+    /// CHECK: Fee wallet SOL account; must match `ProgramConfig::fee_wallet`.
+    #[account(mut)]
+    pub fee_wallet_sol_account: AccountInfo<'info>,
+    /// CHECK: `BlacklistEntry` PDA of the beneficiary; checked only if it has been created.
+    #[account(seeds = [b"blacklist", beneficiary.key().as_ref()], bump)]
+    pub blacklist_entry: AccountInfo<'info>,
+    /// CHECK: `BlacklistEntry` PDA of the new beneficiary; checked only if it has been created.
+    #[account(seeds = [b"blacklist", new_beneficiary.key().as_ref()], bump)]
+    pub new_blacklist_entry: AccountInfo<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+// ---------- InitializeReferralPool ----------
+#[derive(Accounts)]
+pub struct InitializeReferralPool<'info> {
+    /// Referral bonus pool; the token account is its own authority.
+    #[account(
+        init,
+        payer = admin,
+        token::mint = mint,
+        token::authority = referral_pool,
+        seeds = [b"referral_pool"],
+        bump
+    )]
+    pub referral_pool: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + std::mem::size_of::<ReferralProgram>(),
+        seeds = [b"referral_program"],
+        bump
+    )]
+    pub referral_program: Account<'info, ReferralProgram>,
+    pub mint: InterfaceAccount<'info, Mint>,
+    #[account(has_one = admin @ ErrorCode::Unauthorized)]
+    pub presale_state: Account<'info, PresaleState>,
+    #[account(mut, seeds = [b"config"], bump)]
+    pub program_config: Account<'info, ProgramConfig>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+// ---------- SetReferralTiers ----------
+#[derive(Accounts)]
+pub struct SetReferralTiers<'info> {
+    #[account(mut, seeds = [b"referral_program"], bump)]
+    pub referral_program: Account<'info, ReferralProgram>,
+    #[account(has_one = admin @ ErrorCode::Unauthorized)]
+    pub presale_state: Account<'info, PresaleState>,
+    #[account(mut, seeds = [b"config"], bump)]
+    pub program_config: Account<'info, ProgramConfig>,
+    pub admin: Signer<'info>,
+}
+
+// ---------- RegisterReferrer ----------
+#[derive(Accounts)]
+pub struct RegisterReferrer<'info> {
+    #[account(
+        init,
+        payer = referrer,
+        space = 8 + std::mem::size_of::<ReferralAccount>(),
+        seeds = [b"referral", referrer.key().as_ref()],
+        bump
+    )]
+    pub referral_account: Account<'info, ReferralAccount>,
+    #[account(mut)]
+    pub referrer: Signer<'info>,
+    /// CHECK: Referrer's `PositionManager` PDA; updated only if it has been created.
+    #[account(mut, seeds = [b"position_manager", referrer.key().as_ref()], bump)]
+    pub position_manager: AccountInfo<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+// ---------- ClaimReferralRewards ----------
+#[derive(Accounts)]
+pub struct ClaimReferralRewards<'info> {
+    #[account(mut, seeds = [b"referral", referrer.key().as_ref()], bump)]
+    pub referral_account: Account<'info, ReferralAccount>,
+    #[account(seeds = [b"config"], bump)]
+    pub program_config: Account<'info, ProgramConfig>,
+    #[account(mut, seeds = [b"referral_pool"], bump)]
+    pub referral_pool: InterfaceAccount<'info, TokenAccount>,
+    pub referrer: Signer<'info>,
+    /// CHECK: `BlacklistEntry` PDA of the referrer; checked only if it has been created.
+    #[account(seeds = [b"blacklist", referrer.key().as_ref()], bump)]
+    pub blacklist_entry: AccountInfo<'info>,
+    /// The referrer's token account receiving the bonus.
+    #[account(mut)]
+    pub referrer_token_account: InterfaceAccount<'info, TokenAccount>,
+    /// CHECK: `Stats` PDA; updated only if it has been created.
+    #[account(mut, seeds = [b"stats"], bump)]
+    pub stats: AccountInfo<'info>,
+    /// The $BRATS mint.
+    #[account(address = referral_pool.mint)]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+impl<'info> ClaimReferralRewards<'info> {
+    /// Mark the referrer's unclaimed bonus as claimed and return it.
+    pub fn take_rewards(&mut self) -> Result<u64> {
+        self.program_config.check_not_paused(PAUSE_CLAIMS)?;
+        BlacklistEntry::check(&self.blacklist_entry)?;
+        let referral_account = &mut self.referral_account;
+        let amount = referral_account
+            .rewards_accrued
+            .checked_sub(referral_account.rewards_claimed)
+            .unwrap();
+        require!(amount > 0, ErrorCode::NoRewardsAvailable);
+        require!(
+            self.referral_pool.amount >= amount,
+            ErrorCode::InsufficientRewards
+        );
+        referral_account.rewards_claimed = referral_account.rewards_accrued;
+        Stats::update(&self.stats, Clock::get()?.unix_timestamp, |stats| {
+            stats.total_rewards_distributed =
+                stats.total_rewards_distributed.checked_add(amount).unwrap()
+        })?;
+        Ok(amount)
+    }
+
+    /// Returns a CPI context for paying the bonus out of the referral pool.
+    pub fn referral_transfer_context(
+        &self,
+    ) -> CpiContext<'_, '_, '_, 'info, TransferChecked<'info>> {
+        let cpi_accounts = TransferChecked {
+            from: self.referral_pool.to_account_info(),
+            mint: self.token_mint.to_account_info(),
+            to: self.referrer_token_account.to_account_info(),
+            authority: self.referral_pool.to_account_info(),
+        };
+        CpiContext::new(self.token_program.to_account_info(), cpi_accounts)
+    }
+}
+
+// ---------- ClaimReferralRewardsStaked ----------
+#[derive(Accounts)]
+pub struct ClaimReferralRewardsStaked<'info> {
+    pub claim: ClaimReferralRewards<'info>,
+    pub bonus_stake: BonusStake<'info>,
+}
+
+impl<'info> ClaimReferralRewardsStaked<'info> {
+    /// Returns a CPI context for moving the bonus from the referral pool into the stake vault.
+    pub fn stake_transfer_context(
+        &self,
+    ) -> CpiContext<'_, '_, '_, 'info, TransferChecked<'info>> {
+        let cpi_accounts = TransferChecked {
+            from: self.claim.referral_pool.to_account_info(),
+            mint: self.claim.token_mint.to_account_info(),
+            to: self.bonus_stake.stake_vault.to_account_info(),
+            authority: self.claim.referral_pool.to_account_info(),
+        };
+        CpiContext::new(self.claim.token_program.to_account_info(), cpi_accounts)
+    }
+}
+
+// ---------- CreateInvoice ----------
+#[derive(Accounts)]
+#[instruction(invoice_id: u64)]
+pub struct CreateInvoice<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + std::mem::size_of::<Invoice>(),
+        seeds = [b"invoice".as_ref(), &invoice_id.to_le_bytes()],
+        bump
+    )]
+    pub invoice: Account<'info, Invoice>,
+    #[account(has_one = admin @ ErrorCode::Unauthorized)]
+    pub presale_state: Account<'info, PresaleState>,
+    #[account(mut, seeds = [b"config"], bump)]
+    pub program_config: Account<'info, ProgramConfig>,
+    /// CHECK: Treasury SOL account that will receive the invoice payment.
+    pub treasury_sol_account: AccountInfo<'info>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+// ---------- PayInvoice ----------
+#[derive(Accounts)]
+pub struct PayInvoice<'info> {
+    #[account(mut)]
+    pub invoice: Account<'info, Invoice>,
+    #[account(seeds = [b"config"], bump)]
+    pub program_config: Account<'info, ProgramConfig>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    /// CHECK: Treasury SOL account; must match the one recorded on the invoice.
+    #[account(mut)]
+    pub treasury_sol_account: AccountInfo<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+// ---------- AddAcceptedMint ----------
+#[derive(Accounts)]
+pub struct AddAcceptedMint<'info> {
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = 8 + std::mem::size_of::<AcceptedMint>(),
+        seeds = [b"accepted_mint", mint.key().as_ref()],
+        bump
+    )]
+    pub accepted_mint: Account<'info, AcceptedMint>,
+    pub mint: InterfaceAccount<'info, Mint>,
+    /// Escrow for stablecoin presale payments in this mint.
+    #[account(
+        init_if_needed,
+        payer = admin,
+        token::mint = mint,
+        token::authority = stablecoin_vault,
+        seeds = [b"stablecoin_vault", mint.key().as_ref()],
+        bump
+    )]
+    pub stablecoin_vault: InterfaceAccount<'info, TokenAccount>,
+    #[account(has_one = admin @ ErrorCode::Unauthorized)]
+    pub presale_state: Account<'info, PresaleState>,
+    #[account(mut, seeds = [b"config"], bump)]
+    pub program_config: Account<'info, ProgramConfig>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+// ---------- RemoveAcceptedMint ----------
+#[derive(Accounts)]
+pub struct RemoveAcceptedMint<'info> {
+    #[account(mut, seeds = [b"accepted_mint", accepted_mint.mint.as_ref()], bump)]
+    pub accepted_mint: Account<'info, AcceptedMint>,
+    #[account(has_one = admin @ ErrorCode::Unauthorized)]
+    pub presale_state: Account<'info, PresaleState>,
+    #[account(mut, seeds = [b"config"], bump)]
+    pub program_config: Account<'info, ProgramConfig>,
+    pub admin: Signer<'info>,
+}
+
+// ---------- DepositSol ----------
+#[derive(Accounts)]
+pub struct DepositSol<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    /// CHECK: Treasury SOL account where the deposit will be transferred; must match
+    /// `program_config.presale_treasury`.
+    #[account(
+        mut,
+        constraint = treasury_sol_account.key() == program_config.presale_treasury
+            @ ErrorCode::InvalidTreasury
+    )]
+    pub treasury_sol_account: AccountInfo<'info>,
+    #[account(seeds = [b"config"], bump)]
+    pub program_config: Account<'info, ProgramConfig>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + std::mem::size_of::<DepositLog>(),
+        seeds = [b"deposit_log"],
+        bump
+    )]
+    pub deposit_log: Account<'info, DepositLog>,
+    pub system_program: Program<'info, System>,
+}
+
+// ---------- StakeTokens ----------
+#[derive(Accounts)]
+pub struct StakeTokens<'info> {
+    /// The wallet's primary position, created on its first stake.
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + std::mem::size_of::<StakeInfo>(),
+        seeds = [b"stake", payer.key().as_ref()],
+        bump
+    )]
+    pub stake_info: Account<'info, StakeInfo>,
+    #[account(seeds = [b"config"], bump)]
+    pub program_config: Account<'info, ProgramConfig>,
+    #[account(mut)]
+    pub global_state: Account<'info, GlobalState>,
+    #[account(mut)]
+    pub presale_state: Account<'info, PresaleState>,
+    /// Per-wallet staking record, created on the wallet's first stake.
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + std::mem::size_of::<StakerRecord>(),
+        seeds = [b"staker", payer.key().as_ref()],
+        bump
+    )]
+    pub staker_record: Account<'info, StakerRecord>,
+    #[account(seeds = [b"staking_config"], bump)]
+    pub staking_config: Account<'info, StakingConfig>,
+    #[account(seeds = [b"param_ramp"], bump)]
+    pub param_ramp: Account<'info, ParamRamp>,
+    #[account(seeds = [b"emission_schedule"], bump)]
+    pub emission_schedule: Account<'info, EmissionSchedule>,
+    /// CHECK: `PartnerRewardPool` PDA; settled only once partner rewards are initialized.
+    #[account(mut, seeds = [b"partner_rewards"], bump)]
+    pub partner_reward_pool: AccountInfo<'info>,
+    /// CHECK: `StakeMigration` PDA; checked only once a migration is approved.
+    #[account(seeds = [b"stake_migration"], bump)]
+    pub stake_migration: AccountInfo<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    /// CHECK: `BlacklistEntry` PDA of the payer; checked only if it has been created.
+    #[account(seeds = [b"blacklist", payer.key().as_ref()], bump)]
+    pub blacklist_entry: AccountInfo<'info>,
+    /// The user's token account (source).
+    #[account(mut)]
+    pub user_token_account: InterfaceAccount<'info, TokenAccount>,
+    /// The stake vault for the staked mint (destination).
+    #[account(mut, seeds = [b"stake_vault", user_token_account.mint.as_ref()], bump)]
+    pub stake_vault: InterfaceAccount<'info, TokenAccount>,
+    /// CHECK: Payer's `PositionManager` PDA; updated only if it has been created.
+    #[account(mut, seeds = [b"position_manager", payer.key().as_ref()], bump)]
+    pub position_manager: AccountInfo<'info>,
+    /// CHECK: `StakingLeaderboard` PDA; updated only if it has been created.
+    #[account(mut, seeds = [b"staking_leaderboard"], bump)]
+    pub staking_leaderboard: AccountInfo<'info>,
+    /// CHECK: `Stats` PDA; updated only if it has been created.
+    #[account(mut, seeds = [b"stats"], bump)]
+    pub stats: AccountInfo<'info>,
+    /// Mint of the staked tokens.
+    #[account(address = stake_vault.mint)]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> StakeTokens<'info> {
+    /// Returns a CPI context for transferring tokens from the user to the stake vault.
+    pub fn stake_transfer_context(
+        &self,
+    ) -> CpiContext<'_, '_, '_, 'info, TransferChecked<'info>> {
+        let cpi_accounts = TransferChecked {
+            from: self.user_token_account.to_account_info(),
+            mint: self.token_mint.to_account_info(),
+            to: self.stake_vault.to_account_info(),
+            authority: self.payer.to_account_info(),
+        };
+        CpiContext::new(self.token_program.to_account_info(), cpi_accounts)
+    }
+}
+
+// ---------- RequestUnstake ----------
+#[derive(Accounts)]
+pub struct RequestUnstake<'info> {
+    #[account(mut, constraint = stake_info.owner == payer.key() @ ErrorCode::Unauthorized)]
+    pub stake_info: Account<'info, StakeInfo>,
+    #[account(mut)]
+    pub global_state: Account<'info, GlobalState>,
+    #[account(mut)]
+    pub presale_state: Account<'info, PresaleState>,
+    #[account(mut, seeds = [b"staker", payer.key().as_ref()], bump)]
+    pub staker_record: Account<'info, StakerRecord>,
+    #[account(seeds = [b"config"], bump)]
+    pub program_config: Account<'info, ProgramConfig>,
+    #[account(mut, seeds = [b"staking_config"], bump)]
+    pub staking_config: Account<'info, StakingConfig>,
+    #[account(seeds = [b"param_ramp"], bump)]
+    pub param_ramp: Account<'info, ParamRamp>,
+    #[account(seeds = [b"emission_schedule"], bump)]
+    pub emission_schedule: Account<'info, EmissionSchedule>,
+    /// CHECK: `PartnerRewardPool` PDA; settled only once partner rewards are initialized.
+    #[account(mut, seeds = [b"partner_rewards"], bump)]
+    pub partner_reward_pool: AccountInfo<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    /// CHECK: `BlacklistEntry` PDA of the payer; checked only if it has been created.
+    #[account(seeds = [b"blacklist", payer.key().as_ref()], bump)]
+    pub blacklist_entry: AccountInfo<'info>,
+    /// The stake vault for the position's mint (source for penalties).
+    #[account(mut, seeds = [b"stake_vault", stake_info.mint.as_ref()], bump)]
+    pub stake_vault: InterfaceAccount<'info, TokenAccount>,
+    /// The reward vault (source for rewards paid out with a completed unstake, destination for
+    /// redistributed penalties).
+    #[account(mut, seeds = [b"reward_vault"], bump)]
+    pub reward_vault: InterfaceAccount<'info, TokenAccount>,
+    /// The user's token account (destination for rewards).
+    #[account(mut)]
+    pub user_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, constraint = mint.key() == stake_info.mint)]
+    pub mint: InterfaceAccount<'info, Mint>,
+    /// CHECK: Owner's `PositionManager` PDA; updated only if it has been created.
+    #[account(mut, seeds = [b"position_manager", stake_info.owner.as_ref()], bump)]
+    pub position_manager: AccountInfo<'info>,
+    /// CHECK: `StakingLeaderboard` PDA; updated only if it has been created.
+    #[account(mut, seeds = [b"staking_leaderboard"], bump)]
+    pub staking_leaderboard: AccountInfo<'info>,
+    /// CHECK: `Stats` PDA; updated only if it has been created.
+    #[account(mut, seeds = [b"stats"], bump)]
+    pub stats: AccountInfo<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+impl<'info> RequestUnstake<'info> {
+    /// Returns a CPI context for paying rewards from the reward vault to the user.
+    pub fn reward_transfer_context(
+        &self,
+    ) -> CpiContext<'_, '_, '_, 'info, TransferChecked<'info>> {
+        let cpi_accounts = TransferChecked {
+            from: self.reward_vault.to_account_info(),
+            mint: self.mint.to_account_info(),
+            to: self.user_token_account.to_account_info(),
+            authority: self.reward_vault.to_account_info(),
+        };
+        CpiContext::new(self.token_program.to_account_info(), cpi_accounts)
+    }
+    /// Returns a CPI context for moving redistributed penalty tokens into the reward vault.
+    pub fn penalty_redistribution_context(
+        &self,
+    ) -> CpiContext<'_, '_, '_, 'info, TransferChecked<'info>> {
+        let cpi_accounts = TransferChecked {
+            from: self.stake_vault.to_account_info(),
+            mint: self.mint.to_account_info(),
+            to: self.reward_vault.to_account_info(),
+            authority: self.stake_vault.to_account_info(),
+        };
+        CpiContext::new(self.token_program.to_account_info(), cpi_accounts)
+    }
+    /// Returns a CPI context for burning tokens from the stake vault (penalty).
+    pub fn early_unstake_burn_context(
+        &self,
+    ) -> CpiContext<'_, '_, '_, 'info, Burn<'info>> {
+        let cpi_accounts = Burn {
+            mint: self.mint.to_account_info(),
+            from: self.stake_vault.to_account_info(),
+            authority: self.stake_vault.to_account_info(),
+        };
+        CpiContext::new(self.token_program.to_account_info(), cpi_accounts)
+    }
+}
+
+// ---------- WithdrawUnstaked ----------
+#[derive(Accounts)]
+pub struct WithdrawUnstaked<'info> {
+    #[account(mut)]
+    pub stake_info: Account<'info, StakeInfo>,
+    #[account(seeds = [b"config"], bump)]
+    pub program_config: Account<'info, ProgramConfig>,
+    /// The stake vault for the position's mint (source).
+    #[account(mut, seeds = [b"stake_vault", stake_info.mint.as_ref()], bump)]
+    pub stake_vault: InterfaceAccount<'info, TokenAccount>,
+    /// The owner's token account (destination).
+    #[account(mut)]
+    pub user_token_account: InterfaceAccount<'info, TokenAccount>,
+    pub owner: Signer<'info>,
+    /// Mint of the staked tokens.
+    #[account(address = stake_vault.mint)]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+impl<'info> WithdrawUnstaked<'info> {
+    /// Returns a CPI context for transferring tokens from the stake vault back to the user.
+    pub fn withdraw_transfer_context(
+        &self,
+    ) -> CpiContext<'_, '_, '_, 'info, TransferChecked<'info>> {
+        let cpi_accounts = TransferChecked {
+            from: self.stake_vault.to_account_info(),
+            mint: self.token_mint.to_account_info(),
+            to: self.user_token_account.to_account_info(),
+            authority: self.stake_vault.to_account_info(),
+        };
+        CpiContext::new(self.token_program.to_account_info(), cpi_accounts)
+    }
+}
+
+// ---------- QueueUnstake ----------
+#[derive(Accounts)]
+pub struct QueueUnstake<'info> {
+    #[account(mut)]
+    pub stake_info: Account<'info, StakeInfo>,
+    pub global_state: Account<'info, GlobalState>,
+    #[account(mut, seeds = [b"staking_config"], bump)]
+    pub staking_config: Account<'info, StakingConfig>,
+    pub owner: Signer<'info>,
+}
+
+// ---------- TransferStakePosition ----------
+#[derive(Accounts)]
+#[instruction(new_owner: Pubkey)]
+pub struct TransferStakePosition<'info> {
+    #[account(mut, constraint = stake_info.owner == owner.key() @ ErrorCode::Unauthorized)]
+    pub stake_info: Account<'info, StakeInfo>,
+    #[account(mut)]
+    pub global_state: Account<'info, GlobalState>,
+    #[account(mut, seeds = [b"staker", owner.key().as_ref()], bump)]
+    pub staker_record: Account<'info, StakerRecord>,
+    /// The new owner's staking record, created if this is their first position.
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = 8 + std::mem::size_of::<StakerRecord>(),
+        seeds = [b"staker", new_owner.as_ref()],
+        bump,
+        constraint = new_owner != owner.key() @ ErrorCode::InvalidBeneficiary
+    )]
+    pub new_staker_record: Account<'info, StakerRecord>,
+    #[account(seeds = [b"config"], bump)]
+    pub program_config: Account<'info, ProgramConfig>,
+    #[account(seeds = [b"staking_config"], bump)]
+    pub staking_config: Account<'info, StakingConfig>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    /// CHECK: Owner's `PositionManager` PDA; updated only if it has been created.
+    #[account(mut, seeds = [b"position_manager", owner.key().as_ref()], bump)]
+    pub position_manager: AccountInfo<'info>,
+    /// CHECK: New owner's `PositionManager` PDA; updated only if it has been created.
+    #[account(mut, seeds = [b"position_manager", new_owner.as_ref()], bump)]
+    pub new_position_manager: AccountInfo<'info>,
+    /// CHECK: `StakingLeaderboard` PDA; updated only if it has been created.
+    #[account(mut, seeds = [b"staking_leaderboard"], bump)]
+    pub staking_leaderboard: AccountInfo<'info>,
+    /// CHECK: `Stats` PDA; updated only if it has been created.
+    #[account(mut, seeds = [b"stats"], bump)]
+    pub stats: AccountInfo<'info>,
+    /// CHECK: `BlacklistEntry` PDA of the owner; checked only if it has been created.
+    #[account(seeds = [b"blacklist", owner.key().as_ref()], bump)]
+    pub blacklist_entry: AccountInfo<'info>,
+    /// CHECK: `BlacklistEntry` PDA of the new owner; checked only if it has been created.
+    #[account(seeds = [b"blacklist", new_owner.as_ref()], bump)]
+    pub new_blacklist_entry: AccountInfo<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+// ---------- InitializePositionManager ----------
+#[derive(Accounts)]
+pub struct InitializePositionManager<'info> {
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + std::mem::size_of::<PositionManager>(),
+        seeds = [b"position_manager", owner.key().as_ref()],
+        bump
+    )]
+    pub position_manager: Account<'info, PositionManager>,
+    /// CHECK: Owner's `VestingSchedule` PDA; listed if it exists.
+    #[account(seeds = [b"vesting", owner.key().as_ref()], bump)]
+    pub vesting_schedule: AccountInfo<'info>,
+    /// CHECK: Owner's `ReferralAccount` PDA; listed if it exists.
+    #[account(seeds = [b"referral", owner.key().as_ref()], bump)]
+    pub referral_account: AccountInfo<'info>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+// ---------- EmergencyUnstake ----------
+#[derive(Accounts)]
+pub struct EmergencyUnstake<'info> {
+    #[account(mut)]
+    pub stake_info: Account<'info, StakeInfo>,
+    #[account(mut)]
+    pub global_state: Account<'info, GlobalState>,
+    #[account(mut, seeds = [b"staker", payer.key().as_ref()], bump)]
+    pub staker_record: Account<'info, StakerRecord>,
+    #[account(seeds = [b"config"], bump)]
+    pub program_config: Account<'info, ProgramConfig>,
+    #[account(mut, seeds = [b"staking_config"], bump)]
+    pub staking_config: Account<'info, StakingConfig>,
+    #[account(seeds = [b"param_ramp"], bump)]
+    pub param_ramp: Account<'info, ParamRamp>,
+    #[account(seeds = [b"emission_schedule"], bump)]
+    pub emission_schedule: Account<'info, EmissionSchedule>,
+    /// CHECK: `PartnerRewardPool` PDA; settled only once partner rewards are initialized.
+    #[account(mut, seeds = [b"partner_rewards"], bump)]
+    pub partner_reward_pool: AccountInfo<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    /// The stake vault for the position's mint (source).
+    #[account(mut, seeds = [b"stake_vault", stake_info.mint.as_ref()], bump)]
+    pub stake_vault: InterfaceAccount<'info, TokenAccount>,
+    /// The user's token account (destination).
+    #[account(mut)]
+    pub user_token_account: InterfaceAccount<'info, TokenAccount>,
+    /// CHECK: Owner's `PositionManager` PDA; updated only if it has been created.
+    #[account(mut, seeds = [b"position_manager", stake_info.owner.as_ref()], bump)]
+    pub position_manager: AccountInfo<'info>,
+    /// CHECK: `StakingLeaderboard` PDA; updated only if it has been created.
+    #[account(mut, seeds = [b"staking_leaderboard"], bump)]
+    pub staking_leaderboard: AccountInfo<'info>,
+    /// Mint of the staked tokens.
+    #[account(address = stake_vault.mint)]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+impl<'info> EmergencyUnstake<'info> {
+    /// Returns a CPI context for returning the principal from the stake vault.
+    pub fn emergency_transfer_context(
+        &self,
+    ) -> CpiContext<'_, '_, '_, 'info, TransferChecked<'info>> {
+        let cpi_accounts = TransferChecked {
+            from: self.stake_vault.to_account_info(),
+            mint: self.token_mint.to_account_info(),
+            to: self.user_token_account.to_account_info(),
+            authority: self.stake_vault.to_account_info(),
+        };
+        CpiContext::new(self.token_program.to_account_info(), cpi_accounts)
+    }
+}
+
+// ---------- SplitPosition ----------
+#[derive(Accounts)]
+#[instruction(amount: u64, new_position_id: u64)]
+pub struct SplitPosition<'info> {
+    /// The position being split.
+    #[account(mut)]
+    pub stake_info: Account<'info, StakeInfo>,
+    /// The new position, derived from the owner and the new position id.
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + std::mem::size_of::<StakeInfo>(),
+        seeds = [b"stake", owner.key().as_ref(), &new_position_id.to_le_bytes()],
+        bump
+    )]
+    pub new_stake_info: Account<'info, StakeInfo>,
+    #[account(mut)]
+    pub global_state: Account<'info, GlobalState>,
+    #[account(mut, seeds = [b"staker", owner.key().as_ref()], bump)]
+    pub staker_record: Account<'info, StakerRecord>,
+    #[account(seeds = [b"param_ramp"], bump)]
+    pub param_ramp: Account<'info, ParamRamp>,
+    #[account(seeds = [b"emission_schedule"], bump)]
+    pub emission_schedule: Account<'info, EmissionSchedule>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    /// CHECK: Owner's `PositionManager` PDA; updated only if it has been created.
+    #[account(mut, seeds = [b"position_manager", owner.key().as_ref()], bump)]
+    pub position_manager: AccountInfo<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+// ---------- ApproveStakeMigration ----------
+#[derive(Accounts)]
+pub struct ApproveStakeMigration<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + std::mem::size_of::<StakeMigration>(),
+        seeds = [b"stake_migration"],
+        bump
+    )]
+    pub stake_migration: Account<'info, StakeMigration>,
+    /// Vault of new-mint tokens backing migrated positions; the token account is its own authority.
+    #[account(
+        init,
+        payer = admin,
+        token::mint = new_mint,
+        token::authority = migration_vault,
+        seeds = [b"migration_vault"],
+        bump
+    )]
+    pub migration_vault: InterfaceAccount<'info, TokenAccount>,
+    /// Stake vault for the new mint, custodying migrated and new positions.
+    #[account(
+        init,
+        payer = admin,
+        token::mint = new_mint,
+        token::authority = new_stake_vault,
+        seeds = [b"stake_vault", new_mint.key().as_ref()],
+        bump
+    )]
+    pub new_stake_vault: InterfaceAccount<'info, TokenAccount>,
+    pub old_mint: InterfaceAccount<'info, Mint>,
+    pub new_mint: InterfaceAccount<'info, Mint>,
+    #[account(has_one = admin @ ErrorCode::Unauthorized)]
+    pub presale_state: Account<'info, PresaleState>,
+    #[account(mut, seeds = [b"config"], bump)]
+    pub program_config: Account<'info, ProgramConfig>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+// ---------- MigrateStake ----------
+#[derive(Accounts)]
+pub struct MigrateStake<'info> {
+    #[account(mut)]
+    pub stake_info: Account<'info, StakeInfo>,
+    #[account(mut)]
+    pub global_state: Account<'info, GlobalState>,
+    #[account(mut, seeds = [b"staker", owner.key().as_ref()], bump)]
+    pub staker_record: Account<'info, StakerRecord>,
+    #[account(mut, seeds = [b"stake_migration"], bump)]
+    pub stake_migration: Account<'info, StakeMigration>,
+    #[account(mut, seeds = [b"migration_vault"], bump)]
+    pub migration_vault: InterfaceAccount<'info, TokenAccount>,
+    /// The stake vault for the new mint (destination).
+    #[account(mut, seeds = [b"stake_vault", stake_migration.new_mint.as_ref()], bump)]
+    pub new_stake_vault: InterfaceAccount<'info, TokenAccount>,
+    /// CHECK: `PartnerRewardPool` PDA; settled only once partner rewards are initialized.
+    #[account(mut, seeds = [b"partner_rewards"], bump)]
+    pub partner_reward_pool: AccountInfo<'info>,
+    #[account(seeds = [b"param_ramp"], bump)]
+    pub param_ramp: Account<'info, ParamRamp>,
+    #[account(seeds = [b"emission_schedule"], bump)]
+    pub emission_schedule: Account<'info, EmissionSchedule>,
+    pub owner: Signer<'info>,
+    /// Mint the position migrates to.
+    #[account(address = new_stake_vault.mint)]
+    pub new_mint: InterfaceAccount<'info, Mint>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+impl<'info> MigrateStake<'info> {
+    /// Returns a CPI context for moving converted tokens into the new stake vault.
+    pub fn migration_transfer_context(
+        &self,
+    ) -> CpiContext<'_, '_, '_, 'info, TransferChecked<'info>> {
+        let cpi_accounts = TransferChecked {
+            from: self.migration_vault.to_account_info(),
+            mint: self.new_mint.to_account_info(),
+            to: self.new_stake_vault.to_account_info(),
+            authority: self.migration_vault.to_account_info(),
+        };
+        CpiContext::new(self.token_program.to_account_info(), cpi_accounts)
+    }
+}
+
+// ---------- FreezePosition ----------
+#[derive(Accounts)]
+pub struct FreezePosition<'info> {
+    #[account(mut)]
+    pub stake_info: Account<'info, StakeInfo>,
+    #[account(mut)]
+    pub global_state: Account<'info, GlobalState>,
+    #[account(seeds = [b"param_ramp"], bump)]
+    pub param_ramp: Account<'info, ParamRamp>,
+    #[account(seeds = [b"emission_schedule"], bump)]
+    pub emission_schedule: Account<'info, EmissionSchedule>,
+    #[account(has_one = admin @ ErrorCode::Unauthorized)]
+    pub presale_state: Account<'info, PresaleState>,
+    #[account(mut, seeds = [b"config"], bump)]
+    pub program_config: Account<'info, ProgramConfig>,
+    pub admin: Signer<'info>,
+}
+
+// ---------- CreateAirdrop ----------
+#[derive(Accounts)]
+#[instruction(distributor_id: u64)]
+pub struct CreateAirdrop<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + std::mem::size_of::<AirdropDistributor>(),
+        seeds = [b"airdrop".as_ref(), &distributor_id.to_le_bytes()],
+        bump
+    )]
+    pub distributor: Account<'info, AirdropDistributor>,
+    /// Vault holding the airdropped tokens, owned by the distributor PDA.
+    #[account(
+        init,
+        payer = admin,
+        token::mint = mint,
+        token::authority = distributor,
+        seeds = [b"airdrop_vault", distributor.key().as_ref()],
+        bump
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+    pub mint: InterfaceAccount<'info, Mint>,
+    /// The admin's token account funding the airdrop.
+    #[account(mut)]
+    pub source: InterfaceAccount<'info, TokenAccount>,
+    /// Community pool token account that receives unclaimed tokens after the deadline.
+    #[account(constraint = sweep_destination.mint == mint.key())]
+    pub sweep_destination: InterfaceAccount<'info, TokenAccount>,
+    #[account(has_one = admin @ ErrorCode::Unauthorized)]
+    pub presale_state: Account<'info, PresaleState>,
+    #[account(mut, seeds = [b"config"], bump)]
+    pub program_config: Account<'info, ProgramConfig>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+impl<'info> CreateAirdrop<'info> {
+    /// Returns a CPI context for funding the airdrop vault from the admin's account.
+    pub fn fund_vault_context(
+        &self,
+    ) -> CpiContext<'_, '_, '_, 'info, TransferChecked<'info>> {
+        let cpi_accounts = TransferChecked {
+            from: self.source.to_account_info(),
+            mint: self.mint.to_account_info(),
+            to: self.vault.to_account_info(),
+            authority: self.admin.to_account_info(),
+        };
+        CpiContext::new(self.token_program.to_account_info(), cpi_accounts)
+    }
+}
+
+// ---------- ClaimAirdrop ----------
+#[derive(Accounts)]
+pub struct ClaimAirdrop<'info> {
+    #[account(mut)]
+    pub distributor: Account<'info, AirdropDistributor>,
+    #[account(seeds = [b"config"], bump)]
+    pub program_config: Account<'info, ProgramConfig>,
+    #[account(mut, constraint = vault.key() == distributor.vault)]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+    /// Created on claim; its existence prevents a second claim.
+    #[account(
+        init,
+        payer = claimant,
+        space = 8 + std::mem::size_of::<AirdropClaim>(),
+        seeds = [b"airdrop_claim", distributor.key().as_ref(), claimant.key().as_ref()],
+        bump
+    )]
+    pub airdrop_claim: Account<'info, AirdropClaim>,
+    #[account(mut)]
+    pub claimant: Signer<'info>,
+    /// CHECK: `BlacklistEntry` PDA of the claimant; checked only if it has been created.
+    #[account(seeds = [b"blacklist", claimant.key().as_ref()], bump)]
+    pub blacklist_entry: AccountInfo<'info>,
+    /// The claimant's token account receiving the airdrop.
+    #[account(mut)]
+    pub claimant_token_account: InterfaceAccount<'info, TokenAccount>,
+    /// Mint of the airdropped tokens.
+    #[account(address = vault.mint)]
+    pub mint: InterfaceAccount<'info, Mint>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> ClaimAirdrop<'info> {
+    /// Returns a CPI context for transferring airdropped tokens from the vault to the claimant.
+    pub fn claim_transfer_context(
+        &self,
+    ) -> CpiContext<'_, '_, '_, 'info, TransferChecked<'info>> {
+        let cpi_accounts = TransferChecked {
+            from: self.vault.to_account_info(),
+            mint: self.mint.to_account_info(),
+            to: self.claimant_token_account.to_account_info(),
+            authority: self.distributor.to_account_info(),
+        };
+        CpiContext::new(self.token_program.to_account_info(), cpi_accounts)
+    }
+}
+
+// ---------- SweepUnclaimed ----------
+#[derive(Accounts)]
+pub struct SweepUnclaimed<'info> {
+    #[account(mut)]
+    pub distributor: Account<'info, AirdropDistributor>,
+    #[account(mut, constraint = vault.key() == distributor.vault)]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+    /// Community pool token account recorded at creation.
+    #[account(mut, constraint = sweep_destination.key() == distributor.sweep_destination)]
+    pub sweep_destination: InterfaceAccount<'info, TokenAccount>,
+    #[account(has_one = admin @ ErrorCode::Unauthorized)]
+    pub presale_state: Account<'info, PresaleState>,
+    #[account(mut, seeds = [b"config"], bump)]
+    pub program_config: Account<'info, ProgramConfig>,
+    pub admin: Signer<'info>,
+    /// Mint of the airdropped tokens.
+    #[account(address = vault.mint)]
+    pub mint: InterfaceAccount<'info, Mint>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+impl<'info> SweepUnclaimed<'info> {
+    /// Returns a CPI context for returning unclaimed tokens to the community pool.
+    pub fn sweep_transfer_context(
+        &self,
+    ) -> CpiContext<'_, '_, '_, 'info, TransferChecked<'info>> {
+        let cpi_accounts = TransferChecked {
+            from: self.vault.to_account_info(),
+            mint: self.mint.to_account_info(),
+            to: self.sweep_destination.to_account_info(),
+            authority: self.distributor.to_account_info(),
+        };
+        CpiContext::new(self.token_program.to_account_info(), cpi_accounts)
+    }
+}
+
+// ---------- InitializeReflections ----------
+#[derive(Accounts)]
+pub struct InitializeReflections<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + std::mem::size_of::<ReflectionPool>(),
+        seeds = [b"reflection_pool"],
+        bump
+    )]
+    pub reflection_pool: Account<'info, ReflectionPool>,
+    /// Vault holding the reflections; the token account is its own authority.
+    #[account(
+        init,
+        payer = admin,
+        token::mint = token_mint,
+        token::authority = reflection_vault,
+        seeds = [b"reflection_vault"],
+        bump
+    )]
+    pub reflection_vault: InterfaceAccount<'info, TokenAccount>,
+    #[account(constraint = token_mint.key() == program_config.token_mint @ ErrorCode::InvalidTokenMint)]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+    #[account(has_one = admin @ ErrorCode::Unauthorized)]
+    pub presale_state: Account<'info, PresaleState>,
+    #[account(mut, seeds = [b"config"], bump)]
+    pub program_config: Account<'info, ProgramConfig>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+// ---------- DepositReflections ----------
+#[derive(Accounts)]
+pub struct DepositReflections<'info> {
+    #[account(mut, seeds = [b"reflection_pool"], bump)]
+    pub reflection_pool: Account<'info, ReflectionPool>,
+    #[account(mut, seeds = [b"reflection_vault"], bump)]
+    pub reflection_vault: InterfaceAccount<'info, TokenAccount>,
+    /// The depositor's token account funding the reflections.
+    #[account(mut)]
+    pub source: InterfaceAccount<'info, TokenAccount>,
+    pub depositor: Signer<'info>,
+    /// The $BRATS mint.
+    #[account(address = reflection_vault.mint)]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+impl<'info> DepositReflections<'info> {
+    /// Returns a CPI context for moving the deposit into the reflection vault.
+    pub fn deposit_transfer_context(
+        &self,
+    ) -> CpiContext<'_, '_, '_, 'info, TransferChecked<'info>> {
+        let cpi_accounts = TransferChecked {
+            from: self.source.to_account_info(),
+            mint: self.token_mint.to_account_info(),
+            to: self.reflection_vault.to_account_info(),
+            authority: self.depositor.to_account_info(),
+        };
+        CpiContext::new(self.token_program.to_account_info(), cpi_accounts)
+    }
+}
+
+// ---------- PostReflectionSnapshot ----------
+#[derive(Accounts)]
+pub struct PostReflectionSnapshot<'info> {
+    #[account(mut, seeds = [b"reflection_pool"], bump)]
+    pub reflection_pool: Account<'info, ReflectionPool>,
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + std::mem::size_of::<ReflectionSnapshot>(),
+        seeds = [b"reflection_snapshot".as_ref(), &reflection_pool.snapshot_count.to_le_bytes()],
+        bump
+    )]
+    pub snapshot: Account<'info, ReflectionSnapshot>,
+    #[account(seeds = [b"reflection_vault"], bump)]
+    pub reflection_vault: InterfaceAccount<'info, TokenAccount>,
+    #[account(has_one = admin @ ErrorCode::Unauthorized)]
+    pub presale_state: Account<'info, PresaleState>,
+    #[account(mut, seeds = [b"config"], bump)]
+    pub program_config: Account<'info, ProgramConfig>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+// ---------- ClaimReflection ----------
+#[derive(Accounts)]
+pub struct ClaimReflection<'info> {
+    #[account(mut, seeds = [b"reflection_pool"], bump)]
+    pub reflection_pool: Account<'info, ReflectionPool>,
+    #[account(mut, seeds = [b"reflection_snapshot", &snapshot.snapshot_id.to_le_bytes()], bump)]
+    pub snapshot: Account<'info, ReflectionSnapshot>,
+    #[account(mut, seeds = [b"reflection_vault"], bump)]
+    pub reflection_vault: InterfaceAccount<'info, TokenAccount>,
+    #[account(seeds = [b"config"], bump)]
+    pub program_config: Account<'info, ProgramConfig>,
+    /// Created on claim; its existence prevents a second claim.
+    #[account(
+        init,
+        payer = holder,
+        space = 8 + std::mem::size_of::<ReflectionClaim>(),
+        seeds = [b"reflection_claim", snapshot.key().as_ref(), holder.key().as_ref()],
+        bump
+    )]
+    pub reflection_claim: Account<'info, ReflectionClaim>,
+    #[account(mut)]
+    pub holder: Signer<'info>,
+    /// CHECK: `BlacklistEntry` PDA of the holder; checked only if it has been created.
+    #[account(seeds = [b"blacklist", holder.key().as_ref()], bump)]
+    pub blacklist_entry: AccountInfo<'info>,
+    /// The holder's token account receiving the reflection.
+    #[account(mut)]
+    pub holder_token_account: InterfaceAccount<'info, TokenAccount>,
+    /// The $BRATS mint.
+    #[account(address = reflection_vault.mint)]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> ClaimReflection<'info> {
+    /// Returns a CPI context for paying the reflection from the vault to the holder.
+    pub fn claim_transfer_context(
+        &self,
+    ) -> CpiContext<'_, '_, '_, 'info, TransferChecked<'info>> {
+        let cpi_accounts = TransferChecked {
+            from: self.reflection_vault.to_account_info(),
+            mint: self.token_mint.to_account_info(),
+            to: self.holder_token_account.to_account_info(),
+            authority: self.reflection_vault.to_account_info(),
+        };
+        CpiContext::new(self.token_program.to_account_info(), cpi_accounts)
+    }
+}
+
+// ---------- ExpireReflectionSnapshot ----------
+#[derive(Accounts)]
+pub struct ExpireReflectionSnapshot<'info> {
+    #[account(mut, seeds = [b"reflection_pool"], bump)]
+    pub reflection_pool: Account<'info, ReflectionPool>,
+    #[account(mut, seeds = [b"reflection_snapshot", &snapshot.snapshot_id.to_le_bytes()], bump)]
+    pub snapshot: Account<'info, ReflectionSnapshot>,
+}
+
+// ---------- InitializeBonusPool ----------
+#[derive(Accounts)]
+pub struct InitializeBonusPool<'info> {
+    /// Dedicated early-bird bonus pool; the token account is its own authority.
+    #[account(
+        init,
+        payer = admin,
+        token::mint = mint,
+        token::authority = bonus_pool,
+        seeds = [b"bonus_pool"],
+        bump
+    )]
+    pub bonus_pool: InterfaceAccount<'info, TokenAccount>,
+    pub mint: InterfaceAccount<'info, Mint>,
+    #[account(has_one = admin @ ErrorCode::Unauthorized)]
+    pub presale_state: Account<'info, PresaleState>,
+    #[account(mut, seeds = [b"config"], bump)]
+    pub program_config: Account<'info, ProgramConfig>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+// ---------- ClaimEarlyBirdBonus ----------
+#[derive(Accounts)]
+pub struct ClaimEarlyBirdBonus<'info> {
+    #[account(mut)]
+    pub receipt: Account<'info, PurchaseReceipt>,
+    pub presale_state: Account<'info, PresaleState>,
+    #[account(seeds = [b"config"], bump)]
+    pub program_config: Account<'info, ProgramConfig>,
+    #[account(mut, seeds = [b"bonus_pool"], bump)]
+    pub bonus_pool: InterfaceAccount<'info, TokenAccount>,
+    pub beneficiary: Signer<'info>,
+    /// CHECK: `BlacklistEntry` PDA of the beneficiary; checked only if it has been created.
+    #[account(seeds = [b"blacklist", beneficiary.key().as_ref()], bump)]
+    pub blacklist_entry: AccountInfo<'info>,
+    /// The beneficiary's token account receiving the bonus.
+    #[account(mut)]
+    pub beneficiary_token_account: InterfaceAccount<'info, TokenAccount>,
+    /// CHECK: `Stats` PDA; updated only if it has been created.
+    #[account(mut, seeds = [b"stats"], bump)]
+    pub stats: AccountInfo<'info>,
+    /// The $BRATS mint.
+    #[account(address = bonus_pool.mint)]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+impl<'info> ClaimEarlyBirdBonus<'info> {
+    /// Check the receipt's early-bird bonus can be claimed, mark it claimed and return it.
+    pub fn take_bonus(&mut self) -> Result<u64> {
+        self.program_config.check_not_paused(PAUSE_CLAIMS)?;
+        BlacklistEntry::check(&self.blacklist_entry)?;
+        require!(
+            self.presale_state.launch_time.is_some(),
+            ErrorCode::PresaleNotEnded
+        );
+        let receipt = &mut self.receipt;
         require!(
-            ctx.accounts.admin.key() == ctx.accounts.presale_state.admin,
+            receipt.beneficiary == self.beneficiary.key(),
             ErrorCode::Unauthorized
         );
-        let global_state = &mut ctx.accounts.global_state;
-        global_state.apy = new_apy;
-        global_state.transaction_fee_percent = new_fee_percent;
+        require!(!receipt.early_bird_bonus_claimed, ErrorCode::BonusAlreadyClaimed);
+        let bonus = receipt.early_bird_bonus(self.program_config.early_bird_bonus_bps);
+        require!(bonus > 0, ErrorCode::NoRewardsAvailable);
+        require!(
+            self.bonus_pool.amount >= bonus,
+            ErrorCode::InsufficientRewards
+        );
+        receipt.early_bird_bonus_claimed = true;
+        Stats::update(&self.stats, Clock::get()?.unix_timestamp, |stats| {
+            stats.total_rewards_distributed =
+                stats.total_rewards_distributed.checked_add(bonus).unwrap()
+        })?;
+        Ok(bonus)
+    }
+
+    /// Returns a CPI context for paying the bonus out of the bonus pool.
+    pub fn bonus_transfer_context(
+        &self,
+    ) -> CpiContext<'_, '_, '_, 'info, TransferChecked<'info>> {
+        let cpi_accounts = TransferChecked {
+            from: self.bonus_pool.to_account_info(),
+            mint: self.token_mint.to_account_info(),
+            to: self.beneficiary_token_account.to_account_info(),
+            authority: self.bonus_pool.to_account_info(),
+        };
+        CpiContext::new(self.token_program.to_account_info(), cpi_accounts)
+    }
+}
+
+// ---------- ClaimEarlyBirdBonusStaked ----------
+#[derive(Accounts)]
+pub struct ClaimEarlyBirdBonusStaked<'info> {
+    pub claim: ClaimEarlyBirdBonus<'info>,
+    pub bonus_stake: BonusStake<'info>,
+}
+
+impl<'info> ClaimEarlyBirdBonusStaked<'info> {
+    /// Returns a CPI context for moving the bonus from the bonus pool into the stake vault.
+    pub fn stake_transfer_context(
+        &self,
+    ) -> CpiContext<'_, '_, '_, 'info, TransferChecked<'info>> {
+        let cpi_accounts = TransferChecked {
+            from: self.claim.bonus_pool.to_account_info(),
+            mint: self.claim.token_mint.to_account_info(),
+            to: self.bonus_stake.stake_vault.to_account_info(),
+            authority: self.claim.bonus_pool.to_account_info(),
+        };
+        CpiContext::new(self.claim.token_program.to_account_info(), cpi_accounts)
+    }
+}
+
+// ---------- BonusStake ----------
+/// The claimant's bonus stake position that auto-staked bonuses are credited to.
+#[derive(Accounts)]
+pub struct BonusStake<'info> {
+    /// The owner's bonus position, created by the first auto-staked bonus.
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = 8 + std::mem::size_of::<StakeInfo>(),
+        seeds = [b"bonus_stake", owner.key().as_ref()],
+        bump
+    )]
+    pub stake_info: Box<Account<'info, StakeInfo>>,
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = 8 + std::mem::size_of::<StakerRecord>(),
+        seeds = [b"staker", owner.key().as_ref()],
+        bump
+    )]
+    pub staker_record: Box<Account<'info, StakerRecord>>,
+    #[account(mut)]
+    pub global_state: Box<Account<'info, GlobalState>>,
+    #[account(seeds = [b"param_ramp"], bump)]
+    pub param_ramp: Box<Account<'info, ParamRamp>>,
+    #[account(seeds = [b"emission_schedule"], bump)]
+    pub emission_schedule: Box<Account<'info, EmissionSchedule>>,
+    /// CHECK: `PartnerRewardPool` PDA; settled only once partner rewards are initialized.
+    #[account(mut, seeds = [b"partner_rewards"], bump)]
+    pub partner_reward_pool: AccountInfo<'info>,
+    /// The stake vault for the bonus mint (destination).
+    #[account(mut, seeds = [b"stake_vault", stake_vault.mint.as_ref()], bump)]
+    pub stake_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    /// CHECK: Owner's `PositionManager` PDA; updated only if it has been created.
+    #[account(mut, seeds = [b"position_manager", owner.key().as_ref()], bump)]
+    pub position_manager: AccountInfo<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> BonusStake<'info> {
+    /// Credit `amount` bonus tokens, already moved into the stake vault, to the owner's bonus
+    /// position and lock it for `lock_period` seconds from now (never shortening a lock).
+    pub fn stake_bonus(&mut self, amount: u64, lock_period: i64) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let stake_info = &mut self.stake_info;
+        let global_state = &mut self.global_state;
+        if stake_info.owner == Pubkey::default() {
+            stake_info.owner = self.owner.key();
+            stake_info.mint = self.stake_vault.mint;
+        }
+        require!(!stake_info.is_frozen, ErrorCode::PositionFrozen);
+        require!(
+            stake_info.unstake_queue_position == 0,
+            ErrorCode::PositionQueuedForUnstake
+        );
+        PartnerRewardPool::checkpoint(
+            &self.partner_reward_pool,
+            stake_info,
+            global_state.total_staked,
+            now,
+        )?;
+        let acc_reward_per_share = global_state.update_rewards(
+            &self.param_ramp,
+            &self.emission_schedule,
+            now,
+        );
+        stake_info.settle_rewards(acc_reward_per_share);
+
+        let staker_record = &mut self.staker_record;
+        staker_record.owner = self.owner.key();
+        if stake_info.amount == 0 {
+            global_state.open_position(staker_record);
+            stake_info.start_time = now;
+            stake_info.last_claim_time = now;
+            let position = stake_info.key();
+            PositionManager::update(&self.position_manager, |manager| {
+                manager.add_stake_position(position)
+            })?;
+        }
+        staker_record.total_staked = staker_record.total_staked.checked_add(amount).unwrap();
+        stake_info.amount = stake_info.amount.checked_add(amount).unwrap();
+        stake_info.reset_reward_debt(acc_reward_per_share);
+        global_state.total_staked = global_state.total_staked.checked_add(amount).unwrap();
+        stake_info.last_activity = now;
+        stake_info.locked_until = stake_info.locked_until.max(now + lock_period);
         Ok(())
     }
+}
+
+// ---------- InitializeVestingVault ----------
+#[derive(Accounts)]
+pub struct InitializeVestingVault<'info> {
+    /// Vault holding presale tokens for distribution; the token account is its own authority.
+    #[account(
+        init,
+        payer = admin,
+        token::mint = mint,
+        token::authority = vesting_vault,
+        seeds = [b"vesting_vault"],
+        bump
+    )]
+    pub vesting_vault: InterfaceAccount<'info, TokenAccount>,
+    pub mint: InterfaceAccount<'info, Mint>,
+    #[account(has_one = admin @ ErrorCode::Unauthorized)]
+    pub presale_state: Account<'info, PresaleState>,
+    #[account(mut, seeds = [b"config"], bump)]
+    pub program_config: Account<'info, ProgramConfig>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+// ---------- AddReceiptToVesting ----------
+#[derive(Accounts)]
+pub struct AddReceiptToVesting<'info> {
+    #[account(mut)]
+    pub receipt: Account<'info, PurchaseReceipt>,
+    #[account(
+        init_if_needed,
+        payer = beneficiary,
+        space = 8 + std::mem::size_of::<VestingSchedule>(),
+        seeds = [b"vesting", beneficiary.key().as_ref()],
+        bump
+    )]
+    pub vesting_schedule: Account<'info, VestingSchedule>,
+    pub presale_state: Account<'info, PresaleState>,
+    #[account(mut)]
+    pub beneficiary: Signer<'info>,
+    /// CHECK: Beneficiary's `PositionManager` PDA; updated only if it has been created.
+    #[account(mut, seeds = [b"position_manager", beneficiary.key().as_ref()], bump)]
+    pub position_manager: AccountInfo<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+// ---------- ConsolidateAllocations ----------
+#[derive(Accounts)]
+pub struct ConsolidateAllocations<'info> {
+    #[account(
+        init_if_needed,
+        payer = beneficiary,
+        space = 8 + std::mem::size_of::<AllocationAggregate>(),
+        seeds = [b"allocation_aggregate", beneficiary.key().as_ref()],
+        bump
+    )]
+    pub allocation_aggregate: Account<'info, AllocationAggregate>,
+    pub presale_state: Account<'info, PresaleState>,
+    #[account(mut)]
+    pub beneficiary: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+// ---------- ClaimConsolidatedVesting ----------
+#[derive(Accounts)]
+pub struct ClaimConsolidatedVesting<'info> {
+    #[account(mut, seeds = [b"allocation_aggregate", beneficiary.key().as_ref()], bump)]
+    pub allocation_aggregate: Account<'info, AllocationAggregate>,
+    pub presale_state: Account<'info, PresaleState>,
+    #[account(mut, seeds = [b"claim_portal"], bump)]
+    pub claim_portal: Account<'info, ClaimPortal>,
+    #[account(
+        init_if_needed,
+        payer = beneficiary,
+        space = 8 + std::mem::size_of::<ClaimRecord>(),
+        seeds = [b"claim_record", beneficiary.key().as_ref()],
+        bump
+    )]
+    pub claim_record: Account<'info, ClaimRecord>,
+    #[account(mut, seeds = [b"vesting_vault"], bump)]
+    pub vesting_vault: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut)]
+    pub beneficiary: Signer<'info>,
+    /// CHECK: `BlacklistEntry` PDA of the beneficiary; checked only if it has been created.
+    #[account(seeds = [b"blacklist", beneficiary.key().as_ref()], bump)]
+    pub blacklist_entry: AccountInfo<'info>,
+    /// The beneficiary's token account receiving the vested tokens.
+    #[account(mut)]
+    pub beneficiary_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(seeds = [b"config"], bump)]
+    pub program_config: Account<'info, ProgramConfig>,
+    #[account(address = program_config.token_mint @ ErrorCode::InvalidTokenMint)]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> ClaimConsolidatedVesting<'info> {
+    /// Returns a CPI context for releasing tokens from the vesting vault.
+    pub fn vesting_transfer_context(
+        &self,
+    ) -> CpiContext<'_, '_, '_, 'info, TransferChecked<'info>> {
+        let cpi_accounts = TransferChecked {
+            from: self.vesting_vault.to_account_info(),
+            mint: self.token_mint.to_account_info(),
+            to: self.beneficiary_token_account.to_account_info(),
+            authority: self.vesting_vault.to_account_info(),
+        };
+        CpiContext::new(self.token_program.to_account_info(), cpi_accounts)
+    }
+}
+
+// ---------- ClaimPresaleTokens ----------
+#[derive(Accounts)]
+pub struct ClaimPresaleTokens<'info> {
+    #[account(mut)]
+    pub receipt: Account<'info, PurchaseReceipt>,
+    pub presale_state: Account<'info, PresaleState>,
+    #[account(mut, seeds = [b"claim_portal"], bump)]
+    pub claim_portal: Account<'info, ClaimPortal>,
+    #[account(
+        init_if_needed,
+        payer = beneficiary,
+        space = 8 + std::mem::size_of::<ClaimRecord>(),
+        seeds = [b"claim_record", beneficiary.key().as_ref()],
+        bump
+    )]
+    pub claim_record: Account<'info, ClaimRecord>,
+    #[account(mut, seeds = [b"vesting_vault"], bump)]
+    pub vesting_vault: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut)]
+    pub beneficiary: Signer<'info>,
+    /// CHECK: `BlacklistEntry` PDA of the beneficiary; checked only if it has been created.
+    #[account(seeds = [b"blacklist", beneficiary.key().as_ref()], bump)]
+    pub blacklist_entry: AccountInfo<'info>,
+    /// The beneficiary's token account for the presale token.
+    #[account(
+        mut,
+        constraint = beneficiary_token_account.owner == beneficiary.key(),
+        constraint = beneficiary_token_account.mint == vesting_vault.mint
+    )]
+    pub beneficiary_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(seeds = [b"config"], bump)]
+    pub program_config: Account<'info, ProgramConfig>,
+    #[account(address = program_config.token_mint @ ErrorCode::InvalidTokenMint)]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> ClaimPresaleTokens<'info> {
+    /// Returns a CPI context for paying the allocation out of the vault.
+    pub fn distribution_transfer_context(
+        &self,
+    ) -> CpiContext<'_, '_, '_, 'info, TransferChecked<'info>> {
+        let cpi_accounts = TransferChecked {
+            from: self.vesting_vault.to_account_info(),
+            mint: self.token_mint.to_account_info(),
+            to: self.beneficiary_token_account.to_account_info(),
+            authority: self.vesting_vault.to_account_info(),
+        };
+        CpiContext::new(self.token_program.to_account_info(), cpi_accounts)
+    }
+}
+
+// ---------- InitReceiptTree ----------
+#[derive(Accounts)]
+pub struct InitReceiptTree<'info> {
+    #[account(has_one = admin @ ErrorCode::Unauthorized)]
+    pub presale_state: Account<'info, PresaleState>,
+    #[account(mut, seeds = [b"config"], bump)]
+    pub program_config: Account<'info, ProgramConfig>,
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + std::mem::size_of::<ReceiptTree>(),
+        seeds = [b"receipt_tree"],
+        bump
+    )]
+    pub receipt_tree: Account<'info, ReceiptTree>,
+    /// CHECK: Empty tree account, owned by and initialized through spl-account-compression.
+    #[account(mut)]
+    pub merkle_tree: AccountInfo<'info>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub compression_program: Program<'info, SplAccountCompression>,
+    pub noop_program: Program<'info, Noop>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> InitReceiptTree<'info> {
+    /// Returns a CPI context for initializing the tree with the receipt tree PDA as authority.
+    pub fn init_tree_context(
+        &self,
+    ) -> CpiContext<'_, '_, '_, 'info, spl_account_compression::cpi::accounts::Initialize<'info>>
+    {
+        let cpi_accounts = spl_account_compression::cpi::accounts::Initialize {
+            merkle_tree: self.merkle_tree.to_account_info(),
+            authority: self.receipt_tree.to_account_info(),
+            noop: self.noop_program.to_account_info(),
+        };
+        CpiContext::new(self.compression_program.to_account_info(), cpi_accounts)
+    }
+}
 
-    /// Allow the admin to withdraw funds from the treasury SOL account during the presale.
-    pub fn withdraw_funds(ctx: Context<WithdrawFunds>, amount: u64) -> ProgramResult {
-        // Only allow withdrawal while presale is active.
-        require!(
-            ctx.accounts.presale_state.is_presale_active,
-            ErrorCode::WithdrawalNotAllowedAfterPresale
-        );
-        let ix = system_instruction::transfer(
-            ctx.accounts.treasury_sol_account.key,
-            ctx.accounts.admin.key,
-            amount,
-        );
-        solana_program::program::invoke(
-            &ix,
-            &[
-                ctx.accounts.treasury_sol_account.clone(),
-                ctx.accounts.admin.to_account_info(),
-                ctx.accounts.system_program.to_account_info(),
-            ],
-        )?;
-        Ok(())
+// ---------- CompressReceipt ----------
+#[derive(Accounts)]
+pub struct CompressReceipt<'info> {
+    #[account(
+        mut,
+        close = payer,
+        constraint = receipt.beneficiary == beneficiary.key() @ ErrorCode::Unauthorized
+    )]
+    pub receipt: Account<'info, PurchaseReceipt>,
+    /// CHECK: Wallet that paid for the receipt; receives its rent.
+    #[account(mut, address = receipt.payer)]
+    pub payer: AccountInfo<'info>,
+    pub beneficiary: Signer<'info>,
+    pub presale_state: Account<'info, PresaleState>,
+    #[account(seeds = [b"config"], bump)]
+    pub program_config: Account<'info, ProgramConfig>,
+    #[account(mut, seeds = [b"receipt_tree"], bump)]
+    pub receipt_tree: Account<'info, ReceiptTree>,
+    /// CHECK: The receipt tree's spl-account-compression account.
+    #[account(mut, address = receipt_tree.merkle_tree @ ErrorCode::InvalidReceiptTree)]
+    pub merkle_tree: AccountInfo<'info>,
+    pub compression_program: Program<'info, SplAccountCompression>,
+    pub noop_program: Program<'info, Noop>,
+}
+
+impl<'info> CompressReceipt<'info> {
+    /// Returns a CPI context for appending to the receipt tree.
+    pub fn modify_tree_context(
+        &self,
+    ) -> CpiContext<'_, '_, '_, 'info, spl_account_compression::cpi::accounts::Modify<'info>> {
+        let cpi_accounts = spl_account_compression::cpi::accounts::Modify {
+            merkle_tree: self.merkle_tree.to_account_info(),
+            authority: self.receipt_tree.to_account_info(),
+            noop: self.noop_program.to_account_info(),
+        };
+        CpiContext::new(self.compression_program.to_account_info(), cpi_accounts)
     }
+}
 
-    /// Initialize the presale stage information with default stages.
-    pub fn initialize_presale_stages(ctx: Context<InitializePresaleStages>) -> ProgramResult {
-        let presale_stage_info = &mut ctx.accounts.presale_stage_info;
-        presale_stage_info.stages = [
-            // Prices are stored with 8 decimals (e.g. 0.00021 -> 21000)
-            PresaleStage { stage: 1, price: 21000, tokens_sold: 2_500_000_000, total_raised: 525_000 },
-            PresaleStage { stage: 2, price: 25000, tokens_sold: 2_500_000_000, total_raised: 625_000 },
-            PresaleStage { stage: 3, price: 29000, tokens_sold: 2_500_000_000, total_raised: 725_000 },
-            PresaleStage { stage: 4, price: 33000, tokens_sold: 2_500_000_000, total_raised: 825_000 },
-            PresaleStage { stage: 5, price: 37000, tokens_sold: 2_500_000_000, total_raised: 925_000 },
-            PresaleStage { stage: 6, price: 41000, tokens_sold: 2_500_000_000, total_raised: 1_025_000 },
-            PresaleStage { stage: 7, price: 45000, tokens_sold: 2_500_000_000, total_raised: 1_125_000 },
-            PresaleStage { stage: 8, price: 49000, tokens_sold: 2_500_000_000, total_raised: 1_225_000 },
-        ];
-        Ok(())
+// ---------- ClaimCompressedReceipt ----------
+#[derive(Accounts)]
+pub struct ClaimCompressedReceipt<'info> {
+    pub presale_state: Account<'info, PresaleState>,
+    #[account(mut, seeds = [b"receipt_tree"], bump)]
+    pub receipt_tree: Account<'info, ReceiptTree>,
+    /// CHECK: The receipt tree's spl-account-compression account.
+    #[account(mut, address = receipt_tree.merkle_tree @ ErrorCode::InvalidReceiptTree)]
+    pub merkle_tree: AccountInfo<'info>,
+    #[account(mut, seeds = [b"claim_portal"], bump)]
+    pub claim_portal: Account<'info, ClaimPortal>,
+    #[account(
+        init_if_needed,
+        payer = beneficiary,
+        space = 8 + std::mem::size_of::<ClaimRecord>(),
+        seeds = [b"claim_record", beneficiary.key().as_ref()],
+        bump
+    )]
+    pub claim_record: Account<'info, ClaimRecord>,
+    #[account(mut, seeds = [b"vesting_vault"], bump)]
+    pub vesting_vault: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut)]
+    pub beneficiary: Signer<'info>,
+    /// CHECK: `BlacklistEntry` PDA of the beneficiary; checked only if it has been created.
+    #[account(seeds = [b"blacklist", beneficiary.key().as_ref()], bump)]
+    pub blacklist_entry: AccountInfo<'info>,
+    /// The beneficiary's token account for the presale token.
+    #[account(
+        mut,
+        constraint = beneficiary_token_account.owner == beneficiary.key(),
+        constraint = beneficiary_token_account.mint == vesting_vault.mint
+    )]
+    pub beneficiary_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(seeds = [b"config"], bump)]
+    pub program_config: Account<'info, ProgramConfig>,
+    #[account(address = program_config.token_mint @ ErrorCode::InvalidTokenMint)]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+    pub compression_program: Program<'info, SplAccountCompression>,
+    pub noop_program: Program<'info, Noop>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> ClaimCompressedReceipt<'info> {
+    /// Returns a CPI context for replacing a leaf of the receipt tree.
+    pub fn modify_tree_context(
+        &self,
+    ) -> CpiContext<'_, '_, '_, 'info, spl_account_compression::cpi::accounts::Modify<'info>> {
+        let cpi_accounts = spl_account_compression::cpi::accounts::Modify {
+            merkle_tree: self.merkle_tree.to_account_info(),
+            authority: self.receipt_tree.to_account_info(),
+            noop: self.noop_program.to_account_info(),
+        };
+        CpiContext::new(self.compression_program.to_account_info(), cpi_accounts)
     }
 
-    /// Update a specific presale stage (Admin only).
-    /// `stage_index` is 0-based (i.e. 0 for Stage 1, 1 for Stage 2, etc.)
-    pub fn update_presale_stage(
-        ctx: Context<UpdatePresaleStage>,
-        stage_index: u8,
-        price: u64,
-        tokens_sold: u64,
-        total_raised: u64,
-    ) -> ProgramResult {
-        let presale_stage_info = &mut ctx.accounts.presale_stage_info;
-        require!(
-            (stage_index as usize) < presale_stage_info.stages.len(),
-            ErrorCode::InvalidStageIndex
-        );
-        presale_stage_info.stages[stage_index as usize] = PresaleStage {
-            stage: stage_index + 1,
-            price,
-            tokens_sold,
-            total_raised,
+    /// Returns a CPI context for paying the allocation out of the vault.
+    pub fn distribution_transfer_context(
+        &self,
+    ) -> CpiContext<'_, '_, '_, 'info, TransferChecked<'info>> {
+        let cpi_accounts = TransferChecked {
+            from: self.vesting_vault.to_account_info(),
+            mint: self.token_mint.to_account_info(),
+            to: self.beneficiary_token_account.to_account_info(),
+            authority: self.vesting_vault.to_account_info(),
         };
-        Ok(())
+        CpiContext::new(self.token_program.to_account_info(), cpi_accounts)
     }
 }
 
-//
-// ERROR CODES
-//
-#[error]
-pub enum ErrorCode {
-    #[msg("Presale has not ended yet. Staking is only allowed during the presale.")]
-    PresaleNotEnded,
-    #[msg("Presale already ended.")]
-    PresaleAlreadyEnded,
-    #[msg("Unstaking not allowed before 7 days after launch.")]
-    UnstakingNotAllowedBefore7Days,
-    #[msg("Liquidity lock error.")]
-    LiquidityLockError,
-    #[msg("Invalid payment or stake amount.")]
-    InvalidAmount,
-    #[msg("Insufficient funds for SPL token transfer.")]
-    InsufficientFunds,
-    #[msg("No rewards available to claim yet.")]
-    NoRewardsAvailable,
-    #[msg("Invalid token mint address.")]
-    InvalidTokenMint,
-    #[msg("Not enough rewards in the pool.")]
-    InsufficientRewards,
-    #[msg("Unauthorized.")]
-    Unauthorized,
-    #[msg("Fee wallet provided is invalid.")]
-    InvalidFeeWallet,
-    #[msg("Staking is only allowed during the presale.")]
-    StakingClosed,
-    #[msg("Staking rewards pool is exhausted.")]
-    StakingRewardsExhausted,
-    #[msg("Withdrawal allowed only during presale.")]
-    WithdrawalNotAllowedAfterPresale,
-    #[msg("Invalid presale stage index.")]
-    InvalidStageIndex,
+// ---------- ClaimLaunchTax ----------
+#[derive(Accounts)]
+pub struct ClaimLaunchTax<'info> {
+    #[account(mut, has_one = admin @ ErrorCode::Unauthorized)]
+    pub presale_state: Account<'info, PresaleState>,
+    #[account(mut, seeds = [b"config"], bump)]
+    pub program_config: Account<'info, ProgramConfig>,
+    #[account(mut, seeds = [b"vesting_vault"], bump)]
+    pub vesting_vault: InterfaceAccount<'info, TokenAccount>,
+    /// The fee wallet's token account for the presale token.
+    #[account(
+        mut,
+        constraint = fee_wallet_token_account.owner == program_config.fee_wallet
+            @ ErrorCode::InvalidFeeWallet,
+        constraint = fee_wallet_token_account.mint == vesting_vault.mint
+    )]
+    pub fee_wallet_token_account: InterfaceAccount<'info, TokenAccount>,
+    pub admin: Signer<'info>,
+    /// The $BRATS mint.
+    #[account(address = vesting_vault.mint)]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+    pub token_program: Interface<'info, TokenInterface>,
 }
 
-//
-// CONTEXTS & HELPER FUNCTIONS
-//
+impl<'info> ClaimLaunchTax<'info> {
+    /// Returns a CPI context for paying the launch tax out of the vesting vault.
+    pub fn launch_tax_transfer_context(
+        &self,
+    ) -> CpiContext<'_, '_, '_, 'info, TransferChecked<'info>> {
+        let cpi_accounts = TransferChecked {
+            from: self.vesting_vault.to_account_info(),
+            mint: self.token_mint.to_account_info(),
+            to: self.fee_wallet_token_account.to_account_info(),
+            authority: self.vesting_vault.to_account_info(),
+        };
+        CpiContext::new(self.token_program.to_account_info(), cpi_accounts)
+    }
+}
 
-// ---------- InitializeToken ----------
+// ---------- SetTransferFee ----------
 #[derive(Accounts)]
-pub struct InitializeToken<'info> {
-    #[account(init, payer = payer, space = 8 + std::mem::size_of::<PresaleState>())]
+pub struct SetTransferFee<'info> {
+    #[account(has_one = admin @ ErrorCode::Unauthorized)]
     pub presale_state: Account<'info, PresaleState>,
+    #[account(mut, seeds = [b"config"], bump)]
+    pub program_config: Account<'info, ProgramConfig>,
+    /// CHECK: PDA holding the mint's transfer fee config and withdraw authorities.
+    #[account(seeds = [b"fee_authority"], bump)]
+    pub fee_authority: AccountInfo<'info>,
+    /// The $BRATS mint.
+    #[account(mut, address = program_config.token_mint @ ErrorCode::InvalidTokenMint)]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+    pub admin: Signer<'info>,
+    pub token_program: Program<'info, Token2022>,
+}
+
+// ---------- HarvestTransferFees ----------
+#[derive(Accounts)]
+pub struct HarvestTransferFees<'info> {
+    #[account(seeds = [b"config"], bump)]
+    pub program_config: Account<'info, ProgramConfig>,
     #[account(mut)]
-    pub payer: Signer<'info>,
+    pub global_state: Account<'info, GlobalState>,
+    /// CHECK: PDA holding the mint's withdraw authority.
+    #[account(seeds = [b"fee_authority"], bump)]
+    pub fee_authority: AccountInfo<'info>,
+    /// Receives the withheld fees; the reward pool's share stays here.
+    #[account(mut, seeds = [b"reward_vault"], bump)]
+    pub reward_vault: InterfaceAccount<'info, TokenAccount>,
+    /// The fee wallet's $BRATS token account.
+    #[account(
+        mut,
+        constraint = fee_wallet_token_account.owner == program_config.fee_wallet
+            @ ErrorCode::InvalidFeeWallet,
+        constraint = fee_wallet_token_account.mint == token_mint.key()
+    )]
+    pub fee_wallet_token_account: InterfaceAccount<'info, TokenAccount>,
+    /// The $BRATS mint.
+    #[account(mut, address = program_config.token_mint @ ErrorCode::InvalidTokenMint)]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+    pub token_program: Program<'info, Token2022>,
+}
+
+impl<'info> HarvestTransferFees<'info> {
+    /// Returns a CPI context for burning the burn share of the harvested fees.
+    pub fn burn_context(&self) -> CpiContext<'_, '_, '_, 'info, Burn<'info>> {
+        let cpi_accounts = Burn {
+            mint: self.token_mint.to_account_info(),
+            from: self.reward_vault.to_account_info(),
+            authority: self.reward_vault.to_account_info(),
+        };
+        CpiContext::new(self.token_program.to_account_info(), cpi_accounts)
+    }
+
+    /// Returns a CPI context for paying the fee wallet's share out of the reward vault.
+    pub fn fee_transfer_context(&self) -> CpiContext<'_, '_, '_, 'info, TransferChecked<'info>> {
+        let cpi_accounts = TransferChecked {
+            from: self.reward_vault.to_account_info(),
+            mint: self.token_mint.to_account_info(),
+            to: self.fee_wallet_token_account.to_account_info(),
+            authority: self.reward_vault.to_account_info(),
+        };
+        CpiContext::new(self.token_program.to_account_info(), cpi_accounts)
+    }
+}
+
+// ---------- ClaimVestedTokens ----------
+#[derive(Accounts)]
+pub struct ClaimVestedTokens<'info> {
+    #[account(mut, seeds = [b"vesting", beneficiary.key().as_ref()], bump)]
+    pub vesting_schedule: Account<'info, VestingSchedule>,
+    pub presale_state: Account<'info, PresaleState>,
+    #[account(mut, seeds = [b"claim_portal"], bump)]
+    pub claim_portal: Account<'info, ClaimPortal>,
+    #[account(
+        init_if_needed,
+        payer = beneficiary,
+        space = 8 + std::mem::size_of::<ClaimRecord>(),
+        seeds = [b"claim_record", beneficiary.key().as_ref()],
+        bump
+    )]
+    pub claim_record: Account<'info, ClaimRecord>,
+    #[account(mut, seeds = [b"vesting_vault"], bump)]
+    pub vesting_vault: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut)]
+    pub beneficiary: Signer<'info>,
+    /// CHECK: `BlacklistEntry` PDA of the beneficiary; checked only if it has been created.
+    #[account(seeds = [b"blacklist", beneficiary.key().as_ref()], bump)]
+    pub blacklist_entry: AccountInfo<'info>,
+    /// The beneficiary's token account receiving the vested tokens.
+    #[account(mut)]
+    pub beneficiary_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(seeds = [b"config"], bump)]
+    pub program_config: Account<'info, ProgramConfig>,
+    #[account(address = program_config.token_mint @ ErrorCode::InvalidTokenMint)]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+    pub token_program: Interface<'info, TokenInterface>,
     pub system_program: Program<'info, System>,
 }
 
-// ---------- InitializeGlobalState ----------
+impl<'info> ClaimVestedTokens<'info> {
+    /// Returns a CPI context for releasing tokens from the vesting vault.
+    pub fn vesting_transfer_context(
+        &self,
+    ) -> CpiContext<'_, '_, '_, 'info, TransferChecked<'info>> {
+        let cpi_accounts = TransferChecked {
+            from: self.vesting_vault.to_account_info(),
+            mint: self.token_mint.to_account_info(),
+            to: self.beneficiary_token_account.to_account_info(),
+            authority: self.vesting_vault.to_account_info(),
+        };
+        CpiContext::new(self.token_program.to_account_info(), cpi_accounts)
+    }
+}
+
+// ---------- ClaimRewards ----------
 #[derive(Accounts)]
-pub struct InitializeGlobalState<'info> {
-    #[account(init, payer = payer, space = 8 + std::mem::size_of::<GlobalState>())]
+pub struct ClaimRewards<'info> {
+    #[account(mut, constraint = stake_info.owner == payer.key() @ ErrorCode::Unauthorized)]
+    pub stake_info: Account<'info, StakeInfo>,
+    #[account(seeds = [b"config"], bump)]
+    pub program_config: Account<'info, ProgramConfig>,
+    #[account(mut)]
     pub global_state: Account<'info, GlobalState>,
+    #[account(seeds = [b"param_ramp"], bump)]
+    pub param_ramp: Account<'info, ParamRamp>,
+    #[account(seeds = [b"emission_schedule"], bump)]
+    pub emission_schedule: Account<'info, EmissionSchedule>,
+    /// CHECK: `Governance` PDA; read for the participation boost if it exists.
+    #[account(seeds = [b"governance"], bump)]
+    pub governance: AccountInfo<'info>,
     #[account(mut)]
     pub payer: Signer<'info>,
-    pub system_program: Program<'info, System>,
+    /// CHECK: `BlacklistEntry` PDA of the payer; checked only if it has been created.
+    #[account(seeds = [b"blacklist", payer.key().as_ref()], bump)]
+    pub blacklist_entry: AccountInfo<'info>,
+    /// The user's token account that will receive reward tokens.
+    #[account(mut)]
+    pub user_token_account: InterfaceAccount<'info, TokenAccount>,
+    /// The reward vault (source).
+    #[account(mut, seeds = [b"reward_vault"], bump)]
+    pub reward_vault: InterfaceAccount<'info, TokenAccount>,
+    /// CHECK: `Stats` PDA; updated only if it has been created.
+    #[account(mut, seeds = [b"stats"], bump)]
+    pub stats: AccountInfo<'info>,
+    /// The $BRATS mint.
+    #[account(address = reward_vault.mint)]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+    pub token_program: Interface<'info, TokenInterface>,
 }
 
-// ---------- EndPresale ----------
+impl<'info> ClaimRewards<'info> {
+    /// Returns a CPI context for transferring reward tokens from the reward vault to the user.
+    pub fn reward_transfer_context(
+        &self,
+    ) -> CpiContext<'_, '_, '_, 'info, TransferChecked<'info>> {
+        let cpi_accounts = TransferChecked {
+            from: self.reward_vault.to_account_info(),
+            mint: self.token_mint.to_account_info(),
+            to: self.user_token_account.to_account_info(),
+            authority: self.reward_vault.to_account_info(),
+        };
+        CpiContext::new(self.token_program.to_account_info(), cpi_accounts)
+    }
+}
+
+// ---------- CompoundRewards ----------
 #[derive(Accounts)]
-pub struct EndPresale<'info> {
+pub struct CompoundRewards<'info> {
     #[account(mut)]
+    pub stake_info: Account<'info, StakeInfo>,
+    #[account(seeds = [b"config"], bump)]
+    pub program_config: Account<'info, ProgramConfig>,
+    #[account(mut)]
+    pub global_state: Account<'info, GlobalState>,
+    #[account(seeds = [b"param_ramp"], bump)]
+    pub param_ramp: Account<'info, ParamRamp>,
+    #[account(seeds = [b"emission_schedule"], bump)]
+    pub emission_schedule: Account<'info, EmissionSchedule>,
+    #[account(mut, seeds = [b"staker", owner.key().as_ref()], bump)]
+    pub staker_record: Account<'info, StakerRecord>,
+    /// CHECK: `PartnerRewardPool` PDA; settled only once partner rewards are initialized.
+    #[account(mut, seeds = [b"partner_rewards"], bump)]
+    pub partner_reward_pool: AccountInfo<'info>,
+    /// CHECK: `StakingLeaderboard` PDA; updated only if it has been created.
+    #[account(mut, seeds = [b"staking_leaderboard"], bump)]
+    pub staking_leaderboard: AccountInfo<'info>,
+    /// The reward vault (source).
+    #[account(mut, seeds = [b"reward_vault"], bump)]
+    pub reward_vault: InterfaceAccount<'info, TokenAccount>,
+    /// The stake vault for the position's mint (destination).
+    #[account(mut, seeds = [b"stake_vault", stake_info.mint.as_ref()], bump)]
+    pub stake_vault: InterfaceAccount<'info, TokenAccount>,
+    pub owner: Signer<'info>,
+    /// CHECK: `BlacklistEntry` PDA of the owner; checked only if it has been created.
+    #[account(seeds = [b"blacklist", owner.key().as_ref()], bump)]
+    pub blacklist_entry: AccountInfo<'info>,
+    /// CHECK: `Stats` PDA; updated only if it has been created.
+    #[account(mut, seeds = [b"stats"], bump)]
+    pub stats: AccountInfo<'info>,
+    /// The $BRATS mint.
+    #[account(address = reward_vault.mint)]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+impl<'info> CompoundRewards<'info> {
+    /// Returns a CPI context for moving rewards from the reward vault into the stake vault.
+    pub fn compound_transfer_context(
+        &self,
+    ) -> CpiContext<'_, '_, '_, 'info, TransferChecked<'info>> {
+        let cpi_accounts = TransferChecked {
+            from: self.reward_vault.to_account_info(),
+            mint: self.token_mint.to_account_info(),
+            to: self.stake_vault.to_account_info(),
+            authority: self.reward_vault.to_account_info(),
+        };
+        CpiContext::new(self.token_program.to_account_info(), cpi_accounts)
+    }
+}
+
+// ---------- SetNftBoost ----------
+#[derive(Accounts)]
+pub struct SetNftBoost<'info> {
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = 8 + std::mem::size_of::<NftBoostConfig>(),
+        seeds = [b"nft_boost"],
+        bump
+    )]
+    pub nft_boost_config: Account<'info, NftBoostConfig>,
+    #[account(has_one = admin @ ErrorCode::Unauthorized)]
     pub presale_state: Account<'info, PresaleState>,
+    #[account(mut, seeds = [b"config"], bump)]
+    pub program_config: Account<'info, ProgramConfig>,
     #[account(mut)]
     pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
 }
 
-// ---------- AcceptPayment ----------
-/// This context includes accounts for both SOL and SPL branches.
-/// (Unused accounts for one branch can be ignored.)
+// ---------- RegisterBoostNft ----------
 #[derive(Accounts)]
-pub struct AcceptPayment<'info> {
+pub struct RegisterBoostNft<'info> {
     #[account(mut)]
-    pub payer: Signer<'info>,
-
-    // SPL token accounts
+    pub stake_info: Account<'info, StakeInfo>,
+    #[account(seeds = [b"nft_boost"], bump)]
+    pub nft_boost_config: Account<'info, NftBoostConfig>,
     #[account(mut)]
-    pub payer_token_account: Account<'info, TokenAccount>,
+    pub global_state: Account<'info, GlobalState>,
+    #[account(seeds = [b"param_ramp"], bump)]
+    pub param_ramp: Account<'info, ParamRamp>,
+    #[account(seeds = [b"emission_schedule"], bump)]
+    pub emission_schedule: Account<'info, EmissionSchedule>,
+    pub nft_mint: InterfaceAccount<'info, Mint>,
+    /// CHECK: Metaplex metadata of `nft_mint`; verified in `NftBoostConfig::check_collection`.
+    pub nft_metadata: AccountInfo<'info>,
+    /// The owner's token account holding the NFT.
+    #[account(mut, constraint = owner_nft_account.mint == nft_mint.key())]
+    pub owner_nft_account: InterfaceAccount<'info, TokenAccount>,
+    /// Escrow holding the NFT while the boost is active.
+    #[account(
+        init,
+        payer = owner,
+        seeds = [b"nft_escrow", stake_info.key().as_ref()],
+        bump,
+        token::mint = nft_mint,
+        token::authority = nft_escrow
+    )]
+    pub nft_escrow: InterfaceAccount<'info, TokenAccount>,
     #[account(mut)]
-    pub treasury_token_account: Account<'info, TokenAccount>,
+    pub owner: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+impl<'info> RegisterBoostNft<'info> {
+    /// Returns a CPI context for moving the NFT into escrow.
+    pub fn escrow_transfer_context(
+        &self,
+    ) -> CpiContext<'_, '_, '_, 'info, TransferChecked<'info>> {
+        let cpi_accounts = TransferChecked {
+            from: self.owner_nft_account.to_account_info(),
+            mint: self.nft_mint.to_account_info(),
+            to: self.nft_escrow.to_account_info(),
+            authority: self.owner.to_account_info(),
+        };
+        CpiContext::new(self.token_program.to_account_info(), cpi_accounts)
+    }
+}
+
+// ---------- UnregisterBoostNft ----------
+#[derive(Accounts)]
+pub struct UnregisterBoostNft<'info> {
     #[account(mut)]
-    pub fee_wallet_token_account: Account<'info, TokenAccount>,
+    pub stake_info: Account<'info, StakeInfo>,
     #[account(mut)]
-    pub reward_pool_token_account: Account<'info, TokenAccount>,
+    pub global_state: Account<'info, GlobalState>,
+    #[account(seeds = [b"param_ramp"], bump)]
+    pub param_ramp: Account<'info, ParamRamp>,
+    #[account(seeds = [b"emission_schedule"], bump)]
+    pub emission_schedule: Account<'info, EmissionSchedule>,
+    #[account(mut, seeds = [b"nft_escrow", stake_info.key().as_ref()], bump)]
+    pub nft_escrow: InterfaceAccount<'info, TokenAccount>,
+    /// The owner's token account receiving the NFT.
+    #[account(mut, constraint = owner_nft_account.mint == nft_escrow.mint)]
+    pub owner_nft_account: InterfaceAccount<'info, TokenAccount>,
     #[account(mut)]
-    pub mint: Account<'info, Mint>,
-    pub mint_authority: Signer<'info>,
+    pub owner: Signer<'info>,
+    /// Mint of the escrowed NFT.
+    #[account(address = nft_escrow.mint)]
+    pub nft_mint: InterfaceAccount<'info, Mint>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
 
-    // SOL accounts (for SOL payments)
-    /// CHECK: Treasury SOL account (must be a non‑executable wallet)
+impl<'info> UnregisterBoostNft<'info> {
+    /// Returns a CPI context for returning the NFT from escrow.
+    pub fn release_transfer_context(
+        &self,
+    ) -> CpiContext<'_, '_, '_, 'info, TransferChecked<'info>> {
+        let cpi_accounts = TransferChecked {
+            from: self.nft_escrow.to_account_info(),
+            mint: self.nft_mint.to_account_info(),
+            to: self.owner_nft_account.to_account_info(),
+            authority: self.nft_escrow.to_account_info(),
+        };
+        CpiContext::new(self.token_program.to_account_info(), cpi_accounts)
+    }
+
+    /// Returns a CPI context for closing the emptied escrow, refunding its rent to the owner.
+    pub fn close_escrow_context(
+        &self,
+    ) -> CpiContext<'_, '_, '_, 'info, CloseAccount<'info>> {
+        let cpi_accounts = CloseAccount {
+            account: self.nft_escrow.to_account_info(),
+            destination: self.owner.to_account_info(),
+            authority: self.nft_escrow.to_account_info(),
+        };
+        CpiContext::new(self.token_program.to_account_info(), cpi_accounts)
+    }
+}
+
+// ---------- RestakeRewards ----------
+#[derive(Accounts)]
+#[instruction(new_position_id: u64)]
+pub struct RestakeRewards<'info> {
+    /// The position whose rewards are restaked.
     #[account(mut)]
-    pub treasury_sol_account: AccountInfo<'info>,
-    /// CHECK: Fee wallet SOL account (must be a non‑executable wallet)
+    pub stake_info: Account<'info, StakeInfo>,
+    /// The new locked position, derived from the owner and the new position id.
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + std::mem::size_of::<StakeInfo>(),
+        seeds = [b"stake", owner.key().as_ref(), &new_position_id.to_le_bytes()],
+        bump
+    )]
+    pub new_stake_info: Account<'info, StakeInfo>,
+    #[account(seeds = [b"config"], bump)]
+    pub program_config: Account<'info, ProgramConfig>,
+    #[account(seeds = [b"staking_config"], bump)]
+    pub staking_config: Account<'info, StakingConfig>,
     #[account(mut)]
-    pub fee_wallet_sol_account: AccountInfo<'info>,
-    /// CHECK: Reward pool SOL account
+    pub global_state: Account<'info, GlobalState>,
+    #[account(seeds = [b"param_ramp"], bump)]
+    pub param_ramp: Account<'info, ParamRamp>,
+    #[account(seeds = [b"emission_schedule"], bump)]
+    pub emission_schedule: Account<'info, EmissionSchedule>,
+    #[account(mut, seeds = [b"staker", owner.key().as_ref()], bump)]
+    pub staker_record: Account<'info, StakerRecord>,
+    /// CHECK: `PartnerRewardPool` PDA; settled only once partner rewards are initialized.
+    #[account(mut, seeds = [b"partner_rewards"], bump)]
+    pub partner_reward_pool: AccountInfo<'info>,
+    /// The reward vault (source).
+    #[account(mut, seeds = [b"reward_vault"], bump)]
+    pub reward_vault: InterfaceAccount<'info, TokenAccount>,
+    /// The stake vault for the position's mint (destination).
+    #[account(mut, seeds = [b"stake_vault", stake_info.mint.as_ref()], bump)]
+    pub stake_vault: InterfaceAccount<'info, TokenAccount>,
     #[account(mut)]
-    pub reward_pool_sol_account: AccountInfo<'info>,
+    pub owner: Signer<'info>,
+    /// CHECK: `BlacklistEntry` PDA of the owner; checked only if it has been created.
+    #[account(seeds = [b"blacklist", owner.key().as_ref()], bump)]
+    pub blacklist_entry: AccountInfo<'info>,
+    /// CHECK: Owner's `PositionManager` PDA; updated only if it has been created.
+    #[account(mut, seeds = [b"position_manager", owner.key().as_ref()], bump)]
+    pub position_manager: AccountInfo<'info>,
+    /// CHECK: `StakingLeaderboard` PDA; updated only if it has been created.
+    #[account(mut, seeds = [b"staking_leaderboard"], bump)]
+    pub staking_leaderboard: AccountInfo<'info>,
+    /// CHECK: `Stats` PDA; updated only if it has been created.
+    #[account(mut, seeds = [b"stats"], bump)]
+    pub stats: AccountInfo<'info>,
+    /// The $BRATS mint.
+    #[account(address = reward_vault.mint)]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
 
-    // Global state (holds fee parameters and reward pool tracker)
+impl<'info> RestakeRewards<'info> {
+    /// Returns a CPI context for moving restaked rewards into the stake vault.
+    pub fn restake_transfer_context(
+        &self,
+    ) -> CpiContext<'_, '_, '_, 'info, TransferChecked<'info>> {
+        let cpi_accounts = TransferChecked {
+            from: self.reward_vault.to_account_info(),
+            mint: self.token_mint.to_account_info(),
+            to: self.stake_vault.to_account_info(),
+            authority: self.reward_vault.to_account_info(),
+        };
+        CpiContext::new(self.token_program.to_account_info(), cpi_accounts)
+    }
+}
+
+// ---------- InitializeStakingLeaderboard ----------
+#[derive(Accounts)]
+pub struct InitializeStakingLeaderboard<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + std::mem::size_of::<StakingLeaderboard>(),
+        seeds = [b"staking_leaderboard"],
+        bump
+    )]
+    pub staking_leaderboard: AccountLoader<'info, StakingLeaderboard>,
+    #[account(has_one = admin @ ErrorCode::Unauthorized)]
+    pub presale_state: Account<'info, PresaleState>,
+    #[account(mut, seeds = [b"config"], bump)]
+    pub program_config: Account<'info, ProgramConfig>,
     #[account(mut)]
-    pub global_state: Account<'info, GlobalState>,
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
 
-    pub token_program: Program<'info, Token>,
+// ---------- InitializePartnerRewards ----------
+#[derive(Accounts)]
+pub struct InitializePartnerRewards<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + std::mem::size_of::<PartnerRewardPool>(),
+        seeds = [b"partner_rewards"],
+        bump
+    )]
+    pub partner_reward_pool: Account<'info, PartnerRewardPool>,
+    /// Vault holding the partner tokens to emit; the token account is its own authority.
+    #[account(
+        init,
+        payer = admin,
+        token::mint = mint,
+        token::authority = partner_reward_vault,
+        seeds = [b"partner_reward_vault"],
+        bump
+    )]
+    pub partner_reward_vault: InterfaceAccount<'info, TokenAccount>,
+    pub mint: InterfaceAccount<'info, Mint>,
+    #[account(has_one = admin @ ErrorCode::Unauthorized)]
+    pub presale_state: Account<'info, PresaleState>,
+    #[account(mut, seeds = [b"config"], bump)]
+    pub program_config: Account<'info, ProgramConfig>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
     pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
 }
 
-impl<'info> AcceptPayment<'info> {
-    /// A generic transfer context used for SPL token transfers.
-    pub fn stake_transfer_context_generic(
+// ---------- FundPartnerRewards ----------
+#[derive(Accounts)]
+pub struct FundPartnerRewards<'info> {
+    #[account(mut, seeds = [b"partner_rewards"], bump)]
+    pub partner_reward_pool: Account<'info, PartnerRewardPool>,
+    #[account(mut, seeds = [b"partner_reward_vault"], bump)]
+    pub partner_reward_vault: InterfaceAccount<'info, TokenAccount>,
+    pub global_state: Account<'info, GlobalState>,
+    #[account(has_one = admin @ ErrorCode::Unauthorized)]
+    pub presale_state: Account<'info, PresaleState>,
+    #[account(mut, seeds = [b"config"], bump)]
+    pub program_config: Account<'info, ProgramConfig>,
+    /// The admin's partner token account funding the emissions.
+    #[account(mut)]
+    pub source: InterfaceAccount<'info, TokenAccount>,
+    pub admin: Signer<'info>,
+    /// The $BRATS mint.
+    #[account(address = partner_reward_vault.mint)]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+impl<'info> FundPartnerRewards<'info> {
+    /// Returns a CPI context for depositing partner tokens into the vault.
+    pub fn fund_transfer_context(
+        &self,
+    ) -> CpiContext<'_, '_, '_, 'info, TransferChecked<'info>> {
+        let cpi_accounts = TransferChecked {
+            from: self.source.to_account_info(),
+            mint: self.token_mint.to_account_info(),
+            to: self.partner_reward_vault.to_account_info(),
+            authority: self.admin.to_account_info(),
+        };
+        CpiContext::new(self.token_program.to_account_info(), cpi_accounts)
+    }
+}
+
+// ---------- ClaimPartnerRewards ----------
+#[derive(Accounts)]
+pub struct ClaimPartnerRewards<'info> {
+    #[account(mut)]
+    pub stake_info: Account<'info, StakeInfo>,
+    #[account(seeds = [b"config"], bump)]
+    pub program_config: Account<'info, ProgramConfig>,
+    pub global_state: Account<'info, GlobalState>,
+    #[account(mut, seeds = [b"partner_rewards"], bump)]
+    pub partner_reward_pool: Account<'info, PartnerRewardPool>,
+    #[account(mut, seeds = [b"partner_reward_vault"], bump)]
+    pub partner_reward_vault: InterfaceAccount<'info, TokenAccount>,
+    pub owner: Signer<'info>,
+    /// CHECK: `BlacklistEntry` PDA of the owner; checked only if it has been created.
+    #[account(seeds = [b"blacklist", owner.key().as_ref()], bump)]
+    pub blacklist_entry: AccountInfo<'info>,
+    /// The owner's partner token account receiving the rewards.
+    #[account(mut)]
+    pub user_token_account: InterfaceAccount<'info, TokenAccount>,
+    /// CHECK: `Stats` PDA; updated only if it has been created.
+    #[account(mut, seeds = [b"stats"], bump)]
+    pub stats: AccountInfo<'info>,
+    /// The $BRATS mint.
+    #[account(address = partner_reward_vault.mint)]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+impl<'info> ClaimPartnerRewards<'info> {
+    /// Returns a CPI context for paying partner rewards out of the vault.
+    pub fn partner_transfer_context(
         &self,
-        from: AccountInfo<'info>,
-        to: AccountInfo<'info>,
-    ) -> CpiContext<'_, '_, '_, 'info, Transfer<'info>> {
-        let cpi_accounts = Transfer {
-            from,
-            to,
-            authority: self.payer.to_account_info(),
+    ) -> CpiContext<'_, '_, '_, 'info, TransferChecked<'info>> {
+        let cpi_accounts = TransferChecked {
+            from: self.partner_reward_vault.to_account_info(),
+            mint: self.token_mint.to_account_info(),
+            to: self.user_token_account.to_account_info(),
+            authority: self.partner_reward_vault.to_account_info(),
         };
         CpiContext::new(self.token_program.to_account_info(), cpi_accounts)
     }
 }
 
-// ---------- DepositSol ----------
+// ---------- InitializeLpStakePool ----------
 #[derive(Accounts)]
-pub struct DepositSol<'info> {
-    #[account(mut)]
-    pub payer: Signer<'info>,
-    /// CHECK: Treasury SOL account where the deposit will be transferred.
+pub struct InitializeLpStakePool<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + std::mem::size_of::<LpStakePool>(),
+        seeds = [b"lp_stake_pool"],
+        bump
+    )]
+    pub lp_stake_pool: Account<'info, LpStakePool>,
+    /// Vault holding the staked LP tokens; the token account is its own authority.
+    #[account(
+        init,
+        payer = admin,
+        token::mint = lp_mint,
+        token::authority = lp_stake_vault,
+        seeds = [b"lp_stake_vault"],
+        bump
+    )]
+    pub lp_stake_vault: InterfaceAccount<'info, TokenAccount>,
+    /// Vault holding the BRATS emitted to LP stakers; the token account is its own authority.
+    #[account(
+        init,
+        payer = admin,
+        token::mint = token_mint,
+        token::authority = lp_reward_vault,
+        seeds = [b"lp_reward_vault"],
+        bump
+    )]
+    pub lp_reward_vault: InterfaceAccount<'info, TokenAccount>,
+    /// Mint of the BRATS/SOL liquidity pool's LP token.
+    pub lp_mint: InterfaceAccount<'info, Mint>,
+    #[account(constraint = token_mint.key() == program_config.token_mint @ ErrorCode::InvalidTokenMint)]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+    #[account(has_one = admin @ ErrorCode::Unauthorized)]
+    pub presale_state: Account<'info, PresaleState>,
+    #[account(mut, seeds = [b"config"], bump)]
+    pub program_config: Account<'info, ProgramConfig>,
     #[account(mut)]
-    pub treasury_sol_account: AccountInfo<'info>,
+    pub admin: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
     pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
 }
 
-// ---------- StakeTokens ----------
+// ---------- UpdateLpStakePool ----------
 #[derive(Accounts)]
-pub struct StakeTokens<'info> {
-    #[account(mut)]
-    pub stake_info: Account<'info, StakeInfo>,
-    #[account(mut)]
-    pub global_state: Account<'info, GlobalState>,
-    #[account(mut)]
+pub struct UpdateLpStakePool<'info> {
+    #[account(mut, seeds = [b"lp_stake_pool"], bump)]
+    pub lp_stake_pool: Account<'info, LpStakePool>,
+    #[account(has_one = admin @ ErrorCode::Unauthorized)]
     pub presale_state: Account<'info, PresaleState>,
+    #[account(mut, seeds = [b"config"], bump)]
+    pub program_config: Account<'info, ProgramConfig>,
+    pub admin: Signer<'info>,
+}
+
+// ---------- FundLpRewards ----------
+#[derive(Accounts)]
+pub struct FundLpRewards<'info> {
+    #[account(mut, seeds = [b"lp_stake_pool"], bump)]
+    pub lp_stake_pool: Account<'info, LpStakePool>,
+    #[account(mut, seeds = [b"lp_reward_vault"], bump)]
+    pub lp_reward_vault: InterfaceAccount<'info, TokenAccount>,
+    #[account(has_one = admin @ ErrorCode::Unauthorized)]
+    pub presale_state: Account<'info, PresaleState>,
+    #[account(mut, seeds = [b"config"], bump)]
+    pub program_config: Account<'info, ProgramConfig>,
+    /// The admin's BRATS token account funding the emissions.
     #[account(mut)]
-    pub payer: Signer<'info>,
-    /// The user's token account (source).
-    #[account(mut)]
-    pub user_token_account: Account<'info, TokenAccount>,
-    /// The staking pool token account (destination).
-    #[account(mut)]
-    pub staking_pool_token_account: Account<'info, TokenAccount>,
-    pub token_program: Program<'info, Token>,
+    pub source: InterfaceAccount<'info, TokenAccount>,
+    pub admin: Signer<'info>,
+    /// The $BRATS mint.
+    #[account(address = lp_reward_vault.mint)]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+    pub token_program: Interface<'info, TokenInterface>,
 }
 
-impl<'info> StakeTokens<'info> {
-    /// Returns a CPI context for transferring tokens from the user to the staking pool.
-    pub fn stake_transfer_context(
+impl<'info> FundLpRewards<'info> {
+    /// Returns a CPI context for depositing BRATS into the LP reward vault.
+    pub fn fund_transfer_context(
         &self,
-    ) -> CpiContext<'_, '_, '_, 'info, Transfer<'info>> {
-        let cpi_accounts = Transfer {
-            from: self.user_token_account.to_account_info(),
-            to: self.staking_pool_token_account.to_account_info(),
-            authority: self.payer.to_account_info(),
+    ) -> CpiContext<'_, '_, '_, 'info, TransferChecked<'info>> {
+        let cpi_accounts = TransferChecked {
+            from: self.source.to_account_info(),
+            mint: self.token_mint.to_account_info(),
+            to: self.lp_reward_vault.to_account_info(),
+            authority: self.admin.to_account_info(),
         };
         CpiContext::new(self.token_program.to_account_info(), cpi_accounts)
     }
 }
 
-// ---------- UnstakeTokens ----------
+// ---------- StakeLp ----------
 #[derive(Accounts)]
-pub struct UnstakeTokens<'info> {
-    #[account(mut)]
-    pub stake_info: Account<'info, StakeInfo>,
-    #[account(mut)]
-    pub global_state: Account<'info, GlobalState>,
-    #[account(mut)]
-    pub presale_state: Account<'info, PresaleState>,
-    #[account(mut)]
-    pub payer: Signer<'info>,
-    /// The staking pool token account (source for unstake and burn).
-    #[account(mut)]
-    pub staking_pool_token_account: Account<'info, TokenAccount>,
-    /// The user's token account (destination for unstaked tokens).
+pub struct StakeLp<'info> {
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = 8 + std::mem::size_of::<LpStakeInfo>(),
+        seeds = [b"lp_stake", owner.key().as_ref()],
+        bump
+    )]
+    pub lp_stake_info: Account<'info, LpStakeInfo>,
+    #[account(mut, seeds = [b"lp_stake_pool"], bump)]
+    pub lp_stake_pool: Account<'info, LpStakePool>,
+    #[account(mut, seeds = [b"lp_stake_vault"], bump)]
+    pub lp_stake_vault: InterfaceAccount<'info, TokenAccount>,
+    #[account(seeds = [b"config"], bump)]
+    pub program_config: Account<'info, ProgramConfig>,
+    /// The owner's LP token account.
     #[account(mut)]
-    pub user_token_account: Account<'info, TokenAccount>,
+    pub owner_lp_account: InterfaceAccount<'info, TokenAccount>,
     #[account(mut)]
-    pub mint: Account<'info, Mint>,
-    pub token_program: Program<'info, Token>,
+    pub owner: Signer<'info>,
+    /// CHECK: `BlacklistEntry` PDA of the owner; checked only if it has been created.
+    #[account(seeds = [b"blacklist", owner.key().as_ref()], bump)]
+    pub blacklist_entry: AccountInfo<'info>,
+    /// Mint of the BRATS/SOL liquidity pool's LP token.
+    #[account(address = lp_stake_vault.mint)]
+    pub lp_mint: InterfaceAccount<'info, Mint>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
 }
 
-impl<'info> UnstakeTokens<'info> {
-    /// Returns a CPI context for transferring tokens from the staking pool back to the user.
-    pub fn unstake_transfer_context(
+impl<'info> StakeLp<'info> {
+    /// Returns a CPI context for moving LP tokens into the LP stake vault.
+    pub fn stake_transfer_context(
         &self,
-    ) -> CpiContext<'_, '_, '_, 'info, Transfer<'info>> {
-        let cpi_accounts = Transfer {
-            from: self.staking_pool_token_account.to_account_info(),
-            to: self.user_token_account.to_account_info(),
-            authority: self.payer.to_account_info(),
+    ) -> CpiContext<'_, '_, '_, 'info, TransferChecked<'info>> {
+        let cpi_accounts = TransferChecked {
+            from: self.owner_lp_account.to_account_info(),
+            mint: self.lp_mint.to_account_info(),
+            to: self.lp_stake_vault.to_account_info(),
+            authority: self.owner.to_account_info(),
         };
         CpiContext::new(self.token_program.to_account_info(), cpi_accounts)
     }
-    /// Returns a CPI context for burning tokens from the staking pool (penalty).
-    pub fn early_unstake_burn_context(
+}
+
+// ---------- UnstakeLp ----------
+#[derive(Accounts)]
+pub struct UnstakeLp<'info> {
+    #[account(
+        mut,
+        seeds = [b"lp_stake", owner.key().as_ref()],
+        bump,
+        has_one = owner @ ErrorCode::Unauthorized
+    )]
+    pub lp_stake_info: Account<'info, LpStakeInfo>,
+    #[account(mut, seeds = [b"lp_stake_pool"], bump)]
+    pub lp_stake_pool: Account<'info, LpStakePool>,
+    #[account(mut, seeds = [b"lp_stake_vault"], bump)]
+    pub lp_stake_vault: InterfaceAccount<'info, TokenAccount>,
+    #[account(seeds = [b"config"], bump)]
+    pub program_config: Account<'info, ProgramConfig>,
+    /// The owner's LP token account receiving the tokens.
+    #[account(mut)]
+    pub owner_lp_account: InterfaceAccount<'info, TokenAccount>,
+    pub owner: Signer<'info>,
+    /// Mint of the BRATS/SOL liquidity pool's LP token.
+    #[account(address = lp_stake_vault.mint)]
+    pub lp_mint: InterfaceAccount<'info, Mint>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+impl<'info> UnstakeLp<'info> {
+    /// Returns a CPI context for returning LP tokens from the LP stake vault.
+    pub fn unstake_transfer_context(
         &self,
-    ) -> CpiContext<'_, '_, '_, 'info, Burn<'info>> {
-        let cpi_accounts = Burn {
-            mint: self.mint.to_account_info(),
-            to: self.staking_pool_token_account.to_account_info(),
-            authority: self.payer.to_account_info(),
+    ) -> CpiContext<'_, '_, '_, 'info, TransferChecked<'info>> {
+        let cpi_accounts = TransferChecked {
+            from: self.lp_stake_vault.to_account_info(),
+            mint: self.lp_mint.to_account_info(),
+            to: self.owner_lp_account.to_account_info(),
+            authority: self.lp_stake_vault.to_account_info(),
         };
         CpiContext::new(self.token_program.to_account_info(), cpi_accounts)
     }
 }
 
-// ---------- ClaimRewards ----------
+// ---------- ClaimLpRewards ----------
 #[derive(Accounts)]
-pub struct ClaimRewards<'info> {
-    #[account(mut)]
-    pub stake_info: Account<'info, StakeInfo>,
-    #[account(mut)]
-    pub global_state: Account<'info, GlobalState>,
-    #[account(mut)]
-    pub payer: Signer<'info>,
-    /// The user's token account that will receive reward tokens.
+pub struct ClaimLpRewards<'info> {
+    #[account(
+        mut,
+        seeds = [b"lp_stake", owner.key().as_ref()],
+        bump,
+        has_one = owner @ ErrorCode::Unauthorized
+    )]
+    pub lp_stake_info: Account<'info, LpStakeInfo>,
+    #[account(mut, seeds = [b"lp_stake_pool"], bump)]
+    pub lp_stake_pool: Account<'info, LpStakePool>,
+    #[account(mut, seeds = [b"lp_reward_vault"], bump)]
+    pub lp_reward_vault: InterfaceAccount<'info, TokenAccount>,
+    #[account(seeds = [b"config"], bump)]
+    pub program_config: Account<'info, ProgramConfig>,
+    /// The owner's BRATS token account receiving the rewards.
     #[account(mut)]
-    pub user_token_account: Account<'info, TokenAccount>,
-    /// The reward pool token account (source).
-    #[account(mut)]
-    pub reward_pool_token_account: Account<'info, TokenAccount>,
-    pub token_program: Program<'info, Token>,
+    pub owner_token_account: InterfaceAccount<'info, TokenAccount>,
+    pub owner: Signer<'info>,
+    /// CHECK: `BlacklistEntry` PDA of the owner; checked only if it has been created.
+    #[account(seeds = [b"blacklist", owner.key().as_ref()], bump)]
+    pub blacklist_entry: AccountInfo<'info>,
+    /// CHECK: `Stats` PDA; updated only if it has been created.
+    #[account(mut, seeds = [b"stats"], bump)]
+    pub stats: AccountInfo<'info>,
+    /// The $BRATS mint.
+    #[account(address = lp_reward_vault.mint)]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+    pub token_program: Interface<'info, TokenInterface>,
 }
 
-impl<'info> ClaimRewards<'info> {
-    /// Returns a CPI context for transferring reward tokens from the reward pool to the user.
+impl<'info> ClaimLpRewards<'info> {
+    /// Returns a CPI context for paying rewards from the LP reward vault.
     pub fn reward_transfer_context(
         &self,
-    ) -> CpiContext<'_, '_, '_, 'info, Transfer<'info>> {
-        let cpi_accounts = Transfer {
-            from: self.reward_pool_token_account.to_account_info(),
-            to: self.user_token_account.to_account_info(),
-            authority: self.payer.to_account_info(),
+    ) -> CpiContext<'_, '_, '_, 'info, TransferChecked<'info>> {
+        let cpi_accounts = TransferChecked {
+            from: self.lp_reward_vault.to_account_info(),
+            mint: self.token_mint.to_account_info(),
+            to: self.owner_token_account.to_account_info(),
+            authority: self.lp_reward_vault.to_account_info(),
         };
         CpiContext::new(self.token_program.to_account_info(), cpi_accounts)
     }
 }
 
-// ---------- CalculateRewards ----------
+// ---------- RefreshUserFlags ----------
 #[derive(Accounts)]
-pub struct CalculateRewards<'info> {
+pub struct RefreshUserFlags<'info> {
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + std::mem::size_of::<UserFlags>(),
+        seeds = [b"user_flags", wallet.key().as_ref()],
+        bump
+    )]
+    pub user_flags: Account<'info, UserFlags>,
+    /// CHECK: Wallet whose flags are refreshed.
+    pub wallet: AccountInfo<'info>,
+    pub global_state: Account<'info, GlobalState>,
+    #[account(seeds = [b"config"], bump)]
+    pub program_config: Account<'info, ProgramConfig>,
+    /// CHECK: `ClaimPortal` PDA; only read once it exists.
+    #[account(seeds = [b"claim_portal"], bump)]
+    pub claim_portal: AccountInfo<'info>,
+    /// CHECK: `Governance` PDA; only read once it exists.
+    #[account(seeds = [b"governance"], bump)]
+    pub governance: AccountInfo<'info>,
+    /// CHECK: Most recent `Proposal`; only read when governance has proposals.
+    pub latest_proposal: AccountInfo<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+// ---------- ExpireUnclaimedRewards ----------
+#[derive(Accounts)]
+pub struct ExpireUnclaimedRewards<'info> {
     #[account(mut)]
     pub stake_info: Account<'info, StakeInfo>,
     #[account(mut)]
     pub global_state: Account<'info, GlobalState>,
-    pub payer: Signer<'info>,
-    pub token_program: Program<'info, Token>,
+    #[account(seeds = [b"staking_config"], bump)]
+    pub staking_config: Account<'info, StakingConfig>,
+    #[account(seeds = [b"param_ramp"], bump)]
+    pub param_ramp: Account<'info, ParamRamp>,
+    #[account(seeds = [b"emission_schedule"], bump)]
+    pub emission_schedule: Account<'info, EmissionSchedule>,
+}
+
+// ---------- CalculateRewards ----------
+#[derive(Accounts)]
+pub struct CalculateRewards<'info> {
+    pub stake_info: Account<'info, StakeInfo>,
+    pub global_state: Account<'info, GlobalState>,
+    #[account(seeds = [b"param_ramp"], bump)]
+    pub param_ramp: Account<'info, ParamRamp>,
+    #[account(seeds = [b"emission_schedule"], bump)]
+    pub emission_schedule: Account<'info, EmissionSchedule>,
+}
+
+// ---------- GetStakeCollateralMetadata ----------
+#[derive(Accounts)]
+pub struct GetStakeCollateralMetadata<'info> {
+    pub stake_info: Account<'info, StakeInfo>,
+    pub global_state: Account<'info, GlobalState>,
+    #[account(seeds = [b"config"], bump)]
+    pub program_config: Account<'info, ProgramConfig>,
+    #[account(seeds = [b"param_ramp"], bump)]
+    pub param_ramp: Account<'info, ParamRamp>,
+    #[account(seeds = [b"emission_schedule"], bump)]
+    pub emission_schedule: Account<'info, EmissionSchedule>,
+}
+
+// ---------- GetStagePrices ----------
+#[derive(Accounts)]
+pub struct GetStagePrices<'info> {
+    pub presale_stage_info: AccountLoader<'info, PresaleStageInfo>,
+    #[account(
+        seeds = [b"accepted_mint", usdc.mint.as_ref()],
+        bump,
+        constraint = usdc.is_stablecoin @ ErrorCode::MintNotAccepted
+    )]
+    pub usdc: Account<'info, AcceptedMint>,
+    #[account(
+        seeds = [b"accepted_mint", usdt.mint.as_ref()],
+        bump,
+        constraint = usdt.is_stablecoin @ ErrorCode::MintNotAccepted
+    )]
+    pub usdt: Account<'info, AcceptedMint>,
+}
+
+// ---------- VerifyConfig ----------
+#[derive(Accounts)]
+pub struct VerifyConfig<'info> {
+    #[account(seeds = [b"config"], bump)]
+    pub program_config: Account<'info, ProgramConfig>,
+    /// CHECK: Expected to be `program_config.fee_wallet`, a system-owned wallet.
+    pub fee_wallet: AccountInfo<'info>,
+    /// CHECK: PDA that should own the treasury token account.
+    #[account(seeds = [b"treasury"], bump)]
+    pub treasury_authority: AccountInfo<'info>,
+    /// CHECK: Expected to be a token account of `token_mint` owned by the treasury PDA.
+    pub treasury_token_account: AccountInfo<'info>,
+    /// CHECK: Stake vault PDA of `token_mint`; expected to be its own authority.
+    #[account(seeds = [b"stake_vault", program_config.token_mint.as_ref()], bump)]
+    pub stake_vault: AccountInfo<'info>,
+    /// CHECK: Reward vault PDA; expected to be its own authority.
+    #[account(seeds = [b"reward_vault"], bump)]
+    pub reward_vault: AccountInfo<'info>,
+}
+
+// ---------- GetGlobalStats ----------
+#[derive(Accounts)]
+pub struct GetGlobalStats<'info> {
+    pub global_state: Account<'info, GlobalState>,
+}
+
+// ---------- InitializeStats ----------
+#[derive(Accounts)]
+pub struct InitializeStats<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + std::mem::size_of::<Stats>(),
+        seeds = [b"stats"],
+        bump
+    )]
+    pub stats: Account<'info, Stats>,
+    #[account(seeds = [b"presale_stage_info"], bump)]
+    pub presale_stage_info: AccountLoader<'info, PresaleStageInfo>,
+    #[account(has_one = admin @ ErrorCode::Unauthorized)]
+    pub presale_state: Account<'info, PresaleState>,
+    #[account(mut, seeds = [b"config"], bump)]
+    pub program_config: Account<'info, ProgramConfig>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
 }
 
 // ---------- LockLiquidity ----------
@@ -741,24 +12717,30 @@ pub struct CalculateRewards<'info> {
 pub struct LockLiquidity<'info> {
     #[account(mut)]
     pub presale_state: Account<'info, PresaleState>,
+    #[account(seeds = [b"config"], bump)]
+    pub program_config: Account<'info, ProgramConfig>,
     /// The token account holding liquidity tokens to be locked.
     #[account(mut)]
-    pub liquidity_token_account: Account<'info, TokenAccount>,
+    pub liquidity_token_account: InterfaceAccount<'info, TokenAccount>,
     /// The vault token account where liquidity tokens will be stored.
     #[account(mut)]
-    pub vault_account: Account<'info, TokenAccount>,
+    pub vault_account: InterfaceAccount<'info, TokenAccount>,
     #[account(mut)]
     pub payer: Signer<'info>,
-    pub token_program: Program<'info, Token>,
+    /// Mint of the liquidity tokens.
+    #[account(address = liquidity_token_account.mint)]
+    pub liquidity_mint: InterfaceAccount<'info, Mint>,
+    pub token_program: Interface<'info, TokenInterface>,
 }
 
 impl<'info> LockLiquidity<'info> {
     /// Returns a CPI context for transferring liquidity tokens into the vault.
     pub fn liquidity_lock_transfer_context(
         &self,
-    ) -> CpiContext<'_, '_, '_, 'info, Transfer<'info>> {
-        let cpi_accounts = Transfer {
+    ) -> CpiContext<'_, '_, '_, 'info, TransferChecked<'info>> {
+        let cpi_accounts = TransferChecked {
             from: self.liquidity_token_account.to_account_info(),
+            mint: self.liquidity_mint.to_account_info(),
             to: self.vault_account.to_account_info(),
             authority: self.payer.to_account_info(),
         };
@@ -769,15 +12751,20 @@ impl<'info> LockLiquidity<'info> {
 // ---------- BurnTokens ----------
 #[derive(Accounts)]
 pub struct BurnTokens<'info> {
-    #[account(mut)]
+    #[account(mut, has_one = admin @ ErrorCode::Unauthorized)]
     pub presale_state: Account<'info, PresaleState>,
+    #[account(mut, seeds = [b"config"], bump)]
+    pub program_config: Account<'info, ProgramConfig>,
     #[account(mut)]
-    pub mint: Account<'info, Mint>,
+    pub mint: InterfaceAccount<'info, Mint>,
     /// The source token account from which tokens will be burned.
     #[account(mut)]
-    pub source: Account<'info, TokenAccount>,
+    pub source: InterfaceAccount<'info, TokenAccount>,
     pub admin: Signer<'info>,
-    pub token_program: Program<'info, Token>,
+    /// CHECK: `Stats` PDA; updated only if it has been created.
+    #[account(mut, seeds = [b"stats"], bump)]
+    pub stats: AccountInfo<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
 }
 
 impl<'info> BurnTokens<'info> {
@@ -786,73 +12773,473 @@ impl<'info> BurnTokens<'info> {
     ) -> CpiContext<'_, '_, '_, 'info, Burn<'info>> {
         let cpi_accounts = Burn {
             mint: self.mint.to_account_info(),
-            to: self.source.to_account_info(),
+            from: self.source.to_account_info(),
             authority: self.admin.to_account_info(),
         };
         CpiContext::new(self.token_program.to_account_info(), cpi_accounts)
     }
 }
 
+// ---------- BuybackAndBurn ----------
+#[derive(Accounts)]
+pub struct BuybackAndBurn<'info> {
+    #[account(has_one = admin @ ErrorCode::Unauthorized)]
+    pub presale_state: Account<'info, PresaleState>,
+    #[account(mut, seeds = [b"config"], bump)]
+    pub program_config: Account<'info, ProgramConfig>,
+    /// CHECK: PDA that owns the treasury token accounts; signs the swap and the burn.
+    #[account(seeds = [b"treasury"], bump)]
+    pub treasury_authority: AccountInfo<'info>,
+    /// The treasury's wrapped SOL account the swap spends from, owned by the treasury PDA.
+    #[account(
+        mut,
+        constraint = treasury_wsol_account.owner == treasury_authority.key()
+            @ ErrorCode::Unauthorized,
+        constraint = treasury_wsol_account.mint == Pubkey::from_str(NATIVE_MINT).unwrap()
+            @ ErrorCode::InvalidTokenMint
+    )]
+    pub treasury_wsol_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    /// The treasury $BRATS account the swap pays into and the burn takes from.
+    #[account(
+        mut,
+        constraint = treasury_token_account.owner == treasury_authority.key()
+            @ ErrorCode::Unauthorized,
+        constraint = treasury_token_account.mint == token_mint.key() @ ErrorCode::InvalidTokenMint
+    )]
+    pub treasury_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(mut, constraint = token_mint.key() == program_config.token_mint @ ErrorCode::InvalidTokenMint)]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+    /// CHECK: The allow-listed swap program.
+    #[account(executable, address = program_config.buyback_swap_program @ ErrorCode::Unauthorized)]
+    pub swap_program: AccountInfo<'info>,
+    /// CHECK: `Stats` PDA; updated only if it has been created.
+    #[account(mut, seeds = [b"stats"], bump)]
+    pub stats: AccountInfo<'info>,
+    pub admin: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+impl<'info> BuybackAndBurn<'info> {
+    /// Returns a CPI context for burning the bought tokens from the treasury token account.
+    pub fn burn_context(&self) -> CpiContext<'_, '_, '_, 'info, Burn<'info>> {
+        let cpi_accounts = Burn {
+            mint: self.token_mint.to_account_info(),
+            from: self.treasury_token_account.to_account_info(),
+            authority: self.treasury_authority.to_account_info(),
+        };
+        CpiContext::new(self.token_program.to_account_info(), cpi_accounts)
+    }
+}
+
+// ---------- SetBurnSchedule ----------
+#[derive(Accounts)]
+pub struct SetBurnSchedule<'info> {
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = 8 + std::mem::size_of::<BurnSchedule>(),
+        seeds = [b"burn_schedule"],
+        bump
+    )]
+    pub burn_schedule: Account<'info, BurnSchedule>,
+    /// Vault holding the tokens set aside for scheduled burns; the token account is its own
+    /// authority.
+    #[account(
+        init_if_needed,
+        payer = admin,
+        token::mint = token_mint,
+        token::authority = burn_reserve,
+        seeds = [b"burn_reserve"],
+        bump
+    )]
+    pub burn_reserve: InterfaceAccount<'info, TokenAccount>,
+    #[account(constraint = token_mint.key() == program_config.token_mint @ ErrorCode::InvalidTokenMint)]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+    #[account(has_one = admin @ ErrorCode::Unauthorized)]
+    pub presale_state: Account<'info, PresaleState>,
+    #[account(mut, seeds = [b"config"], bump)]
+    pub program_config: Account<'info, ProgramConfig>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+// ---------- ExecuteScheduledBurn ----------
+#[derive(Accounts)]
+pub struct ExecuteScheduledBurn<'info> {
+    #[account(mut, seeds = [b"burn_schedule"], bump)]
+    pub burn_schedule: Account<'info, BurnSchedule>,
+    #[account(mut, seeds = [b"burn_reserve"], bump)]
+    pub burn_reserve: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, address = burn_reserve.mint)]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+impl<'info> ExecuteScheduledBurn<'info> {
+    /// Returns a CPI context for burning from the burn reserve vault.
+    pub fn burn_context(&self) -> CpiContext<'_, '_, '_, 'info, Burn<'info>> {
+        let cpi_accounts = Burn {
+            mint: self.token_mint.to_account_info(),
+            from: self.burn_reserve.to_account_info(),
+            authority: self.burn_reserve.to_account_info(),
+        };
+        CpiContext::new(self.token_program.to_account_info(), cpi_accounts)
+    }
+}
+
 // ---------- RefillRewardPool ----------
 #[derive(Accounts)]
 pub struct RefillRewardPool<'info> {
-    #[account(mut)]
-    pub presale_state: Account<'info, PresaleState>,
+    #[account(seeds = [b"roles"], bump, has_one = operator @ ErrorCode::Unauthorized)]
+    pub roles: Account<'info, Roles>,
     #[account(mut)]
     pub global_state: Account<'info, GlobalState>,
-    /// The source token account (admin’s account) from which tokens will be transferred.
+    /// The source token account (operator’s account) from which tokens will be transferred.
     #[account(mut)]
-    pub source: Account<'info, TokenAccount>,
-    /// The reward pool token account to be refilled.
-    #[account(mut)]
-    pub reward_pool_token_account: Account<'info, TokenAccount>,
-    pub admin: Signer<'info>,
-    pub token_program: Program<'info, Token>,
+    pub source: InterfaceAccount<'info, TokenAccount>,
+    /// The reward vault to be refilled.
+    #[account(mut, seeds = [b"reward_vault"], bump)]
+    pub reward_vault: InterfaceAccount<'info, TokenAccount>,
+    pub operator: Signer<'info>,
+    /// The $BRATS mint.
+    #[account(address = reward_vault.mint)]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+    pub token_program: Interface<'info, TokenInterface>,
 }
 
 impl<'info> RefillRewardPool<'info> {
     pub fn refill_transfer_context(
         &self,
-    ) -> CpiContext<'_, '_, '_, 'info, Transfer<'info>> {
-        let cpi_accounts = Transfer {
+    ) -> CpiContext<'_, '_, '_, 'info, TransferChecked<'info>> {
+        let cpi_accounts = TransferChecked {
             from: self.source.to_account_info(),
-            to: self.reward_pool_token_account.to_account_info(),
-            authority: self.admin.to_account_info(),
+            mint: self.token_mint.to_account_info(),
+            to: self.reward_vault.to_account_info(),
+            authority: self.operator.to_account_info(),
         };
         CpiContext::new(self.token_program.to_account_info(), cpi_accounts)
     }
 }
 
+// ---------- SetStakingConfig ----------
+#[derive(Accounts)]
+pub struct SetStakingConfig<'info> {
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = 8 + std::mem::size_of::<StakingConfig>(),
+        seeds = [b"staking_config"],
+        bump
+    )]
+    pub staking_config: Account<'info, StakingConfig>,
+    #[account(has_one = admin @ ErrorCode::Unauthorized)]
+    pub presale_state: Account<'info, PresaleState>,
+    #[account(mut, seeds = [b"config"], bump)]
+    pub program_config: Account<'info, ProgramConfig>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+// ---------- AdjustApy ----------
+#[derive(Accounts)]
+pub struct AdjustApy<'info> {
+    #[account(mut, seeds = [b"staking_config"], bump)]
+    pub staking_config: Account<'info, StakingConfig>,
+    #[account(mut)]
+    pub global_state: Account<'info, GlobalState>,
+    #[account(seeds = [b"param_ramp"], bump)]
+    pub param_ramp: Account<'info, ParamRamp>,
+    #[account(seeds = [b"emission_schedule"], bump)]
+    pub emission_schedule: Account<'info, EmissionSchedule>,
+}
+
+// ---------- SetApyCurve ----------
+#[derive(Accounts)]
+pub struct SetApyCurve<'info> {
+    #[account(mut)]
+    pub global_state: Account<'info, GlobalState>,
+    #[account(seeds = [b"param_ramp"], bump)]
+    pub param_ramp: Account<'info, ParamRamp>,
+    #[account(seeds = [b"emission_schedule"], bump)]
+    pub emission_schedule: Account<'info, EmissionSchedule>,
+    #[account(has_one = admin @ ErrorCode::Unauthorized)]
+    pub presale_state: Account<'info, PresaleState>,
+    #[account(mut, seeds = [b"config"], bump)]
+    pub program_config: Account<'info, ProgramConfig>,
+    pub admin: Signer<'info>,
+}
+
 // ---------- UpdateParameters ----------
 #[derive(Accounts)]
 pub struct UpdateParameters<'info> {
+    #[account(mut, seeds = [b"pending_params"], bump)]
+    pub pending_params: Account<'info, PendingParams>,
+    #[account(has_one = admin @ ErrorCode::Unauthorized)]
+    pub presale_state: Account<'info, PresaleState>,
+    #[account(mut, seeds = [b"config"], bump)]
+    pub program_config: Account<'info, ProgramConfig>,
+    pub admin: Signer<'info>,
+}
+
+// ---------- InitializePendingParams ----------
+#[derive(Accounts)]
+pub struct InitializePendingParams<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + std::mem::size_of::<PendingParams>(),
+        seeds = [b"pending_params"],
+        bump
+    )]
+    pub pending_params: Account<'info, PendingParams>,
+    #[account(has_one = admin @ ErrorCode::Unauthorized)]
+    pub presale_state: Account<'info, PresaleState>,
+    #[account(mut, seeds = [b"config"], bump)]
+    pub program_config: Account<'info, ProgramConfig>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+// ---------- ExecuteParameters ----------
+#[derive(Accounts)]
+pub struct ExecuteParameters<'info> {
+    #[account(mut, seeds = [b"pending_params"], bump)]
+    pub pending_params: Account<'info, PendingParams>,
+    #[account(mut)]
+    pub global_state: Account<'info, GlobalState>,
+    #[account(seeds = [b"param_ramp"], bump)]
+    pub param_ramp: Account<'info, ParamRamp>,
+    #[account(seeds = [b"emission_schedule"], bump)]
+    pub emission_schedule: Account<'info, EmissionSchedule>,
+}
+
+// ---------- SetGovernanceConfig ----------
+#[derive(Accounts)]
+pub struct SetGovernanceConfig<'info> {
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = 8 + std::mem::size_of::<Governance>(),
+        seeds = [b"governance"],
+        bump
+    )]
+    pub governance: Account<'info, Governance>,
+    #[account(has_one = admin @ ErrorCode::Unauthorized)]
+    pub presale_state: Account<'info, PresaleState>,
+    #[account(mut, seeds = [b"config"], bump)]
+    pub program_config: Account<'info, ProgramConfig>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+// ---------- CreateProposal ----------
+#[derive(Accounts)]
+pub struct CreateProposal<'info> {
+    #[account(mut, seeds = [b"governance"], bump)]
+    pub governance: Account<'info, Governance>,
+    #[account(
+        init,
+        payer = proposer,
+        space = 8 + std::mem::size_of::<Proposal>(),
+        seeds = [b"proposal", &governance.proposal_count.to_le_bytes()],
+        bump
+    )]
+    pub proposal: Account<'info, Proposal>,
+    #[account(seeds = [b"staker", proposer.key().as_ref()], bump)]
+    pub staker_record: Account<'info, StakerRecord>,
     #[account(mut)]
+    pub proposer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+// ---------- CastVote ----------
+#[derive(Accounts)]
+pub struct CastVote<'info> {
+    #[account(mut, seeds = [b"proposal", &proposal.id.to_le_bytes()], bump)]
+    pub proposal: Account<'info, Proposal>,
+    #[account(mut)]
+    pub stake_info: Account<'info, StakeInfo>,
+    /// One vote per position per proposal.
+    #[account(
+        init,
+        payer = voter,
+        space = 8 + std::mem::size_of::<VoteRecord>(),
+        seeds = [b"vote", proposal.key().as_ref(), stake_info.key().as_ref()],
+        bump
+    )]
+    pub vote_record: Account<'info, VoteRecord>,
+    #[account(mut, seeds = [b"staker", voter.key().as_ref()], bump)]
+    pub staker_record: Account<'info, StakerRecord>,
+    #[account(mut)]
+    pub voter: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+// ---------- ExecuteProposal ----------
+#[derive(Accounts)]
+pub struct ExecuteProposal<'info> {
+    #[account(seeds = [b"governance"], bump)]
+    pub governance: Account<'info, Governance>,
+    #[account(mut, seeds = [b"proposal", &proposal.id.to_le_bytes()], bump)]
+    pub proposal: Account<'info, Proposal>,
+    #[account(mut)]
+    pub global_state: Account<'info, GlobalState>,
+    #[account(seeds = [b"param_ramp"], bump)]
+    pub param_ramp: Account<'info, ParamRamp>,
+    #[account(seeds = [b"emission_schedule"], bump)]
+    pub emission_schedule: Account<'info, EmissionSchedule>,
+    #[account(mut, seeds = [b"staking_config"], bump)]
+    pub staking_config: Account<'info, StakingConfig>,
+    /// CHECK: Market-maker allowance; only read for `RevokeMarketMaker` proposals.
+    #[account(mut, seeds = [b"market_maker"], bump)]
+    pub market_maker_allowance: AccountInfo<'info>,
+}
+
+// ---------- InitializeParamRamp ----------
+#[derive(Accounts)]
+pub struct InitializeParamRamp<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + std::mem::size_of::<ParamRamp>(),
+        seeds = [b"param_ramp"],
+        bump
+    )]
+    pub param_ramp: Account<'info, ParamRamp>,
+    #[account(has_one = admin @ ErrorCode::Unauthorized)]
     pub presale_state: Account<'info, PresaleState>,
+    #[account(mut, seeds = [b"config"], bump)]
+    pub program_config: Account<'info, ProgramConfig>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+// ---------- UpdateParamRamp ----------
+#[derive(Accounts)]
+pub struct UpdateParamRamp<'info> {
+    #[account(mut, seeds = [b"param_ramp"], bump)]
+    pub param_ramp: Account<'info, ParamRamp>,
+    #[account(seeds = [b"emission_schedule"], bump)]
+    pub emission_schedule: Account<'info, EmissionSchedule>,
+    #[account(has_one = admin @ ErrorCode::Unauthorized)]
+    pub presale_state: Account<'info, PresaleState>,
+    #[account(mut, seeds = [b"config"], bump)]
+    pub program_config: Account<'info, ProgramConfig>,
     #[account(mut)]
     pub global_state: Account<'info, GlobalState>,
     pub admin: Signer<'info>,
 }
 
-// ---------- WithdrawFunds ----------
+// ---------- InitializeEmissionSchedule ----------
 #[derive(Accounts)]
-pub struct WithdrawFunds<'info> {
+pub struct InitializeEmissionSchedule<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + std::mem::size_of::<EmissionSchedule>(),
+        seeds = [b"emission_schedule"],
+        bump
+    )]
+    pub emission_schedule: Account<'info, EmissionSchedule>,
+    #[account(has_one = admin @ ErrorCode::Unauthorized)]
+    pub presale_state: Account<'info, PresaleState>,
+    #[account(mut, seeds = [b"config"], bump)]
+    pub program_config: Account<'info, ProgramConfig>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+// ---------- UpdateEmissionSchedule ----------
+#[derive(Accounts)]
+pub struct UpdateEmissionSchedule<'info> {
+    #[account(mut, seeds = [b"emission_schedule"], bump)]
+    pub emission_schedule: Account<'info, EmissionSchedule>,
+    #[account(seeds = [b"param_ramp"], bump)]
+    pub param_ramp: Account<'info, ParamRamp>,
     #[account(mut)]
+    pub global_state: Account<'info, GlobalState>,
+    #[account(has_one = admin @ ErrorCode::Unauthorized)]
     pub presale_state: Account<'info, PresaleState>,
-    /// CHECK: Treasury SOL account from which funds will be withdrawn.
+    #[account(mut, seeds = [b"config"], bump)]
+    pub program_config: Account<'info, ProgramConfig>,
+    pub admin: Signer<'info>,
+}
+
+// ---------- InitializeClaimPortal ----------
+#[derive(Accounts)]
+pub struct InitializeClaimPortal<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + std::mem::size_of::<ClaimPortal>(),
+        seeds = [b"claim_portal"],
+        bump
+    )]
+    pub claim_portal: Account<'info, ClaimPortal>,
+    #[account(has_one = admin @ ErrorCode::Unauthorized)]
+    pub presale_state: Account<'info, PresaleState>,
+    #[account(mut, seeds = [b"config"], bump)]
+    pub program_config: Account<'info, ProgramConfig>,
     #[account(mut)]
-    pub treasury_sol_account: AccountInfo<'info>,
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+// ---------- UpdateClaimPortal ----------
+#[derive(Accounts)]
+pub struct UpdateClaimPortal<'info> {
+    #[account(mut, seeds = [b"claim_portal"], bump)]
+    pub claim_portal: Account<'info, ClaimPortal>,
+    #[account(has_one = admin @ ErrorCode::Unauthorized)]
+    pub presale_state: Account<'info, PresaleState>,
+    #[account(mut, seeds = [b"config"], bump)]
+    pub program_config: Account<'info, ProgramConfig>,
+    pub admin: Signer<'info>,
+}
+
+// ---------- SetProjectInfo ----------
+#[derive(Accounts)]
+pub struct SetProjectInfo<'info> {
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = 8 + ProjectInfo::LEN,
+        seeds = [b"project_info"],
+        bump
+    )]
+    pub project_info: Account<'info, ProjectInfo>,
+    #[account(has_one = admin @ ErrorCode::Unauthorized)]
+    pub presale_state: Account<'info, PresaleState>,
+    #[account(mut, seeds = [b"config"], bump)]
+    pub program_config: Account<'info, ProgramConfig>,
     #[account(mut)]
     pub admin: Signer<'info>,
     pub system_program: Program<'info, System>,
 }
 
-// ---------- InitializePresaleStages ----------
+// ---------- WithdrawFunds ----------
 #[derive(Accounts)]
-pub struct InitializePresaleStages<'info> {
-    #[account(init, payer = payer, space = 8 + std::mem::size_of::<PresaleStageInfo>())]
-    pub presale_stage_info: Account<'info, PresaleStageInfo>,
+pub struct WithdrawFunds<'info> {
+    #[account(seeds = [b"roles"], bump, has_one = treasurer @ ErrorCode::Unauthorized)]
+    pub roles: Account<'info, Roles>,
+    pub presale_state: Account<'info, PresaleState>,
+    #[account(seeds = [b"config"], bump)]
+    pub program_config: Account<'info, ProgramConfig>,
+    /// CHECK: Treasury SOL account from which funds will be withdrawn.
     #[account(mut)]
-    pub payer: Signer<'info>,
+    pub treasury_sol_account: AccountInfo<'info>,
+    #[account(mut)]
+    pub treasurer: Signer<'info>,
+    /// CHECK: Withdrawal co-signer; must sign when the amount is above the co-sign threshold.
+    pub cosigner: AccountInfo<'info>,
     pub system_program: Program<'info, System>,
 }
 
@@ -860,6 +13247,31 @@ pub struct InitializePresaleStages<'info> {
 #[derive(Accounts)]
 pub struct UpdatePresaleStage<'info> {
     #[account(mut)]
-    pub presale_stage_info: Account<'info, PresaleStageInfo>,
+    pub presale_stage_info: AccountLoader<'info, PresaleStageInfo>,
+    #[account(mut, seeds = [b"presale_progress"], bump)]
+    pub presale_progress: Account<'info, PresaleProgress>,
+    #[account(seeds = [b"roles"], bump, has_one = operator @ ErrorCode::Unauthorized)]
+    pub roles: Account<'info, Roles>,
+    pub operator: Signer<'info>,
+}
+
+// ---------- InitializePresaleProgress ----------
+#[derive(Accounts)]
+pub struct InitializePresaleProgress<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + std::mem::size_of::<PresaleProgress>(),
+        seeds = [b"presale_progress"],
+        bump
+    )]
+    pub presale_progress: Account<'info, PresaleProgress>,
+    #[account(has_one = admin @ ErrorCode::Unauthorized)]
+    pub presale_state: Account<'info, PresaleState>,
+    #[account(mut, seeds = [b"config"], bump)]
+    pub program_config: Account<'info, ProgramConfig>,
+    pub presale_stage_info: AccountLoader<'info, PresaleStageInfo>,
+    #[account(mut)]
     pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
 }